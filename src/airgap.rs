@@ -0,0 +1,149 @@
+//! Signing on an air-gapped machine
+//!
+//! The online side of the workflow: the event to sign is shown as a QR
+//! code holding the unsigned event (NIP-01 JSON with its id), the offline
+//! signer checks and signs it, and the signature it shows is scanned back
+//! and put together with the event here. Nothing secret crosses the gap.
+
+use crate::error::{Result, SignerError};
+use nostr::prelude::*;
+use nostr::secp256k1::schnorr::Signature;
+use std::str::FromStr;
+
+/// An unsigned event waiting for a signature from an offline device
+#[derive(Debug, Clone)]
+pub struct AirgapChallenge {
+    event: UnsignedEvent,
+    id: EventId,
+}
+
+impl AirgapChallenge {
+    /// Challenge for `event`, computing its id
+    pub fn new(event: UnsignedEvent) -> Self {
+        let id = EventId::new(&event.pubkey, &event.created_at, &event.kind, &event.tags, &event.content);
+        Self { event, id }
+    }
+
+    /// Id of the event; it is what the offline device signs
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    pub fn event(&self) -> &UnsignedEvent {
+        &self.event
+    }
+
+    /// Text for the QR code: the unsigned event as JSON, id included
+    pub fn encode(&self) -> String {
+        let mut event = self.event.clone();
+        event.id = Some(self.id);
+        event.as_json()
+    }
+
+    /// Parse a challenge from its QR text; an id that doesn't match the
+    /// content is refused
+    pub fn decode(payload: &str) -> Result<Self> {
+        let event = UnsignedEvent::from_json(payload.trim())
+            .map_err(|e| SignerError::InvalidRequest(format!("Not an unsigned event: {}", e)))?;
+        let claimed = event.id;
+        let challenge = Self::new(event);
+        if claimed.is_some_and(|id| id != challenge.id) {
+            return Err(SignerError::InvalidRequest("The event id doesn't match its content".into()));
+        }
+        Ok(challenge)
+    }
+
+    /// The QR code to show the offline device
+    ///
+    /// Fails for events too large for a single QR code (about 2.9 kB of
+    /// JSON).
+    pub fn to_qr(&self) -> Result<iced::widget::qr_code::Data> {
+        iced::widget::qr_code::Data::new(self.encode())
+            .map_err(|e| SignerError::InvalidRequest(format!("Event doesn't fit in a QR code: {}", e)))
+    }
+
+    /// Complete the event with what was scanned from the offline device:
+    /// the 64-byte Schnorr signature in hex, or the whole signed event
+    ///
+    /// The signature must be valid for this event's id and public key.
+    pub fn assemble(&self, scanned: &str) -> Result<Event> {
+        let scanned = scanned.trim();
+        let event = if scanned.starts_with('{') {
+            let event = Event::from_json(scanned)
+                .map_err(|e| SignerError::InvalidRequest(format!("Not a signed event: {}", e)))?;
+            if event.id != self.id {
+                return Err(SignerError::InvalidRequest("The scanned event isn't the one that was asked for".into()));
+            }
+            event
+        } else {
+            let sig = Signature::from_str(scanned)
+                .map_err(|e| SignerError::InvalidRequest(format!("Not a signature: {}", e)))?;
+            Event::new(
+                self.id,
+                self.event.pubkey,
+                self.event.created_at,
+                self.event.kind,
+                self.event.tags.iter().cloned(),
+                self.event.content.clone(),
+                sig,
+            )
+        };
+        event.verify()
+            .map_err(|_| SignerError::InvalidRequest("The signature doesn't match this event and key".into()))?;
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge_for(keys: &Keys) -> AirgapChallenge {
+        let event = EventBuilder::text_note("signed offline")
+            .tag(Tag::hashtag("airgap"))
+            .custom_created_at(Timestamp::from(1_700_000_000))
+            .build(keys.public_key());
+        AirgapChallenge::new(event)
+    }
+
+    #[test]
+    fn test_challenge_round_trips_through_qr_text() {
+        let keys = Keys::generate();
+        let challenge = challenge_for(&keys);
+        let payload = challenge.encode();
+        assert!(payload.contains(&challenge.id().to_hex()));
+        assert!(!payload.contains("\"sig\""));
+
+        let decoded = AirgapChallenge::decode(&payload).unwrap();
+        assert_eq!(decoded.id(), challenge.id());
+        assert_eq!(decoded.event().content, "signed offline");
+        assert!(challenge.to_qr().is_ok());
+
+        // Content changed after the id was computed
+        let tampered = payload.replace("signed offline", "signed elsewhere");
+        assert!(AirgapChallenge::decode(&tampered).is_err());
+        assert!(AirgapChallenge::decode("{\"kind\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_assemble_scanned_signature() {
+        let keys = Keys::generate();
+        let challenge = challenge_for(&keys);
+
+        // What the offline device does with the decoded challenge
+        let offline = AirgapChallenge::decode(&challenge.encode()).unwrap();
+        let signed = offline.event().clone().sign_with_keys(&keys).unwrap();
+
+        let event = challenge.assemble(&signed.sig.to_string()).unwrap();
+        assert_eq!(event.id, challenge.id());
+        assert_eq!(event.pubkey, keys.public_key());
+        assert_eq!(event.tags.len(), 1);
+        assert_eq!(challenge.assemble(&signed.as_json()).unwrap(), event);
+
+        // A signature for another event is refused
+        let other = EventBuilder::text_note("something else").sign_with_keys(&keys).unwrap();
+        assert!(challenge.assemble(&other.sig.to_string()).is_err());
+        assert!(challenge.assemble(&other.as_json()).is_err());
+        assert!(challenge.assemble("not a signature").is_err());
+    }
+}
@@ -1,13 +1,25 @@
 //! Application state management
+//!
+//! Lock order: the `AppState` RwLock is always taken before a `KeyManager`
+//! Mutex, and nothing waits on the app state while holding a key manager.
+//! Code that needs both at once goes through [`lock_state_and_keys`].
 
-use crate::bunker::{BunkerSigner, BunkerState};
+use crate::approval::ApprovalQueue;
+use crate::audit::{AuditEntry, AuditLog, DenialAlert};
+use crate::autolock::InactivityTimer;
+use crate::bunker::{BunkerSigner, BunkerState, LockGate, RelayAuthStatus, ResponseRate, SignerIdentity};
 use crate::config::Config;
 use crate::error::Result;
 use crate::keys::KeyManager;
+use crate::notify::RequestNotice;
 use crate::permissions::RateLimiter;
+use crate::presence;
+use crate::signing::{BlockedKinds, SignPolicy};
 use async_channel::{Receiver, Sender};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex, MutexGuard, RwLock, RwLockWriteGuard};
 
 /// Message types for communication between components
 #[derive(Debug, Clone)]
@@ -16,6 +28,22 @@ pub enum AppMessage {
     Lock,
     /// Unlock the application
     Unlock,
+    /// Ask the user to unlock (a remote request is waiting)
+    RequestUnlock,
+    /// The inactivity lock fires in this many seconds
+    LockWarning(u64),
+    /// The user chose to stay unlocked from the warning
+    StayUnlocked,
+    /// An app was refused unusually often
+    DenialAlert(DenialAlert),
+    /// A new signing request arrived (D-Bus or bunker)
+    NewRequest(RequestNotice),
+    /// The bunker handled a request; record it in the audit log
+    BunkerActivity(AuditEntry),
+    /// The bunker is ready to auto-start; ask the user first
+    ConfirmBunkerStart,
+    /// Start the bunker (the user confirmed the auto-start)
+    StartBunker,
     /// Show the main window
     ShowWindow,
     /// Hide to tray
@@ -24,6 +52,19 @@ pub enum AppMessage {
     Quit,
 }
 
+/// Settings only read at startup: changing them needs a restart, while the
+/// rest of the config applies on `reload_config`
+pub const RESTART_REQUIRED: [&str; 5] = [
+    "bunker",
+    "engines",
+    "general.missing_active_key",
+    "security.presence_check",
+    "ui.tray_left_click",
+];
+
+/// How long a "window focused" report from the UI holds without being renewed
+const WINDOW_FOCUS_LEASE: Duration = Duration::from_secs(5);
+
 /// Main application state
 pub struct AppState {
     /// Application configuration
@@ -32,10 +73,28 @@ pub struct AppState {
     pub key_manager: KeyManager,
     /// Rate limiter for auto-approved requests
     pub rate_limiter: RateLimiter,
+    /// Audit log of handled requests (also backs daily quotas)
+    pub audit_log: AuditLog,
+    /// Requests waiting for the user to approve or reject
+    pub approval_queue: Arc<ApprovalQueue>,
+    /// Apps asked about their first NIP-04 request since launch
+    pub nip04_warned: HashSet<String>,
     /// Whether the application is currently locked
     pub is_locked: bool,
+    /// Key whose own password lifts the lock the signer launched with
+    pub launch_password_key: Option<String>,
+    /// Broadcasts lock state changes to background tasks (e.g. the bunker)
+    lock_tx: watch::Sender<bool>,
+    /// Inactivity lock countdown, restarted by the signing engines
+    pub inactivity: Arc<std::sync::Mutex<InactivityTimer>>,
+    /// Event kinds the signing engines and the bunker refuse
+    pub blocked_kinds: BlockedKinds,
+    /// Signing policy the bunker applies to remote requests
+    pub sign_policy: SignPolicy,
     /// Whether the main window is visible
     pub window_visible: bool,
+    /// Until when the UI window counts as focused (renewed while it is)
+    window_focused_until: Option<Instant>,
     /// Channel for internal messages
     pub message_sender: Sender<AppMessage>,
     pub message_receiver: Receiver<AppMessage>,
@@ -46,34 +105,174 @@ pub struct AppState {
 impl AppState {
     /// Create a new application state
     pub async fn new(config: Config) -> Result<Self> {
+        Ok(Self::with_audit_log(config, AuditLog::load().await?))
+    }
+
+    /// Create the application state around an already loaded audit log
+    pub fn with_audit_log(config: Config, audit_log: AuditLog) -> Self {
         let (message_sender, message_receiver) = async_channel::unbounded();
         let key_manager = KeyManager::new();
         let rate_limiter = RateLimiter::new(config.security.max_auto_approvals_per_min);
+        // Start unlocked since we use OS keyring (see lock_for_launch)
+        let (lock_tx, _) = watch::channel(false);
+        let approval_queue = ApprovalQueue::new()
+            .with_presence(presence::verifier_for(config.security.presence_check))
+            .with_max_pending(config.security.max_pending_requests);
+        let inactivity = InactivityTimer::new(
+            config.security.lock_timeout_mins,
+            config.security.lock_warning_secs,
+            Instant::now(),
+        );
+        let blocked_kinds = BlockedKinds::new(config.security.blocked_event_kinds.clone());
+        let sign_policy = SignPolicy::new(config.security.clone());
 
-        Ok(Self {
+        Self {
             config,
             key_manager,
             rate_limiter,
-            is_locked: false, // Start unlocked since we use OS keyring
+            audit_log,
+            approval_queue: Arc::new(approval_queue),
+            nip04_warned: HashSet::new(),
+            is_locked: false,
+            launch_password_key: None,
+            lock_tx,
+            inactivity: Arc::new(std::sync::Mutex::new(inactivity)),
+            blocked_kinds,
+            sign_policy,
             window_visible: true,
+            window_focused_until: None,
             message_sender,
             message_receiver,
             bunker_signer: None,
-        })
+        }
     }
     
+    /// Apply a re-read config to the running signer
+    ///
+    /// Limits, timeouts and approval settings take effect right away. Returns
+    /// the changed settings from [`RESTART_REQUIRED`], which don't.
+    pub fn reload_config(&mut self, mut config: Config) -> Vec<&'static str> {
+        if self.config.safe_mode {
+            config.apply_safe_mode();
+        }
+        let old = &self.config;
+        let restart: Vec<&'static str> = [
+            changed(&old.bunker, &config.bunker),
+            changed(&old.engines, &config.engines),
+            changed(&old.general.missing_active_key, &config.general.missing_active_key),
+            changed(&old.security.presence_check, &config.security.presence_check),
+            changed(&old.ui.tray_left_click, &config.ui.tray_left_click),
+        ]
+        .into_iter()
+        .zip(RESTART_REQUIRED)
+        .filter_map(|(changed, name)| changed.then_some(name))
+        .collect();
+
+        self.rate_limiter.set_max_per_minute(config.security.max_auto_approvals_per_min);
+        self.approval_queue.set_max_pending(config.security.max_pending_requests);
+        self.inactivity.lock().unwrap().reconfigure(
+            config.security.lock_timeout_mins,
+            config.security.lock_warning_secs,
+        );
+        self.blocked_kinds.set(config.security.blocked_event_kinds.clone());
+        self.sign_policy.set(config.security.clone());
+        self.config = config;
+        restart
+    }
+
     /// Initialize bunker signer with key manager
     pub fn init_bunker(&mut self, key_manager: Arc<Mutex<KeyManager>>) {
-        let bunker = BunkerSigner::new(key_manager)
-            .with_relays(vec![
-                "wss://relay.nsec.app".to_string(),
-                "wss://relay.damus.io".to_string(),
-            ]);
+        let mut bunker = BunkerSigner::new(key_manager)
+            .with_key(self.config.bunker.key.clone())
+            .with_relay_routes(&self.config.bunker.inbound_relays, &self.config.bunker.outbound_relays)
+            .with_response_rate(ResponseRate {
+                per_sec: self.config.bunker.max_responses_per_sec,
+                backlog: self.config.bunker.response_backlog,
+            })
+            .with_request_notices(self.message_sender.clone())
+            .with_identity(SignerIdentity {
+                name: self.config.bunker.name.clone(),
+                icon_url: self.config.bunker.icon_url.clone(),
+            })
+            .with_disabled_methods(self.config.bunker.disabled_methods.clone())
+            .with_blocked_kinds(self.blocked_kinds.clone())
+            .with_sign_policy(self.sign_policy.clone());
+        if let Some(ref secret) = self.config.bunker.secret {
+            bunker = bunker.with_secret(secret.clone());
+        }
+        if self.config.bunker.queue_while_locked {
+            bunker = bunker.with_lock_gate(LockGate {
+                locked: self.lock_tx.subscribe(),
+                unlock_prompt: self.message_sender.clone(),
+                timeout: Duration::from_secs(self.config.general.request_timeout_secs),
+            });
+        }
         self.bunker_signer = Some(Arc::new(bunker));
     }
+
+    /// Lock or unlock the application, notifying background tasks
+    pub fn set_locked(&mut self, locked: bool) {
+        self.is_locked = locked;
+        self.lock_tx.send_replace(locked);
+        if !locked {
+            self.record_activity();
+        }
+    }
+
+    /// Start locked if the bunker auto-starts and its key has its own
+    /// password, so remote signing waits until `Unlock` is given that
+    /// password. Returns whether the signer is now locked.
+    pub fn lock_for_launch(&mut self, keys: &KeyManager) -> bool {
+        let Some(name) = launch_password_key(&self.config, keys) else {
+            return false;
+        };
+        self.launch_password_key = Some(name);
+        self.set_locked(true);
+        true
+    }
+
+    /// Unlock, unless the launch lock is waiting for its password; true if
+    /// the signer is now unlocked
+    pub fn unlock_without_password(&mut self) -> bool {
+        if self.launch_password_key.is_some() {
+            return false;
+        }
+        self.set_locked(false);
+        true
+    }
+
+    /// Record the UI window gaining or losing focus. A focus report lapses
+    /// unless renewed, so a window that closed without saying so doesn't
+    /// count as focused for long.
+    pub fn set_window_focused(&mut self, focused: bool, now: Instant) {
+        self.window_focused_until = focused.then(|| now + WINDOW_FOCUS_LEASE);
+    }
+
+    /// Whether the UI window has focus
+    pub fn window_focused(&self, now: Instant) -> bool {
+        self.window_focused_until.is_some_and(|until| now < until)
+    }
+
+    /// Restart the inactivity lock countdown
+    pub fn record_activity(&self) {
+        self.inactivity.lock().unwrap().touch(Instant::now());
+    }
     
+    /// Gate for starting the bunker on launch, if `bunker.auto_start` is on
+    pub fn bunker_auto_start(&self) -> Option<AutoStartGate> {
+        (self.config.bunker.auto_start && !self.config.safe_mode).then(|| AutoStartGate {
+            locked: self.lock_tx.subscribe(),
+            delay: Duration::from_secs(self.config.bunker.auto_start_delay_secs),
+        })
+    }
+
     /// Start bunker listener and return connection URI
     pub async fn start_bunker(&self) -> Result<String> {
+        if self.config.safe_mode {
+            return Err(crate::error::SignerError::PermissionDenied(
+                "Bunker is disabled in safe mode".into(),
+            ));
+        }
         if let Some(ref bunker) = self.bunker_signer {
             // Generate connection URI first
             let uri = bunker.generate_bunker_uri().await?;
@@ -94,6 +293,28 @@ impl AppState {
         }
     }
     
+    /// Pause the bunker, keeping relay connections and sessions
+    pub fn pause_bunker(&self) -> Result<()> {
+        match self.bunker_signer {
+            Some(ref bunker) => {
+                bunker.pause();
+                Ok(())
+            }
+            None => Err(crate::error::SignerError::NostrError("Bunker not initialized".into())),
+        }
+    }
+
+    /// Resume a paused bunker
+    pub fn resume_bunker(&self) -> Result<()> {
+        match self.bunker_signer {
+            Some(ref bunker) => {
+                bunker.resume();
+                Ok(())
+            }
+            None => Err(crate::error::SignerError::NostrError("Bunker not initialized".into())),
+        }
+    }
+    
     /// Get bunker connection URI
     pub async fn get_bunker_uri(&self) -> Result<String> {
         if let Some(ref bunker) = self.bunker_signer {
@@ -103,6 +324,30 @@ impl AppState {
         }
     }
     
+    /// Get the NIP-42 authentication status of each bunker relay
+    pub fn get_bunker_relay_auth(&self) -> HashMap<String, RelayAuthStatus> {
+        self.bunker_signer
+            .as_ref()
+            .map(|bunker| bunker.relay_auth_status())
+            .unwrap_or_default()
+    }
+    
+    /// Get each bunker relay and whether it is connected
+    pub fn get_bunker_relay_status(&self) -> Vec<(String, bool)> {
+        self.bunker_signer
+            .as_ref()
+            .map(|bunker| bunker.relay_status())
+            .unwrap_or_default()
+    }
+    
+    /// Get the relays the bunker uses (empty if not initialized)
+    pub fn get_bunker_relays(&self) -> Vec<String> {
+        self.bunker_signer
+            .as_ref()
+            .map(|bunker| bunker.relays())
+            .unwrap_or_default()
+    }
+    
     /// Get bunker state
     pub async fn get_bunker_state(&self) -> BunkerState {
         if let Some(ref bunker) = self.bunker_signer {
@@ -122,3 +367,220 @@ impl AppState {
         self.message_sender.clone()
     }
 }
+
+/// Key whose password has to be entered before the bunker auto-starts: the
+/// bunker's key, if `require_password_on_start` is on and it has its own
+/// password. Without one there is nothing to enter, so nothing to wait for.
+pub fn launch_password_key(config: &Config, keys: &KeyManager) -> Option<String> {
+    if !config.bunker.auto_start || !config.security.require_password_on_start {
+        return None;
+    }
+    let name = config.bunker.key.as_deref().or(keys.get_active_key_name())?;
+    keys.key_metadata(name)
+        .filter(|meta| meta.is_password_protected())
+        .map(|meta| meta.name.clone())
+}
+
+/// Holds the bunker's auto-start back until the signer is unlocked and has
+/// stayed unlocked for the configured delay
+pub struct AutoStartGate {
+    locked: watch::Receiver<bool>,
+    delay: Duration,
+}
+
+impl AutoStartGate {
+    /// Wait until the bunker may start; locking again during the delay
+    /// starts the wait over. Returns false if the signer is shutting down.
+    pub async fn wait(mut self) -> bool {
+        loop {
+            if self.locked.wait_for(|locked| !locked).await.is_err() {
+                return false;
+            }
+            match tokio::time::timeout(self.delay, self.locked.wait_for(|locked| *locked)).await {
+                Err(_) => return true,
+                Ok(Ok(_)) => continue,
+                Ok(Err(_)) => return false,
+            }
+        }
+    }
+}
+
+/// Start the bunker on launch (`bunker.auto_start`)
+///
+/// Runs once the [`AutoStartGate`] opens, or asks first with
+/// `bunker.auto_start_confirm`.
+pub async fn auto_start_bunker(app_state: Arc<RwLock<AppState>>) {
+    let (gate, confirm, sender) = {
+        let state = app_state.read().await;
+        match state.bunker_auto_start() {
+            Some(gate) => (gate, state.config.bunker.auto_start_confirm, state.get_message_sender()),
+            None => return,
+        }
+    };
+    if !gate.wait().await {
+        return;
+    }
+    if confirm {
+        let _ = sender.send(AppMessage::ConfirmBunkerStart).await;
+        return;
+    }
+    start_bunker_unless_locked(&app_state).await;
+}
+
+/// Start the bunker, unless the signer was locked again in the meantime
+pub async fn start_bunker_unless_locked(app_state: &RwLock<AppState>) {
+    let state = app_state.read().await;
+    if state.is_locked {
+        tracing::info!("Signer is locked; not starting the bunker");
+        return;
+    }
+    match state.start_bunker().await {
+        Ok(_) => tracing::info!("Bunker started"),
+        Err(e) => tracing::warn!("Failed to start the bunker: {}", e),
+    }
+}
+
+/// Whether a config value differs, compared by its serialized form
+fn changed<T: serde::Serialize>(old: &T, new: &T) -> bool {
+    serde_json::to_value(old).ok() != serde_json::to_value(new).ok()
+}
+
+/// Take the app state and a key manager together, in the lock order
+pub async fn lock_state_and_keys<'a>(
+    app_state: &'a RwLock<AppState>,
+    key_manager: &'a Mutex<KeyManager>,
+) -> (RwLockWriteGuard<'a, AppState>, MutexGuard<'a, KeyManager>) {
+    let state = app_state.write().await;
+    let keys = key_manager.lock().await;
+    (state, keys)
+}
+
+/// Lock the signer and drop the cached keys in one step, so no request sees
+/// a locked signer that still holds keys
+pub async fn lock_signer(app_state: &RwLock<AppState>, key_manager: &Mutex<KeyManager>) {
+    let (mut state, mut keys) = lock_state_and_keys(app_state, key_manager).await;
+    keys.lock();
+    state.set_locked(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::RequestType;
+    use crate::signing::{SigningEngine, UnsignedEventData};
+    use nostr::Keys;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_sign_list_and_lock_do_not_hang() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_log = AuditLog::load_from(dir.path().join("audit.jsonl")).await.unwrap();
+        let app_state = Arc::new(RwLock::new(AppState::with_audit_log(Config::default_config(), audit_log)));
+        let mut km = KeyManager::with_namespace("stress");
+        km.add_test_key("main", Keys::generate());
+        let key_manager = Arc::new(Mutex::new(km));
+        let engine = Arc::new(SigningEngine::new(Arc::clone(&key_manager)));
+
+        let mut tasks = Vec::new();
+        for i in 0..8 {
+            let (app_state, key_manager, engine) = (Arc::clone(&app_state), Arc::clone(&key_manager), Arc::clone(&engine));
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..50 {
+                    match i % 4 {
+                        // Sign: readiness check, then the key manager
+                        0 => {
+                            let _ready = app_state.read().await.is_ready();
+                            let event = UnsignedEventData {
+                                kind: 1,
+                                content: "stress".into(),
+                                tags: Vec::new(),
+                                created_at: None,
+                                relay_hints: Vec::new(),
+                            };
+                            engine.sign_event(&event, Some("main")).await.unwrap();
+                        }
+                        // List: both held, in order
+                        1 => {
+                            let _state = app_state.read().await;
+                            assert_eq!(key_manager.lock().await.list_keys().len(), 1);
+                        }
+                        2 => lock_signer(&app_state, &key_manager).await,
+                        _ => app_state.write().await.set_locked(false),
+                    }
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+
+        tokio::time::timeout(Duration::from_secs(30), async {
+            for task in tasks {
+                task.await.unwrap();
+            }
+        })
+        .await
+        .expect("lock operations deadlocked");
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_rate_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_log = AuditLog::load_from(dir.path().join("audit.jsonl")).await.unwrap();
+        let mut config = Config::default_config();
+        config.security.max_auto_approvals_per_min = 1;
+        let mut state = AppState::with_audit_log(config.clone(), audit_log);
+
+        assert!(state.rate_limiter.check_and_record("app", RequestType::SignEvent));
+        assert!(!state.rate_limiter.check_and_record("app", RequestType::SignEvent));
+
+        config.security.max_auto_approvals_per_min = 3;
+        assert!(state.reload_config(config.clone()).is_empty());
+        assert_eq!(state.config.security.max_auto_approvals_per_min, 3);
+        assert!(state.rate_limiter.check_and_record("app", RequestType::SignEvent));
+        assert!(state.rate_limiter.check_and_record("app", RequestType::SignEvent));
+        assert!(!state.rate_limiter.check_and_record("app", RequestType::SignEvent));
+
+        // Startup-only settings are applied to the config but reported
+        config.bunker.inbound_relays = vec!["wss://relay.example.com".into()];
+        config.ui.tray_left_click = crate::config::TrayClickAction::ToggleLock;
+        assert_eq!(state.reload_config(config), vec!["bunker", "ui.tray_left_click"]);
+        assert_eq!(state.config.bunker.inbound_relays, vec!["wss://relay.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_bunker_auto_start_waits_for_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_log = || AuditLog::load_from(dir.path().join("audit.jsonl"));
+        assert!(AppState::with_audit_log(Config::default_config(), audit_log().await.unwrap())
+            .bunker_auto_start()
+            .is_none());
+
+        let mut config = Config::default_config();
+        config.bunker.auto_start = true;
+        config.bunker.auto_start_delay_secs = 0;
+        assert!(config.security.require_password_on_start);
+        let mut state = AppState::with_audit_log(config.clone(), audit_log().await.unwrap());
+        let mut keys = KeyManager::with_test_key("main", Keys::generate()).with_metadata_file(dir.path().join("keys.json"));
+
+        // No password to enter: nothing to wait for
+        assert!(!state.lock_for_launch(&keys));
+        assert!(!state.is_locked);
+
+        keys.set_key_password("main", Some("bunker password")).await.unwrap();
+        assert!(state.lock_for_launch(&keys));
+        assert!(state.is_locked);
+
+        let gate = tokio::spawn(state.bunker_auto_start().unwrap().wait());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!gate.is_finished(), "auto-start went ahead while locked");
+
+        // The tray can't lift the launch lock; Unlock does once the password checks out
+        assert!(!state.unlock_without_password());
+        assert!(state.is_locked);
+        state.launch_password_key = None;
+        assert!(state.unlock_without_password());
+        let opened = tokio::time::timeout(Duration::from_secs(5), gate).await.unwrap().unwrap();
+        assert!(opened);
+
+        config.security.require_password_on_start = false;
+        assert!(launch_password_key(&config, &keys).is_none());
+    }
+}
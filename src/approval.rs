@@ -0,0 +1,483 @@
+//! Queue of requests waiting for user approval
+//!
+//! Request handlers submit a `SigningRequest` and wait; the user's decision
+//! (or the request timeout) releases them. The user can also approve an app
+//! for a limited session, during which its requests skip the queue.
+//! Rejections can carry a short reason that is passed back to the app.
+//! Optionally, an approval only counts once a presence check passes.
+//! The queue can be capped so a flood of requests can't bury the user.
+
+use crate::error::{Result, SignerError};
+use crate::presence::PresenceVerifier;
+use crate::signing::{RequestOrigin, SigningRequest};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Reasons offered when rejecting a request; free text is accepted too
+pub const REJECTION_REASONS: &[&str] = &[
+    "Wrong account",
+    "Looks suspicious",
+    "I didn't request this",
+    "Content is wrong",
+];
+
+/// Longest rejection reason passed on to the app
+const MAX_REASON_CHARS: usize = 140;
+
+/// A request waiting for the user
+struct PendingApproval {
+    request: SigningRequest,
+    responder: oneshot::Sender<bool>,
+}
+
+/// Shared queue of pending approvals
+#[derive(Default)]
+pub struct ApprovalQueue {
+    pending: Mutex<Vec<PendingApproval>>,
+    /// App ID -> expiry of its session grant (kept in memory only)
+    session_grants: Mutex<HashMap<String, DateTime<Utc>>>,
+    /// Request ID -> key the user picked to sign it with
+    chosen_keys: Mutex<HashMap<String, String>>,
+    /// Request ID -> reason the user gave for rejecting it
+    rejection_reasons: Mutex<HashMap<String, String>>,
+    /// Presence check run after each approval
+    presence: Option<Arc<dyn PresenceVerifier>>,
+    /// Most requests waiting at once (0 = unlimited)
+    max_pending: AtomicUsize,
+    /// Listeners told about each newly queued request
+    watchers: Mutex<Vec<mpsc::UnboundedSender<SigningRequest>>>,
+}
+
+impl ApprovalQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `verifier` to confirm the user's presence after each approval
+    pub fn with_presence(mut self, verifier: Option<Arc<dyn PresenceVerifier>>) -> Self {
+        self.presence = verifier;
+        self
+    }
+
+    /// Refuse new requests while `max` are already waiting (0 = unlimited)
+    pub fn with_max_pending(self, max: usize) -> Self {
+        self.set_max_pending(max);
+        self
+    }
+
+    /// Change the cap on waiting requests (e.g. after a config reload)
+    pub fn set_max_pending(&self, max: usize) {
+        self.max_pending.store(max, Ordering::Relaxed);
+    }
+
+    /// Add a request to the queue and return the channel the decision arrives on
+    pub fn submit(&self, request: SigningRequest) -> Result<oneshot::Receiver<bool>> {
+        let mut pending = self.pending.lock().unwrap();
+        let max_pending = self.max_pending.load(Ordering::Relaxed);
+        if max_pending > 0 && pending.len() >= max_pending {
+            tracing::warn!("Approval queue full, refusing request from {}", request.app_id);
+            return Err(SignerError::SignerBusy);
+        }
+        let (responder, decision) = oneshot::channel();
+        self.watchers.lock().unwrap().retain(|watcher| watcher.send(request.clone()).is_ok());
+        pending.push(PendingApproval { request, responder });
+        Ok(decision)
+    }
+
+    /// Receive each request as it is queued
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<SigningRequest> {
+        let (watcher, queued) = mpsc::unbounded_channel();
+        self.watchers.lock().unwrap().push(watcher);
+        queued
+    }
+
+    /// Submit a request and wait for the user's decision
+    ///
+    /// On approval, returns the key the user picked for this request, if
+    /// they overrode the requested one.
+    pub async fn request_approval(&self, request: SigningRequest, timeout: Duration) -> Result<Option<String>> {
+        if request.origin == RequestOrigin::Internal || self.has_session_grant(&request.app_id, Utc::now()) {
+            return Ok(None);
+        }
+
+        let id = request.id.clone();
+        let presence = self.presence.clone().map(|verifier| (verifier, request.clone()));
+        let decision = self.submit(request)?;
+
+        match tokio::time::timeout(timeout, decision).await {
+            Ok(Ok(true)) => {
+                let chosen_key = self.chosen_keys.lock().unwrap().remove(&id);
+                if let Some((verifier, request)) = presence {
+                    Self::confirm_presence(verifier, request).await?;
+                }
+                Ok(chosen_key)
+            }
+            Ok(Ok(false)) | Ok(Err(_)) => match self.rejection_reasons.lock().unwrap().remove(&id) {
+                Some(reason) => Err(SignerError::UserRejectedWithReason(reason)),
+                None => Err(SignerError::UserRejected),
+            },
+            Err(_) => {
+                self.remove(&id);
+                Err(SignerError::Timeout)
+            }
+        }
+    }
+
+    /// Run the (possibly blocking) presence check for an approved request
+    async fn confirm_presence(verifier: Arc<dyn PresenceVerifier>, request: SigningRequest) -> Result<()> {
+        let confirmed = tokio::task::spawn_blocking(move || verifier.verify(&request))
+            .await
+            .map_err(|e| SignerError::PresenceCheckFailed(e.to_string()))??;
+        if confirmed {
+            Ok(())
+        } else {
+            Err(SignerError::PresenceCheckFailed("not confirmed".into()))
+        }
+    }
+
+    /// Requests currently waiting, oldest first
+    pub fn pending(&self) -> Vec<SigningRequest> {
+        self.pending.lock().unwrap().iter().map(|p| p.request.clone()).collect()
+    }
+
+    /// Number of requests currently waiting
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Approve or reject a pending request. Returns false if it wasn't found.
+    pub fn resolve(&self, id: &str, approved: bool) -> bool {
+        match self.remove(id) {
+            Some(pending) => pending.responder.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Reject a pending request, telling the app why. The reason is trimmed
+    /// and shortened; an empty reason is a plain rejection.
+    pub fn reject_with_reason(&self, id: &str, reason: &str) -> bool {
+        let Some(pending) = self.remove(id) else {
+            return false;
+        };
+        let reason: String = reason.trim().chars().take(MAX_REASON_CHARS).collect();
+        if !reason.is_empty() {
+            self.rejection_reasons.lock().unwrap().insert(id.to_string(), reason);
+        }
+        if pending.responder.send(false).is_err() {
+            self.rejection_reasons.lock().unwrap().remove(id);
+            return false;
+        }
+        true
+    }
+
+    /// Approve a pending request, signing it with `key_id` (name, npub or hex)
+    /// instead of the requested key. The active key is left unchanged.
+    pub fn approve_with_key(&self, id: &str, key_id: &str) -> bool {
+        let Some(pending) = self.remove(id) else {
+            return false;
+        };
+        self.chosen_keys.lock().unwrap().insert(id.to_string(), key_id.to_string());
+        if pending.responder.send(true).is_err() {
+            self.chosen_keys.lock().unwrap().remove(id);
+            return false;
+        }
+        true
+    }
+
+    /// Approve a pending request and auto-approve the same app until the grant expires
+    pub fn approve_for_session(&self, id: &str, length: Duration) -> bool {
+        let Some(pending) = self.remove(id) else {
+            return false;
+        };
+        self.grant_session(&pending.request.app_id, length, Utc::now());
+        pending.responder.send(true).is_ok()
+    }
+
+    /// Auto-approve an app's requests for `length` from `now`
+    pub fn grant_session(&self, app_id: &str, length: Duration, now: DateTime<Utc>) {
+        let expiry = now + chrono::Duration::from_std(length).unwrap_or(chrono::Duration::zero());
+        self.session_grants.lock().unwrap().insert(app_id.to_string(), expiry);
+    }
+
+    /// Whether the app has an unexpired session grant, dropping it once expired
+    pub fn has_session_grant(&self, app_id: &str, now: DateTime<Utc>) -> bool {
+        let mut grants = self.session_grants.lock().unwrap();
+        match grants.get(app_id) {
+            Some(expiry) if *expiry > now => true,
+            Some(_) => {
+                grants.remove(app_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&self, id: &str) -> Option<PendingApproval> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(|p| p.request.id == id)?;
+        Some(pending.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::KeyManager;
+    use crate::permissions::RequestType;
+    use crate::signing::{SigningEngine, SigningPayload, SigningResultData, UnsignedEventData};
+    use nostr::{Event, JsonUtil, Keys};
+
+    fn decrypt_request(id: &str) -> SigningRequest {
+        request_from("messenger", id)
+    }
+
+    fn request_from(app_id: &str, id: &str) -> SigningRequest {
+        SigningRequest {
+            id: id.to_string(),
+            request_type: RequestType::Nip44Decrypt,
+            app_id: app_id.to_string(),
+            app_name: None,
+            key_id: None,
+            payload: SigningPayload::Decrypt {
+                ciphertext: "ciphertext".to_string(),
+                sender_pubkey: "sender".to_string(),
+            },
+            timestamp: chrono::Utc::now(),
+            warnings: Vec::new(),
+            origin: RequestOrigin::External,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approval_and_rejection() {
+        let queue = Arc::new(ApprovalQueue::new());
+
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { queue.request_approval(decrypt_request("a"), Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(queue.resolve("a", true));
+        assert!(waiter.await.unwrap().is_ok());
+
+        let rejected = queue.submit(decrypt_request("b")).unwrap();
+        assert!(queue.resolve("b", false));
+        assert!(!rejected.await.unwrap());
+        assert!(!queue.resolve("missing", true));
+    }
+
+    #[tokio::test]
+    async fn test_approve_with_different_key() {
+        let main = Keys::generate();
+        let alt = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", main.clone());
+        km.add_test_key("alt", alt.clone());
+        let km = Arc::new(tokio::sync::Mutex::new(km));
+        let engine = SigningEngine::new(Arc::clone(&km));
+        let queue = Arc::new(ApprovalQueue::new());
+
+        let event_data = UnsignedEventData {
+            kind: 1,
+            content: "wrong identity?".into(),
+            tags: Vec::new(),
+            created_at: None,
+            relay_hints: Vec::new(),
+        };
+        let sign_request = |id: &str| SigningRequest {
+            request_type: RequestType::SignEvent,
+            payload: SigningPayload::Event(event_data.clone()),
+            ..decrypt_request(id)
+        };
+        let signer_of = |result: SigningResultData| match result {
+            SigningResultData::Event { event_json, .. } => Event::from_json(&event_json).unwrap().pubkey,
+            other => panic!("unexpected result {:?}", other),
+        };
+
+        // The user picks "alt" in the approval dialog
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            let request = sign_request("pick-alt");
+            tokio::spawn(async move { queue.request_approval(request, Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(queue.approve_with_key("pick-alt", "alt"));
+        let chosen = waiter.await.unwrap().unwrap();
+        assert_eq!(chosen.as_deref(), Some("alt"));
+
+        let signed = engine.sign_event(&event_data, chosen.as_deref()).await.unwrap();
+        assert_eq!(signer_of(signed), alt.public_key());
+        assert_eq!(km.lock().await.get_active_key_name(), Some("main"));
+
+        // A plain approval of the next request uses the active key again
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            let request = sign_request("plain");
+            tokio::spawn(async move { queue.request_approval(request, Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(queue.resolve("plain", true));
+        let chosen = waiter.await.unwrap().unwrap();
+        assert_eq!(chosen, None);
+        let signed = engine.sign_event(&event_data, chosen.as_deref()).await.unwrap();
+        assert_eq!(signer_of(signed), main.public_key());
+    }
+
+    /// Presence verifier with a fixed answer, counting how often it's asked
+    struct MockPresence {
+        present: bool,
+        checks: std::sync::atomic::AtomicUsize,
+    }
+
+    impl PresenceVerifier for MockPresence {
+        fn verify(&self, _request: &SigningRequest) -> Result<bool> {
+            self.checks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(self.present)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_presence_check_after_approval() {
+        for present in [true, false] {
+            let verifier = Arc::new(MockPresence { present, checks: Default::default() });
+            let queue = Arc::new(ApprovalQueue::new().with_presence(Some(verifier.clone())));
+
+            let waiter = {
+                let queue = Arc::clone(&queue);
+                tokio::spawn(async move { queue.request_approval(decrypt_request("touch"), Duration::from_secs(5)).await })
+            };
+            while queue.pending().is_empty() {
+                tokio::task::yield_now().await;
+            }
+            // Not asked before the user decides
+            assert_eq!(verifier.checks.load(std::sync::atomic::Ordering::SeqCst), 0);
+            assert!(queue.resolve("touch", true));
+
+            let result = waiter.await.unwrap();
+            assert_eq!(verifier.checks.load(std::sync::atomic::Ordering::SeqCst), 1);
+            if present {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(result, Err(SignerError::PresenceCheckFailed(_))));
+            }
+        }
+
+        // Rejections never reach the verifier
+        let verifier = Arc::new(MockPresence { present: true, checks: Default::default() });
+        let queue = Arc::new(ApprovalQueue::new().with_presence(Some(verifier.clone())));
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { queue.request_approval(decrypt_request("no"), Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(queue.resolve("no", false));
+        assert!(matches!(waiter.await.unwrap(), Err(SignerError::UserRejected)));
+        assert_eq!(verifier.checks.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_see_queued_requests() {
+        let queue = ApprovalQueue::new();
+        let mut queued = queue.subscribe();
+        let dropped = queue.subscribe();
+        drop(dropped);
+
+        let _decision = queue.submit(request_from("gossip", "a")).unwrap();
+        let request = queued.recv().await.unwrap();
+        assert_eq!(request.id, "a");
+        assert_eq!(request.summary(), "gossip: NIP-44 Decrypt");
+        // Closed subscriptions are dropped
+        assert_eq!(queue.watchers.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_refuses_overflow() {
+        let queue = ApprovalQueue::new().with_max_pending(2);
+        let first = queue.submit(decrypt_request("a")).unwrap();
+        let second = queue.submit(decrypt_request("b")).unwrap();
+
+        assert!(matches!(queue.submit(decrypt_request("c")), Err(SignerError::SignerBusy)));
+        assert_eq!(queue.pending_count(), 2);
+
+        // The queued requests can still be answered, which makes room again
+        assert!(queue.resolve("a", true));
+        assert!(queue.resolve("b", false));
+        assert!(first.await.unwrap());
+        assert!(!second.await.unwrap());
+        assert!(queue.submit(decrypt_request("c")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_approval_timeout_clears_queue() {
+        let queue = ApprovalQueue::new();
+        let result = queue.request_approval(decrypt_request("slow"), Duration::from_millis(20)).await;
+
+        assert!(matches!(result, Err(SignerError::Timeout)));
+        assert!(queue.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_grant_auto_approves_until_expiry() {
+        let queue = Arc::new(ApprovalQueue::new());
+        let hour = Duration::from_secs(3600);
+
+        // First request is approved "for 1 hour"
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { queue.request_approval(decrypt_request("first"), Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(queue.approve_for_session("first", hour));
+        assert!(waiter.await.unwrap().is_ok());
+
+        // Within the window the app's requests never reach the queue
+        let result = queue.request_approval(decrypt_request("second"), Duration::from_millis(20)).await;
+        assert!(result.is_ok());
+        assert!(queue.pending().is_empty());
+
+        // Other apps still prompt
+        let other = queue.request_approval(request_from("other", "third"), Duration::from_millis(20)).await;
+        assert!(matches!(other, Err(SignerError::Timeout)));
+
+        // After expiry the app prompts again
+        let later = Utc::now() + chrono::Duration::minutes(61);
+        assert!(!queue.has_session_grant("messenger", later));
+        let expired = queue.request_approval(decrypt_request("fourth"), Duration::from_millis(20)).await;
+        assert!(matches!(expired, Err(SignerError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_internal_requests_skip_approval() {
+        let queue = ApprovalQueue::new();
+
+        let internal = SigningRequest {
+            origin: RequestOrigin::Internal,
+            ..decrypt_request("ui")
+        };
+        assert!(queue.request_approval(internal, Duration::from_millis(20)).await.is_ok());
+        assert!(queue.pending().is_empty());
+
+        // The same request from outside waits for the user
+        let external = queue.request_approval(decrypt_request("app"), Duration::from_millis(20)).await;
+        assert!(matches!(external, Err(SignerError::Timeout)));
+
+        // The marker can't be smuggled in through a serialized request
+        let mut json = serde_json::to_value(decrypt_request("forged")).unwrap();
+        json["origin"] = serde_json::json!("Internal");
+        let forged: SigningRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(forged.origin, RequestOrigin::External);
+    }
+}
@@ -0,0 +1,433 @@
+//! Audit log of handled requests
+//!
+//! Every request the signer answers is appended to a JSON-lines file in the
+//! data directory. The log doubles as the store for per-app daily quotas, so
+//! quota usage survives restarts.
+//!
+//! Refusals are counted per app over a rolling window; an app refused
+//! unusually often raises an alert so the user can review or revoke it.
+//!
+//! A single app's slice of the log can be exported as a redacted "session
+//! log" that users can attach to bug reports.
+//!
+//! Per-kind and per-app statistics are kept up to date as entries are
+//! recorded (see [`AuditStats`]).
+
+use crate::config::Config;
+use crate::error::{Result, SignerError};
+use crate::permissions::RequestType;
+use crate::stats::AuditStats;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+pub(crate) const AUDIT_FILE: &str = "audit_log.jsonl";
+
+/// Directory (under the data dir) session logs are exported to
+pub(crate) const EXPORT_DIR: &str = "exports";
+
+/// Length of the rolling quota window
+const QUOTA_WINDOW_HOURS: i64 = 24;
+
+/// A single audited request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the request was handled
+    pub timestamp: DateTime<Utc>,
+    /// Requesting application ID
+    pub app_id: String,
+    /// Type of request
+    pub request_type: RequestType,
+    /// Event kind (sign requests only)
+    #[serde(default)]
+    pub event_kind: Option<u16>,
+    /// Whether the request was carried out
+    pub approved: bool,
+    /// Why the request was refused (e.g. the reason the user gave)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(app_id: &str, request_type: RequestType, event_kind: Option<u16>, approved: bool) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            app_id: app_id.to_string(),
+            request_type,
+            event_kind,
+            approved,
+            reason: None,
+        }
+    }
+
+    /// Entry for a refused request
+    pub fn refused(app_id: &str, request_type: RequestType, event_kind: Option<u16>, reason: &str) -> Self {
+        Self {
+            reason: Some(reason.to_string()),
+            ..Self::new(app_id, request_type, event_kind, false)
+        }
+    }
+}
+
+/// An app was refused more often than the alert threshold allows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenialAlert {
+    pub app_id: String,
+    /// Refusals within the window, including the one that raised the alert
+    pub denials: u32,
+    pub window_mins: u64,
+}
+
+/// Append-only audit log persisted to disk
+pub struct AuditLog {
+    path: PathBuf,
+    entries: Vec<AuditEntry>,
+    stats: AuditStats,
+}
+
+impl AuditLog {
+    /// Load the audit log from the default data directory
+    pub async fn load() -> Result<Self> {
+        Self::load_from(Config::data_dir()?.join(AUDIT_FILE)).await
+    }
+
+    /// Load the audit log from a specific file, skipping malformed lines
+    pub async fn load_from(path: PathBuf) -> Result<Self> {
+        let mut entries = Vec::new();
+        if path.exists() {
+            let content = fs::read_to_string(&path).await?;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str(line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => warn!("Skipping malformed audit entry: {}", e),
+                }
+            }
+        }
+        let stats = AuditStats::from_entries(&entries);
+        Ok(Self { path, entries, stats })
+    }
+
+    /// Append an entry and persist it
+    pub async fn record(&mut self, entry: AuditEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+
+        self.stats.add(&entry);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// All recorded entries, oldest first
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Per-kind and per-app statistics over all entries
+    pub fn stats(&self) -> &AuditStats {
+        &self.stats
+    }
+
+    /// Entries recorded for a single app, oldest first
+    pub fn entries_for<'a>(&'a self, app_id: &'a str) -> impl Iterator<Item = &'a AuditEntry> + 'a {
+        self.entries.iter().filter(move |e| e.app_id == app_id)
+    }
+
+    /// Plain-text session log for one app, safe to share in a bug report
+    ///
+    /// Only the app's own entries are included. Secret keys are removed and
+    /// public keys truncated wherever they appear (e.g. in bunker app IDs).
+    pub fn session_report(&self, app_id: &str, app_name: Option<&str>, now: DateTime<Utc>) -> String {
+        let entries: Vec<_> = self.entries_for(app_id).collect();
+        let approved = entries.iter().filter(|e| e.approved).count();
+
+        let mut report = String::from("Pleb Signer session log\n");
+        report.push_str(&format!("Version: {}\n", env!("CARGO_PKG_VERSION")));
+        if let Some(name) = app_name {
+            report.push_str(&format!("App: {}\n", redact(name)));
+        }
+        report.push_str(&format!("App ID: {}\n", redact(app_id)));
+        report.push_str(&format!("Generated: {}\n", now.to_rfc3339()));
+        report.push_str(&format!(
+            "Requests: {} ({} approved, {} denied)\n\n",
+            entries.len(),
+            approved,
+            entries.len() - approved
+        ));
+
+        for entry in entries {
+            let kind = entry.event_kind.map(|k| format!(" kind={}", k)).unwrap_or_default();
+            let reason = entry.reason.as_deref().map(|r| format!(" ({})", redact(r))).unwrap_or_default();
+            report.push_str(&format!(
+                "{} {}{} {}{}\n",
+                entry.timestamp.to_rfc3339(),
+                entry.request_type.as_str(),
+                kind,
+                if entry.approved { "approved" } else { "denied" },
+                reason
+            ));
+        }
+        report
+    }
+
+    /// Write an app's session log to the exports directory, returning its path
+    pub async fn export_session_log(&self, app_id: &str, app_name: Option<&str>) -> Result<PathBuf> {
+        let now = Utc::now();
+        let dir = Config::data_dir()?.join(EXPORT_DIR);
+        fs::create_dir_all(&dir).await?;
+
+        let slug: String = app_name
+            .unwrap_or("app")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        let path = dir.join(format!("session-log-{}-{}.txt", slug, now.format("%Y%m%d-%H%M%S")));
+        fs::write(&path, self.session_report(app_id, app_name, now)).await?;
+        Ok(path)
+    }
+
+    /// Number of events signed for an app within the rolling quota window
+    pub fn signatures_in_window(&self, app_id: &str, now: DateTime<Utc>) -> u32 {
+        let window_start = now - Duration::hours(QUOTA_WINDOW_HOURS);
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.app_id == app_id
+                    && e.approved
+                    && e.request_type == RequestType::SignEvent
+                    && e.timestamp > window_start
+            })
+            .count() as u32
+    }
+
+    /// Number of refused requests for an app since `now - window`
+    pub fn denials_in_window(&self, app_id: &str, window: Duration, now: DateTime<Utc>) -> u32 {
+        let window_start = now - window;
+        self.entries
+            .iter()
+            .filter(|e| e.app_id == app_id && !e.approved && e.timestamp > window_start)
+            .count() as u32
+    }
+
+    /// Record a refused request, calling `on_alert` once the app goes over
+    /// `threshold` refusals within `window_mins` (threshold 0 = never)
+    ///
+    /// The alert fires when the count first exceeds the threshold, not on
+    /// every refusal after that. Counts come from the persisted log, so they
+    /// survive restarts.
+    pub async fn record_refusal(
+        &mut self,
+        entry: AuditEntry,
+        threshold: u32,
+        window_mins: u64,
+        on_alert: impl FnOnce(DenialAlert),
+    ) -> Result<()> {
+        let app_id = entry.app_id.clone();
+        let now = entry.timestamp;
+        self.record(entry).await?;
+
+        if threshold == 0 {
+            return Ok(());
+        }
+        let window = Duration::minutes(window_mins.min(i64::MAX as u64) as i64);
+        let denials = self.denials_in_window(&app_id, window, now);
+        if denials == threshold + 1 {
+            on_alert(DenialAlert { app_id, denials, window_mins });
+        }
+        Ok(())
+    }
+
+    /// Remaining signatures for an app (None = unlimited)
+    pub fn remaining_quota(&self, app_id: &str, quota: Option<u32>, now: DateTime<Utc>) -> Option<u32> {
+        quota.map(|q| q.saturating_sub(self.signatures_in_window(app_id, now)))
+    }
+
+    /// Fail with `PermissionDenied` if the app has used up its daily quota
+    pub fn check_daily_quota(&self, app_id: &str, quota: Option<u32>, now: DateTime<Utc>) -> Result<()> {
+        match self.remaining_quota(app_id, quota, now) {
+            Some(0) => Err(SignerError::PermissionDenied(format!(
+                "Daily signing quota exhausted for {}",
+                app_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Remove secret keys and truncate public keys in free text
+fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() {
+            token.push(c);
+            continue;
+        }
+        out.push_str(&redact_token(&token));
+        token.clear();
+        out.push(c);
+    }
+    out.pop();
+    out
+}
+
+fn redact_token(token: &str) -> String {
+    let lower = token.to_ascii_lowercase();
+    if lower.starts_with("nsec1") || lower.starts_with("ncryptsec1") {
+        return "[redacted]".to_string();
+    }
+    let is_hex_key = token.len() == 64 && token.chars().all(|c| c.is_ascii_hexdigit());
+    let is_bech32_key = ["npub1", "nprofile1"].iter().any(|p| lower.starts_with(p)) && token.len() > 20;
+    if is_hex_key || is_bech32_key {
+        format!("{}…{}", &token[..8], &token[token.len() - 4..])
+    } else {
+        token.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::prelude::{Keys, ToBech32};
+
+    fn signed_at(app_id: &str, timestamp: DateTime<Utc>) -> AuditEntry {
+        AuditEntry {
+            timestamp,
+            ..AuditEntry::new(app_id, RequestType::SignEvent, Some(1), true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quota_consumption() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::load_from(dir.path().join(AUDIT_FILE)).await.unwrap();
+        let now = Utc::now();
+
+        log.record(signed_at("app1", now)).await.unwrap();
+        assert_eq!(log.remaining_quota("app1", Some(2), now), Some(1));
+        assert!(log.check_daily_quota("app1", Some(2), now).is_ok());
+
+        log.record(signed_at("app1", now)).await.unwrap();
+        assert_eq!(log.remaining_quota("app1", Some(2), now), Some(0));
+        assert!(log.check_daily_quota("app1", Some(2), now).is_err());
+
+        // Other apps and unlimited quotas are unaffected
+        assert!(log.check_daily_quota("app2", Some(2), now).is_ok());
+        assert!(log.check_daily_quota("app1", None, now).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_quota_resets_after_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::load_from(dir.path().join(AUDIT_FILE)).await.unwrap();
+        let now = Utc::now();
+
+        log.record(signed_at("app1", now - Duration::hours(25))).await.unwrap();
+        log.record(signed_at("app1", now - Duration::hours(1))).await.unwrap();
+
+        assert_eq!(log.signatures_in_window("app1", now), 1);
+        assert!(log.check_daily_quota("app1", Some(1), now).is_err());
+        assert!(log.check_daily_quota("app1", Some(1), now + Duration::hours(24)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_denial_threshold_raises_alert() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::load_from(dir.path().join(AUDIT_FILE)).await.unwrap();
+        let now = Utc::now();
+        let refused_at = |timestamp| AuditEntry {
+            timestamp,
+            ..AuditEntry::refused("spammy", RequestType::SignEvent, Some(1), "User rejected the request")
+        };
+        let mut alerts = Vec::new();
+
+        // Outside the window: doesn't count
+        log.record_refusal(refused_at(now - Duration::minutes(30)), 3, 10, |a| alerts.push(a)).await.unwrap();
+        for _ in 0..3 {
+            log.record_refusal(refused_at(now), 3, 10, |a| alerts.push(a)).await.unwrap();
+        }
+        assert!(alerts.is_empty());
+
+        // Going over the threshold alerts once
+        log.record_refusal(refused_at(now), 3, 10, |a| alerts.push(a)).await.unwrap();
+        log.record_refusal(refused_at(now), 3, 10, |a| alerts.push(a)).await.unwrap();
+        assert_eq!(alerts, [DenialAlert { app_id: "spammy".into(), denials: 4, window_mins: 10 }]);
+
+        // Other apps and a disabled threshold never alert
+        log.record_refusal(AuditEntry::refused("other", RequestType::Nip44Decrypt, None, "denied"), 3, 10, |a| alerts.push(a)).await.unwrap();
+        log.record_refusal(refused_at(now), 0, 10, |a| alerts.push(a)).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quota_persists_across_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_FILE);
+        let now = Utc::now();
+
+        {
+            let mut log = AuditLog::load_from(path.clone()).await.unwrap();
+            log.record(signed_at("app1", now)).await.unwrap();
+            log.record(signed_at("app1", now)).await.unwrap();
+        }
+
+        let reloaded = AuditLog::load_from(path).await.unwrap();
+        assert_eq!(reloaded.entries().len(), 2);
+        assert_eq!(reloaded.remaining_quota("app1", Some(3), now), Some(1));
+        assert_eq!(reloaded.stats().summary(crate::stats::StatsRange::Day, now, 5).requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_session_report_only_includes_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::load_from(dir.path().join(AUDIT_FILE)).await.unwrap();
+        let now = Utc::now();
+
+        log.record(signed_at("app1", now)).await.unwrap();
+        log.record(AuditEntry::new("app2", RequestType::Nip44Decrypt, None, true)).await.unwrap();
+        log.record(AuditEntry::new("app1", RequestType::Nip04Decrypt, None, false)).await.unwrap();
+
+        assert_eq!(log.entries_for("app1").count(), 2);
+        let report = log.session_report("app1", Some("App One"), now);
+        assert!(report.contains("Requests: 2 (1 approved, 1 denied)"));
+        assert!(report.contains("sign_event kind=1 approved"));
+        assert!(report.contains("nip04_decrypt denied"));
+        assert!(!report.contains("nip44_decrypt"));
+        assert!(!report.contains("app2"));
+    }
+
+    #[tokio::test]
+    async fn test_session_report_redacts_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = AuditLog::load_from(dir.path().join(AUDIT_FILE)).await.unwrap();
+        let now = Utc::now();
+        let keys = Keys::generate();
+        let hex = keys.public_key().to_hex();
+        let npub = keys.public_key().to_bech32().unwrap();
+        let nsec = keys.secret_key().to_bech32().unwrap();
+
+        let app_id = format!("bunker:{}", hex);
+        log.record(signed_at(&app_id, now)).await.unwrap();
+        let report = log.session_report(&app_id, Some(&format!("{} {}", npub, nsec)), now);
+
+        assert!(!report.contains(&hex));
+        assert!(!report.contains(&npub));
+        assert!(!report.contains(&nsec));
+        assert!(report.contains(&format!("bunker:{}…{}", &hex[..8], &hex[60..])));
+        assert!(report.contains(&format!("{}…", &npub[..8])));
+        assert!(report.contains("[redacted]"));
+        assert!(report.contains("Requests: 1 (1 approved, 0 denied)"));
+    }
+}
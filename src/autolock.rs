@@ -0,0 +1,127 @@
+//! Inactivity lock with an advance warning
+//!
+//! The signer locks itself after `lock_timeout_mins` without activity. A
+//! configurable number of seconds before that, a warning is raised so the
+//! user can choose to stay unlocked instead of being locked mid-task.
+
+use std::time::{Duration, Instant};
+
+/// What the caller should do after polling the timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Nothing to do
+    None,
+    /// Show the "locking soon" warning (raised once per idle period)
+    Warn { secs_left: u64 },
+    /// The timeout has passed: lock now
+    Lock,
+}
+
+/// Tracks activity and decides when to warn and lock
+///
+/// Time is passed in by the caller so the sequence can be driven by tests.
+#[derive(Debug)]
+pub struct InactivityTimer {
+    /// Idle time before locking (None = never lock)
+    timeout: Option<Duration>,
+    /// How long before locking to warn
+    warn_before: Duration,
+    last_activity: Instant,
+    warned: bool,
+    locked: bool,
+}
+
+impl InactivityTimer {
+    pub fn new(timeout_mins: u64, warn_before_secs: u64, now: Instant) -> Self {
+        Self {
+            timeout: (timeout_mins > 0).then(|| Duration::from_secs(timeout_mins * 60)),
+            warn_before: Duration::from_secs(warn_before_secs),
+            last_activity: now,
+            warned: false,
+            locked: false,
+        }
+    }
+
+    /// Change the timeout and warning, keeping the time of the last activity
+    pub fn reconfigure(&mut self, timeout_mins: u64, warn_before_secs: u64) {
+        self.timeout = (timeout_mins > 0).then(|| Duration::from_secs(timeout_mins * 60));
+        self.warn_before = Duration::from_secs(warn_before_secs);
+        self.warned = false;
+    }
+
+    /// Record activity (a request, or "stay unlocked"), restarting the countdown
+    pub fn touch(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.warned = false;
+        self.locked = false;
+    }
+
+    /// Check the timer; returns each warning and the lock only once
+    pub fn poll(&mut self, now: Instant) -> IdleAction {
+        let Some(timeout) = self.timeout else {
+            return IdleAction::None;
+        };
+        if self.locked {
+            return IdleAction::None;
+        }
+
+        let idle = now.saturating_duration_since(self.last_activity);
+        if idle >= timeout {
+            self.locked = true;
+            return IdleAction::Lock;
+        }
+
+        let left = timeout - idle;
+        if !self.warned && !self.warn_before.is_zero() && left <= self.warn_before {
+            self.warned = true;
+            return IdleAction::Warn { secs_left: left.as_secs_f64().ceil() as u64 };
+        }
+        IdleAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warns_then_locks() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut timer = InactivityTimer::new(1, 30, start);
+
+        assert_eq!(timer.poll(at(29)), IdleAction::None);
+        assert_eq!(timer.poll(at(30)), IdleAction::Warn { secs_left: 30 });
+        // The warning is only raised once
+        assert_eq!(timer.poll(at(45)), IdleAction::None);
+        assert_eq!(timer.poll(at(60)), IdleAction::Lock);
+        assert_eq!(timer.poll(at(61)), IdleAction::None);
+    }
+
+    #[test]
+    fn test_stay_unlocked_resets_countdown() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut timer = InactivityTimer::new(1, 30, start);
+
+        assert_eq!(timer.poll(at(40)), IdleAction::Warn { secs_left: 20 });
+        // User clicks "stay unlocked"
+        timer.touch(at(50));
+        assert_eq!(timer.poll(at(60)), IdleAction::None);
+        assert_eq!(timer.poll(at(79)), IdleAction::None);
+        assert_eq!(timer.poll(at(80)), IdleAction::Warn { secs_left: 30 });
+        assert_eq!(timer.poll(at(110)), IdleAction::Lock);
+    }
+
+    #[test]
+    fn test_disabled_timer_never_locks() {
+        let start = Instant::now();
+        let mut timer = InactivityTimer::new(0, 30, start);
+        assert_eq!(timer.poll(start + Duration::from_secs(86_400)), IdleAction::None);
+
+        // No lead time: lock without warning
+        let mut silent = InactivityTimer::new(1, 0, start);
+        assert_eq!(silent.poll(start + Duration::from_secs(59)), IdleAction::None);
+        assert_eq!(silent.poll(start + Duration::from_secs(60)), IdleAction::Lock);
+    }
+}
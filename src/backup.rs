@@ -0,0 +1,197 @@
+//! Backup and restore of the full app state
+//!
+//! Bundles the config file, key metadata and (only when a password is given)
+//! NIP-49 encrypted secret keys into a single tar archive, so a setup can be
+//! moved to a new machine in one step.
+
+use crate::error::{Result, SignerError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use tokio::fs;
+
+const CONFIG_ENTRY: &str = "config.toml";
+const METADATA_ENTRY: &str = "keys_metadata.json";
+const KEYS_ENTRY: &str = "keys.json";
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Describes the archive contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    includes_keys: bool,
+}
+
+/// In-memory contents of a state archive
+#[derive(Debug, Clone, Default)]
+pub struct StateBackup {
+    /// Raw config file (TOML)
+    pub config: Option<String>,
+    /// Raw key metadata file (JSON)
+    pub metadata: Option<String>,
+    /// Key name -> ncryptsec (only present when keys were opted in)
+    pub encrypted_keys: Option<HashMap<String, String>>,
+}
+
+impl StateBackup {
+    /// Read the config and metadata files that exist on disk
+    pub async fn collect(config_path: &Path, metadata_path: &Path) -> Result<Self> {
+        let config = if config_path.exists() {
+            Some(fs::read_to_string(config_path).await?)
+        } else {
+            None
+        };
+        let metadata = if metadata_path.exists() {
+            Some(fs::read_to_string(metadata_path).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            config,
+            metadata,
+            encrypted_keys: None,
+        })
+    }
+
+    /// Write the backup as a tar archive
+    pub fn write_archive(&self, path: &Path) -> Result<()> {
+        let manifest = Manifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now(),
+            includes_keys: self.encrypted_keys.is_some(),
+        };
+
+        let file = std::fs::File::create(path)?;
+        let mut builder = tar::Builder::new(file);
+
+        append_entry(&mut builder, MANIFEST_ENTRY, &serde_json::to_string_pretty(&manifest)?)?;
+        if let Some(ref config) = self.config {
+            append_entry(&mut builder, CONFIG_ENTRY, config)?;
+        }
+        if let Some(ref metadata) = self.metadata {
+            append_entry(&mut builder, METADATA_ENTRY, metadata)?;
+        }
+        if let Some(ref keys) = self.encrypted_keys {
+            append_entry(&mut builder, KEYS_ENTRY, &serde_json::to_string_pretty(keys)?)?;
+        }
+
+        builder.finish()?;
+        restrict_permissions(path)?;
+        Ok(())
+    }
+
+    /// Read a backup from a tar archive
+    pub fn read_archive(path: &Path) -> Result<Self> {
+        let mut archive = tar::Archive::new(std::fs::File::open(path)?);
+        let mut backup = StateBackup::default();
+        let mut has_manifest = false;
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+
+            match name.as_str() {
+                MANIFEST_ENTRY => has_manifest = true,
+                CONFIG_ENTRY => backup.config = Some(content),
+                METADATA_ENTRY => backup.metadata = Some(content),
+                KEYS_ENTRY => backup.encrypted_keys = Some(serde_json::from_str(&content)?),
+                other => tracing::warn!("Ignoring unexpected backup entry: {}", other),
+            }
+        }
+
+        if !has_manifest {
+            return Err(SignerError::InvalidRequest("Not a Pleb Signer state archive".into()));
+        }
+        Ok(backup)
+    }
+
+    /// Write the config and metadata files into place
+    pub async fn restore(&self, config_path: &Path, metadata_path: &Path) -> Result<()> {
+        if let Some(ref config) = self.config {
+            write_file(config_path, config).await?;
+        }
+        if let Some(ref metadata) = self.metadata {
+            write_file(metadata_path, metadata).await?;
+        }
+        Ok(())
+    }
+}
+
+fn append_entry<W: std::io::Write>(builder: &mut tar::Builder<W>, name: &str, content: &str) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, name, content.as_bytes())?;
+    Ok(())
+}
+
+async fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn restrict_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn restrict_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip_into_fresh_data_dir() {
+        let old_machine = tempfile::tempdir().unwrap();
+        let new_machine = tempfile::tempdir().unwrap();
+        let archive = old_machine.path().join("backup.tar");
+
+        let config_path = old_machine.path().join("config/config.toml");
+        let metadata_path = old_machine.path().join("data/keys_metadata.json");
+        write_file(&config_path, "[general]\nauto_start = true\n").await.unwrap();
+        write_file(&metadata_path, r#"{"keys":{},"active_key":null}"#).await.unwrap();
+
+        let mut backup = StateBackup::collect(&config_path, &metadata_path).await.unwrap();
+        backup.encrypted_keys = Some(HashMap::from([("main".to_string(), "ncryptsec1test".to_string())]));
+        backup.write_archive(&archive).unwrap();
+
+        let restored = StateBackup::read_archive(&archive).unwrap();
+        let new_config = new_machine.path().join("config/config.toml");
+        let new_metadata = new_machine.path().join("data/keys_metadata.json");
+        restored.restore(&new_config, &new_metadata).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&new_config).await.unwrap(), "[general]\nauto_start = true\n");
+        assert_eq!(fs::read_to_string(&new_metadata).await.unwrap(), r#"{"keys":{},"active_key":null}"#);
+        assert_eq!(restored.encrypted_keys.unwrap()["main"], "ncryptsec1test");
+    }
+
+    #[tokio::test]
+    async fn test_keys_excluded_unless_opted_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("backup.tar");
+        let config_path = dir.path().join("config.toml");
+        write_file(&config_path, "").await.unwrap();
+
+        let backup = StateBackup::collect(&config_path, &dir.path().join("missing.json")).await.unwrap();
+        backup.write_archive(&archive).unwrap();
+
+        let restored = StateBackup::read_archive(&archive).unwrap();
+        assert!(restored.encrypted_keys.is_none());
+        assert!(restored.metadata.is_none());
+    }
+}
@@ -0,0 +1,130 @@
+//! Batch signing from a file, for scripts (`--sign-batch`)
+//!
+//! Signs locally without approval prompts, so the same event checks as the
+//! D-Bus API (created_at cap, unknown-kind policy) are applied to every
+//! event. A bad event is reported in its slot and the rest are still signed.
+
+use crate::config::SecurityConfig;
+use crate::error::{Result, SignerError};
+use crate::permissions::PermissionChecker;
+use crate::signing::{SigningEngine, SigningResultData, UnsignedEventData};
+use serde::Serialize;
+use std::path::Path;
+use tracing::warn;
+
+/// Outcome for one event of the batch, in input order
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem {
+    pub index: usize,
+    /// The signed event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchItem {
+    pub fn is_signed(&self) -> bool {
+        self.event.is_some()
+    }
+}
+
+/// Sign every event in a file holding a JSON array of unsigned events
+///
+/// Fails only if the file can't be read or isn't a JSON array.
+pub async fn sign_batch_file(
+    engine: &SigningEngine,
+    path: &Path,
+    key_id: Option<&str>,
+    security: &SecurityConfig,
+    now: u64,
+) -> Result<Vec<BatchItem>> {
+    let input = tokio::fs::read_to_string(path).await?;
+    let events: Vec<serde_json::Value> = serde_json::from_str(&input)
+        .map_err(|e| SignerError::InvalidRequest(format!("Expected a JSON array of events: {}", e)))?;
+
+    let mut items = Vec::with_capacity(events.len());
+    for (index, event) in events.into_iter().enumerate() {
+        let item = match sign_one(engine, event, key_id, security, now).await {
+            Ok(event) => BatchItem { index, event: Some(event), error: None },
+            Err(e) => BatchItem { index, event: None, error: Some(e) },
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+async fn sign_one(
+    engine: &SigningEngine,
+    event: serde_json::Value,
+    key_id: Option<&str>,
+    security: &SecurityConfig,
+    now: u64,
+) -> std::result::Result<serde_json::Value, String> {
+    let mut event_data: UnsignedEventData = serde_json::from_value(event)
+        .map_err(|e| format!("Invalid event: {}", e))?;
+    event_data.created_at = PermissionChecker::check_created_at(
+        event_data.created_at,
+        security.max_future_created_at_secs,
+        security.future_created_at_policy,
+        now,
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(caution) = PermissionChecker::check_event_kind(security.unknown_kind_policy, event_data.kind)
+        .map_err(|e| e.to_string())?
+    {
+        warn!("{}", caution);
+    }
+
+    match engine.sign_event(&event_data, key_id).await.map_err(|e| e.to_string())? {
+        SigningResultData::Event { event_json, .. } => serde_json::from_str(&event_json).map_err(|e| e.to_string()),
+        other => Err(format!("Unexpected signing result {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::KeyManager;
+    use nostr::{Event, JsonUtil, Keys};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_mixed_batch_signs_valid_events() {
+        let keys = Keys::generate();
+        let engine = SigningEngine::new(Arc::new(Mutex::new(KeyManager::with_test_key("main", keys.clone()))));
+        let security = SecurityConfig::default();
+        let now = 1_700_000_000;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.json");
+        std::fs::write(&path, serde_json::json!([
+            {"kind": 1, "content": "first", "tags": [], "created_at": now},
+            {"content": "no kind"},
+            "not an event",
+            {"kind": 70_000, "content": "kind out of range", "tags": [], "created_at": now},
+            {"kind": 7, "content": "+", "tags": [["e", "a".repeat(64)]], "created_at": now},
+        ]).to_string()).unwrap();
+
+        let items = sign_batch_file(&engine, &path, None, &security, now).await.unwrap();
+        let signed: Vec<usize> = items.iter().filter(|i| i.is_signed()).map(|i| i.index).collect();
+        assert_eq!(signed, [0, 4]);
+        for item in items.iter().filter(|i| i.is_signed()) {
+            let event = Event::from_json(item.event.as_ref().unwrap().to_string()).unwrap();
+            assert!(event.verify().is_ok());
+            assert_eq!(event.pubkey, keys.public_key());
+        }
+        for failed in [1, 2, 3] {
+            assert!(items[failed].error.as_deref().is_some_and(|e| !e.is_empty()), "{:?}", items[failed]);
+        }
+
+        // A missing key fails each event on its own
+        let items = sign_batch_file(&engine, &path, Some("nope"), &security, now).await.unwrap();
+        assert_eq!(items.len(), 5);
+        assert!(items.iter().all(|i| !i.is_signed()));
+
+        std::fs::write(&path, "{\"kind\": 1}").unwrap();
+        assert!(sign_batch_file(&engine, &path, None, &security, now).await.is_err());
+    }
+}
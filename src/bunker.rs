@@ -3,15 +3,101 @@
 //! This module allows Pleb Signer to act as a remote signer via NIP-46,
 //! enabling signing from any device that can connect to Nostr relays.
 
+use crate::app::AppMessage;
+use crate::audit::AuditEntry;
+use crate::config::Config;
 use crate::error::{Result, SignerError};
 use crate::keys::KeyManager;
+use crate::notify::RequestNotice;
+use crate::permissions::RequestType;
+use crate::relays::normalize_relay_url;
+use crate::signing::{BlockedKinds, SignPolicy};
 use nostr::prelude::*;
 use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex};
 use tracing::{error, info, warn};
 
+/// URL returned in `auth_url` responses while a request waits for a local unlock
+const UNLOCK_AUTH_URL: &str = "pleb-signer://unlock";
+
+/// File (in the data dir) recording how far the bunker has read
+pub(crate) const CURSOR_FILE: &str = "bunker_cursor.json";
+
+/// File (in the data dir) holding the connection secret and paired clients
+pub(crate) const PAIRING_FILE: &str = "bunker_pairing.json";
+
+/// Relays used when the active key has none of its own
+const DEFAULT_RELAYS: [&str; 2] = ["wss://relay.nsec.app", "wss://relay.damus.io"];
+
+/// Requests older than this are never replayed after a restart (seconds)
+const MAX_REPLAY_SECS: u64 = 600;
+
+/// How far before the last processed request to resubscribe, to tolerate
+/// clock skew between clients (seconds)
+const CURSOR_OVERLAP_SECS: u64 = 60;
+
+/// Prefix of the audit log app ID for NIP-46 clients
+pub const BUNKER_APP_PREFIX: &str = "bunker:";
+
+/// Audit log app ID of a NIP-46 client: its npub, so remote requests can
+/// be told apart from local apps
+pub fn bunker_app_id(client: &PublicKey) -> String {
+    format!("{}{}", BUNKER_APP_PREFIX, client.to_bech32().unwrap_or_else(|_| client.to_hex()))
+}
+
+/// How the signer introduces itself to NIP-46 clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerIdentity {
+    pub name: String,
+    pub icon_url: Option<String>,
+}
+
+impl Default for SignerIdentity {
+    fn default() -> Self {
+        Self { name: "Pleb Signer".into(), icon_url: None }
+    }
+}
+
+impl SignerIdentity {
+    /// Metadata object sent with the `connect` ack and in the bunker URI
+    pub fn metadata(&self) -> serde_json::Value {
+        let mut metadata = serde_json::json!({ "name": self.name });
+        if let Some(ref icon) = self.icon_url {
+            metadata["image"] = serde_json::json!(icon);
+        }
+        metadata
+    }
+}
+
+/// Build a `bunker://` URI, skipping relays that aren't valid websocket URLs
+fn bunker_uri(pubkey: &str, relays: &[String], secret: Option<&str>, identity: Option<&SignerIdentity>) -> Result<String> {
+    let mut params = Vec::new();
+    for relay in relays {
+        match normalize_relay_url(relay) {
+            Some(url) => params.push(format!("relay={}", urlencoding::encode(&url))),
+            None => warn!("Leaving invalid relay URL out of the bunker URI: {:?}", relay),
+        }
+    }
+    if params.is_empty() {
+        return Err(SignerError::ConfigError("No valid bunker relays configured".into()));
+    }
+
+    if let Some(secret) = secret {
+        params.push(format!("secret={}", urlencoding::encode(secret)));
+    }
+    if let Some(identity) = identity {
+        params.push(format!("metadata={}", urlencoding::encode(&identity.metadata().to_string())));
+    }
+
+    Ok(format!("bunker://{}?{}", pubkey, params.join("&")))
+}
+
 /// Bunker connection state
 #[derive(Debug, Clone)]
 pub enum BunkerState {
@@ -21,78 +107,555 @@ pub enum BunkerState {
     WaitingForConnection { connection_string: String },
     /// Connected to a client
     Connected { client_pubkey: String, app_name: Option<String> },
+    /// Listening, but refusing to sign or decrypt until resumed
+    Paused,
     /// Error state
     Error(String),
 }
 
+/// Lets the bunker hold requests while the signer is locked
+#[derive(Clone)]
+pub struct LockGate {
+    /// Current lock state (true = locked)
+    pub locked: watch::Receiver<bool>,
+    /// Channel used to raise the local unlock prompt
+    pub unlock_prompt: async_channel::Sender<AppMessage>,
+    /// How long a held request waits for the user to unlock
+    pub timeout: Duration,
+}
+
+impl LockGate {
+    fn is_locked(&self) -> bool {
+        *self.locked.borrow()
+    }
+}
+
+/// Limits on outgoing NIP-46 responses, to protect relays from runaway clients
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseRate {
+    /// Responses published per second (0 = unlimited)
+    pub per_sec: u32,
+    /// Responses that may wait to be published; extras are dropped
+    pub backlog: usize,
+}
+
+impl Default for ResponseRate {
+    fn default() -> Self {
+        Self {
+            per_sec: 5,
+            backlog: 50,
+        }
+    }
+}
+
+/// Relays the bunker subscribes to for requests and publishes responses on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayRoutes {
+    /// Where requests are received
+    pub inbound: Vec<String>,
+    /// Where responses are published
+    pub outbound: Vec<String>,
+}
+
+impl RelayRoutes {
+    /// The same relays in both directions
+    pub fn shared(relays: Vec<String>) -> Self {
+        Self {
+            inbound: relays.clone(),
+            outbound: relays,
+        }
+    }
+
+    /// Override either direction; an empty list keeps the current relays
+    pub fn split(mut self, inbound: &[String], outbound: &[String]) -> Self {
+        if !inbound.is_empty() {
+            self.inbound = inbound.to_vec();
+        }
+        if !outbound.is_empty() {
+            self.outbound = outbound.to_vec();
+        }
+        self
+    }
+
+    /// Every relay the bunker connects to, without duplicates
+    pub fn all(&self) -> Vec<String> {
+        let mut all: Vec<String> = Vec::new();
+        for relay in self.inbound.iter().chain(&self.outbound) {
+            if !all.contains(relay) {
+                all.push(relay.clone());
+            }
+        }
+        all
+    }
+
+    /// The NIP-46 `get_relays` answer: each relay with whether the bunker
+    /// reads requests from it and writes responses to it
+    fn nip46_relays(&self) -> serde_json::Value {
+        let relays = self.all().into_iter()
+            .map(|relay| {
+                let flags = serde_json::json!({
+                    "read": self.inbound.contains(&relay),
+                    "write": self.outbound.contains(&relay),
+                });
+                (relay, flags)
+            })
+            .collect();
+        serde_json::Value::Object(relays)
+    }
+
+    /// Whether requests are received from this relay
+    fn is_inbound(&self, url: &RelayUrl) -> bool {
+        self.inbound.iter().any(|relay| RelayUrl::parse(relay).is_ok_and(|relay| relay == *url))
+    }
+}
+
+/// Paces outgoing responses through a bounded queue
+#[derive(Clone)]
+struct ResponseThrottle {
+    queue: mpsc::Sender<Event>,
+}
+
+impl ResponseThrottle {
+    /// Start the pacing task, publishing each queued event with `send`
+    fn spawn<F, Fut>(rate: ResponseRate, send: F) -> Self
+    where
+        F: Fn(Event) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send,
+    {
+        let (queue, mut pending) = mpsc::channel::<Event>(rate.backlog.max(1));
+
+        tokio::spawn(async move {
+            let mut ticker = (rate.per_sec > 0).then(|| {
+                let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate.per_sec as f64));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                ticker
+            });
+            while let Some(event) = pending.recv().await {
+                if let Some(ref mut ticker) = ticker {
+                    ticker.tick().await;
+                }
+                send(event).await;
+            }
+        });
+
+        Self { queue }
+    }
+
+    /// Queue a response, dropping it if the backlog is full
+    fn enqueue(&self, event: Event) -> bool {
+        match self.queue.try_send(event) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                warn!("Response backlog full, dropping NIP-46 response");
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                warn!("Response publisher stopped, dropping NIP-46 response");
+                false
+            }
+        }
+    }
+}
+
+/// NIP-42 authentication status of a bunker relay
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum RelayAuthStatus {
+    /// Challenge answered, waiting for the relay's OK
+    Pending,
+    /// Relay accepted our AUTH event
+    Authenticated,
+    /// Relay rejected our AUTH event (or we couldn't sign it)
+    Failed(String),
+}
+
+/// Tracks NIP-42 authentication per relay
+#[derive(Debug, Default)]
+struct RelayAuth {
+    /// AUTH events awaiting an OK, by event ID
+    pending: HashMap<EventId, RelayUrl>,
+    /// Latest status per relay URL
+    status: HashMap<String, RelayAuthStatus>,
+}
+
+/// What the listener should do after handling a relay message
+#[derive(Debug)]
+enum AuthAction {
+    /// Send this message to the relay
+    Send(Box<ClientMessage<'static>>),
+    /// Authentication succeeded; subscribe again in case the relay closed us
+    Resubscribe,
+    /// Nothing to do
+    None,
+}
+
+impl RelayAuth {
+    /// Answer AUTH challenges with the active key and track the relay's verdict
+    fn handle_message(&mut self, keys: &Keys, relay_url: &RelayUrl, message: &RelayMessage<'_>) -> AuthAction {
+        match message {
+            RelayMessage::Auth { challenge } => {
+                info!("Relay {} requested authentication", relay_url);
+                match EventBuilder::auth(challenge.to_string(), relay_url.clone()).sign_with_keys(keys) {
+                    Ok(event) => {
+                        self.pending.insert(event.id, relay_url.clone());
+                        self.status.insert(relay_url.to_string(), RelayAuthStatus::Pending);
+                        AuthAction::Send(Box::new(ClientMessage::auth(event)))
+                    }
+                    Err(e) => {
+                        warn!("Failed to sign AUTH for {}: {}", relay_url, e);
+                        self.status.insert(relay_url.to_string(), RelayAuthStatus::Failed(e.to_string()));
+                        AuthAction::None
+                    }
+                }
+            }
+            RelayMessage::Ok { event_id, status, message } => {
+                let Some(relay_url) = self.pending.remove(event_id) else {
+                    return AuthAction::None;
+                };
+                if *status {
+                    info!("Authenticated with relay {}", relay_url);
+                    self.status.insert(relay_url.to_string(), RelayAuthStatus::Authenticated);
+                    AuthAction::Resubscribe
+                } else {
+                    warn!("Relay {} rejected authentication: {}", relay_url, message);
+                    self.status.insert(relay_url.to_string(), RelayAuthStatus::Failed(message.to_string()));
+                    AuthAction::None
+                }
+            }
+            _ => AuthAction::None,
+        }
+    }
+}
+
+/// Position of the bunker subscription, persisted so a restart resumes
+/// where it left off instead of at "now"
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BunkerCursor {
+    /// Bunker pubkey (hex) the cursor belongs to
+    pubkey: String,
+    /// created_at of the newest processed request
+    last_seen: u64,
+    /// Requests processed within the overlap window, so they aren't handled twice
+    recent: Vec<(EventId, u64)>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl BunkerCursor {
+    /// Load the cursor for a bunker key, starting fresh if it belongs to another key
+    fn load(path: PathBuf, pubkey: &PublicKey) -> Self {
+        let pubkey = pubkey.to_hex();
+        let cursor = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|cursor| cursor.pubkey == pubkey)
+            .unwrap_or_else(|| Self { pubkey, ..Default::default() });
+        Self { path: Some(path), ..cursor }
+    }
+
+    /// Subscription start: a little before the last processed request,
+    /// but never more than `MAX_REPLAY_SECS` ago
+    fn since(&self, now: Timestamp) -> Timestamp {
+        let now = now.as_secs();
+        if self.last_seen == 0 {
+            return Timestamp::from(now);
+        }
+        let resume = self.last_seen.saturating_sub(CURSOR_OVERLAP_SECS);
+        Timestamp::from(resume.max(now.saturating_sub(MAX_REPLAY_SECS)).min(now))
+    }
+
+    /// Record a request before handling it; false if it was already handled
+    fn record(&mut self, event: &Event) -> bool {
+        if self.recent.iter().any(|(id, _)| *id == event.id) {
+            return false;
+        }
+
+        // A client clock running ahead mustn't push the cursor past real requests
+        let created_at = event.created_at.as_secs().min(Timestamp::now().as_secs());
+        self.last_seen = self.last_seen.max(created_at);
+        self.recent.push((event.id, created_at));
+        let cutoff = self.last_seen.saturating_sub(CURSOR_OVERLAP_SECS);
+        self.recent.retain(|(_, at)| *at >= cutoff);
+
+        self.save();
+        true
+    }
+
+    fn save(&self) {
+        if let Some(ref path) = self.path {
+            save_private(path, self, "bunker cursor");
+        }
+    }
+}
+
+/// Relay settings handed to the listener thread
+struct ListenerConfig {
+    routes: RelayRoutes,
+    response_rate: ResponseRate,
+    /// Shared NIP-42 auth state, readable through `BunkerSigner::relay_auth_status`
+    relay_auth: Arc<std::sync::Mutex<RelayAuth>>,
+    /// Where the subscription resumes after a restart
+    cursor: Arc<std::sync::Mutex<BunkerCursor>>,
+    /// Encryption and pairing of each client
+    clients: BunkerClients,
+    /// Shared connection state, readable through `BunkerSigner::relay_status`
+    relay_status: Arc<std::sync::Mutex<Vec<(String, bool)>>>,
+    /// Set while the bunker is paused
+    paused: Arc<AtomicBool>,
+    /// Holds paired clients' requests while the signer is locked
+    lock_gate: Option<LockGate>,
+}
+
+/// Warning for the UI when some bunker relays aren't connected (None if
+/// all are, or the bunker isn't running)
+pub fn relay_status_warning(status: &[(String, bool)]) -> Option<String> {
+    let connected = status.iter().filter(|(_, up)| *up).count();
+    if connected == status.len() {
+        return None;
+    }
+    let mut warning = format!("{} of {} relays connected", connected, status.len());
+    if connected == 0 {
+        warning.push_str(": remote clients can't reach the bunker");
+    }
+    Some(warning)
+}
+
+/// Record which of `relays` the client is connected to
+async fn refresh_relay_status(client: &Client, relays: &[String], status: &std::sync::Mutex<Vec<(String, bool)>>) {
+    let connected = client.relays().await;
+    let current = relays
+        .iter()
+        .map(|relay| {
+            let up = RelayUrl::parse(relay).ok()
+                .and_then(|url| connected.get(&url).map(|r| r.is_connected()))
+                .unwrap_or(false);
+            (relay.clone(), up)
+        })
+        .collect();
+    *status.lock().unwrap() = current;
+}
+
 /// NIP-46 Bunker signer that allows remote signing
 pub struct BunkerSigner {
+    /// Taken while the app state is held (start, URI), never the other way
+    /// round; see the lock order in app.rs
     key_manager: Arc<Mutex<KeyManager>>,
     state: Arc<Mutex<BunkerState>>,
-    relays: Vec<String>,
+    routes: RelayRoutes,
+    /// Configured connection secret (None = generate one)
     secret: Option<String>,
+    /// Encryption and pairing of each client
+    clients: BunkerClients,
+    /// Pacing for outgoing responses
+    response_rate: ResponseRate,
+    /// NIP-42 authentication state per relay
+    relay_auth: Arc<std::sync::Mutex<RelayAuth>>,
+    /// Whether each relay is connected (empty while stopped)
+    relay_status: Arc<std::sync::Mutex<Vec<(String, bool)>>>,
+    /// Optional gate for holding requests until unlocked
+    lock_gate: Option<LockGate>,
+    /// Flag to refuse sign/decrypt requests without tearing down connections
+    paused: Arc<AtomicBool>,
     /// Flag to signal the listener thread to stop
     stop_flag: Arc<AtomicBool>,
-    /// Handle to the listener thread
-    listener_handle: std::sync::Mutex<Option<std::thread::JoinHandle<()>>>,
+    /// The running listener thread, if any
+    listener: std::sync::Mutex<Option<Listener>>,
+}
+
+/// How long `stop` waits for the listener to disconnect from its relays
+const LISTENER_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A listener thread and the signal that wakes it up to shut down
+struct Listener {
+    thread: std::thread::JoinHandle<()>,
+    shutdown: Arc<tokio::sync::Notify>,
+}
+
+impl Listener {
+    /// Wake the listener, then wait (up to `timeout`) for it to disconnect
+    /// and exit. Returns false if the thread had to be left behind.
+    async fn shut_down(self, timeout: std::time::Duration) -> bool {
+        // A stored permit covers a listener that isn't waiting right now
+        self.shutdown.notify_one();
+        let thread = self.thread;
+        match tokio::time::timeout(timeout, tokio::task::spawn_blocking(move || thread.join())).await {
+            Ok(Ok(Ok(()))) => true,
+            Ok(Ok(Err(_))) => {
+                warn!("Bunker listener thread panicked");
+                true
+            }
+            Ok(Err(e)) => {
+                warn!("Couldn't wait for the bunker listener: {}", e);
+                false
+            }
+            Err(_) => {
+                warn!("Bunker listener didn't stop within {:?}", timeout);
+                false
+            }
+        }
+    }
 }
 
 impl BunkerSigner {
-    /// Create a new bunker signer
+    /// Create a new bunker signer on the active key's relays (or the
+    /// defaults if it has none)
     pub fn new(key_manager: Arc<Mutex<KeyManager>>) -> Self {
+        // Nothing holds the key manager while the bunker is set up; if
+        // something does, fall back to the defaults rather than block
+        let mut relays = key_manager.try_lock()
+            .map(|km| km.active_key_relays())
+            .unwrap_or_default();
+        if relays.is_empty() {
+            relays = DEFAULT_RELAYS.map(String::from).to_vec();
+        }
         Self {
             key_manager,
             state: Arc::new(Mutex::new(BunkerState::Disconnected)),
-            relays: vec![
-                "wss://relay.nsec.app".to_string(),
-                "wss://relay.damus.io".to_string(),
-            ],
+            routes: RelayRoutes::shared(relays),
             secret: None,
+            clients: BunkerClients::default(),
+            response_rate: ResponseRate::default(),
+            relay_auth: Arc::new(std::sync::Mutex::new(RelayAuth::default())),
+            relay_status: Arc::new(std::sync::Mutex::new(Vec::new())),
+            lock_gate: None,
+            paused: Arc::new(AtomicBool::new(false)),
             stop_flag: Arc::new(AtomicBool::new(false)),
-            listener_handle: std::sync::Mutex::new(None),
+            listener: std::sync::Mutex::new(None),
         }
     }
 
     /// Set custom relays for bunker connection
     pub fn with_relays(mut self, relays: Vec<String>) -> Self {
-        self.relays = relays;
+        self.routes = RelayRoutes::shared(relays);
+        self
+    }
+
+    /// Receive requests and publish responses on different relays (an empty
+    /// list keeps the relays set with `with_relays`)
+    pub fn with_relay_routes(mut self, inbound: &[String], outbound: &[String]) -> Self {
+        self.routes = self.routes.split(inbound, outbound);
         self
     }
 
-    /// Set a secret for the connection (optional additional security)
+    /// Relays the bunker connects to, in either direction
+    pub fn relays(&self) -> Vec<String> {
+        self.routes.all()
+    }
+
+    /// Use a fixed connection secret instead of a generated one
     pub fn with_secret(mut self, secret: String) -> Self {
         self.secret = Some(secret);
         self
     }
 
+    /// Limit how fast responses are published to relays
+    pub fn with_response_rate(mut self, rate: ResponseRate) -> Self {
+        self.response_rate = rate;
+        self
+    }
+
+    /// Name and icon shown to NIP-46 clients
+    pub fn with_identity(mut self, identity: SignerIdentity) -> Self {
+        self.clients.identity = identity;
+        self
+    }
+
+    /// Refuse these NIP-46 methods (e.g. `nip44_decrypt`) for every client
+    pub fn with_disabled_methods(mut self, methods: Vec<String>) -> Self {
+        for method in methods.iter().filter(|m| nip46_key_method_type(m).is_none()) {
+            warn!("Ignoring disabled_methods entry {:?}: not a NIP-46 method that uses a key", method);
+        }
+        self.clients.disabled_methods = methods.into_iter().filter(|m| nip46_key_method_type(m).is_some()).collect();
+        self
+    }
+
+    /// Sign with the key named `name` instead of the active key (None = the
+    /// active key); its relays replace the active key's
+    pub fn with_key(mut self, name: Option<String>) -> Self {
+        if let Some(ref name) = name {
+            let relays = self.key_manager.try_lock()
+                .ok()
+                .and_then(|km| km.key_metadata(name).map(|m| m.relays.clone()))
+                .unwrap_or_default();
+            if !relays.is_empty() {
+                self.routes = RelayRoutes::shared(relays);
+            }
+        }
+        self.clients.key_name = name;
+        self
+    }
+
+    /// Refuse to sign the event kinds in `blocked`, as the D-Bus engines do
+    pub fn with_blocked_kinds(mut self, blocked: BlockedKinds) -> Self {
+        self.clients.blocked_kinds = blocked;
+        self
+    }
+
+    /// Apply the signing policy in `policy` to events, as D-Bus requests are
+    pub fn with_sign_policy(mut self, policy: SignPolicy) -> Self {
+        self.clients.sign_policy = policy;
+        self
+    }
+
+    /// Announce sign and encryption requests on `notices` (for desktop notifications)
+    pub fn with_request_notices(mut self, notices: async_channel::Sender<AppMessage>) -> Self {
+        self.clients.notices = Some(notices);
+        self
+    }
+
+    /// Hold requests received while locked until the user unlocks
+    pub fn with_lock_gate(mut self, gate: LockGate) -> Self {
+        self.lock_gate = Some(gate);
+        self
+    }
+
     /// Get current state
     pub async fn state(&self) -> BunkerState {
-        self.state.lock().await.clone()
+        let state = self.state.lock().await.clone();
+        match state {
+            BunkerState::Disconnected | BunkerState::Error(_) => state,
+            _ if self.is_paused() => BunkerState::Paused,
+            _ => state,
+        }
+    }
+
+    /// NIP-42 authentication status of each relay that challenged us
+    pub fn relay_auth_status(&self) -> HashMap<String, RelayAuthStatus> {
+        self.relay_auth.lock().unwrap().status.clone()
+    }
+
+    /// Each bunker relay and whether it is connected (empty while stopped)
+    pub fn relay_status(&self) -> Vec<(String, bool)> {
+        self.relay_status.lock().unwrap().clone()
+    }
+
+    /// Refuse sign/decrypt requests while keeping relay connections and sessions
+    pub fn pause(&self) {
+        info!("Pausing bunker");
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume processing sign/decrypt requests
+    pub fn resume(&self) {
+        info!("Resuming bunker");
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the bunker is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
     }
 
     /// Generate a bunker:// URI for clients that support it
     pub async fn generate_bunker_uri(&self) -> Result<String> {
         let km = self.key_manager.lock().await;
-        let pubkey = km.get_active_pubkey()
-            .ok_or_else(|| SignerError::KeyNotFound("No active key".into()))?;
-        
-        let mut uri = format!("bunker://{}", pubkey);
-        
-        let mut params = Vec::new();
-        for relay in &self.relays {
-            params.push(format!("relay={}", urlencoding::encode(relay)));
-        }
-        
-        if let Some(ref secret) = self.secret {
-            params.push(format!("secret={}", urlencoding::encode(secret)));
-        }
-        
-        if !params.is_empty() {
-            uri.push('?');
-            uri.push_str(&params.join("&"));
-        }
+        let pubkey = self.clients.npub(&km)?;
+        let parsed = PublicKey::parse(pubkey)
+            .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()))?;
+        let secret = self.clients.load_pairing(pairing_path(), &parsed, self.secret.as_deref());
         
-        Ok(uri)
+        // Clients send requests and read responses on every listed relay
+        bunker_uri(pubkey, &self.routes.all(), Some(&secret), Some(&self.clients.identity))
     }
 
     /// Start listening for bunker connections
@@ -103,19 +666,21 @@ impl BunkerSigner {
         
         // Check if already running
         {
-            let handle = self.listener_handle.lock().unwrap();
-            if handle.is_some() {
+            let listener = self.listener.lock().unwrap();
+            if listener.is_some() {
                 info!("Bunker listener already running");
                 return Ok(());
             }
         }
         
-        // Get the keys we need
-        let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await
-            .map_err(|e| SignerError::NostrError(e.to_string()))?
-            .clone();
-        drop(km);
+        // Get the keys we need; a configured bunker key must exist
+        let keys = match self.clients.signing_keys(&self.key_manager).await {
+            Ok(keys) => keys,
+            Err(e) => match self.clients.key_name {
+                Some(ref name) => return Err(SignerError::ConfigError(format!("bunker.key '{}': {}", name, e))),
+                None => return Err(SignerError::NostrError(e.to_string())),
+            },
+        };
         
         // Update state
         {
@@ -132,8 +697,29 @@ impl BunkerSigner {
         // Clone what we need for the thread
         let state = Arc::clone(&self.state);
         let key_manager = Arc::clone(&self.key_manager);
-        let relays = self.relays.clone();
+        let cursor = match Config::data_dir() {
+            Ok(dir) => BunkerCursor::load(dir.join(CURSOR_FILE), &keys.public_key()),
+            Err(e) => {
+                warn!("Bunker progress won't survive restarts: {}", e);
+                BunkerCursor::default()
+            }
+        };
+        self.clients.load_pairing(pairing_path(), &keys.public_key(), self.secret.as_deref());
+        let listener_config = ListenerConfig {
+            routes: self.routes.clone(),
+            response_rate: self.response_rate,
+            relay_auth: Arc::clone(&self.relay_auth),
+            cursor: Arc::new(std::sync::Mutex::new(cursor)),
+            clients: BunkerClients { routes: self.routes.clone(), ..self.clients.clone() },
+            relay_status: Arc::clone(&self.relay_status),
+            paused: Arc::clone(&self.paused),
+            lock_gate: self.lock_gate.clone(),
+        };
+        // Nothing is connected until the listener says otherwise
+        *self.relay_status.lock().unwrap() = self.routes.all().into_iter().map(|r| (r, false)).collect();
         let stop_flag = Arc::clone(&self.stop_flag);
+        let shutdown = Arc::new(tokio::sync::Notify::new());
+        let thread_shutdown = Arc::clone(&shutdown);
         
         // Spawn a real OS thread with its own tokio runtime
         let handle = std::thread::spawn(move || {
@@ -150,7 +736,7 @@ impl BunkerSigner {
             
             // Run the listener
             rt.block_on(async {
-                if let Err(e) = run_bunker_listener(keys, relays, stop_flag, state, key_manager).await {
+                if let Err(e) = run_bunker_listener(keys, listener_config, stop_flag, thread_shutdown, state, key_manager).await {
                     error!("Bunker listener error: {}", e);
                 }
             });
@@ -158,13 +744,14 @@ impl BunkerSigner {
             info!("Bunker listener thread exiting");
         });
         
-        // Store the handle
-        {
-            let mut guard = self.listener_handle.lock().unwrap();
-            *guard = Some(handle);
-        }
+        // Keep the handle so `stop` can wait for the relays to be released
+        *self.listener.lock().unwrap() = Some(Listener { thread: handle, shutdown });
         
-        info!("Bunker signer started listening on {} relays", self.relays.len());
+        info!(
+            "Bunker signer listening on {} relays, responding on {}",
+            self.routes.inbound.len(),
+            self.routes.outbound.len()
+        );
         Ok(())
     }
 
@@ -180,24 +767,30 @@ impl BunkerSigner {
             let mut state = self.state.lock().await;
             *state = BunkerState::Disconnected;
         }
+        self.relay_status.lock().unwrap().clear();
         
-        // Wait for thread to finish (with timeout)
-        let handle = {
-            let mut guard = self.listener_handle.lock().unwrap();
-            guard.take()
-        };
-        
-        if let Some(h) = handle {
-            // Give it a moment to finish
-            std::thread::sleep(std::time::Duration::from_millis(500));
-            // We don't join because it might block, just let it finish
-            drop(h);
+        // Wake the listener and wait for it to disconnect, so toggling
+        // the bunker doesn't pile up relay connections
+        let listener = self.listener.lock().unwrap().take();
+        if let Some(listener) = listener {
+            listener.shut_down(LISTENER_STOP_TIMEOUT).await;
         }
         
         info!("Bunker listener stopped");
     }
 }
 
+/// Where the bunker pairing is kept (None = memory only)
+fn pairing_path() -> Option<PathBuf> {
+    match Config::data_dir() {
+        Ok(dir) => Some(dir.join(PAIRING_FILE)),
+        Err(e) => {
+            warn!("Bunker pairing won't survive restarts: {}", e);
+            None
+        }
+    }
+}
+
 /// URL encoding helper
 mod urlencoding {
     pub fn encode(s: &str) -> String {
@@ -216,22 +809,46 @@ mod urlencoding {
         }
         result
     }
+
+    /// Undo `encode`; None for a malformed escape or invalid UTF-8
+    #[cfg(test)]
+    pub fn decode(s: &str) -> Option<String> {
+        let mut bytes = Vec::with_capacity(s.len());
+        let mut rest = s.as_bytes();
+        while let Some((&byte, tail)) = rest.split_first() {
+            if byte == b'%' {
+                let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                rest = &tail[2..];
+            } else {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+        String::from_utf8(bytes).ok()
+    }
 }
 
 /// Background task that handles NIP-46 requests
 async fn run_bunker_listener(
     keys: Keys,
-    relays: Vec<String>,
+    config: ListenerConfig,
     stop_flag: Arc<AtomicBool>,
+    shutdown: Arc<tokio::sync::Notify>,
     state: Arc<Mutex<BunkerState>>,
     key_manager: Arc<Mutex<KeyManager>>,
 ) -> Result<()> {
     info!("Bunker listener initializing...");
     
-    // Create a Nostr client
-    let client = Client::new(keys.clone());
+    // Create a Nostr client; NIP-42 challenges are answered below so
+    // failures can be tracked per relay
+    let client = Client::builder()
+        .signer(keys.clone())
+        .opts(ClientOptions::new().automatic_authentication(false))
+        .build();
     
     // Add relays
+    let relays = config.routes.all();
     for relay in &relays {
         info!("Adding relay: {}", relay);
         if let Err(e) = client.add_relay(relay).await {
@@ -242,18 +859,37 @@ async fn run_bunker_listener(
     // Connect
     info!("Connecting to relays...");
     client.connect().await;
+    refresh_relay_status(&client, &relays, &config.relay_status).await;
     info!("Connected to relays");
     
-    // Subscribe to NIP-46 requests addressed to our pubkey
+    // All responses go out through the throttle, on the outbound relays
+    let publisher = client.clone();
+    let outbound = config.routes.outbound.clone();
+    let throttle = ResponseThrottle::spawn(config.response_rate, move |response| {
+        let publisher = publisher.clone();
+        let outbound = outbound.clone();
+        async move {
+            match publisher.send_event_to(outbound, &response).await {
+                Ok(output) => info!("Sent NIP-46 response {}", output.id()),
+                Err(e) => error!("Failed to send response: {}", e),
+            }
+        }
+    });
+    
+    // Subscribe to NIP-46 requests addressed to our pubkey, picking up
+    // anything sent while we were briefly offline
     let pubkey = keys.public_key();
+    let since = config.cursor.lock().unwrap().since(Timestamp::now());
     let filter = Filter::new()
         .kind(Kind::NostrConnect)
         .pubkey(pubkey)
-        .since(Timestamp::now());
+        .since(since);
     
     info!("Subscribing to NIP-46 events for pubkey: {}", pubkey.to_bech32().unwrap_or_default());
-    client.subscribe(filter, None).await
-        .map_err(|e| SignerError::DbusError(e.to_string()))?;
+    if let Err(e) = client.subscribe_to(config.routes.inbound.clone(), filter.clone(), None).await {
+        client.disconnect().await;
+        return Err(SignerError::DbusError(e.to_string()));
+    }
     
     info!("Bunker listener ready and waiting for connections...");
     
@@ -264,23 +900,40 @@ async fn run_bunker_listener(
             info!("Stop flag set, exiting bunker listener");
             break;
         }
+        refresh_relay_status(&client, &relays, &config.relay_status).await;
         
         // Clone state for closure
         let state_clone = Arc::clone(&state);
         let key_manager_clone = Arc::clone(&key_manager);
         let keys_clone = keys.clone();
+        let throttle_clone = throttle.clone();
         let client_clone = client.clone();
         let stop_flag_clone = Arc::clone(&stop_flag);
+        let paused_clone = Arc::clone(&config.paused);
+        let lock_gate_clone = config.lock_gate.clone();
+        let relay_auth_clone = Arc::clone(&config.relay_auth);
+        let routes_clone = config.routes.clone();
+        let cursor_clone = Arc::clone(&config.cursor);
+        let clients_clone = config.clients.clone();
+        let filter_clone = filter.clone();
         
         // Handle notifications for a short period, then check stop flag
-        let handle_result = tokio::time::timeout(
+        let notifications = tokio::time::timeout(
             std::time::Duration::from_secs(2),
             client.handle_notifications(|notification| {
                 let state = Arc::clone(&state_clone);
                 let key_manager = Arc::clone(&key_manager_clone);
                 let keys = keys_clone.clone();
-                let client_send = client_clone.clone();
+                let throttle = throttle_clone.clone();
                 let stop_flag = Arc::clone(&stop_flag_clone);
+                let paused = Arc::clone(&paused_clone);
+                let lock_gate = lock_gate_clone.clone();
+                let relay_auth = Arc::clone(&relay_auth_clone);
+                let routes = routes_clone.clone();
+                let cursor = Arc::clone(&cursor_clone);
+                let clients = clients_clone.clone();
+                let filter = filter_clone.clone();
+                let client = client_clone.clone();
                 
                 async move {
                     // Check stop flag
@@ -288,6 +941,27 @@ async fn run_bunker_listener(
                         return Ok(true); // Signal to stop
                     }
                     
+                    if let RelayPoolNotification::Message { relay_url, message } = &notification {
+                        let action = relay_auth.lock().unwrap().handle_message(&keys, relay_url, message);
+                        match action {
+                            AuthAction::Send(auth) => match client.relay(relay_url).await {
+                                Ok(relay) => {
+                                    if let Err(e) = relay.send_msg(*auth) {
+                                        error!("Failed to send AUTH to {}: {}", relay_url, e);
+                                    }
+                                }
+                                Err(e) => error!("Unknown relay {}: {}", relay_url, e),
+                            },
+                            AuthAction::Resubscribe if routes.is_inbound(relay_url) => {
+                                if let Err(e) = client.subscribe_to([relay_url.clone()], filter, None).await {
+                                    warn!("Failed to resubscribe to {}: {}", relay_url, e);
+                                }
+                            }
+                            AuthAction::Resubscribe | AuthAction::None => {}
+                        }
+                        return Ok(false);
+                    }
+                    
                     if let RelayPoolNotification::Event { event, .. } = notification {
                         if event.kind == Kind::NostrConnect {
                             // Check if this is for us
@@ -295,14 +969,25 @@ async fn run_bunker_listener(
                             let p_tags: Vec<_> = event.tags.public_keys().collect();
                             
                             if p_tags.contains(&&our_pubkey) {
+                                if !cursor.lock().unwrap().record(&event) {
+                                    info!("Skipping already handled request {}", event.id);
+                                    return Ok(false);
+                                }
+                                
                                 info!("Received NIP-46 request from {}", event.pubkey.to_bech32().unwrap_or_default());
                                 
-                                match handle_nip46_request(&event, &keys, &key_manager, &state).await {
+                                // Unpaired clients are answered at once: `connect`
+                                // needs no key, and anything else is refused
+                                if let Some(gate) = lock_gate.filter(|g| g.is_locked() && clients.is_paired(&event.pubkey)) {
+                                    hold_until_unlocked(*event, keys, key_manager, state, clients, throttle, gate, paused);
+                                    return Ok(false);
+                                }
+                                
+                                let is_paused = paused.load(Ordering::SeqCst);
+                                match handle_nip46_request(&event, &keys, &key_manager, &state, &clients, is_paused).await {
                                     Ok(Some(response)) => {
                                         info!("Sending NIP-46 response");
-                                        if let Err(e) = client_send.send_event(&response).await {
-                                            error!("Failed to send response: {}", e);
-                                        }
+                                        throttle.enqueue(response);
                                     }
                                     Ok(None) => {}
                                     Err(e) => {
@@ -316,7 +1001,16 @@ async fn run_bunker_listener(
                     Ok(false) // Continue listening
                 }
             })
-        ).await;
+        );
+        
+        // A shutdown request cuts the wait short
+        let handle_result = tokio::select! {
+            _ = shutdown.notified() => {
+                info!("Shutdown requested, exiting bunker listener");
+                break;
+            }
+            result = notifications => result,
+        };
         
         // Check if we should exit
         if stop_flag.load(Ordering::SeqCst) {
@@ -349,11 +1043,12 @@ async fn handle_nip46_request(
     keys: &Keys,
     key_manager: &Arc<Mutex<KeyManager>>,
     state: &Arc<Mutex<BunkerState>>,
+    clients: &BunkerClients,
+    paused: bool,
 ) -> Result<Option<Event>> {
-    // Decrypt the request content using NIP-04
+    // Decrypt the request content (NIP-44 or NIP-04)
     let sender_pubkey = event.pubkey;
-    let decrypted = nip04::decrypt(keys.secret_key(), &sender_pubkey, &event.content)
-        .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+    let decrypted = clients.decrypt(keys, &sender_pubkey, &event.content)?;
     
     // Parse the request
     let request: serde_json::Value = serde_json::from_str(&decrypted)?;
@@ -364,6 +1059,18 @@ async fn handle_nip46_request(
     
     info!("Received NIP-46 request: {} (id: {})", method, id);
     
+    // Only clients that connected with the secret may use the bunker
+    if method == "connect" {
+        let secret = params.get(1).and_then(|v| v.as_str());
+        if !clients.pair(&sender_pubkey, secret) {
+            warn!("Refusing NIP-46 connect from {}: wrong or missing secret", sender_pubkey.to_bech32().unwrap_or_default());
+            return error_response(keys, &sender_pubkey, id, "Invalid secret", clients);
+        }
+    } else if !clients.is_paired(&sender_pubkey) {
+        warn!("Refusing NIP-46 {} from unpaired client {}", method, sender_pubkey.to_bech32().unwrap_or_default());
+        return error_response(keys, &sender_pubkey, id, "Not connected: send connect with the secret first", clients);
+    }
+    
     // Update state to show connected client
     {
         let mut s = state.lock().await;
@@ -373,134 +1080,512 @@ async fn handle_nip46_request(
         };
     }
     
+    // Methods turned off in the config are refused for every client
+    if clients.is_disabled(method) {
+        info!("Refusing disabled NIP-46 method {}", method);
+        let error = format!("{} is disabled on this signer", method);
+        clients.audit(&sender_pubkey, method, requested_kind(method, params), Some(error.clone()));
+        return error_response(keys, &sender_pubkey, id, &error, clients);
+    }
+    
+    // Refuse anything that uses the secret key while paused
+    if paused && is_secret_key_method(method) {
+        info!("Bunker paused, refusing {}", method);
+        clients.audit(&sender_pubkey, method, requested_kind(method, params), Some("Remote signing is paused".into()));
+        return error_response(keys, &sender_pubkey, id, "Remote signing is paused", clients);
+    }
+    clients.announce(&sender_pubkey, method);
+    
     // Handle the request
-    let result: serde_json::Value = match method {
-        "connect" => {
-            // Client is connecting
-            let app_pubkey = params.get(0).and_then(|v| v.as_str()).unwrap_or("");
-            info!("Client connecting: {}", app_pubkey);
-            serde_json::json!("ack")
-        }
+    let outcome = async {
+        Ok::<_, SignerError>(match method {
+            "connect" => {
+                // Client is connecting
+                let app_pubkey = params.get(0).and_then(|v| v.as_str()).unwrap_or("");
+                info!("Client connecting: {}", app_pubkey);
+                serde_json::json!("ack")
+            }
         
-        "get_public_key" => {
-            let km = key_manager.lock().await;
-            let pubkey = km.get_active_pubkey()
-                .ok_or_else(|| SignerError::KeyNotFound("No active key".into()))?;
-            serde_json::json!(pubkey)
-        }
+            "get_public_key" => {
+                let km = key_manager.lock().await;
+                serde_json::json!(clients.npub(&km)?)
+            }
         
-        "sign_event" => {
-            let event_json = params.get(0).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing event".into()))?;
+            "sign_event" => {
+                let event_json = params.get(0).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing event".into()))?;
             
-            // Parse the unsigned event data
-            let event_data: serde_json::Value = serde_json::from_str(event_json)?;
-            let kind = event_data["kind"].as_u64().unwrap_or(1) as u16;
-            let content = event_data["content"].as_str().unwrap_or("");
-            let created_at = event_data["created_at"].as_u64()
-                .map(Timestamp::from)
-                .unwrap_or_else(Timestamp::now);
+                // Parse the unsigned event data
+                let event_data: serde_json::Value = serde_json::from_str(event_json)?;
+                let kind = event_data["kind"].as_u64().unwrap_or(1) as u16;
+                clients.blocked_kinds.check(kind)?;
+                let content = event_data["content"].as_str().unwrap_or("");
+                let created_at = clients.sign_policy
+                    .check_unattended(kind, content, event_data["created_at"].as_u64())?
+                    .map(Timestamp::from)
+                    .unwrap_or_else(Timestamp::now);
+                let tags = parse_event_tags(&event_data["tags"])?;
             
-            let mut km = key_manager.lock().await;
-            let active_keys = km.get_signing_keys().await
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                let signing_keys = clients.signing_keys(key_manager).await
+                    .map_err(|e| SignerError::NostrError(e.to_string()))?;
             
-            // Build and sign the event
-            let signed = EventBuilder::new(Kind::from(kind), content)
-                .custom_created_at(created_at)
-                .sign_with_keys(active_keys)
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                // Build and sign the event
+                let signed = EventBuilder::new(Kind::from(kind), content)
+                    .tags(tags)
+                    .custom_created_at(created_at)
+                    .sign_with_keys(&signing_keys)
+                    .map_err(|e| SignerError::NostrError(e.to_string()))?;
             
-            serde_json::to_value(&signed)?
-        }
+                serde_json::to_value(&signed)?
+            }
         
-        "nip04_encrypt" => {
-            let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
-            let plaintext = params.get(1).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing plaintext".into()))?;
+            "nip04_encrypt" => {
+                let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
+                let plaintext = params.get(1).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing plaintext".into()))?;
             
-            let pubkey = PublicKey::from_hex(third_party_pubkey)
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                let pubkey = PublicKey::parse(third_party_pubkey)
+                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
             
-            let ciphertext = nip04::encrypt(keys.secret_key(), &pubkey, plaintext)
-                .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+                let ciphertext = nip04::encrypt(keys.secret_key(), &pubkey, plaintext)
+                    .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
             
-            serde_json::json!(ciphertext)
-        }
+                serde_json::json!(ciphertext)
+            }
         
-        "nip04_decrypt" => {
-            let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
-            let ciphertext = params.get(1).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing ciphertext".into()))?;
+            "nip04_decrypt" => {
+                let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
+                let ciphertext = params.get(1).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing ciphertext".into()))?;
             
-            let pubkey = PublicKey::from_hex(third_party_pubkey)
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                let pubkey = PublicKey::parse(third_party_pubkey)
+                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
             
-            let plaintext = nip04::decrypt(keys.secret_key(), &pubkey, ciphertext)
-                .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+                let plaintext = nip04::decrypt(keys.secret_key(), &pubkey, ciphertext)
+                    .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
             
-            serde_json::json!(plaintext)
-        }
+                serde_json::json!(plaintext)
+            }
         
-        "nip44_encrypt" => {
-            let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
-            let plaintext = params.get(1).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing plaintext".into()))?;
+            "nip44_encrypt" => {
+                let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
+                let plaintext = params.get(1).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing plaintext".into()))?;
             
-            let pubkey = PublicKey::from_hex(third_party_pubkey)
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                let pubkey = PublicKey::parse(third_party_pubkey)
+                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
             
-            let ciphertext = nip44::encrypt(keys.secret_key(), &pubkey, plaintext, nip44::Version::default())
-                .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+                let ciphertext = nip44::encrypt(keys.secret_key(), &pubkey, plaintext, nip44::Version::default())
+                    .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
             
-            serde_json::json!(ciphertext)
-        }
+                serde_json::json!(ciphertext)
+            }
         
-        "nip44_decrypt" => {
-            let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
-            let ciphertext = params.get(1).and_then(|v| v.as_str())
-                .ok_or_else(|| SignerError::InvalidRequest("Missing ciphertext".into()))?;
+            "nip44_decrypt" => {
+                let third_party_pubkey = params.get(0).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing pubkey".into()))?;
+                let ciphertext = params.get(1).and_then(|v| v.as_str())
+                    .ok_or_else(|| SignerError::InvalidRequest("Missing ciphertext".into()))?;
             
-            let pubkey = PublicKey::from_hex(third_party_pubkey)
-                .map_err(|e| SignerError::NostrError(e.to_string()))?;
+                let pubkey = PublicKey::parse(third_party_pubkey)
+                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
             
-            let plaintext = nip44::decrypt(keys.secret_key(), &pubkey, ciphertext)
-                .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+                let plaintext = nip44::decrypt(keys.secret_key(), &pubkey, ciphertext)
+                    .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
             
-            serde_json::json!(plaintext)
-        }
+                serde_json::json!(plaintext)
+            }
         
-        "ping" => {
-            serde_json::json!("pong")
-        }
+            "ping" => {
+                serde_json::json!("pong")
+            }
         
-        _ => {
-            warn!("Unknown NIP-46 method: {}", method);
-            return Err(SignerError::InvalidRequest(format!("Unknown method: {}", method)));
-        }
-    };
+            "get_relays" => clients.routes.nip46_relays(),
+        
+            _ => {
+                warn!("Unknown NIP-46 method: {}", method);
+                return Err(SignerError::InvalidRequest(format!("Unknown method: {}", method)));
+            }
+        })
+    }.await;
+    clients.audit(&sender_pubkey, method, requested_kind(method, params), outcome.as_ref().err().map(|e| e.to_string()));
+    let result = outcome?;
     
-    // Build response
-    let response = serde_json::json!({
+    // Build response; connecting clients also learn who they're talking to
+    let mut response = serde_json::json!({
         "id": id,
         "result": result,
     });
+    if method == "connect" {
+        response["metadata"] = clients.identity.metadata();
+    }
     
+    Ok(Some(build_response_event(keys, &sender_pubkey, &response, clients.scheme(&sender_pubkey))?))
+}
+
+/// Tags of an unsigned event, in their original order (the event id
+/// depends on it); any malformed tag fails the whole request
+fn parse_event_tags(tags: &serde_json::Value) -> Result<Vec<Tag>> {
+    if tags.is_null() {
+        return Ok(Vec::new());
+    }
+    let tags: Vec<Vec<String>> = serde_json::from_value(tags.clone())
+        .map_err(|e| SignerError::InvalidRequest(format!("Invalid tags: {}", e)))?;
+    tags.iter()
+        .map(|tag| Tag::parse(tag)
+            .map_err(|e| SignerError::InvalidRequest(format!("Invalid tag {:?}: {}", tag, e))))
+        .collect()
+}
+
+/// Reply to request `id` with an error
+fn error_response(keys: &Keys, client: &PublicKey, id: &str, error: &str, clients: &BunkerClients) -> Result<Option<Event>> {
+    let response = serde_json::json!({
+        "id": id,
+        "result": serde_json::Value::Null,
+        "error": error,
+    });
+    Ok(Some(build_response_event(keys, client, &response, clients.scheme(client))?))
+}
+
+/// Kind of the event a NIP-46 `sign_event` asks to sign
+fn requested_kind(method: &str, params: &serde_json::Value) -> Option<u16> {
+    if method != "sign_event" {
+        return None;
+    }
+    let event: serde_json::Value = serde_json::from_str(params.get(0)?.as_str()?).ok()?;
+    Some(event["kind"].as_u64().unwrap_or(1) as u16)
+}
+
+/// NIP-46 methods that sign or decrypt with the secret key
+fn is_secret_key_method(method: &str) -> bool {
+    nip46_request_type(method).is_some()
+}
+
+/// Request type of a NIP-46 method that uses the secret key
+fn nip46_request_type(method: &str) -> Option<RequestType> {
+    match method {
+        "sign_event" => Some(RequestType::SignEvent),
+        "nip04_encrypt" => Some(RequestType::Nip04Encrypt),
+        "nip04_decrypt" => Some(RequestType::Nip04Decrypt),
+        "nip44_encrypt" => Some(RequestType::Nip44Encrypt),
+        "nip44_decrypt" => Some(RequestType::Nip44Decrypt),
+        _ => None,
+    }
+}
+
+/// Request type of a NIP-46 method that uses a key (the secret key, or
+/// the public key for `get_public_key`)
+fn nip46_key_method_type(method: &str) -> Option<RequestType> {
+    match method {
+        "get_public_key" => Some(RequestType::GetPublicKey),
+        _ => nip46_request_type(method),
+    }
+}
+
+/// Encryption used to wrap NIP-46 messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Nip46Encryption {
+    #[default]
+    Nip04,
+    Nip44,
+}
+
+/// What the bunker knows about its NIP-46 clients
+#[derive(Clone, Default)]
+struct BunkerClients {
+    /// The scheme each client last wrote to us with; replies use the same one,
+    /// since a NIP-44 client may not read NIP-04 (and older clients only NIP-04)
+    schemes: Arc<std::sync::Mutex<HashMap<PublicKey, Nip46Encryption>>>,
+    /// Connection secret and the clients that presented it
+    pairing: Arc<std::sync::Mutex<Pairing>>,
+    /// Where requests that use the secret key are announced, and handled
+    /// requests are sent to be audited
+    notices: Option<async_channel::Sender<AppMessage>>,
+    /// Name and icon sent to clients when they connect
+    identity: SignerIdentity,
+    /// Methods refused for every client
+    disabled_methods: Vec<String>,
+    /// Event kinds never signed
+    blocked_kinds: BlockedKinds,
+    /// Policy for the events clients ask us to sign
+    sign_policy: SignPolicy,
+    /// Key the bunker signs with (None = the active key)
+    key_name: Option<String>,
+    /// Relays reported to clients that ask with `get_relays`
+    routes: RelayRoutes,
+}
+
+impl BunkerClients {
+    /// Announce a request from `client` if it uses the secret key
+    fn announce(&self, client: &PublicKey, method: &str) {
+        let (Some(notices), Some(request_type)) = (&self.notices, nip46_request_type(method)) else {
+            return;
+        };
+        let npub = client.to_bech32().unwrap_or_default();
+        let notice = RequestNotice {
+            app: format!("Remote app {}…", &npub[..npub.len().min(16)]),
+            request_type,
+        };
+        if let Err(e) = notices.try_send(AppMessage::NewRequest(notice)) {
+            warn!("Failed to announce NIP-46 request: {}", e);
+        }
+    }
+
+    /// The bunker's npub: the configured key's, or the active key's
+    fn npub<'a>(&self, km: &'a KeyManager) -> Result<&'a str> {
+        match self.key_name {
+            Some(ref name) => km.key_metadata(name)
+                .map(|m| m.npub.as_str())
+                .ok_or_else(|| SignerError::KeyNotFound(name.clone())),
+            None => km.get_active_pubkey()
+                .ok_or_else(|| SignerError::KeyNotFound("No active key".into())),
+        }
+    }
+
+    /// Keys the bunker signs with: the configured key, or the active key
+    async fn signing_keys(&self, key_manager: &Arc<Mutex<KeyManager>>) -> Result<Keys> {
+        let mut km = key_manager.lock().await;
+        match self.key_name {
+            Some(ref name) => km.get_keys_by_name(name).await,
+            None => km.get_signing_keys().await.cloned(),
+        }
+    }
+
+    /// Whether `method` is turned off in the config
+    fn is_disabled(&self, method: &str) -> bool {
+        self.disabled_methods.iter().any(|m| m == method)
+    }
+
+    /// Send a handled request that used a key to the audit log; `error` is
+    /// why it failed or was refused
+    fn audit(&self, client: &PublicKey, method: &str, event_kind: Option<u16>, error: Option<String>) {
+        let (Some(notices), Some(request_type)) = (&self.notices, nip46_key_method_type(method)) else {
+            return;
+        };
+        let app_id = bunker_app_id(client);
+        let entry = match error {
+            None => AuditEntry::new(&app_id, request_type, event_kind, true),
+            Some(reason) => AuditEntry::refused(&app_id, request_type, event_kind, &reason),
+        };
+        if let Err(e) = notices.try_send(AppMessage::BunkerActivity(entry)) {
+            warn!("Failed to audit NIP-46 request: {}", e);
+        }
+    }
+
+    /// Scheme to reply to `client` with (NIP-04 until it has sent NIP-44)
+    fn scheme(&self, client: &PublicKey) -> Nip46Encryption {
+        self.schemes.lock().unwrap().get(client).copied().unwrap_or_default()
+    }
+
+    /// Decrypt a request from `sender`, trying NIP-44 first and falling back
+    /// to NIP-04, and remember which one the client uses
+    fn decrypt(&self, keys: &Keys, sender: &PublicKey, content: &str) -> Result<String> {
+        let (plaintext, scheme) = match nip44::decrypt(keys.secret_key(), sender, content) {
+            Ok(plaintext) => (plaintext, Nip46Encryption::Nip44),
+            Err(_) => {
+                let plaintext = nip04::decrypt(keys.secret_key(), sender, content)
+                    .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+                (plaintext, Nip46Encryption::Nip04)
+            }
+        };
+        self.schemes.lock().unwrap().insert(*sender, scheme);
+        Ok(plaintext)
+    }
+
+    /// Switch to the pairing of bunker key `pubkey` (loading it if needed)
+    /// and return its connection secret
+    fn load_pairing(&self, path: Option<PathBuf>, pubkey: &PublicKey, configured: Option<&str>) -> String {
+        let mut pairing = self.pairing.lock().unwrap();
+        if pairing.pubkey != pubkey.to_hex() || configured.is_some_and(|secret| secret != pairing.secret) {
+            *pairing = Pairing::load(path, pubkey, configured);
+        }
+        pairing.secret.clone()
+    }
+
+    /// Whether `client` completed `connect` with the secret
+    fn is_paired(&self, client: &PublicKey) -> bool {
+        self.pairing.lock().unwrap().is_paired(client)
+    }
+
+    /// Pair `client` if it presented the connection secret
+    fn pair(&self, client: &PublicKey, secret: Option<&str>) -> bool {
+        self.pairing.lock().unwrap().pair(client, secret)
+    }
+}
+
+/// The NIP-46 connection secret and the clients that presented it,
+/// persisted so paired clients keep working after a restart
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Pairing {
+    /// Bunker pubkey (hex) the pairing belongs to
+    pubkey: String,
+    secret: String,
+    /// Clients (hex pubkeys) that connected with the secret
+    clients: Vec<String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Pairing {
+    /// Load the pairing for a bunker key. A new random secret is made when
+    /// none is stored for this key; a configured secret replaces the stored
+    /// one, and changing it unpairs every client.
+    fn load(path: Option<PathBuf>, pubkey: &PublicKey, configured: Option<&str>) -> Self {
+        let pubkey = pubkey.to_hex();
+        let stored = path.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|pairing| pairing.pubkey == pubkey && !pairing.secret.is_empty());
+        let pairing = match (stored, configured) {
+            (Some(stored), Some(secret)) if stored.secret != secret => {
+                info!("Bunker secret changed, clients must connect again");
+                Self { pubkey, secret: secret.to_string(), ..Default::default() }
+            }
+            (Some(stored), _) => stored,
+            (None, Some(secret)) => Self { pubkey, secret: secret.to_string(), ..Default::default() },
+            (None, None) => Self { pubkey, secret: generate_secret(), ..Default::default() },
+        };
+        let pairing = Self { path, ..pairing };
+        pairing.save();
+        pairing
+    }
+
+    fn is_paired(&self, client: &PublicKey) -> bool {
+        self.clients.contains(&client.to_hex())
+    }
+
+    fn pair(&mut self, client: &PublicKey, secret: Option<&str>) -> bool {
+        if self.secret.is_empty() || secret != Some(self.secret.as_str()) {
+            return false;
+        }
+        if !self.is_paired(client) {
+            self.clients.push(client.to_hex());
+            self.save();
+        }
+        true
+    }
+
+    fn save(&self) {
+        if let Some(ref path) = self.path {
+            save_private(path, self, "bunker pairing");
+        }
+    }
+}
+
+/// Save bunker state atomically, readable only by the user; failures are
+/// logged, as the bunker keeps working from memory
+fn save_private(path: &Path, state: &impl Serialize, what: &str) {
+    let result = serde_json::to_string(state)
+        .map_err(SignerError::from)
+        .and_then(|content| crate::config::write_atomic_blocking(path, &content))
+        .and_then(|()| crate::backup::restrict_permissions(path));
+    if let Err(e) = result {
+        warn!("Failed to save {}: {}", what, e);
+    }
+}
+
+/// Random connection secret for the bunker URI
+fn generate_secret() -> String {
+    hex::encode(&SecretKey::generate().to_secret_bytes()[..16])
+}
+
+/// Encrypt a NIP-46 response payload and wrap it in a signed event
+fn build_response_event(
+    keys: &Keys,
+    recipient: &PublicKey,
+    response: &serde_json::Value,
+    scheme: Nip46Encryption,
+) -> Result<Event> {
     // Encrypt response
-    let encrypted = nip04::encrypt(keys.secret_key(), &sender_pubkey, &response.to_string())
-        .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+    let encrypted = match scheme {
+        Nip46Encryption::Nip04 => nip04::encrypt(keys.secret_key(), recipient, response.to_string())
+            .map_err(|e| SignerError::EncryptionError(e.to_string()))?,
+        Nip46Encryption::Nip44 => nip44::encrypt(keys.secret_key(), recipient, response.to_string(), nip44::Version::default())
+            .map_err(|e| SignerError::EncryptionError(e.to_string()))?,
+    };
     
     // Create response event
-    let response_event = EventBuilder::new(Kind::NostrConnect, encrypted)
-        .tag(Tag::public_key(sender_pubkey))
+    EventBuilder::new(Kind::NostrConnect, encrypted)
+        .tag(Tag::public_key(*recipient))
         .sign_with_keys(keys)
-        .map_err(|e| SignerError::NostrError(e.to_string()))?;
+        .map_err(|e| SignerError::NostrError(e.to_string()))
+}
+
+/// Reply with an `auth_url` for a request received while locked, prompt the
+/// user to unlock, and complete the request in the background once unlocked
+#[allow(clippy::too_many_arguments)]
+fn hold_until_unlocked(
+    event: Event,
+    keys: Keys,
+    key_manager: Arc<Mutex<KeyManager>>,
+    state: Arc<Mutex<BunkerState>>,
+    clients: BunkerClients,
+    throttle: ResponseThrottle,
+    gate: LockGate,
+    paused: Arc<AtomicBool>,
+) {
+    info!("Signer is locked, holding NIP-46 request until unlock");
+    
+    // Tell the client the request is pending local approval
+    let pending = clients.decrypt(&keys, &event.pubkey, &event.content)
+        .ok()
+        .and_then(|d| serde_json::from_str::<serde_json::Value>(&d).ok())
+        .and_then(|req| req["id"].as_str().map(String::from));
+    if let Some(id) = pending {
+        let response = serde_json::json!({
+            "id": id,
+            "result": "auth_url",
+            "error": UNLOCK_AUTH_URL,
+        });
+        match build_response_event(&keys, &event.pubkey, &response, clients.scheme(&event.pubkey)) {
+            Ok(auth_event) => {
+                throttle.enqueue(auth_event);
+            }
+            Err(e) => error!("Failed to build auth_url response: {}", e),
+        }
+    }
+    
+    if let Err(e) = gate.unlock_prompt.try_send(AppMessage::RequestUnlock) {
+        warn!("Failed to raise unlock prompt: {}", e);
+    }
     
-    Ok(Some(response_event))
+    tokio::spawn(async move {
+        match process_after_unlock(&event, &keys, &key_manager, &state, &clients, gate, &paused).await {
+            Ok(Some(response)) => {
+                info!("Sending NIP-46 response for held request");
+                throttle.enqueue(response);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Held NIP-46 request not completed: {}", e),
+        }
+    });
+}
+
+/// Wait until the signer is unlocked (or the timeout elapses), then handle the request
+async fn process_after_unlock(
+    event: &Event,
+    keys: &Keys,
+    key_manager: &Arc<Mutex<KeyManager>>,
+    state: &Arc<Mutex<BunkerState>>,
+    clients: &BunkerClients,
+    mut gate: LockGate,
+    paused: &AtomicBool,
+) -> Result<Option<Event>> {
+    if !wait_for_unlock(&mut gate.locked, gate.timeout).await {
+        return Err(SignerError::Timeout);
+    }
+    handle_nip46_request(event, keys, key_manager, state, clients, paused.load(Ordering::SeqCst)).await
+}
+
+/// Returns true once unlocked, false if the timeout elapsed first
+async fn wait_for_unlock(locked: &mut watch::Receiver<bool>, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, locked.wait_for(|is_locked| !*is_locked)).await,
+        Ok(Ok(_))
+    )
 }
 
 #[cfg(test)]
@@ -511,5 +1596,803 @@ mod tests {
     fn test_url_encoding() {
         assert_eq!(urlencoding::encode("hello world"), "hello%20world");
         assert_eq!(urlencoding::encode("wss://relay.damus.io"), "wss%3A%2F%2Frelay.damus.io");
+        assert_eq!(urlencoding::decode(&urlencoding::encode("{\"name\": \"Plëb\"}")).unwrap(), "{\"name\": \"Plëb\"}");
+        assert_eq!(urlencoding::decode("bad%2"), None);
+    }
+
+    #[test]
+    fn test_bunker_uri_skips_invalid_relays() {
+        let relays = vec![
+            "  wss://relay.damus.io/ ".to_string(),
+            "https://relay.example.com".to_string(),
+            "wss://".to_string(),
+            "not a url".to_string(),
+            "ws://localhost:7777".to_string(),
+        ];
+        let uri = bunker_uri("npub1test", &relays, Some("s3cret"), None).unwrap();
+        assert_eq!(
+            uri,
+            "bunker://npub1test?relay=wss%3A%2F%2Frelay.damus.io&relay=ws%3A%2F%2Flocalhost%3A7777&secret=s3cret"
+        );
+    }
+
+    #[test]
+    fn test_bunker_uri_requires_a_valid_relay() {
+        let relays = vec!["http://relay.example.com".to_string(), "   ".to_string()];
+        assert!(matches!(bunker_uri("npub1test", &relays, None, None), Err(SignerError::ConfigError(_))));
+        assert!(bunker_uri("npub1test", &[], None, None).is_err());
+    }
+
+    #[test]
+    fn test_split_relay_routes() {
+        let relays = |urls: &[&str]| urls.iter().map(|u| u.to_string()).collect::<Vec<_>>();
+        let defaults = RelayRoutes::shared(relays(&["wss://a.example.com", "wss://b.example.com"]));
+        assert_eq!(defaults.inbound, defaults.outbound);
+
+        // Only outbound overridden: requests still arrive on the defaults
+        let routes = defaults.clone().split(&[], &relays(&["wss://out.example.com"]));
+        assert_eq!(routes.inbound, defaults.inbound);
+        assert_eq!(routes.outbound, relays(&["wss://out.example.com"]));
+
+        let routes = defaults.split(&relays(&["wss://in.example.com"]), &relays(&["wss://out.example.com", "wss://in.example.com"]));
+        assert_eq!(routes.all(), relays(&["wss://in.example.com", "wss://out.example.com"]));
+
+        // Subscriptions (and re-subscriptions after AUTH) only go to inbound relays
+        assert!(routes.is_inbound(&RelayUrl::parse("wss://in.example.com").unwrap()));
+        assert!(!routes.is_inbound(&RelayUrl::parse("wss://out.example.com").unwrap()));
+        assert!(!routes.is_inbound(&RelayUrl::parse("wss://a.example.com").unwrap()));
+
+        // The bunker URI lists every relay, since clients use one list both ways
+        let signer = BunkerSigner::new(Arc::new(Mutex::new(KeyManager::new())))
+            .with_relays(relays(&["wss://a.example.com"]))
+            .with_relay_routes(&[], &relays(&["wss://out.example.com"]));
+        assert_eq!(signer.relays(), relays(&["wss://a.example.com", "wss://out.example.com"]));
+    }
+
+    #[test]
+    fn test_relay_status_warning() {
+        let up = |url: &str, connected| (url.to_string(), connected);
+        assert_eq!(relay_status_warning(&[]), None);
+        assert_eq!(relay_status_warning(&[up("wss://a.example", true), up("wss://b.example", true)]), None);
+        assert_eq!(
+            relay_status_warning(&[up("wss://a.example", true), up("wss://b.example", false)]).as_deref(),
+            Some("1 of 2 relays connected")
+        );
+        let warning = relay_status_warning(&[up("wss://a.example", false), up("wss://b.example", false)]).unwrap();
+        assert!(warning.starts_with("0 of 2 relays connected"), "{}", warning);
+
+        // Nothing is reported before the bunker starts
+        let signer = BunkerSigner::new(Arc::new(Mutex::new(KeyManager::new())));
+        assert!(signer.relay_status().is_empty());
+    }
+
+    #[test]
+    fn test_bunker_uses_active_key_relays() {
+        let mut km = KeyManager::with_test_key("main", Keys::generate());
+        let defaults = BunkerSigner::new(Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate()))));
+        assert_eq!(defaults.relays(), DEFAULT_RELAYS);
+
+        km.apply_key_relays("main", &["wss://relay.example.com".to_string()]).unwrap();
+        let signer = BunkerSigner::new(Arc::new(Mutex::new(km)));
+        assert_eq!(signer.relays(), ["wss://relay.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_configured_key_used_over_active_key() {
+        let active = Keys::generate();
+        let bunker = Keys::generate();
+        let client_keys = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", active.clone());
+        km.add_test_key("bunker", bunker.clone());
+        km.apply_key_relays("bunker", &["wss://bunker.example.com".to_string()]).unwrap();
+        let key_manager = Arc::new(Mutex::new(km));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+
+        let signer = BunkerSigner::new(Arc::clone(&key_manager)).with_key(Some("bunker".into()));
+        assert_eq!(signer.relays(), ["wss://bunker.example.com"]);
+        let mut clients = paired(&bunker, &client_keys);
+        clients.key_name = signer.clients.key_name.clone();
+        assert_eq!(clients.signing_keys(&key_manager).await.unwrap().public_key(), bunker.public_key());
+
+        let request = nip46_request(&client_keys, &bunker, serde_json::json!({"id": "k", "method": "get_public_key", "params": []}));
+        let body = ask(request, &bunker, &client_keys, &key_manager, &state, &clients).await;
+        assert_eq!(body["result"], bunker.public_key().to_bech32().unwrap());
+
+        let unsigned = serde_json::json!({"kind": 1, "content": "remote", "tags": [], "created_at": 1_700_000_000});
+        let request = nip46_request(&client_keys, &bunker, serde_json::json!({"id": "s", "method": "sign_event", "params": [unsigned.to_string()]}));
+        let body = ask(request, &bunker, &client_keys, &key_manager, &state, &clients).await;
+        let signed: Event = serde_json::from_value(body["result"].clone()).unwrap();
+        assert_eq!(signed.pubkey, bunker.public_key());
+        assert_eq!(key_manager.lock().await.get_active_key_name(), Some("main"));
+
+        // A key that doesn't exist is refused before anything starts
+        let missing = BunkerSigner::new(Arc::clone(&key_manager)).with_key(Some("gone".into()));
+        assert!(matches!(missing.start_listening().await, Err(SignerError::ConfigError(_))));
+        assert!(missing.listener.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_locked_request_completes_after_unlock() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let request = serde_json::json!({"id": "held-1", "method": "ping", "params": []});
+        let content = nip04::encrypt(client_keys.secret_key(), &signer_keys.public_key(), request.to_string()).unwrap();
+        let event = EventBuilder::new(Kind::NostrConnect, content)
+            .tag(Tag::public_key(signer_keys.public_key()))
+            .sign_with_keys(&client_keys)
+            .unwrap();
+
+        let (lock_tx, locked) = watch::channel(true);
+        let (unlock_prompt, _prompts) = async_channel::unbounded();
+        let gate = LockGate { locked, unlock_prompt, timeout: Duration::from_secs(5) };
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = paired(&signer_keys, &client_keys);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            lock_tx.send_replace(false);
+        });
+
+        let paused = AtomicBool::new(false);
+        let response = process_after_unlock(&event, &signer_keys, &key_manager, &state, &clients, gate, &paused)
+            .await
+            .unwrap()
+            .expect("held request should produce a response");
+        let decrypted = nip04::decrypt(client_keys.secret_key(), &signer_keys.public_key(), &response.content).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&decrypted).unwrap();
+        assert_eq!(body["id"], "held-1");
+        assert_eq!(body["result"], "pong");
+    }
+
+    const TEST_SECRET: &str = "test-secret";
+
+    /// Client state for `signer_keys` with `client_keys` already paired
+    fn paired(signer_keys: &Keys, client_keys: &Keys) -> BunkerClients {
+        let clients = BunkerClients::default();
+        clients.load_pairing(None, &signer_keys.public_key(), Some(TEST_SECRET));
+        assert!(clients.pair(&client_keys.public_key(), Some(TEST_SECRET)));
+        clients
+    }
+
+    /// Build a NIP-46 request event from `client_keys` to `signer_keys`
+    fn nip46_request(client_keys: &Keys, signer_keys: &Keys, request: serde_json::Value) -> Event {
+        let content = nip04::encrypt(client_keys.secret_key(), &signer_keys.public_key(), request.to_string()).unwrap();
+        EventBuilder::new(Kind::NostrConnect, content)
+            .tag(Tag::public_key(signer_keys.public_key()))
+            .sign_with_keys(client_keys)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_request_before_restart_picked_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CURSOR_FILE);
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let now = Timestamp::now().as_secs();
+        let request_at = |secs_ago: u64, content: &str| {
+            EventBuilder::new(Kind::NostrConnect, content)
+                .tag(Tag::public_key(signer_keys.public_key()))
+                .custom_created_at(Timestamp::from(now - secs_ago))
+                .sign_with_keys(&client_keys)
+                .unwrap()
+        };
+        let handled = request_at(30, "handled");
+        // Reached the relay just before the restart, but was never delivered
+        let missed = request_at(40, "missed");
+
+        {
+            let mut cursor = BunkerCursor::load(path.clone(), &signer_keys.public_key());
+            // Nothing processed yet: start at now
+            assert_eq!(cursor.since(Timestamp::from(now)), Timestamp::from(now));
+            assert!(cursor.record(&handled));
+        }
+
+        // After the restart the subscription reaches back far enough...
+        let mut cursor = BunkerCursor::load(path.clone(), &signer_keys.public_key());
+        let since = cursor.since(Timestamp::from(now));
+        assert!(since <= missed.created_at);
+        assert!(since <= handled.created_at);
+
+        // ...but only the missed request is handled
+        assert!(!cursor.record(&handled));
+        assert!(cursor.record(&missed));
+
+        // Long downtimes don't replay ancient requests
+        let much_later = now + 86_400;
+        assert_eq!(cursor.since(Timestamp::from(much_later)), Timestamp::from(much_later - MAX_REPLAY_SECS));
+
+        // A different bunker key starts fresh
+        let other = BunkerCursor::load(path, &Keys::generate().public_key());
+        assert_eq!(other.last_seen, 0);
+    }
+
+    /// Decrypt a NIP-46 response event sent to `client_keys`
+    fn decrypt_response(client_keys: &Keys, signer_keys: &Keys, response: &Event) -> serde_json::Value {
+        let decrypted = nip04::decrypt(client_keys.secret_key(), &signer_keys.public_key(), &response.content).unwrap();
+        serde_json::from_str(&decrypted).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_paused_bunker_rejects_then_resumes() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = paired(&signer_keys, &client_keys);
+        let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "enc-1",
+            "method": "nip04_encrypt",
+            "params": [Keys::generate().public_key().to_hex(), "hello"],
+        }));
+
+        let paused = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, true)
+            .await.unwrap().unwrap();
+        let body = decrypt_response(&client_keys, &signer_keys, &paused);
+        assert_eq!(body["error"], "Remote signing is paused");
+        assert!(body["result"].is_null());
+
+        let resumed = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false)
+            .await.unwrap().unwrap();
+        let body = decrypt_response(&client_keys, &signer_keys, &resumed);
+        assert!(body["result"].as_str().is_some());
+        assert!(body.get("error").is_none());
+
+        // Non-signing methods still work while paused
+        let ping = nip46_request(&client_keys, &signer_keys, serde_json::json!({"id": "p", "method": "ping", "params": []}));
+        let pong = handle_nip46_request(&ping, &signer_keys, &key_manager, &state, &clients, true)
+            .await.unwrap().unwrap();
+        assert_eq!(decrypt_response(&client_keys, &signer_keys, &pong)["result"], "pong");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_method_refused_for_paired_client() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let signer = BunkerSigner::new(Arc::clone(&key_manager))
+            .with_disabled_methods(vec!["nip44_decrypt".into(), "connect".into(), "bogus".into()]);
+        let mut clients = paired(&signer_keys, &client_keys);
+        clients.disabled_methods = signer.clients.disabled_methods.clone();
+        // Only methods that use a key can be turned off
+        assert_eq!(clients.disabled_methods, ["nip44_decrypt"]);
+
+        let peer = Keys::generate();
+        let ciphertext = nip44::encrypt(peer.secret_key(), &signer_keys.public_key(), "secret", nip44::Version::default()).unwrap();
+        let decrypt = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "dec-1",
+            "method": "nip44_decrypt",
+            "params": [peer.public_key().to_hex(), ciphertext],
+        }));
+        let body = ask(decrypt, &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert_eq!(body["error"], "nip44_decrypt is disabled on this signer");
+        assert!(body["result"].is_null());
+
+        // Other methods are unaffected
+        let encrypt = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "enc-1",
+            "method": "nip44_encrypt",
+            "params": [peer.public_key().to_hex(), "hello"],
+        }));
+        let body = ask(encrypt, &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert!(body["result"].as_str().is_some());
+        assert!(body.get("error").is_none());
+    }
+
+    /// Handle `event` and decrypt the response
+    async fn ask(
+        event: Event,
+        signer_keys: &Keys,
+        client_keys: &Keys,
+        key_manager: &Arc<Mutex<KeyManager>>,
+        state: &Arc<Mutex<BunkerState>>,
+        clients: &BunkerClients,
+    ) -> serde_json::Value {
+        let response = handle_nip46_request(&event, signer_keys, key_manager, state, clients, false)
+            .await.unwrap().unwrap();
+        decrypt_response(client_keys, signer_keys, &response)
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PAIRING_FILE);
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = BunkerClients::default();
+        let secret = clients.load_pairing(Some(path.clone()), &signer_keys.public_key(), None);
+        assert_eq!(secret.len(), 32);
+        let signer_hex = signer_keys.public_key().to_hex();
+        let send = |request: serde_json::Value| {
+            let event = nip46_request(&client_keys, &signer_keys, request);
+            ask(event, &signer_keys, &client_keys, &key_manager, &state, &clients)
+        };
+
+        // Nothing works before connecting
+        let body = send(serde_json::json!({"id": "1", "method": "ping", "params": []})).await;
+        assert!(body["error"].as_str().unwrap().starts_with("Not connected"));
+        assert!(matches!(*state.lock().await, BunkerState::Disconnected));
+
+        // A wrong or missing secret is refused
+        let body = send(serde_json::json!({"id": "2", "method": "connect", "params": [signer_hex, "guess"]})).await;
+        assert_eq!(body["error"], "Invalid secret");
+        let body = send(serde_json::json!({"id": "3", "method": "connect", "params": [signer_hex]})).await;
+        assert_eq!(body["error"], "Invalid secret");
+        assert!(!clients.is_paired(&client_keys.public_key()));
+
+        // The right one pairs the client
+        let body = send(serde_json::json!({"id": "4", "method": "connect", "params": [signer_hex, secret]})).await;
+        assert_eq!(body["result"], "ack");
+        assert!(matches!(*state.lock().await, BunkerState::Connected { .. }));
+        let body = send(serde_json::json!({"id": "5", "method": "ping", "params": []})).await;
+        assert_eq!(body["result"], "pong");
+
+        // The secret and pairing survive a restart, in a file only we can read
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let restarted = BunkerClients::default();
+        assert_eq!(restarted.load_pairing(Some(path.clone()), &signer_keys.public_key(), None), secret);
+        assert!(restarted.is_paired(&client_keys.public_key()));
+
+        // Configuring a different secret unpairs everyone
+        let reconfigured = BunkerClients::default();
+        assert_eq!(reconfigured.load_pairing(Some(path), &signer_keys.public_key(), Some("fixed")), "fixed");
+        assert!(!reconfigured.is_paired(&client_keys.public_key()));
+    }
+
+    #[tokio::test]
+    async fn test_connect_ack_carries_signer_metadata() {
+        let identity = SignerIdentity {
+            name: "Office \"Signer\"".into(),
+            icon_url: Some("https://example.com/icon.png".into()),
+        };
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let mut clients = paired(&signer_keys, &client_keys);
+        clients.identity = identity.clone();
+
+        let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "c1",
+            "method": "connect",
+            "params": [signer_keys.public_key().to_hex(), TEST_SECRET],
+        }));
+        let body = ask(request, &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert_eq!(body["result"], "ack");
+        assert_eq!(body["metadata"]["name"], "Office \"Signer\"");
+        assert_eq!(body["metadata"]["image"], "https://example.com/icon.png");
+
+        // The URI carries the same object, percent-encoded
+        let uri = bunker_uri("npub1test", &["wss://relay.example.com".into()], None, Some(&identity)).unwrap();
+        let encoded = uri.split("metadata=").nth(1).unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(&urlencoding::decode(encoded).unwrap()).unwrap();
+        assert_eq!(metadata, identity.metadata());
+        assert_eq!(SignerIdentity::default().metadata(), serde_json::json!({"name": "Pleb Signer"}));
+    }
+
+    #[tokio::test]
+    async fn test_sign_event_request_round_trip() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let user_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", user_keys.clone())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = paired(&signer_keys, &client_keys);
+        let unsigned = serde_json::json!({"kind": 1, "content": "from afar", "tags": [], "created_at": 1_700_000_000});
+        let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "sign-1",
+            "method": "sign_event",
+            "params": [unsigned.to_string()],
+        }));
+
+        let response = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false)
+            .await.unwrap().expect("a response event");
+
+        // Addressed to the client, from the bunker key
+        assert_eq!(response.kind, Kind::NostrConnect);
+        assert_eq!(response.pubkey, signer_keys.public_key());
+        assert!(response.tags.public_keys().any(|p| *p == client_keys.public_key()));
+        assert!(response.verify().is_ok());
+
+        // The client can decrypt it and gets an event signed by the user's key
+        let body = decrypt_response(&client_keys, &signer_keys, &response);
+        assert_eq!(body["id"], "sign-1");
+        let signed: Event = serde_json::from_value(body["result"].clone()).unwrap();
+        assert!(signed.verify().is_ok());
+        assert_eq!(signed.pubkey, user_keys.public_key());
+        assert_eq!(signed.content, "from afar");
+        assert_eq!(signed.created_at, Timestamp::from(1_700_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_bunker_requests_are_audited() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let (sender, receiver) = async_channel::unbounded();
+        let mut clients = paired(&signer_keys, &client_keys);
+        clients.notices = Some(sender);
+        let unsigned = serde_json::json!({"kind": 7, "content": "+", "tags": [], "created_at": 1_700_000_000});
+        let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "sign-1",
+            "method": "sign_event",
+            "params": [unsigned.to_string()],
+        }));
+
+        handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false).await.unwrap();
+        handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, true).await.unwrap();
+        let ping = nip46_request(&client_keys, &signer_keys, serde_json::json!({"id": "p", "method": "ping", "params": []}));
+        handle_nip46_request(&ping, &signer_keys, &key_manager, &state, &clients, false).await.unwrap();
+
+        let entries: Vec<AuditEntry> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                AppMessage::BunkerActivity(entry) => Some(entry),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+        let client_id = bunker_app_id(&client_keys.public_key());
+        assert!(client_id.ends_with(&client_keys.public_key().to_bech32().unwrap()));
+        for entry in &entries {
+            assert_eq!(entry.app_id, client_id);
+            assert_eq!(entry.request_type, RequestType::SignEvent);
+            assert_eq!(entry.event_kind, Some(7));
+        }
+        assert!(entries[0].approved);
+        assert!(!entries[1].approved);
+        assert_eq!(entries[1].reason.as_deref(), Some("Remote signing is paused"));
+
+        // Recorded like any D-Bus request
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = crate::audit::AuditLog::load_from(dir.path().join("audit.jsonl")).await.unwrap();
+        for entry in entries {
+            log.record(entry).await.unwrap();
+        }
+        let log = crate::audit::AuditLog::load_from(dir.path().join("audit.jsonl")).await.unwrap();
+        assert_eq!(log.entries_for(&client_id).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sign_event_applies_unknown_kind_policy() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let mut clients = paired(&signer_keys, &client_keys);
+        clients.sign_policy = SignPolicy::new(crate::config::SecurityConfig {
+            unknown_kind_policy: crate::config::UnknownKindPolicy::Block,
+            ..Default::default()
+        });
+        let sign = |kind: u16| {
+            let unsigned = serde_json::json!({"kind": kind, "content": "hi", "tags": [], "created_at": 1_700_000_000});
+            nip46_request(&client_keys, &signer_keys, serde_json::json!({
+                "id": "sign",
+                "method": "sign_event",
+                "params": [unsigned.to_string()],
+            }))
+        };
+
+        let refused = handle_nip46_request(&sign(12345), &signer_keys, &key_manager, &state, &clients, false).await;
+        assert!(refused.unwrap_err().to_string().contains("12345"));
+        let body = ask(sign(1), &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert!(body["result"].is_object(), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_sign_event_applies_created_at_cap_and_content_scan() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let mut clients = paired(&signer_keys, &client_keys);
+        let sign = |content: &str, created_at: u64| {
+            let unsigned = serde_json::json!({"kind": 1, "content": content, "tags": [], "created_at": created_at});
+            nip46_request(&client_keys, &signer_keys, serde_json::json!({
+                "id": "sign",
+                "method": "sign_event",
+                "params": [unsigned.to_string()],
+            }))
+        };
+        let far_future = Timestamp::now().as_secs() + 86_400;
+
+        let refused = handle_nip46_request(&sign("later", far_future), &signer_keys, &key_manager, &state, &clients, false).await;
+        assert!(matches!(refused, Err(SignerError::InvalidRequest(_))));
+        let nsec = Keys::generate().secret_key().to_bech32().unwrap();
+        let refused = handle_nip46_request(&sign(&nsec, 1_700_000_000), &signer_keys, &key_manager, &state, &clients, false).await;
+        assert!(matches!(refused, Err(SignerError::PermissionDenied(_))));
+
+        clients.sign_policy = SignPolicy::new(crate::config::SecurityConfig {
+            future_created_at_policy: crate::config::FutureTimestampPolicy::Clamp,
+            ..Default::default()
+        });
+        let body = ask(sign("later", far_future), &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert!(body["result"]["created_at"].as_u64().unwrap() < far_future, "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_sign_event_keeps_tags() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate())));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = paired(&signer_keys, &client_keys);
+        let mentioned = Keys::generate().public_key().to_hex();
+        let tags = serde_json::json!([
+            ["t", "nostr"],
+            ["e", EventId::all_zeros().to_hex(), "wss://relay.example.com", "reply"],
+            ["p", mentioned],
+            ["client", "pleb"],
+        ]);
+        let sign = |tags: serde_json::Value| {
+            let unsigned = serde_json::json!({"kind": 1, "content": "hi", "tags": tags, "created_at": 1_700_000_000});
+            nip46_request(&client_keys, &signer_keys, serde_json::json!({
+                "id": "sign",
+                "method": "sign_event",
+                "params": [unsigned.to_string()],
+            }))
+        };
+
+        let body = ask(sign(tags.clone()), &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        let signed: Event = serde_json::from_value(body["result"].clone()).unwrap();
+        assert!(signed.verify().is_ok());
+        let signed_tags: Vec<Vec<String>> = signed.tags.iter().map(|t| t.as_slice().to_vec()).collect();
+        assert_eq!(serde_json::to_value(signed_tags).unwrap(), tags);
+
+        for bad in [serde_json::json!("t"), serde_json::json!([["t", 1]]), serde_json::json!([[]])] {
+            assert!(matches!(
+                handle_nip46_request(&sign(bad), &signer_keys, &key_manager, &state, &clients, false).await,
+                Err(SignerError::InvalidRequest(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reply_uses_request_encryption() {
+        let signer_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let ping = serde_json::json!({"id": "p", "method": "ping", "params": []});
+
+        // A NIP-44 client (e.g. nsec.app) gets a NIP-44 reply
+        let modern = Keys::generate();
+        let clients = paired(&signer_keys, &modern);
+        let content = nip44::encrypt(modern.secret_key(), &signer_keys.public_key(), ping.to_string(), nip44::Version::default()).unwrap();
+        let request = EventBuilder::new(Kind::NostrConnect, content)
+            .tag(Tag::public_key(signer_keys.public_key()))
+            .sign_with_keys(&modern)
+            .unwrap();
+        let response = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false)
+            .await.unwrap().unwrap();
+        let decrypted = nip44::decrypt(modern.secret_key(), &signer_keys.public_key(), &response.content).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&decrypted).unwrap()["result"], "pong");
+        assert!(nip04::decrypt(modern.secret_key(), &signer_keys.public_key(), &response.content).is_err());
+        assert_eq!(clients.scheme(&modern.public_key()), Nip46Encryption::Nip44);
+
+        // A NIP-04 client still gets NIP-04
+        let legacy = Keys::generate();
+        assert!(clients.pair(&legacy.public_key(), Some(TEST_SECRET)));
+        let request = nip46_request(&legacy, &signer_keys, ping);
+        let response = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false)
+            .await.unwrap().unwrap();
+        assert_eq!(decrypt_response(&legacy, &signer_keys, &response)["result"], "pong");
+        assert_eq!(clients.scheme(&legacy.public_key()), Nip46Encryption::Nip04);
+        assert_eq!(clients.scheme(&modern.public_key()), Nip46Encryption::Nip44);
+    }
+
+    #[tokio::test]
+    async fn test_mixed_encryption_clients_in_one_session() {
+        let signer_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let modern = Keys::generate();
+        let legacy = Keys::generate();
+        let clients = paired(&signer_keys, &modern);
+        assert!(clients.pair(&legacy.public_key(), Some(TEST_SECRET)));
+
+        let nip44_request = |id: &str| {
+            let ping = serde_json::json!({"id": id, "method": "ping", "params": []});
+            let content = nip44::encrypt(modern.secret_key(), &signer_keys.public_key(), ping.to_string(), nip44::Version::default()).unwrap();
+            EventBuilder::new(Kind::NostrConnect, content)
+                .tag(Tag::public_key(signer_keys.public_key()))
+                .sign_with_keys(&modern)
+                .unwrap()
+        };
+        let nip04_request = |id: &str| nip46_request(&legacy, &signer_keys, serde_json::json!({"id": id, "method": "ping", "params": []}));
+
+        // Requests from both clients interleave; each reply follows its own client
+        for round in 0..3 {
+            let (modern_id, legacy_id) = (format!("m{}", round), format!("l{}", round));
+            let (modern_event, legacy_event) = (nip44_request(&modern_id), nip04_request(&legacy_id));
+            let (to_modern, to_legacy) = tokio::join!(
+                handle_nip46_request(&modern_event, &signer_keys, &key_manager, &state, &clients, false),
+                handle_nip46_request(&legacy_event, &signer_keys, &key_manager, &state, &clients, false),
+            );
+            let (to_modern, to_legacy) = (to_modern.unwrap().unwrap(), to_legacy.unwrap().unwrap());
+
+            let decrypted = nip44::decrypt(modern.secret_key(), &signer_keys.public_key(), &to_modern.content).unwrap();
+            assert_eq!(serde_json::from_str::<serde_json::Value>(&decrypted).unwrap()["id"], modern_id.as_str());
+            assert!(nip04::decrypt(modern.secret_key(), &signer_keys.public_key(), &to_modern.content).is_err());
+            assert_eq!(decrypt_response(&legacy, &signer_keys, &to_legacy)["id"], legacy_id.as_str());
+            assert!(nip44::decrypt(legacy.secret_key(), &signer_keys.public_key(), &to_legacy.content).is_err());
+        }
+        assert_eq!(clients.scheme(&modern.public_key()), Nip46Encryption::Nip44);
+        assert_eq!(clients.scheme(&legacy.public_key()), Nip46Encryption::Nip04);
+    }
+
+    #[tokio::test]
+    async fn test_get_relays_reports_routes() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let mut clients = paired(&signer_keys, &client_keys);
+        clients.routes = RelayRoutes::shared(vec!["wss://a.example".into(), "wss://b.example".into()])
+            .split(&[], &["wss://b.example".into(), "wss://c.example".into()]);
+
+        let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({"id": "r", "method": "get_relays", "params": []}));
+        let body = ask(request, &signer_keys, &client_keys, &key_manager, &state, &clients).await;
+        assert_eq!(body["result"], serde_json::json!({
+            "wss://a.example": {"read": true, "write": false},
+            "wss://b.example": {"read": true, "write": true},
+            "wss://c.example": {"read": false, "write": true},
+        }));
+        assert!(body.get("error").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nip44_encrypt_accepts_hex_and_npub() {
+        let signer_keys = Keys::generate();
+        let client_keys = Keys::generate();
+        let recipient = Keys::generate();
+        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let state = Arc::new(Mutex::new(BunkerState::Disconnected));
+        let clients = paired(&signer_keys, &client_keys);
+
+        for pubkey in [recipient.public_key().to_hex(), recipient.public_key().to_bech32().unwrap()] {
+            let request = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+                "id": "enc",
+                "method": "nip44_encrypt",
+                "params": [pubkey, "hello"],
+            }));
+            let response = handle_nip46_request(&request, &signer_keys, &key_manager, &state, &clients, false)
+                .await.unwrap().unwrap();
+            let body = decrypt_response(&client_keys, &signer_keys, &response);
+            let ciphertext = body["result"].as_str().expect("ciphertext");
+
+            // The recipient can read it
+            let plaintext = nip44::decrypt(recipient.secret_key(), &signer_keys.public_key(), ciphertext).unwrap();
+            assert_eq!(plaintext, "hello");
+        }
+
+        let bad = nip46_request(&client_keys, &signer_keys, serde_json::json!({
+            "id": "bad",
+            "method": "nip44_encrypt",
+            "params": ["not-a-pubkey", "hello"],
+        }));
+        assert!(matches!(
+            handle_nip46_request(&bad, &signer_keys, &key_manager, &state, &clients, false).await,
+            Err(SignerError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_relay_auth_challenge_answered() {
+        let keys = Keys::generate();
+        let relay_url = RelayUrl::parse("wss://auth.example.com").unwrap();
+        let mut auth = RelayAuth::default();
+
+        // The mock relay challenges us
+        let action = auth.handle_message(&keys, &relay_url, &RelayMessage::auth("challenge-123"));
+        let AuthAction::Send(message) = action else {
+            panic!("expected an AUTH response, got {:?}", action);
+        };
+        let ClientMessage::Auth(event) = *message else {
+            panic!("expected an AUTH response, got {:?}", message);
+        };
+        assert_eq!(event.kind, Kind::Authentication);
+        assert_eq!(event.pubkey, keys.public_key());
+        assert!(event.verify().is_ok());
+        assert!(event.tags.iter().any(|t| t.as_slice() == ["challenge", "challenge-123"]));
+        assert!(event.tags.iter().any(|t| t.as_slice() == ["relay", "wss://auth.example.com"]));
+        assert_eq!(auth.status[relay_url.as_str()], RelayAuthStatus::Pending);
+
+        // It accepts, so we resubscribe
+        let accepted = auth.handle_message(&keys, &relay_url, &RelayMessage::ok(event.id, true, ""));
+        assert!(matches!(accepted, AuthAction::Resubscribe));
+        assert_eq!(auth.status[relay_url.as_str()], RelayAuthStatus::Authenticated);
+
+        // A second challenge is rejected and surfaced as a failure
+        let AuthAction::Send(message) = auth.handle_message(&keys, &relay_url, &RelayMessage::auth("challenge-456")) else {
+            panic!("expected an AUTH response");
+        };
+        let ClientMessage::Auth(retry) = *message else {
+            panic!("expected an AUTH response");
+        };
+        let rejected = auth.handle_message(&keys, &relay_url, &RelayMessage::ok(retry.id, false, "restricted: not a member"));
+        assert!(matches!(rejected, AuthAction::None));
+        assert_eq!(
+            auth.status[relay_url.as_str()],
+            RelayAuthStatus::Failed("restricted: not a member".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_burst_is_paced() {
+        let keys = Keys::generate();
+        let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+        let rate = ResponseRate { per_sec: 20, backlog: 10 };
+        let throttle = ResponseThrottle::spawn(rate, move |_event| {
+            let sent_tx = sent_tx.clone();
+            async move {
+                let _ = sent_tx.send(tokio::time::Instant::now());
+            }
+        });
+
+        let start = tokio::time::Instant::now();
+        for i in 0..5 {
+            let event = EventBuilder::text_note(format!("response {}", i)).sign_with_keys(&keys).unwrap();
+            assert!(throttle.enqueue(event));
+        }
+
+        let mut sent = Vec::new();
+        for _ in 0..5 {
+            sent.push(sent_rx.recv().await.unwrap());
+        }
+        // 5 responses at 20/s: the first goes out at once, the rest 50ms apart
+        assert!(sent[4] - start >= Duration::from_millis(195));
+        for pair in sent.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(45));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_backlog_overflow_dropped() {
+        let keys = Keys::generate();
+        let rate = ResponseRate { per_sec: 1, backlog: 2 };
+        let throttle = ResponseThrottle::spawn(rate, |_event| async {});
+
+        let accepted = (0..10)
+            .filter(|i| {
+                let event = EventBuilder::text_note(format!("response {}", i)).sign_with_keys(&keys).unwrap();
+                throttle.enqueue(event)
+            })
+            .count();
+        // The backlog holds 2, plus at most one already taken by the pacer
+        assert!((2..=3).contains(&accepted));
+    }
+
+    #[tokio::test]
+    async fn test_locked_request_times_out() {
+        let (_lock_tx, mut locked) = watch::channel(true);
+        assert!(!wait_for_unlock(&mut locked, Duration::from_millis(50)).await);
+    }
+
+    #[tokio::test]
+    async fn test_listener_shut_down_joins_thread() {
+        // Stands in for the listener: its own runtime, parked until woken
+        let shutdown = Arc::new(tokio::sync::Notify::new());
+        let signal = Arc::clone(&shutdown);
+        let thread = std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(signal.notified());
+        });
+        let listener = Listener { thread, shutdown };
+        assert!(listener.shut_down(Duration::from_secs(5)).await);
+
+        // A thread that never exits is left behind rather than blocking stop
+        let (_hold, wait) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let _ = wait.recv();
+        });
+        let listener = Listener { thread, shutdown: Arc::new(tokio::sync::Notify::new()) };
+        assert!(!listener.shut_down(Duration::from_millis(50)).await);
     }
 }
@@ -15,6 +15,25 @@ pub struct SignerResponse {
     pub error: Option<String>,
 }
 
+/// Response whose result is a JSON value rather than a string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerObjectResponse {
+    pub success: bool,
+    pub id: String,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+/// One key's outcome from `sign_event_multi`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeySignResult {
+    pub key: String,
+    #[serde(default)]
+    pub event: Option<nostr::Event>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
 /// Public key response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicKeyResult {
@@ -28,6 +47,12 @@ pub struct SignedEventResult {
     pub event_json: String,
     pub signature: String,
     pub event_id: String,
+    /// NIP-19 `nevent1...` (with any relay hints that were passed)
+    #[serde(default)]
+    pub nevent: String,
+    /// NIP-19 `note1...`
+    #[serde(default)]
+    pub note: String,
 }
 
 /// Encryption result
@@ -42,6 +67,13 @@ pub struct DecryptResult {
     pub plaintext: String,
 }
 
+/// NIP-26 delegation result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationResult {
+    /// `["delegation", <delegator hex>, <conditions>, <signature>]`
+    pub tag: Vec<String>,
+}
+
 /// Key info
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyInfo {
@@ -52,6 +84,120 @@ pub struct KeyInfo {
     pub is_default: bool,
 }
 
+/// Signer branding info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerInfo {
+    pub name: String,
+    pub version: String,
+    /// 22x22 PNG icon, base64 encoded
+    pub icon_png_base64: String,
+}
+
+/// Features the signer supports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Version of the `com.plebsigner.Signer1` interface
+    pub interface_version: u32,
+    /// e.g. `nip04`, `nip44`, `nip46`, `sign_event`, `get_public_key`
+    pub capabilities: Vec<String>,
+}
+
+impl Capabilities {
+    /// Whether the signer advertises `capability`
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+/// Heartbeat response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingResult {
+    /// Server time in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    /// Number of pings the signer has handled
+    pub counter: u64,
+    /// Measured round-trip time
+    #[serde(skip)]
+    pub latency: std::time::Duration,
+}
+
+/// What the calling app is allowed to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppPermissionsInfo {
+    pub get_public_key: bool,
+    /// None = all kinds, Some([]) = none, Some([1, 4]) = only these kinds
+    pub sign_event: Option<Vec<u16>>,
+    pub nip04_encrypt: bool,
+    pub nip04_decrypt: bool,
+    pub nip44_encrypt: bool,
+    pub nip44_decrypt: bool,
+    pub decrypt_zap_event: bool,
+    #[serde(default)]
+    pub create_delegation: bool,
+    #[serde(default)]
+    pub daily_sign_quota: Option<u32>,
+}
+
+/// Permissions the signer resolved for this client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MyPermissions {
+    /// False if the app hasn't been authorized (everything is denied)
+    pub authorized: bool,
+    /// Identity the signer knows this client by
+    pub app_id: String,
+    pub permissions: AppPermissionsInfo,
+}
+
+/// Result of a NIP-05 lookup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Nip05Verification {
+    /// The identifier maps to the pubkey
+    pub valid: bool,
+    /// Relays the domain lists for the pubkey
+    pub relays: Vec<String>,
+}
+
+/// A relay from a NIP-65 relay list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayListEntry {
+    pub url: String,
+    pub read: bool,
+    pub write: bool,
+}
+
+/// What one queried relay returned for `GetRelays`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelaySource {
+    pub source: String,
+    /// "ok" or "failed"
+    pub status: String,
+    /// The relay list it returned (None if it has none, or it failed)
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+    /// Why the relay failed
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Result of `GetRelays`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelaysResult {
+    /// The newest relay list found
+    pub relays: Vec<RelayListEntry>,
+    /// One entry per queried relay
+    pub sources: Vec<RelaySource>,
+}
+
+impl RelaysResult {
+    /// Relays that couldn't be queried, with the reason
+    pub fn failed_sources(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.sources
+            .iter()
+            .filter(|s| s.status == "failed")
+            .map(|s| (s.source.as_str(), s.reason.as_deref().unwrap_or_default()))
+    }
+}
+
 /// Client error type that is Send + Sync
 #[derive(Debug, Clone)]
 pub struct ClientError(pub String);
@@ -88,6 +234,49 @@ impl From<&str> for ClientError {
     }
 }
 
+/// A change to the signer's keys, from its `KeyAdded`, `KeyRemoved` and
+/// `ActiveKeyChanged` signals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyChange {
+    Added(String),
+    Removed(String),
+    /// npub of the new active key, empty when no key is active
+    ActiveChanged(String),
+}
+
+impl KeyChange {
+    /// Parse a key signal; None for any other message
+    pub fn from_message(message: &zbus::Message) -> Option<Self> {
+        let header = message.header();
+        let member = header.member()?;
+        let change: fn(String) -> Self = match member.as_str() {
+            "KeyAdded" => Self::Added,
+            "KeyRemoved" => Self::Removed,
+            "ActiveKeyChanged" => Self::ActiveChanged,
+            _ => return None,
+        };
+        message.body().deserialize::<String>().ok().map(change)
+    }
+}
+
+/// Key changes as they happen, from [`PlebSignerClient::key_changes`]
+pub struct KeyChanges {
+    signals: zbus::proxy::SignalStream<'static>,
+}
+
+impl KeyChanges {
+    /// Wait for the next key change (None once the connection closes)
+    pub async fn next(&mut self) -> Option<KeyChange> {
+        use futures_util::StreamExt;
+        while let Some(message) = self.signals.next().await {
+            if let Some(change) = KeyChange::from_message(&message) {
+                return Some(change);
+            }
+        }
+        None
+    }
+}
+
 /// Pleb Signer client
 pub struct PlebSignerClient {
     connection: Connection,
@@ -117,6 +306,30 @@ impl PlebSignerClient {
         proxy.is_ok()
     }
 
+    /// Ping the signer and measure round-trip latency
+    pub async fn ping(&self) -> Result<PingResult, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let started = std::time::Instant::now();
+        let result: String = proxy.call("Ping", &()).await?;
+        let latency = started.elapsed();
+
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let mut ping: PingResult = serde_json::from_str(&response.result.unwrap_or_default())?;
+            ping.latency = latency;
+            Ok(ping)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// Check if the signer is unlocked and ready
     pub async fn is_ready(&self) -> Result<bool, ClientError> {
         let proxy = Proxy::new(
@@ -145,6 +358,106 @@ impl PlebSignerClient {
         Ok(result)
     }
 
+    /// Get the signer's name, version and icon
+    pub async fn signer_info(&self) -> Result<SignerInfo, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("GetSignerInfo", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let info: SignerInfo = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(info)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Get the features the signer supports, so missing ones can be hidden
+    pub async fn capabilities(&self) -> Result<Capabilities, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("GetCapabilities", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let capabilities: Capabilities = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(capabilities)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Get the permissions granted to this app
+    pub async fn my_permissions(&self) -> Result<MyPermissions, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("GetMyPermissions", &(&self.app_id,)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let permissions: MyPermissions = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(permissions)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Check that a NIP-05 identifier (`name@domain`) maps to a pubkey
+    pub async fn verify_nip05(&self, identifier: &str, pubkey: &str) -> Result<Nip05Verification, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("VerifyNip05", &(identifier, pubkey)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let verification: Nip05Verification = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(verification)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Look up the NIP-65 relay list of a key (None = active key)
+    pub async fn get_relays(&self, key_id: Option<&str>) -> Result<RelaysResult, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("GetRelays", &(key_id.unwrap_or(""), &self.app_id)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let relays: RelaysResult = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(relays)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// List all available keys
     pub async fn list_keys(&self) -> Result<Vec<KeyInfo>, ClientError> {
         let proxy = Proxy::new(
@@ -160,6 +473,46 @@ impl PlebSignerClient {
         Ok(keys)
     }
 
+    /// Make the signer re-read keys changed by another process
+    pub async fn reload_keys(&self) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ReloadKeys", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Subscribe to key changes; changes made after this returns are seen
+    pub async fn key_changes(&self) -> Result<KeyChanges, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let signals = proxy.receive_all_signals().await?;
+        Ok(KeyChanges { signals })
+    }
+
+    /// Wait for the next key to be added, removed or activated
+    pub async fn next_key_change(&self) -> Result<KeyChange, ClientError> {
+        self.key_changes().await?
+            .next().await
+            .ok_or_else(|| ClientError("Connection closed".into()))
+    }
+
     /// Get the public key
     pub async fn get_public_key(
         &self,
@@ -174,7 +527,7 @@ impl PlebSignerClient {
         .await?;
 
         let key_id_str = key_id.unwrap_or("");
-        let result: String = proxy.call("GetPublicKey", &(key_id_str,)).await?;
+        let result: String = proxy.call("GetPublicKey", &(key_id_str, &self.app_id)).await?;
 
         let response: SignerResponse = serde_json::from_str(&result)?;
         if response.success {
@@ -215,6 +568,89 @@ impl PlebSignerClient {
         }
     }
 
+    /// Sign an event and get the signed event back as an object
+    pub async fn sign_event_object(
+        &self,
+        event_json: &str,
+        key_id: Option<&str>,
+    ) -> Result<nostr::Event, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let key_id_str = key_id.unwrap_or("");
+        let result: String = proxy
+            .call("SignEventObject", &(event_json, key_id_str, &self.app_id))
+            .await?;
+
+        let response: SignerObjectResponse = serde_json::from_str(&result)?;
+        match response.result {
+            Some(event) if response.success => Ok(serde_json::from_value(event)?),
+            _ => Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into()))),
+        }
+    }
+
+    /// Sign the same event with several keys (empty = every key that can
+    /// sign); each key gets its own result, so one failure doesn't lose the rest
+    pub async fn sign_event_multi(
+        &self,
+        event_json: &str,
+        key_names: &[&str],
+    ) -> Result<Vec<KeySignResult>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy
+            .call("SignEventMulti", &(event_json, key_names, &self.app_id))
+            .await?;
+
+        let response: SignerObjectResponse = serde_json::from_str(&result)?;
+        match response.result {
+            Some(results) if response.success => Ok(serde_json::from_value(results)?),
+            _ => Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into()))),
+        }
+    }
+
+    /// Sign several events in order; with `sequential`, their created_at
+    /// values become base + index seconds so relays keep them in order
+    pub async fn sign_event_batch(
+        &self,
+        events_json: &str,
+        sequential: bool,
+        key_id: Option<&str>,
+    ) -> Result<Vec<SignedEventResult>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let key_id_str = key_id.unwrap_or("");
+        let result: String = proxy
+            .call("SignEventBatch", &(events_json, sequential, key_id_str, &self.app_id))
+            .await?;
+
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let signed: Vec<SignedEventResult> =
+                serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(signed)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// NIP-04 encrypt
     pub async fn nip04_encrypt(
         &self,
@@ -347,6 +783,240 @@ impl PlebSignerClient {
         }
     }
 
+    /// Create a NIP-26 delegation for `delegatee_pubkey`, limited by
+    /// `conditions` (e.g. `kind=1&created_at<1700000000`); returns the
+    /// `delegation` tag for the delegatee's events
+    pub async fn create_delegation(
+        &self,
+        delegatee_pubkey: &str,
+        conditions: &str,
+        key_id: Option<&str>,
+    ) -> Result<Vec<String>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let key_id_str = key_id.unwrap_or("");
+        let result: String = proxy
+            .call(
+                "CreateDelegation",
+                &(delegatee_pubkey, conditions, key_id_str, &self.app_id),
+            )
+            .await?;
+
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let delegation: DelegationResult =
+                serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(delegation.tag)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Ask the running signer to show its window
+    pub async fn show_window(&self) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ShowWindow", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Requests waiting for approval, oldest first (only for Pleb Signer's UI)
+    pub async fn list_pending_requests(&self) -> Result<Vec<crate::signing::SigningRequest>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ListPendingRequests", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(serde_json::from_str(&response.result.unwrap_or_default())?)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Approve or reject a pending request (only for Pleb Signer's UI);
+    /// `reason` is told to the app on rejection unless it's empty
+    pub async fn answer_request(&self, request_id: &str, approve: bool, reason: &str) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = if approve {
+            proxy.call("ApproveRequest", &(request_id,)).await?
+        } else {
+            proxy.call("RejectRequest", &(request_id, reason)).await?
+        };
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Approve a pending request and the app's further requests for the
+    /// configured session length (only for Pleb Signer's UI)
+    pub async fn approve_for_session(&self, request_id: &str) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ApproveRequestForSession", &(request_id,)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Approve a pending request, signing with `key_id` instead of the
+    /// requested key (only for Pleb Signer's UI)
+    pub async fn approve_with_key(&self, request_id: &str, key_id: &str) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ApproveRequestWithKey", &(request_id, key_id)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Report whether the signer's own window has focus (signer UI only)
+    pub async fn set_window_focused(&self, focused: bool) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("SetWindowFocused", &(focused,)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Lock the signer; requests fail until it is unlocked again
+    pub async fn lock(&self) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("Lock", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Make the signer re-read its config file
+    ///
+    /// Returns the changed settings that need a restart to apply.
+    pub async fn reload_config(&self) -> Result<Vec<String>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ReloadConfig", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(serde_json::from_str(&response.result.unwrap_or_default())?)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Unlock the signer (succeeds if it is already unlocked)
+    pub async fn unlock(&self, password: &str) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("Unlock", &(password,)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Unlock a key that has its own password
+    pub async fn unlock_key(&self, key_id: &str, password: &str) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("UnlockKey", &(key_id, password)).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// Start the bunker listener and get the connection URI
     pub async fn start_bunker(&self) -> Result<String, ClientError> {
         let proxy = Proxy::new(
@@ -389,6 +1059,44 @@ impl PlebSignerClient {
         }
     }
 
+    /// Pause the bunker without disconnecting from relays
+    pub async fn pause_bunker(&self) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("PauseBunker", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
+    /// Resume a paused bunker
+    pub async fn resume_bunker(&self) -> Result<(), ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("ResumeBunker", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            Ok(())
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// Get the current bunker state
     pub async fn get_bunker_state(&self) -> Result<String, ClientError> {
         let proxy = Proxy::new(
@@ -410,6 +1118,26 @@ impl PlebSignerClient {
         }
     }
 
+    /// Get each bunker relay and whether it is connected
+    pub async fn get_bunker_relay_status(&self) -> Result<Vec<(String, bool)>, ClientError> {
+        let proxy = Proxy::new(
+            &self.connection,
+            "com.plebsigner.Signer",
+            "/com/plebsigner/Signer",
+            "com.plebsigner.Signer1",
+        )
+        .await?;
+
+        let result: String = proxy.call("GetBunkerRelayStatus", &()).await?;
+        let response: SignerResponse = serde_json::from_str(&result)?;
+        if response.success {
+            let status = serde_json::from_str(&response.result.unwrap_or_default())?;
+            Ok(status)
+        } else {
+            Err(ClientError(response.error.unwrap_or_else(|| "Unknown error".into())))
+        }
+    }
+
     /// Get the bunker URI (without starting)
     pub async fn get_bunker_uri(&self) -> Result<String, ClientError> {
         let proxy = Proxy::new(
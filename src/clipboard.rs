@@ -0,0 +1,96 @@
+//! Copying secrets to the clipboard and clearing them again
+//!
+//! On Wayland the clipboard is driven through `wl-copy`/`wl-paste`
+//! (arboard doesn't work there), elsewhere through arboard. After
+//! `security.clipboard_clear_secs` the clipboard is emptied, but only if it
+//! still holds what we put there; anything the user copied since is left
+//! alone.
+
+use std::time::Duration;
+
+/// Put `text` on the clipboard and clear it after `clear_secs` (0 = never)
+pub fn copy_sensitive(text: &str, clear_secs: u64) -> Result<(), String> {
+    set_text(text)?;
+    if clear_secs > 0 {
+        let copied = text.to_string();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(clear_secs));
+            if should_clear(get_text().as_deref(), &copied) {
+                if let Err(e) = clear() {
+                    tracing::warn!("Couldn't clear the clipboard: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Whether the clipboard still holds the value we copied
+fn should_clear(current: Option<&str>, copied: &str) -> bool {
+    // wl-paste may hand back a trailing newline
+    current.is_some_and(|current| current.trim_end_matches('\n') == copied)
+}
+
+fn on_wayland() -> bool {
+    std::env::var("WAYLAND_DISPLAY").is_ok()
+}
+
+fn set_text(text: &str) -> Result<(), String> {
+    if on_wayland() {
+        let status = std::process::Command::new("wl-copy")
+            .arg(text)
+            .status()
+            .map_err(|e| format!("wl-copy: {}", e))?;
+        if !status.success() {
+            return Err(format!("wl-copy exited with {}", status));
+        }
+        Ok(())
+    } else {
+        arboard::Clipboard::new()
+            .and_then(|mut clip| clip.set_text(text.to_string()))
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn get_text() -> Option<String> {
+    if on_wayland() {
+        let output = std::process::Command::new("wl-paste")
+            .arg("--no-newline")
+            .output()
+            .ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        arboard::Clipboard::new().and_then(|mut clip| clip.get_text()).ok()
+    }
+}
+
+fn clear() -> Result<(), String> {
+    if on_wayland() {
+        std::process::Command::new("wl-copy")
+            .arg("--clear")
+            .status()
+            .map(|_| ())
+            .map_err(|e| format!("wl-copy: {}", e))
+    } else {
+        arboard::Clipboard::new()
+            .and_then(|mut clip| clip.clear())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clears_only_our_value() {
+        let uri = "bunker://abc?relay=wss://relay.example&secret=s3cret";
+        assert!(should_clear(Some(uri), uri));
+        assert!(should_clear(Some(&format!("{}\n", uri)), uri));
+        // The user copied something else in the meantime
+        assert!(!should_clear(Some("lunch at noon"), uri));
+        assert!(!should_clear(Some(""), uri));
+        // Clipboard empty or unreadable
+        assert!(!should_clear(None, uri));
+    }
+}
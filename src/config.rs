@@ -2,10 +2,24 @@
 
 use crate::error::{Result, SignerError};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use directories::ProjectDirs;
 use tokio::fs;
 
+/// Environment variable pointing at a specific config file (`--config` sets it)
+pub const CONFIG_ENV: &str = "PLEB_SIGNER_CONFIG";
+
+/// Legacy encrypted key file in the data dir
+pub(crate) const KEYS_FILE: &str = "keys.enc";
+
+/// Themes the UI understands
+pub const THEMES: [&str; 3] = ["light", "dark", "system"];
+
+/// Window opacity the UI accepts; lower values leave dialogs unreadable
+pub const WINDOW_OPACITY: std::ops::RangeInclusive<f32> = 0.3..=1.0;
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -25,9 +39,22 @@ pub struct Config {
     #[serde(default)]
     pub ui: UiConfig,
 
+    /// Bunker (NIP-46) settings
+    #[serde(default)]
+    pub bunker: BunkerConfig,
+
     /// List of authorized applications
     #[serde(default)]
     pub authorized_apps: Vec<AuthorizedApp>,
+
+    /// Extra signing engines with their own key sets (empty = single engine)
+    #[serde(default)]
+    pub engines: Vec<EngineConfig>,
+
+    /// Started with `--safe-mode`: bunker and auto-approval are disabled at
+    /// runtime and security settings are never written back to disk
+    #[serde(skip)]
+    pub safe_mode: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +74,21 @@ pub struct GeneralConfig {
     /// Default timeout for signing requests (seconds)
     #[serde(default = "default_timeout")]
     pub request_timeout_secs: u64,
+
+    /// What to do at startup when keys exist but none is active
+    #[serde(default)]
+    pub missing_active_key: MissingActiveKeyPolicy,
+}
+
+/// Startup handling of key metadata without an active key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingActiveKeyPolicy {
+    /// Activate the most recently created key
+    #[default]
+    SelectNewest,
+    /// Leave it unset and ask the user to pick one in the UI
+    Prompt,
 }
 
 impl Default for GeneralConfig {
@@ -56,6 +98,7 @@ impl Default for GeneralConfig {
             auto_start: false,
             show_notifications: true,
             request_timeout_secs: 60,
+            missing_active_key: MissingActiveKeyPolicy::SelectNewest,
         }
     }
 }
@@ -70,6 +113,10 @@ pub struct SecurityConfig {
     #[serde(default = "default_lock_timeout")]
     pub lock_timeout_mins: u64,
 
+    /// Warn this many seconds before the inactivity lock (0 = no warning)
+    #[serde(default = "default_lock_warning")]
+    pub lock_warning_secs: u64,
+
     /// Require confirmation for all signing requests
     #[serde(default = "default_true")]
     pub always_confirm: bool,
@@ -78,9 +125,129 @@ pub struct SecurityConfig {
     #[serde(default)]
     pub allow_auto_approve: bool,
 
+    /// Auto-approve reads (public key, decrypt) for authorized apps and
+    /// always prompt for writes (sign, encrypt)
+    #[serde(default)]
+    pub auto_approve_read_only: bool,
+
     /// Maximum number of auto-approvals per minute (rate limiting)
     #[serde(default = "default_rate_limit")]
     pub max_auto_approvals_per_min: u32,
+
+    /// What to do when asked to sign an event kind outside the known set
+    #[serde(default)]
+    pub unknown_kind_policy: UnknownKindPolicy,
+
+    /// Gate NIP-04/NIP-44 decryption behind approval (when always_confirm is on)
+    #[serde(default)]
+    pub confirm_decrypt: bool,
+
+    /// Length of an "allow this app for a while" session grant (minutes)
+    #[serde(default = "default_session_grant")]
+    pub session_grant_mins: u64,
+
+    /// How far ahead of now an event's created_at may be (seconds, 0 = no cap)
+    #[serde(default = "default_max_future")]
+    pub max_future_created_at_secs: u64,
+
+    /// What to do with events dated beyond the cap
+    #[serde(default)]
+    pub future_created_at_policy: FutureTimestampPolicy,
+
+    /// Skip approval prompts for requests from Pleb Signer's own UI
+    #[serde(default = "default_true")]
+    pub trust_own_ui: bool,
+
+    /// Executables allowed to call the D-Bus API (empty = any)
+    #[serde(default)]
+    pub allowed_executables: Vec<String>,
+
+    /// Hardware presence check required after approving a request
+    #[serde(default)]
+    pub presence_check: PresenceCheck,
+
+    /// Let apps missing from `authorized_apps` sign and encrypt (subject to
+    /// the other checks); otherwise they are refused
+    #[serde(default)]
+    pub authorize_unknown_apps: bool,
+
+    /// Alert when an app is refused more than this many times within
+    /// `denial_alert_window_mins` (0 = never alert)
+    #[serde(default = "default_denial_threshold")]
+    pub denial_alert_threshold: u32,
+
+    /// Window for counting refusals (minutes)
+    #[serde(default = "default_denial_window")]
+    pub denial_alert_window_mins: u64,
+
+    /// Requests allowed to wait for approval at once; more are refused as
+    /// busy (0 = unlimited)
+    #[serde(default = "default_max_pending")]
+    pub max_pending_requests: usize,
+
+    /// Ask for confirmation when an event's content looks like it contains
+    /// an nsec or a recovery phrase
+    #[serde(default = "default_true")]
+    pub scan_content_for_secrets: bool,
+
+    /// Ask for confirmation when an event's content contains one of these
+    /// (case-insensitive)
+    #[serde(default)]
+    pub sensitive_keywords: Vec<String>,
+
+    /// scrypt cost (log2 of N) for NIP-49 key exports, 14-22; higher is
+    /// slower to brute-force and to decrypt
+    #[serde(default = "default_ncryptsec_log_n")]
+    pub ncryptsec_log_n: u8,
+
+    /// Event kinds never signed, whatever app asks (e.g. 4 for NIP-04 DMs,
+    /// 5 for deletions)
+    #[serde(default)]
+    pub blocked_event_kinds: Vec<u16>,
+
+    /// Seconds before a copied bunker URI or key is cleared from the
+    /// clipboard (0 = never)
+    #[serde(default = "default_clipboard_clear_secs")]
+    pub clipboard_clear_secs: u64,
+
+    /// Apps the user asked not to be warned about using NIP-04
+    #[serde(default)]
+    pub nip04_warning_suppressed: Vec<String>,
+}
+
+/// Proof of presence asked for after the user approves a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceCheck {
+    /// Approval alone is enough
+    #[default]
+    Off,
+    /// Fingerprint scan through fprintd
+    Fingerprint,
+}
+
+/// Handling of events dated too far in the future
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FutureTimestampPolicy {
+    /// Refuse to sign
+    #[default]
+    Reject,
+    /// Sign with created_at moved back to the cap
+    Clamp,
+}
+
+/// Handling of event kinds the signer doesn't recognize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownKindPolicy {
+    /// Sign as usual
+    #[default]
+    Allow,
+    /// Sign, but flag the request with a caution
+    Warn,
+    /// Refuse to sign
+    Block,
 }
 
 impl Default for SecurityConfig {
@@ -88,16 +255,36 @@ impl Default for SecurityConfig {
         Self {
             require_password_on_start: true,
             lock_timeout_mins: 15,
+            lock_warning_secs: 30,
             always_confirm: true,
             allow_auto_approve: false,
+            auto_approve_read_only: false,
             max_auto_approvals_per_min: 10,
+            unknown_kind_policy: UnknownKindPolicy::Allow,
+            confirm_decrypt: false,
+            session_grant_mins: 60,
+            max_future_created_at_secs: 900,
+            future_created_at_policy: FutureTimestampPolicy::Reject,
+            trust_own_ui: true,
+            allowed_executables: Vec::new(),
+            presence_check: PresenceCheck::Off,
+            authorize_unknown_apps: false,
+            denial_alert_threshold: 5,
+            denial_alert_window_mins: 10,
+            max_pending_requests: 20,
+            scan_content_for_secrets: true,
+            sensitive_keywords: Vec::new(),
+            ncryptsec_log_n: 16,
+            blocked_event_kinds: Vec::new(),
+            clipboard_clear_secs: 45,
+            nip04_warning_suppressed: Vec::new(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UiConfig {
-    /// Theme (light, dark, system)
+    /// Theme (light, dark, system; anything else is reset to system)
     #[serde(default = "default_theme")]
     pub theme: String,
 
@@ -109,9 +296,152 @@ pub struct UiConfig {
     #[serde(default)]
     pub compact_mode: bool,
 
-    /// Window opacity (0.0-1.0)
+    /// Window opacity (0.3-1.0, clamped on load)
     #[serde(default = "default_opacity")]
     pub window_opacity: f32,
+
+    /// Event kinds whose Approve button stays disabled for a few seconds
+    #[serde(default)]
+    pub approval_countdowns: Vec<KindCountdown>,
+
+    /// Banner shown in the approval dialog (e.g. a compliance notice).
+    /// Supports `{app}`, `{kind}` and `{npub}`; empty = no banner
+    #[serde(default)]
+    pub confirmation_template: String,
+
+    /// What a left click on the tray icon does (right click opens the menu)
+    #[serde(default)]
+    pub tray_left_click: TrayClickAction,
+}
+
+/// Action for a left click on the tray icon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayClickAction {
+    /// Open the signer window
+    #[default]
+    ShowWindow,
+    /// Lock if unlocked, unlock if locked
+    ToggleLock,
+    /// Approve the oldest pending request
+    ApproveTopRequest,
+}
+
+impl TrayClickAction {
+    pub const ALL: [TrayClickAction; 3] = [
+        TrayClickAction::ShowWindow,
+        TrayClickAction::ToggleLock,
+        TrayClickAction::ApproveTopRequest,
+    ];
+}
+
+impl std::fmt::Display for TrayClickAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TrayClickAction::ShowWindow => "Show window",
+            TrayClickAction::ToggleLock => "Lock / unlock",
+            TrayClickAction::ApproveTopRequest => "Approve oldest pending request",
+        })
+    }
+}
+
+/// Longest confirmation banner shown, in characters
+const MAX_BANNER_CHARS: usize = 300;
+
+/// Most lines a confirmation banner may take up
+const MAX_BANNER_LINES: usize = 3;
+
+/// Approval countdown for one event kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KindCountdown {
+    /// Event kind (e.g. 5 for deletions)
+    pub kind: u16,
+    /// Seconds before the request can be approved
+    pub secs: u64,
+}
+
+impl UiConfig {
+    /// Countdown in seconds before a request for `kind` can be approved
+    pub fn countdown_for(&self, kind: u16) -> Option<u64> {
+        self.approval_countdowns.iter().find(|c| c.kind == kind).map(|c| c.secs)
+    }
+
+    /// Render the confirmation banner for a request (None if not configured)
+    ///
+    /// Unknown placeholders are left as written. The result is stripped of
+    /// control characters and capped in lines and length so it can't break
+    /// the dialog layout.
+    pub fn confirmation_banner(&self, app: &str, kind: Option<u16>, npub: &str) -> Option<String> {
+        if self.confirmation_template.trim().is_empty() {
+            return None;
+        }
+
+        let kind = kind.map(|k| k.to_string()).unwrap_or_default();
+        let mut rendered = String::new();
+        let mut rest = self.confirmation_template.as_str();
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            let value = tail.find('}').and_then(|end| match &tail[1..end] {
+                "app" => Some((app, end)),
+                "kind" => Some((kind.as_str(), end)),
+                "npub" => Some((npub, end)),
+                _ => None,
+            });
+            match value {
+                Some((value, end)) => {
+                    rendered.push_str(value);
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &tail[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+
+        let cleaned: String = rendered
+            .chars()
+            .filter(|c| *c == '\n' || !c.is_control())
+            .collect();
+        let mut banner = cleaned
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(MAX_BANNER_LINES)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if banner.chars().count() > MAX_BANNER_CHARS {
+            banner = banner.chars().take(MAX_BANNER_CHARS - 1).collect();
+            banner.push('…');
+        }
+        Some(banner).filter(|b| !b.is_empty())
+    }
+}
+
+impl UiConfig {
+    /// Bring out-of-range values back to something the UI can show.
+    /// Returns whether anything changed.
+    pub fn normalize(&mut self) -> bool {
+        let mut changed = false;
+        if !THEMES.contains(&self.theme.as_str()) {
+            tracing::warn!("Unknown theme '{}' in config; using system", self.theme);
+            self.theme = default_theme();
+            changed = true;
+        }
+        if !WINDOW_OPACITY.contains(&self.window_opacity) {
+            let opacity = if self.window_opacity.is_nan() {
+                default_opacity()
+            } else {
+                self.window_opacity.clamp(*WINDOW_OPACITY.start(), *WINDOW_OPACITY.end())
+            };
+            tracing::warn!("Window opacity {} in config is out of range; using {}", self.window_opacity, opacity);
+            self.window_opacity = opacity;
+            changed = true;
+        }
+        changed
+    }
 }
 
 impl Default for UiConfig {
@@ -121,10 +451,125 @@ impl Default for UiConfig {
             show_event_content: true,
             compact_mode: false,
             window_opacity: 1.0,
+            approval_countdowns: Vec::new(),
+            confirmation_template: String::new(),
+            tray_left_click: TrayClickAction::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BunkerConfig {
+    /// Hold remote requests that arrive while locked and prompt for unlock
+    #[serde(default = "default_true")]
+    pub queue_while_locked: bool,
+
+    /// Start the bunker on launch, once the signer is unlocked
+    #[serde(default)]
+    pub auto_start: bool,
+
+    /// How long the signer must stay unlocked before the bunker auto-starts
+    /// (seconds, 0 = right after unlock)
+    #[serde(default = "default_auto_start_delay")]
+    pub auto_start_delay_secs: u64,
+
+    /// Ask (desktop notification) before auto-starting the bunker
+    #[serde(default)]
+    pub auto_start_confirm: bool,
+
+    /// Maximum NIP-46 responses published per second (0 = unlimited)
+    #[serde(default = "default_bunker_rate")]
+    pub max_responses_per_sec: u32,
+
+    /// Responses allowed to wait for publishing before new ones are dropped
+    #[serde(default = "default_bunker_backlog")]
+    pub response_backlog: usize,
+
+    /// Relays to receive requests on (empty = the default bunker relays)
+    #[serde(default)]
+    pub inbound_relays: Vec<String>,
+
+    /// Relays to publish responses on (empty = the default bunker relays)
+    #[serde(default)]
+    pub outbound_relays: Vec<String>,
+
+    /// Connection secret clients must send with `connect` (unset = generate
+    /// one and keep it in the data dir)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// How long the bunker URI share page stays up (seconds)
+    #[serde(default = "default_share_page_secs")]
+    pub share_page_secs: u64,
+
+    /// Serve the share page on the local network instead of loopback only
+    #[serde(default)]
+    pub share_on_lan: bool,
+
+    /// Name NIP-46 clients show for this signer
+    #[serde(default = "default_signer_name")]
+    pub name: String,
+
+    /// Icon URL NIP-46 clients show for this signer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+
+    /// NIP-46 methods refused for every client (`get_public_key`,
+    /// `sign_event`, `nip04_encrypt`, `nip04_decrypt`, `nip44_encrypt`,
+    /// `nip44_decrypt`)
+    #[serde(default)]
+    pub disabled_methods: Vec<String>,
+
+    /// Name of the key the bunker signs with, whichever key is active
+    /// locally (None = the active key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+fn default_signer_name() -> String {
+    "Pleb Signer".into()
+}
+
+fn default_share_page_secs() -> u64 {
+    120
+}
+
+fn default_auto_start_delay() -> u64 {
+    10
+}
+
+impl Default for BunkerConfig {
+    fn default() -> Self {
+        Self {
+            queue_while_locked: true,
+            auto_start: false,
+            auto_start_delay_secs: default_auto_start_delay(),
+            auto_start_confirm: false,
+            max_responses_per_sec: 5,
+            response_backlog: 50,
+            inbound_relays: Vec::new(),
+            outbound_relays: Vec::new(),
+            secret: None,
+            share_page_secs: default_share_page_secs(),
+            share_on_lan: false,
+            name: default_signer_name(),
+            icon_url: None,
+            disabled_methods: Vec::new(),
+            key: None,
+        }
+    }
+}
+
+/// A named signing engine with its own key set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Engine name; also the keyring/metadata namespace for its keys
+    pub name: String,
+    /// Verified caller identities (executable paths) routed to this engine
+    #[serde(default)]
+    pub senders: Vec<String>,
+}
+
 /// Represents an authorized application
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthorizedApp {
@@ -142,6 +587,30 @@ pub struct AuthorizedApp {
 
     /// Whether auto-approval is enabled for this app
     pub auto_approve: bool,
+
+    /// Skip decrypt confirmation for this app (e.g. a trusted messenger)
+    #[serde(default)]
+    pub auto_approve_decrypt: bool,
+
+    /// Add a NIP-40 expiration tag to events this app signs (None = off)
+    #[serde(default)]
+    pub expiration: Option<AutoExpiration>,
+}
+
+/// Automatic NIP-40 expiration for an app's events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoExpiration {
+    /// Event kinds to expire (empty = all kinds)
+    #[serde(default)]
+    pub kinds: Vec<u16>,
+    /// Seconds from signing until the event expires
+    pub after_secs: u64,
+}
+
+impl AutoExpiration {
+    pub fn applies_to(&self, kind: u16) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -162,6 +631,24 @@ pub struct AppPermissions {
 
     /// Can decrypt zap events
     pub decrypt_zap_event: bool,
+
+    /// Can ask for NIP-26 delegations (always confirmed by the user)
+    #[serde(default)]
+    pub create_delegation: bool,
+
+    /// Maximum events signed per rolling 24h window (None = unlimited)
+    #[serde(default)]
+    pub daily_sign_quota: Option<u32>,
+}
+
+impl AppPermissions {
+    /// Permissions of an app that hasn't been authorized: nothing allowed
+    pub fn deny_all() -> Self {
+        Self {
+            sign_event: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
 }
 
 impl Config {
@@ -172,45 +659,97 @@ impl Config {
             general: GeneralConfig::default(),
             security: SecurityConfig::default(),
             ui: UiConfig::default(),
+            bunker: BunkerConfig::default(),
             authorized_apps: Vec::new(),
+            engines: Vec::new(),
+            safe_mode: false,
         }
     }
 
     /// Load configuration from disk, creating default if not exists
+    ///
+    /// The file is taken from `PLEB_SIGNER_CONFIG` when set, otherwise from
+    /// the platform config directory.
     pub async fn load() -> Result<Self> {
-        let config_path = Self::get_config_path()?;
+        Self::load_from(Self::get_config_path()?).await
+    }
+
+    /// Load configuration from a specific file, creating default if not exists
+    pub async fn load_from(config_path: PathBuf) -> Result<Self> {
+        if config_path.as_os_str().is_empty() {
+            return Err(SignerError::ConfigError("Config path is empty".into()));
+        }
+        if config_path.is_dir() {
+            return Err(SignerError::ConfigError(format!(
+                "Config path {} is a directory",
+                config_path.display()
+            )));
+        }
 
         if config_path.exists() {
             let content = fs::read_to_string(&config_path).await?;
             let mut config: Config = toml::from_str(&content)
                 .map_err(|e| SignerError::ConfigError(e.to_string()))?;
             config.config_path = config_path;
+            // Write the corrected values back so the file matches what runs
+            if config.ui.normalize() {
+                if let Err(e) = config.save().await {
+                    tracing::warn!("Couldn't save corrected config: {}", e);
+                }
+            }
             Ok(config)
         } else {
             // Create default configuration
             let config = Config {
                 config_path: config_path.clone(),
-                general: GeneralConfig::default(),
-                security: SecurityConfig::default(),
-                ui: UiConfig::default(),
-                authorized_apps: Vec::new(),
+                ..Self::default_config()
             };
             config.save().await?;
             Ok(config)
         }
     }
 
+    /// Override security-relevant settings for a safe-mode launch
+    ///
+    /// Only the in-memory config changes; `save` keeps the on-disk values.
+    pub fn apply_safe_mode(&mut self) {
+        self.safe_mode = true;
+        // Blocked kinds and the executable allowlist only ever refuse more,
+        // so they stay
+        self.security = SecurityConfig {
+            blocked_event_kinds: std::mem::take(&mut self.security.blocked_event_kinds),
+            allowed_executables: std::mem::take(&mut self.security.allowed_executables),
+            ..SecurityConfig::default()
+        };
+        self.bunker = BunkerConfig::default();
+        for app in &mut self.authorized_apps {
+            app.auto_approve = false;
+            app.auto_approve_decrypt = false;
+        }
+    }
+
     /// Save configuration to disk
+    ///
+    /// In safe mode only the general and UI sections are written, on top of
+    /// what is already on disk.
     pub async fn save(&self) -> Result<()> {
         // Ensure parent directory exists
         if let Some(parent) = self.config_path.parent() {
             fs::create_dir_all(parent).await?;
         }
 
-        let content = toml::to_string_pretty(self)
-            .map_err(|e| SignerError::ConfigError(e.to_string()))?;
-        fs::write(&self.config_path, content).await?;
-        Ok(())
+        let content = if self.safe_mode && self.config_path.exists() {
+            let on_disk = fs::read_to_string(&self.config_path).await?;
+            let mut config: Config = toml::from_str(&on_disk)
+                .map_err(|e| SignerError::ConfigError(e.to_string()))?;
+            config.general = self.general.clone();
+            config.ui = self.ui.clone();
+            toml::to_string_pretty(&config)
+        } else {
+            toml::to_string_pretty(self)
+        }
+        .map_err(|e| SignerError::ConfigError(e.to_string()))?;
+        write_atomic(&self.config_path, &content).await
     }
 
     /// Get the configuration file path
@@ -227,13 +766,22 @@ impl Config {
 
     /// Get the keys file path
     pub fn keys_path() -> Result<PathBuf> {
-        Ok(Self::data_dir()?.join("keys.enc"))
+        Ok(Self::data_dir()?.join(KEYS_FILE))
     }
 
-    fn get_config_path() -> Result<PathBuf> {
+    /// Get the default configuration directory (`PLEB_SIGNER_CONFIG` may
+    /// point elsewhere)
+    pub fn config_dir() -> Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("com", "plebsigner", "PlebSigner")
             .ok_or_else(|| SignerError::ConfigError("Could not determine config directory".into()))?;
-        Ok(proj_dirs.config_dir().join("config.toml"))
+        Ok(proj_dirs.config_dir().to_path_buf())
+    }
+
+    fn get_config_path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+        Ok(Self::config_dir()?.join("config.toml"))
     }
 
     /// Add or update an authorized application
@@ -261,10 +809,258 @@ impl Config {
     }
 }
 
+/// Replace the file at `path` with `content`, all or nothing
+///
+/// The content goes to a temp file in the same directory, which is synced
+/// and then renamed over `path`. A crash or power loss mid-save leaves
+/// either the old file or the new one, never a truncated mix. Each writer
+/// uses its own temp file, so concurrent saves can't interleave either; the
+/// last rename wins. The old file's permissions are kept.
+pub(crate) async fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let (path, content) = (path.to_path_buf(), content.to_string());
+    tokio::task::spawn_blocking(move || write_atomic_blocking(&path, &content))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// [`write_atomic`] for callers that can't await, e.g. under a std mutex
+pub(crate) fn write_atomic_blocking(path: &Path, content: &str) -> Result<()> {
+    static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+    let name = path.file_name()
+        .ok_or_else(|| SignerError::ConfigError(format!("Not a file path: {}", path.display())))?;
+    let temp = dir.join(format!(
+        ".{}.{}-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed),
+    ));
+
+    let written = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(existing) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp, existing.permissions())?;
+        }
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+        return written;
+    }
+
+    // Make the rename itself durable (directories can't be opened elsewhere)
+    if cfg!(unix) {
+        if let Ok(dir) = std::fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
 // Default value helpers
 fn default_true() -> bool { true }
 fn default_timeout() -> u64 { 60 }
 fn default_lock_timeout() -> u64 { 15 }
+fn default_lock_warning() -> u64 { 30 }
 fn default_rate_limit() -> u32 { 10 }
+fn default_session_grant() -> u64 { 60 }
+fn default_max_future() -> u64 { 900 }
+fn default_denial_threshold() -> u32 { 5 }
+fn default_denial_window() -> u64 { 10 }
+fn default_max_pending() -> usize { 20 }
+fn default_ncryptsec_log_n() -> u8 { 16 }
+fn default_clipboard_clear_secs() -> u64 { 45 }
+fn default_bunker_rate() -> u32 { 5 }
+fn default_bunker_backlog() -> usize { 50 }
 fn default_theme() -> String { "system".to_string() }
 fn default_opacity() -> f32 { 1.0 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trusting_config() -> Config {
+        let mut config = Config::default_config();
+        config.security.allow_auto_approve = true;
+        config.security.always_confirm = false;
+        config.bunker.max_responses_per_sec = 0;
+        config.authorized_apps.push(AuthorizedApp {
+            app_id: "/usr/bin/gossip".into(),
+            name: "Gossip".into(),
+            authorized_at: chrono::Utc::now(),
+            permissions: AppPermissions::default(),
+            auto_approve: true,
+            auto_approve_decrypt: true,
+            expiration: None,
+        });
+        config
+    }
+
+    #[test]
+    fn test_safe_mode_overrides_runtime_config() {
+        let mut config = trusting_config();
+        config.security.blocked_event_kinds = vec![4];
+        config.security.allowed_executables = vec!["/usr/bin/gossip".into()];
+        config.apply_safe_mode();
+
+        assert!(config.safe_mode);
+        assert!(!config.security.allow_auto_approve);
+        assert!(config.security.always_confirm);
+        assert_eq!(config.security.blocked_event_kinds, [4]);
+        assert_eq!(config.security.allowed_executables, ["/usr/bin/gossip"]);
+        assert_eq!(config.bunker.max_responses_per_sec, BunkerConfig::default().max_responses_per_sec);
+        let app = &config.authorized_apps[0];
+        assert!(!app.auto_approve);
+        assert!(!app.auto_approve_decrypt);
+    }
+
+    #[tokio::test]
+    async fn test_safe_mode_save_keeps_security_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = trusting_config();
+        config.config_path = dir.path().join("config.toml");
+        config.save().await.unwrap();
+
+        config.apply_safe_mode();
+        config.general.auto_start = true;
+        config.save().await.unwrap();
+
+        let saved: Config = toml::from_str(&fs::read_to_string(&config.config_path).await.unwrap()).unwrap();
+        assert!(saved.general.auto_start);
+        assert!(saved.security.allow_auto_approve);
+        assert!(saved.authorized_apps[0].auto_approve);
+        assert!(!saved.safe_mode);
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic_replaces_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data/keys_metadata.json");
+        write_atomic(&path, "{\"version\": 1, \"padding\": \"a long first version\"}").await.unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        // Concurrent saves each land whole; one of them wins
+        let saves = (0..8).map(|i| {
+            let path = path.clone();
+            tokio::spawn(async move { write_atomic(&path, &format!("{{\"version\": {}}}", i)).await })
+        });
+        for save in saves {
+            save.await.unwrap().unwrap();
+        }
+
+        let content = fs::read_to_string(&path).await.unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert!(saved["padding"].is_null(), "{}", content);
+        // No temp files left behind
+        let names: Vec<_> = std::fs::read_dir(path.parent().unwrap()).unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["keys_metadata.json"]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_and_save_explicit_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles/work.toml");
+
+        // Missing file: defaults are written to the given path
+        let mut config = Config::load_from(path.clone()).await.unwrap();
+        assert_eq!(config.config_path(), &path);
+        assert!(path.exists());
+
+        config.general.request_timeout_secs = 5;
+        config.save().await.unwrap();
+        let reloaded = Config::load_from(path.clone()).await.unwrap();
+        assert_eq!(reloaded.general.request_timeout_secs, 5);
+        assert_eq!(reloaded.config_path(), &path);
+
+        assert!(Config::load_from(dir.path().to_path_buf()).await.is_err());
+        assert!(Config::load_from(PathBuf::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_clamps_window_opacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[ui]\nwindow_opacity = 1.7\n").await.unwrap();
+
+        let config = Config::load_from(path.clone()).await.unwrap();
+        assert_eq!(config.ui.window_opacity, 1.0);
+        let saved: Config = toml::from_str(&fs::read_to_string(&path).await.unwrap()).unwrap();
+        assert_eq!(saved.ui.window_opacity, 1.0);
+
+        fs::write(&path, "[ui]\nwindow_opacity = 0.05\n").await.unwrap();
+        let config = Config::load_from(path).await.unwrap();
+        assert_eq!(config.ui.window_opacity, 0.3);
+    }
+
+    #[tokio::test]
+    async fn test_load_resets_unknown_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[ui]\ntheme = \"solarized\"\nwindow_opacity = 0.8\n").await.unwrap();
+
+        let config = Config::load_from(path.clone()).await.unwrap();
+        assert_eq!(config.ui.theme, "system");
+        assert_eq!(config.ui.window_opacity, 0.8);
+        let saved: Config = toml::from_str(&fs::read_to_string(&path).await.unwrap()).unwrap();
+        assert_eq!(saved.ui.theme, "system");
+
+        // Known themes are left alone
+        let mut ui = UiConfig { theme: "dark".into(), ..UiConfig::default() };
+        assert!(!ui.normalize());
+        assert_eq!(ui.theme, "dark");
+    }
+
+    #[test]
+    fn test_confirmation_banner_placeholders() {
+        let mut ui = UiConfig::default();
+        assert_eq!(ui.confirmation_banner("Gossip", Some(1), "npub1abc"), None);
+
+        ui.confirmation_template = "{app} wants kind {kind} from {npub}. {unknown} {app".into();
+        assert_eq!(
+            ui.confirmation_banner("Gossip", Some(1), "npub1abc").as_deref(),
+            Some("Gossip wants kind 1 from npub1abc. {unknown} {app")
+        );
+
+        // Substituted values aren't expanded again
+        assert_eq!(
+            ui.confirmation_banner("{npub}", None, "npub1abc").as_deref(),
+            Some("{npub} wants kind  from npub1abc. {unknown} {app")
+        );
+    }
+
+    #[test]
+    fn test_confirmation_banner_sanitized() {
+        let mut ui = UiConfig {
+            confirmation_template: "Notice\u{7}\r\n\n{app}\nline 3\nline 4".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ui.confirmation_banner("Evil\u{1b}[2J", None, "").as_deref(),
+            Some("Notice\nEvil[2J\nline 3")
+        );
+
+        ui.confirmation_template = "x".repeat(1000);
+        let banner = ui.confirmation_banner("", None, "").unwrap();
+        assert_eq!(banner.chars().count(), MAX_BANNER_CHARS);
+        assert!(banner.ends_with('…'));
+    }
+}
@@ -3,15 +3,28 @@
 //! This module provides a D-Bus service that allows other applications
 //! to request signing operations, similar to how Android apps use intents.
 
-use crate::app::AppState;
+use crate::app::{AppMessage, AppState};
+use crate::approval::ApprovalQueue;
+use crate::audit::{AuditEntry, DenialAlert};
+use crate::config::{AppPermissions, AuthorizedApp, Config};
 use crate::error::{Result, SignerError};
-use crate::keys::KeyManager;
-use crate::signing::{SigningEngine, UnsignedEventData};
+use crate::keys::{KeyEvent, KeyManager};
+use crate::nip05;
+use crate::nip26::DelegationConditions;
+use crate::permissions::{Approval, PermissionChecker, RequestType};
+use crate::relays;
+use crate::signing::{assign_sequential_timestamps, EngineRouter, RequestOrigin, SigningPayload, SigningRequest, SigningResultData, UnsignedEventData};
+use crate::tray;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::sync::{Mutex, RwLock};
-use tracing::info;
-use zbus::{interface, ConnectionBuilder};
+use tracing::{info, warn};
+use zbus::message::Header;
+use zbus::names::BusName;
+use zbus::{interface, Connection, ConnectionBuilder, SignalContext};
 
 /// D-Bus service name
 pub const DBUS_NAME: &str = "com.plebsigner.Signer";
@@ -56,17 +69,261 @@ impl DbusResponse {
     }
 }
 
+/// Response whose `result` is nested JSON rather than a string, so clients
+/// parse it once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbusObjectResponse {
+    pub success: bool,
+    pub id: String,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl DbusObjectResponse {
+    fn success(id: String, result: serde_json::Value) -> String {
+        serde_json::to_string(&DbusObjectResponse {
+            success: true,
+            id,
+            result: Some(result),
+            error: None,
+        }).unwrap_or_default()
+    }
+}
+
+/// One key's outcome in `SignEventMulti`: the signed event or why that key failed
+#[derive(Debug, Clone, Serialize)]
+struct KeySignResult {
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The signed event from a `sign_event` result, as a JSON object
+fn signed_event_object(result: &SigningResultData) -> std::result::Result<serde_json::Value, String> {
+    match result {
+        SigningResultData::Event { event_json, .. } => serde_json::from_str(event_json).map_err(|e| e.to_string()),
+        _ => Err("Signing returned no event".into()),
+    }
+}
+
+/// Version of the `com.plebsigner.Signer1` interface, bumped whenever a
+/// method changes incompatibly
+pub const INTERFACE_VERSION: u32 = 1;
+
+/// Features clients can probe for with `GetCapabilities`
+pub const CAPABILITIES: [&str; 11] = [
+    "get_public_key",
+    "sign_event",
+    "sign_event_object",
+    "sign_event_multi",
+    "sign_event_batch",
+    "nip04",
+    "nip44",
+    "nip46",
+    "decrypt_zap_event",
+    "verify_nip05",
+    "create_delegation",
+];
+
+/// Result of `GetCapabilities`
+fn capabilities() -> serde_json::Value {
+    serde_json::json!({
+        "interface_version": INTERFACE_VERSION,
+        "capabilities": CAPABILITIES,
+    })
+}
+
+/// Heartbeat returned by `Ping`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingInfo {
+    /// Server time in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    /// Number of pings handled since the service started
+    pub counter: u64,
+}
+
+/// Monotonic heartbeat counter
+#[derive(Default)]
+pub struct Heartbeat {
+    counter: AtomicU64,
+}
+
+impl Heartbeat {
+    /// Record a ping and return the new heartbeat
+    pub fn beat(&self) -> PingInfo {
+        PingInfo {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            counter: self.counter.fetch_add(1, Ordering::SeqCst) + 1,
+        }
+    }
+}
+
+/// Who is calling, as verified by the bus rather than claimed by the caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallerIdentity {
+    /// Verified identity used for permission lookup, quotas and audit:
+    /// the caller's executable path, or its unique bus name if that can't be resolved
+    pub id: String,
+    /// Unique bus name of the sending connection (e.g. ":1.42")
+    pub unique_name: String,
+    /// Process ID reported by the bus daemon
+    pub pid: Option<u32>,
+    /// The `app_id` the caller passed in; only used as a display hint
+    pub claimed_app_id: String,
+}
+
+impl CallerIdentity {
+    /// Build an identity from what the bus reported about the sender
+    pub fn new(unique_name: &str, pid: Option<u32>, executable: Option<String>, claimed_app_id: &str) -> Self {
+        Self {
+            id: executable.unwrap_or_else(|| unique_name.to_string()),
+            unique_name: unique_name.to_string(),
+            pid,
+            claimed_app_id: claimed_app_id.to_string(),
+        }
+    }
+
+    /// Build an identity from the sender's PID, looking up its executable with `lookup`
+    pub fn from_pid(
+        unique_name: &str,
+        pid: Option<u32>,
+        claimed_app_id: &str,
+        lookup: impl FnOnce(u32) -> Option<String>,
+    ) -> Self {
+        Self::new(unique_name, pid, pid.and_then(lookup), claimed_app_id)
+    }
+
+    /// The caller's executable path, if it could be resolved
+    pub fn executable(&self) -> Option<&str> {
+        (self.pid.is_some() && self.id != self.unique_name).then_some(self.id.as_str())
+    }
+
+    /// Resolve the sender of a method call via the message header and the bus daemon
+    async fn resolve(connection: &Connection, header: &Header<'_>, claimed_app_id: &str) -> Self {
+        let Some(sender) = header.sender() else {
+            // Only peer-to-peer connections lack a sender; the service only
+            // accepts those in tests (see test_bus.rs)
+            return Self::new(claimed_app_id, None, None, claimed_app_id);
+        };
+
+        let pid = match zbus::fdo::DBusProxy::new(connection).await {
+            Ok(proxy) => proxy
+                .get_connection_unix_process_id(BusName::from(sender.clone()))
+                .await
+                .map_err(|e| warn!("Could not get PID of {}: {}", sender, e))
+                .ok(),
+            Err(e) => {
+                warn!("Could not query the bus daemon: {}", e);
+                None
+            }
+        };
+        let caller = Self::from_pid(sender.as_str(), pid, claimed_app_id, process_executable);
+        if !claimed_app_id.is_empty() && caller.id != claimed_app_id {
+            info!(
+                "Request from {} ({}, pid {:?}) claiming to be '{}'",
+                caller.id, caller.unique_name, caller.pid, claimed_app_id
+            );
+        }
+        caller
+    }
+
+    /// Whether the caller is running the given executable
+    ///
+    /// Only identities resolved from a PID count; the claimed app id and bus
+    /// names are never trusted.
+    pub fn runs_executable(&self, executable: &std::path::Path) -> bool {
+        self.pid.is_some() && std::path::Path::new(&self.id) == executable
+    }
+
+    /// Internal if the caller is our own binary (the UI subprocess) and the
+    /// config trusts it, external otherwise
+    pub fn origin(&self, trust_own_ui: bool) -> RequestOrigin {
+        let own_ui = trust_own_ui
+            && std::env::current_exe().is_ok_and(|exe| self.runs_executable(&exe));
+        if own_ui {
+            RequestOrigin::Internal
+        } else {
+            RequestOrigin::External
+        }
+    }
+
+    /// Name to show the user: the configured app name, else the claimed id
+    pub fn display_name(&self, app: Option<&AuthorizedApp>) -> String {
+        match app {
+            Some(app) => app.name.clone(),
+            None if !self.claimed_app_id.is_empty() => self.claimed_app_id.clone(),
+            None => self.id.clone(),
+        }
+    }
+}
+
+/// Executable path of a process, from procfs
+fn process_executable(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Check a caller against the executable allow-list (empty = allow all)
+///
+/// Callers whose executable can't be resolved are rejected when a list is set.
+pub fn check_executable_allowed(allowed: &[String], caller: &CallerIdentity) -> Result<()> {
+    if allowed.is_empty() {
+        return Ok(());
+    }
+    match caller.executable() {
+        Some(exe) if allowed.iter().any(|a| std::path::Path::new(a) == std::path::Path::new(exe)) => Ok(()),
+        Some(exe) => Err(SignerError::PermissionDenied(format!("{} is not an allowed executable", exe))),
+        None => Err(SignerError::PermissionDenied(
+            "Could not verify the caller's executable".into(),
+        )),
+    }
+}
+
+/// Look up the authorization for a caller by its verified identity
+pub fn authorized_app_for<'a>(config: &'a Config, caller: &CallerIdentity) -> Option<&'a AuthorizedApp> {
+    config.get_authorized_app(&caller.id)
+}
+
+/// Permissions granted to the calling app, as returned by `GetMyPermissions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MyPermissions {
+    /// Whether the caller has been authorized at all
+    pub authorized: bool,
+    /// Verified identity the permissions are looked up by
+    pub app_id: String,
+    /// Resolved grant (all denied when unauthorized)
+    pub permissions: AppPermissions,
+}
+
+impl MyPermissions {
+    pub fn resolve(config: &Config, caller: &CallerIdentity) -> Self {
+        let app = authorized_app_for(config, caller);
+        Self {
+            authorized: app.is_some(),
+            app_id: caller.id.clone(),
+            permissions: app.map(|a| a.permissions.clone()).unwrap_or_else(AppPermissions::deny_all),
+        }
+    }
+}
+
 /// The D-Bus interface implementation
 pub struct SignerInterface {
     app_state: Arc<RwLock<AppState>>,
-    signing_engine: Arc<SigningEngine>,
+    engines: EngineRouter,
+    heartbeat: Heartbeat,
 }
 
 impl SignerInterface {
-    pub fn new(app_state: Arc<RwLock<AppState>>, key_manager: Arc<Mutex<KeyManager>>) -> Self {
+    pub fn new(app_state: Arc<RwLock<AppState>>, engines: EngineRouter) -> Self {
         Self {
             app_state,
-            signing_engine: Arc::new(SigningEngine::new(key_manager)),
+            engines,
+            heartbeat: Heartbeat::default(),
         }
     }
 
@@ -87,6 +344,262 @@ impl SignerInterface {
             Ok(())
         }
     }
+
+    /// Resolve the caller and enforce the executable allow-list
+    async fn admit(&self, connection: &Connection, header: &Header<'_>, claimed_app_id: &str) -> std::result::Result<CallerIdentity, String> {
+        let caller = CallerIdentity::resolve(connection, header, claimed_app_id).await;
+        let state = self.app_state.read().await;
+        check_executable_allowed(&state.config.security.allowed_executables, &caller).map_err(|e| {
+            warn!("Rejected D-Bus call from {} (pid {:?}): {}", caller.id, caller.pid, e);
+            e.to_string()
+        })?;
+        Ok(caller)
+    }
+
+    /// Enforce the allow-list on methods that don't otherwise need the caller
+    async fn gate(&self, connection: &Connection, header: &Header<'_>) -> std::result::Result<(), String> {
+        if self.app_state.read().await.config.security.allowed_executables.is_empty() {
+            return Ok(());
+        }
+        self.admit(connection, header, "").await.map(|_| ())
+    }
+
+    /// Only let Pleb Signer's own processes through (for answering approval
+    /// prompts and unlocking)
+    async fn own_ui(&self, connection: &Connection, header: &Header<'_>) -> std::result::Result<(), String> {
+        let caller = CallerIdentity::resolve(connection, header, "").await;
+        match caller.origin(true) {
+            RequestOrigin::Internal => Ok(()),
+            RequestOrigin::External => {
+                warn!("{} tried a method reserved for Pleb Signer's UI", caller.id);
+                Err(SignerError::PermissionDenied("Only Pleb Signer's UI can do this".into()).to_string())
+            }
+        }
+    }
+
+    /// Reject the request if the app has exhausted its daily signing quota
+    async fn check_quota(&self, caller: &CallerIdentity) -> std::result::Result<(), String> {
+        let state = self.app_state.read().await;
+        let quota = authorized_app_for(&state.config, caller)
+            .and_then(|app| app.permissions.daily_sign_quota);
+        state.audit_log.check_daily_quota(&caller.id, quota, chrono::Utc::now())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Apply the approval policy, waiting for the user when a prompt is required
+    ///
+    /// Returns the key the user picked when approving, if they overrode the
+    /// requested one.
+    async fn authorize(&self, caller: &CallerIdentity, request_type: RequestType, key_id: Option<&str>, payload: SigningPayload) -> std::result::Result<Option<String>, String> {
+        let (event_kind, expiration) = match payload {
+            SigningPayload::Event(ref event) => (Some(event.kind), event.expiration()),
+            _ => (None, None),
+        };
+
+        // Show NIP-40 expiry (requested by the app or added by us) when approving
+        let mut warnings: Vec<String> = expiration
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts as i64, 0))
+            .map(|at| format!("Event expires at {} (NIP-40)", at.format("%Y-%m-%d %H:%M UTC")))
+            .into_iter()
+            .collect();
+
+        let (nip04_nudge, mut approval, origin, app_name, queue, timeout) = {
+            let state = self.app_state.read().await;
+            let app = authorized_app_for(&state.config, caller);
+            let origin = caller.origin(state.config.security.trust_own_ui);
+            let mut approval = match origin {
+                RequestOrigin::Internal => Approval::Allow,
+                RequestOrigin::External => {
+                    PermissionChecker::approval_for(&state.config.security, app, request_type, event_kind)
+                }
+            };
+            // Content that may leak a secret, or a kind unknown under the
+            // "warn" policy, always needs the user, even from trusted or
+            // auto-approved apps
+            if let SigningPayload::Event(ref event) = payload {
+                let mut cautions = PermissionChecker::content_warnings(&state.config.security, &event.content);
+                if let Ok(Some(caution)) = PermissionChecker::check_event_kind(state.config.security.unknown_kind_policy, event.kind) {
+                    cautions.push(caution);
+                }
+                if !cautions.is_empty() {
+                    warn!("Event from {} needs confirmation: {}", caller.id, cautions.join("; "));
+                    if approval == Approval::Allow {
+                        approval = Approval::Prompt;
+                    }
+                    warnings.extend(cautions);
+                }
+            }
+            // Spell out what a delegation grants; it can't be taken back
+            if let SigningPayload::Delegation { ref delegatee, ref conditions } = payload {
+                if let Ok(conditions) = conditions.parse::<DelegationConditions>() {
+                    warnings.push(format!(
+                        "{} could publish {} as you. A delegation can't be revoked before it expires.",
+                        delegatee, conditions.describe()
+                    ));
+                }
+            }
+            // Shown whenever the user is asked; an app's first NIP-04 request
+            // is asked so the user sees it at least once
+            let nip04_nudge = PermissionChecker::nip04_warning(&state.config.security, request_type, &caller.id);
+            if let Some(nudge) = nip04_nudge {
+                warnings.push(nudge.to_string());
+            }
+            (
+                nip04_nudge.is_some(),
+                approval,
+                origin,
+                caller.display_name(app),
+                Arc::clone(&state.approval_queue),
+                Duration::from_secs(state.config.general.request_timeout_secs),
+            )
+        };
+
+        // Past max_auto_approvals_per_min, an app's requests go back to the user
+        if approval == Approval::Allow && origin == RequestOrigin::External {
+            let mut state = self.app_state.write().await;
+            if nip04_nudge && state.nip04_warned.insert(caller.id.clone()) {
+                info!("First NIP-04 request from {}; asking once", caller.id);
+                approval = Approval::Prompt;
+            } else if !state.rate_limiter.check_and_record(&caller.id, request_type) {
+                warn!("{} exceeded the auto-approval rate limit", caller.id);
+                warnings.push(format!(
+                    "More than {} automatic approvals this minute",
+                    state.config.security.max_auto_approvals_per_min
+                ));
+                approval = Approval::Prompt;
+            }
+        }
+
+        match approval {
+            Approval::Allow => Ok(None),
+            Approval::Deny(reason) => Err(SignerError::PermissionDenied(reason).to_string()),
+            Approval::Prompt => {
+                let request = SigningRequest {
+                    id: Self::generate_request_id(),
+                    request_type,
+                    app_id: caller.id.clone(),
+                    app_name: Some(app_name),
+                    key_id: key_id.map(str::to_string),
+                    payload,
+                    timestamp: chrono::Utc::now(),
+                    warnings,
+                    origin: RequestOrigin::External,
+                };
+                queue.request_approval(request, timeout).await.map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Apply the signing policy to an event before it's signed: blocked
+    /// kinds, the created_at cap, automatic expiration, unknown-kind policy, daily quota
+    /// and approval. Returns the key picked on approval, as `authorize` does
+    async fn prepare_event(&self, caller: &CallerIdentity, event_data: &mut UnsignedEventData, key_id: Option<&str>) -> std::result::Result<Option<String>, String> {
+        let (security, expiration) = {
+            let state = self.app_state.read().await;
+            // Refused before anyone is asked to approve it
+            state.blocked_kinds.check(event_data.kind).map_err(|e| e.to_string())?;
+            let expiration = authorized_app_for(&state.config, caller).and_then(|app| app.expiration.clone());
+            (state.config.security.clone(), expiration)
+        };
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        event_data.created_at = PermissionChecker::check_created_at(
+            event_data.created_at,
+            security.max_future_created_at_secs,
+            security.future_created_at_policy,
+            now,
+        )
+        .map_err(|e| e.to_string())?;
+
+        if let Some(expiration) = expiration.filter(|e| e.applies_to(event_data.kind)) {
+            event_data.add_expiration(expiration.after_secs, now);
+        }
+
+        // Blocked here; the caution for an unknown kind is shown by authorize
+        if let Err(e) = PermissionChecker::check_event_kind(security.unknown_kind_policy, event_data.kind) {
+            return Err(e.to_string());
+        }
+
+        self.check_quota(caller).await?;
+
+        let payload = SigningPayload::Event(event_data.clone());
+        self.authorize(caller, RequestType::SignEvent, key_id, payload).await
+    }
+
+    /// Answer a queued request on behalf of the UI; `resolve` applies the
+    /// answer, described by `answer` in the log
+    async fn answer_request(
+        &self,
+        request_id: &str,
+        answer: &str,
+        resolve: impl FnOnce(&ApprovalQueue, &Config) -> bool,
+        header: &Header<'_>,
+        connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.own_ui(connection, header).await {
+            return DbusResponse::error(id, e);
+        }
+        let answered = {
+            let state = self.app_state.read().await;
+            resolve(&state.approval_queue, &state.config)
+        };
+        if answered {
+            info!("Request {} {} from the UI", request_id, answer);
+            DbusResponse::success(id, request_id)
+        } else {
+            DbusResponse::error(id, format!("No pending request {}", request_id))
+        }
+    }
+
+    /// Parse, authorize, sign and audit a single event
+    async fn sign_one(&self, caller: &CallerIdentity, event_json: &str, key_id: Option<&str>) -> std::result::Result<SigningResultData, String> {
+        let mut event_data: UnsignedEventData = serde_json::from_str(event_json)
+            .map_err(|e| format!("Invalid event: {}", e))?;
+
+        let chosen_key = match self.prepare_event(caller, &mut event_data, key_id).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(caller, RequestType::SignEvent, Some(event_data.kind), &e).await;
+                return Err(e);
+            }
+        };
+
+        let key_id = chosen_key.as_deref().or(key_id);
+        let result = self.engines.route(&caller.id).sign_event(&event_data, key_id).await;
+        self.audit(caller, RequestType::SignEvent, Some(event_data.kind), result.is_ok()).await;
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Key selection from a D-Bus `key_id` (name, npub or hex; empty = active key)
+    fn key_selection(key_id: &str) -> Option<&str> {
+        Some(key_id.trim()).filter(|k| !k.is_empty())
+    }
+
+    /// Record a handled request in the audit log
+    async fn audit(&self, caller: &CallerIdentity, request_type: RequestType, event_kind: Option<u16>, approved: bool) {
+        let mut state = self.app_state.write().await;
+        let entry = AuditEntry::new(&caller.id, request_type, event_kind, approved);
+        if let Err(e) = state.audit_log.record(entry).await {
+            warn!("Failed to write audit entry: {}", e);
+        }
+    }
+
+    /// Record a refused request in the audit log, with the reason it was
+    /// refused, alerting the user if the app is refused unusually often
+    async fn audit_refused(&self, caller: &CallerIdentity, request_type: RequestType, event_kind: Option<u16>, reason: &str) {
+        let mut state = self.app_state.write().await;
+        let entry = AuditEntry::refused(&caller.id, request_type, event_kind, reason);
+        let threshold = state.config.security.denial_alert_threshold;
+        let window_mins = state.config.security.denial_alert_window_mins;
+        let sender = state.get_message_sender();
+        let on_alert = |alert: DenialAlert| {
+            warn!("{} was refused {} times in {} minutes", alert.app_id, alert.denials, alert.window_mins);
+            let _ = sender.try_send(AppMessage::DenialAlert(alert));
+        };
+        if let Err(e) = state.audit_log.record_refusal(entry, threshold, window_mins, on_alert).await {
+            warn!("Failed to write audit entry: {}", e);
+        }
+    }
 }
 
 #[interface(name = "com.plebsigner.Signer1")]
@@ -96,28 +609,427 @@ impl SignerInterface {
         env!("CARGO_PKG_VERSION").to_string()
     }
 
+    /// Get the signer's name, version and icon (base64 PNG) for client branding
+    async fn get_signer_info(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        match tray::key_icon_png() {
+            Ok(png) => DbusResponse::success(id, serde_json::json!({
+                "name": "Pleb Signer",
+                "version": env!("CARGO_PKG_VERSION"),
+                "icon_png_base64": base64::engine::general_purpose::STANDARD.encode(png),
+            })),
+            Err(e) => DbusResponse::error(id, format!("Failed to encode icon: {}", e)),
+        }
+    }
+
+    /// List the features this signer supports and the interface version
+    ///
+    /// Needs no authorization, so clients can probe before asking for any.
+    async fn get_capabilities(&self) -> String {
+        DbusResponse::success(Self::generate_request_id(), capabilities())
+    }
+
+    /// Heartbeat: returns a timestamp and an increasing counter
+    ///
+    /// Goes through the shared app state, so a hung service stops answering.
+    async fn ping(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+        let _state = self.app_state.read().await;
+        DbusResponse::success(id, self.heartbeat.beat())
+    }
+
+    /// Permissions granted to the calling app, so clients can adapt their UI
+    async fn get_my_permissions(
+        &self,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+
+        let state = self.app_state.read().await;
+        DbusResponse::success(id, MyPermissions::resolve(&state.config, &caller))
+    }
+
+    /// Check that a NIP-05 identifier maps to a pubkey (hex or npub)
+    ///
+    /// Public lookup: works for any pubkey and doesn't require unlocking.
+    async fn verify_nip05(
+        &self,
+        identifier: &str,
+        pubkey: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        match nip05::verify(identifier, pubkey).await {
+            Ok(verification) => DbusResponse::success(id, verification),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Look up a key's NIP-65 relay list on the bunker relays
+    ///
+    /// Each relay is reported separately, so a partial answer says which
+    /// relays failed and why.
+    async fn get_relays(
+        &self,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
+
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
+
+        // A relay list is public data about the key, so it's granted with it
+        if let Err(e) = self.authorize(&caller, RequestType::GetPublicKey, key_id, SigningPayload::Empty).await {
+            self.audit_refused(&caller, RequestType::GetPublicKey, None, &e).await;
+            return DbusResponse::error(id, e);
+        }
+
+        let pubkey = match self.engines.route(&caller.id).get_public_key(key_id).await {
+            Ok(SigningResultData::PublicKey { hex, .. }) => hex,
+            Ok(_) => return DbusResponse::error(id, "Unexpected public key result"),
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let pubkey = match nostr::PublicKey::from_hex(&pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+
+        let sources = self.app_state.read().await.get_bunker_relays();
+        if sources.is_empty() {
+            return DbusResponse::error(id, "No relays configured");
+        }
+
+        let response = relays::fetch_relay_list(pubkey, &sources).await;
+        if response.report.is_partial() {
+            warn!("Relay list lookup partially failed: {:?}", response.report.failed().collect::<Vec<_>>());
+        }
+        DbusResponse::success(id, response)
+    }
+
+    /// Bring the signer window to the front (used when a second instance starts)
+    async fn show_window(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let sender = self.app_state.read().await.get_message_sender();
+        match sender.try_send(AppMessage::ShowWindow) {
+            Ok(()) => DbusResponse::success(id, "Window requested"),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Lock the signer: cached keys are dropped and requests are refused
+    async fn lock(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        // App state before key managers (see app::lock_state_and_keys)
+        let mut state = self.app_state.write().await;
+        self.engines.lock_all().await;
+        state.set_locked(true);
+        drop(state);
+        info!("Locked over D-Bus");
+        DbusResponse::success(id, "Locked")
+    }
+
+    /// Re-read the config file and apply it without restarting
+    ///
+    /// The result lists changed settings that only apply after a restart.
+    async fn reload_config(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let path = self.app_state.read().await.config.config_path().clone();
+        let config = match Config::load_from(path).await {
+            Ok(config) => config,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let restart = self.app_state.write().await.reload_config(config);
+        if restart.is_empty() {
+            info!("Config reloaded over D-Bus");
+        } else {
+            info!("Config reloaded over D-Bus; restart to apply {}", restart.join(", "));
+        }
+        DbusResponse::success(id, restart)
+    }
+
+    /// Re-read key metadata saved by another process, such as the UI
+    /// subprocess adding a key or switching the active one
+    async fn reload_keys(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        match self.engines.reload_all().await {
+            Ok(()) => {
+                info!("Keys reloaded over D-Bus");
+                DbusResponse::success(id, "Reloaded")
+            }
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Unlock the signer by loading every engine's active key from the keyring
+    /// (Pleb Signer's UI and `--unlock` only)
+    ///
+    /// The OS keyring does its own authentication, so `password` is only
+    /// checked when the signer launched locked for the bunker: it is then the
+    /// bunker key's own password. Only Pleb Signer may unlock, so an app
+    /// can't undo a lock. Unlocking an unlocked signer succeeds.
+    async fn unlock(
+        &self,
+        password: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.own_ui(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let launch_key = self.app_state.read().await.launch_password_key.clone();
+        if let Some(name) = launch_key {
+            if let Err(e) = self.engines.default_engine().unlock_key(&name, password).await {
+                return DbusResponse::error(id, e);
+            }
+        }
+        if let Err(e) = self.engines.unlock_all().await {
+            return DbusResponse::error(id, e);
+        }
+        let mut state = self.app_state.write().await;
+        state.launch_password_key = None;
+        state.set_locked(false);
+        drop(state);
+        info!("Unlocked over D-Bus");
+        DbusResponse::success(id, "Unlocked")
+    }
+
+    /// Unlock a key that has its own password (name, npub or hex)
+    async fn unlock_key(
+        &self,
+        key_id: &str,
+        password: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        match self.engines.default_engine().unlock_key(key_id, password).await {
+            Ok(()) => DbusResponse::success(id, "Unlocked"),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Requests waiting for approval, oldest first (Pleb Signer's UI only)
+    async fn list_pending_requests(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.own_ui(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+        let queue = Arc::clone(&self.app_state.read().await.approval_queue);
+        DbusResponse::success(id, queue.pending())
+    }
+
+    /// Approve a pending request (Pleb Signer's UI only)
+    async fn approve_request(
+        &self,
+        request_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        self.answer_request(request_id, "approved", |queue, _| queue.resolve(request_id, true), &header, connection).await
+    }
+
+    /// Approve a pending request and the app's further requests for
+    /// `session_grant_mins` (Pleb Signer's UI only)
+    async fn approve_request_for_session(
+        &self,
+        request_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let approve = |queue: &ApprovalQueue, config: &Config| {
+            let length = Duration::from_secs(config.security.session_grant_mins * 60);
+            queue.approve_for_session(request_id, length)
+        };
+        self.answer_request(request_id, "approved for the session", approve, &header, connection).await
+    }
+
+    /// Approve a pending request, signing with `key_id` instead of the
+    /// requested key (Pleb Signer's UI only)
+    async fn approve_request_with_key(
+        &self,
+        request_id: &str,
+        key_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let approve = |queue: &ApprovalQueue, _: &Config| queue.approve_with_key(request_id, key_id);
+        self.answer_request(request_id, "approved with another key", approve, &header, connection).await
+    }
+
+    /// Reject a pending request, passing `reason` on to the app unless it's
+    /// empty (Pleb Signer's UI only)
+    async fn reject_request(
+        &self,
+        request_id: &str,
+        reason: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let reject = |queue: &ApprovalQueue, _: &Config| queue.reject_with_reason(request_id, reason);
+        self.answer_request(request_id, "rejected", reject, &header, connection).await
+    }
+
+    /// Report whether the signer's window has focus (Pleb Signer's UI only);
+    /// request notifications are skipped while it does
+    async fn set_window_focused(
+        &self,
+        focused: bool,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.own_ui(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+        self.app_state.write().await.set_window_focused(focused, std::time::Instant::now());
+        DbusResponse::success(id, focused)
+    }
+
+    /// Emitted when a request starts waiting for approval
+    #[zbus(signal)]
+    async fn pending_request(ctxt: &SignalContext<'_>, id: &str, app_id: &str, summary: &str) -> zbus::Result<()>;
+
+    /// Emitted when a key is generated, imported or added watch-only
+    #[zbus(signal)]
+    async fn key_added(ctxt: &SignalContext<'_>, npub: &str) -> zbus::Result<()>;
+
+    /// Emitted when a key is deleted
+    #[zbus(signal)]
+    async fn key_removed(ctxt: &SignalContext<'_>, npub: &str) -> zbus::Result<()>;
+
+    /// Emitted when another key becomes active (empty when none is)
+    #[zbus(signal)]
+    async fn active_key_changed(ctxt: &SignalContext<'_>, npub: &str) -> zbus::Result<()>;
+
     /// Check if the signer is unlocked and ready
-    async fn is_ready(&self) -> bool {
+    async fn is_ready(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> bool {
+        if self.gate(connection, &header).await.is_err() {
+            return false;
+        }
         let state = self.app_state.read().await;
         !state.is_locked
     }
 
     /// Get the active public key
-    async fn get_public_key(&self) -> String {
+    async fn get_public_key(
+        &self,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.get_public_key().await {
+        if let Err(e) = self.authorize(&caller, RequestType::GetPublicKey, key_id, SigningPayload::Empty).await {
+            self.audit_refused(&caller, RequestType::GetPublicKey, None, &e).await;
+            return DbusResponse::error(id, e);
+        }
+
+        match self.engines.route(&caller.id).get_public_key(key_id).await {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// List all available keys (returns public info only)
-    async fn list_keys(&self) -> String {
+    async fn list_keys(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        if self.gate(connection, &header).await.is_err() {
+            return "[]".to_string();
+        }
         let state = self.app_state.read().await;
         let keys: Vec<_> = state.key_manager.list_keys()
             .iter()
@@ -126,109 +1038,453 @@ impl SignerInterface {
                 "npub": k.npub,
                 "pubkey_hex": k.pubkey_hex,
                 "is_active": k.is_active,
+                "watch_only": k.watch_only,
             }))
             .collect();
         serde_json::to_string(&keys).unwrap_or_default()
     }
 
     /// Sign a Nostr event
-    async fn sign_event(&self, event_json: &str, _app_id: &str) -> String {
+    async fn sign_event(
+        &self,
+        event_json: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        let event_data: UnsignedEventData = match serde_json::from_str(event_json) {
-            Ok(e) => e,
-            Err(e) => return DbusResponse::error(id, format!("Invalid event: {}", e)),
+        match self.sign_one(&caller, event_json, Self::key_selection(key_id)).await {
+            Ok(result) => DbusResponse::success(id, result),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Sign a Nostr event, returning the signed event as a JSON object in
+    /// `result` (`SignEvent` returns it as an escaped string)
+    async fn sign_event_object(
+        &self,
+        event_json: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
         };
+        
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
 
-        match self.signing_engine.sign_event(&event_data).await {
-            Ok(result) => DbusResponse::success(id, result),
+        let result = self.sign_one(&caller, event_json, Self::key_selection(key_id)).await;
+        match result.and_then(|result| signed_event_object(&result)) {
+            Ok(event) => DbusObjectResponse::success(id, event),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
+    /// Sign the same event with several keys (names, npubs or hex; empty =
+    /// every key that can sign), e.g. to cross-post a note
+    ///
+    /// Each key is authorized on its own. `result` has one entry per key, in
+    /// order, with either the signed event or that key's error; one failing
+    /// key doesn't stop the others.
+    async fn sign_event_multi(
+        &self,
+        event_json: &str,
+        key_names: Vec<String>,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
+
+        let key_names = if key_names.is_empty() {
+            match self.engines.route(&caller.id).signing_key_names().await {
+                Ok(names) => names,
+                Err(e) => return DbusResponse::error(id, e),
+            }
+        } else {
+            key_names
+        };
+
+        let mut results = Vec::with_capacity(key_names.len());
+        for key in key_names {
+            let signed = self.sign_one(&caller, event_json, Self::key_selection(&key)).await;
+            let (event, error) = match signed.and_then(|result| signed_event_object(&result)) {
+                Ok(event) => (Some(event), None),
+                Err(e) => (None, Some(e)),
+            };
+            results.push(KeySignResult { key, event, error });
+        }
+
+        DbusObjectResponse::success(id, serde_json::to_value(results).unwrap_or_default())
+    }
+
+    /// Sign several events in order
+    ///
+    /// With `sequential`, the events get `created_at` values of base + index
+    /// seconds (base = the first event's created_at, or now) so relays keep
+    /// them in order. Results are returned in the same order; if any event is
+    /// refused, no signatures are returned.
+    async fn sign_event_batch(
+        &self,
+        events_json: &str,
+        sequential: bool,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
+        
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
+
+        let mut events: Vec<UnsignedEventData> = match serde_json::from_str(events_json) {
+            Ok(events) => events,
+            Err(e) => return DbusResponse::error(id, format!("Invalid events: {}", e)),
+        };
+        if sequential {
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            assign_sequential_timestamps(&mut events, now);
+        }
+
+        let mut chosen_keys = Vec::with_capacity(events.len());
+        for (index, event_data) in events.iter_mut().enumerate() {
+            match self.prepare_event(&caller, event_data, key_id).await {
+                Ok(chosen_key) => chosen_keys.push(chosen_key),
+                Err(e) => {
+                    self.audit_refused(&caller, RequestType::SignEvent, Some(event_data.kind), &e).await;
+                    return DbusResponse::error(id, format!("Event {}: {}", index, e));
+                }
+            }
+        }
+
+        let engine = self.engines.route(&caller.id);
+        let mut signed = Vec::with_capacity(events.len());
+        for (index, (event_data, chosen_key)) in events.iter().zip(&chosen_keys).enumerate() {
+            let result = engine.sign_event(event_data, chosen_key.as_deref().or(key_id)).await;
+            self.audit(&caller, RequestType::SignEvent, Some(event_data.kind), result.is_ok()).await;
+            match result {
+                Ok(result) => signed.push(result),
+                Err(e) => return DbusResponse::error(id, format!("Event {}: {}", index, e)),
+            }
+        }
+
+        DbusResponse::success(id, signed)
+    }
+
     /// NIP-04 encrypt
-    async fn nip04_encrypt(&self, plaintext: &str, recipient_pubkey: &str, _app_id: &str) -> String {
+    async fn nip04_encrypt(
+        &self,
+        plaintext: &str,
+        recipient_pubkey: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.nip04_encrypt(recipient_pubkey, plaintext).await {
+        let payload = SigningPayload::Encrypt {
+            plaintext: plaintext.to_string(),
+            recipient_pubkey: recipient_pubkey.to_string(),
+        };
+        let chosen_key = match self.authorize(&caller, RequestType::Nip04Encrypt, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::Nip04Encrypt, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).nip04_encrypt(recipient_pubkey, plaintext, key_id).await;
+        self.audit(&caller, RequestType::Nip04Encrypt, None, result.is_ok()).await;
+
+        match result {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// NIP-04 decrypt
-    async fn nip04_decrypt(&self, ciphertext: &str, sender_pubkey: &str, _app_id: &str) -> String {
+    async fn nip04_decrypt(
+        &self,
+        ciphertext: &str,
+        sender_pubkey: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.nip04_decrypt(sender_pubkey, ciphertext).await {
+        let payload = SigningPayload::Decrypt {
+            ciphertext: ciphertext.to_string(),
+            sender_pubkey: sender_pubkey.to_string(),
+        };
+        let chosen_key = match self.authorize(&caller, RequestType::Nip04Decrypt, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::Nip04Decrypt, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).nip04_decrypt(sender_pubkey, ciphertext, key_id).await;
+        self.audit(&caller, RequestType::Nip04Decrypt, None, result.is_ok()).await;
+
+        match result {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// NIP-44 encrypt
-    async fn nip44_encrypt(&self, plaintext: &str, recipient_pubkey: &str, _app_id: &str) -> String {
+    async fn nip44_encrypt(
+        &self,
+        plaintext: &str,
+        recipient_pubkey: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.nip44_encrypt(recipient_pubkey, plaintext).await {
+        let payload = SigningPayload::Encrypt {
+            plaintext: plaintext.to_string(),
+            recipient_pubkey: recipient_pubkey.to_string(),
+        };
+        let chosen_key = match self.authorize(&caller, RequestType::Nip44Encrypt, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::Nip44Encrypt, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).nip44_encrypt(recipient_pubkey, plaintext, key_id).await;
+        self.audit(&caller, RequestType::Nip44Encrypt, None, result.is_ok()).await;
+
+        match result {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// NIP-44 decrypt
-    async fn nip44_decrypt(&self, ciphertext: &str, sender_pubkey: &str, _app_id: &str) -> String {
+    async fn nip44_decrypt(
+        &self,
+        ciphertext: &str,
+        sender_pubkey: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.nip44_decrypt(sender_pubkey, ciphertext).await {
+        let payload = SigningPayload::Decrypt {
+            ciphertext: ciphertext.to_string(),
+            sender_pubkey: sender_pubkey.to_string(),
+        };
+        let chosen_key = match self.authorize(&caller, RequestType::Nip44Decrypt, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::Nip44Decrypt, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).nip44_decrypt(sender_pubkey, ciphertext, key_id).await;
+        self.audit(&caller, RequestType::Nip44Decrypt, None, result.is_ok()).await;
+
+        match result {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// Decrypt a zap event
-    async fn decrypt_zap_event(&self, event_json: &str, _app_id: &str) -> String {
+    async fn decrypt_zap_event(
+        &self,
+        event_json: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        match self.signing_engine.decrypt_zap_event(event_json).await {
+        let payload = SigningPayload::ZapEvent(event_json.to_string());
+        let chosen_key = match self.authorize(&caller, RequestType::DecryptZapEvent, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::DecryptZapEvent, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).decrypt_zap_event(event_json, key_id).await;
+        self.audit(&caller, RequestType::DecryptZapEvent, None, result.is_ok()).await;
+
+        match result {
+            Ok(result) => DbusResponse::success(id, result),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Create a NIP-26 delegation letting `delegatee_pubkey` publish events
+    /// as the key within `conditions` (e.g. `kind=1&created_at<1700000000`)
+    ///
+    /// Needs the app's `create_delegation` permission and always asks the
+    /// user. `result` holds the `delegation` tag.
+    async fn create_delegation(
+        &self,
+        delegatee_pubkey: &str,
+        conditions: &str,
+        key_id: &str,
+        app_id: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        let caller = match self.admit(connection, &header, app_id).await {
+            Ok(caller) => caller,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        let key_id = Self::key_selection(key_id);
+
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
+
+        // Refuse bad input before anyone is asked to approve it
+        let conditions: DelegationConditions = match conditions.parse() {
+            Ok(conditions) => conditions,
+            Err(e) => return DbusResponse::error(id, e),
+        };
+        if let Err(e) = nostr::PublicKey::parse(delegatee_pubkey) {
+            return DbusResponse::error(id, format!("Invalid delegatee public key: {}", e));
+        }
+
+        let payload = SigningPayload::Delegation {
+            delegatee: delegatee_pubkey.to_string(),
+            conditions: conditions.to_string(),
+        };
+        let chosen_key = match self.authorize(&caller, RequestType::CreateDelegation, key_id, payload).await {
+            Ok(chosen_key) => chosen_key,
+            Err(e) => {
+                self.audit_refused(&caller, RequestType::CreateDelegation, None, &e).await;
+                return DbusResponse::error(id, e);
+            }
+        };
+        let key_id = chosen_key.as_deref().or(key_id);
+
+        let result = self.engines.route(&caller.id).create_delegation(delegatee_pubkey, &conditions, key_id).await;
+        self.audit(&caller, RequestType::CreateDelegation, None, result.is_ok()).await;
+
+        match result {
             Ok(result) => DbusResponse::success(id, result),
             Err(e) => DbusResponse::error(id, e),
         }
     }
 
     /// Start bunker listener for NIP-46 remote signing
-    async fn start_bunker(&self) -> String {
+    async fn start_bunker(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
         }
 
-        let mut state = self.app_state.write().await;
+        let state = self.app_state.write().await;
         match state.start_bunker().await {
             Ok(uri) => DbusResponse::success(id, uri),
             Err(e) => DbusResponse::error(id, e),
@@ -236,8 +1492,15 @@ impl SignerInterface {
     }
 
     /// Get bunker connection URI
-    async fn get_bunker_uri(&self) -> String {
+    async fn get_bunker_uri(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
         
         if let Err(e) = self.check_ready().await {
             return DbusResponse::error(id, e);
@@ -251,8 +1514,15 @@ impl SignerInterface {
     }
 
     /// Stop bunker listener
-    async fn stop_bunker(&self) -> String {
+    async fn stop_bunker(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
         
         let state = self.app_state.read().await;
         state.stop_bunker().await;
@@ -260,9 +1530,87 @@ impl SignerInterface {
         DbusResponse::success(id, "Bunker stopped")
     }
 
+    /// Pause the bunker: keep relay connections but refuse sign/decrypt requests
+    async fn pause_bunker(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let state = self.app_state.read().await;
+        match state.pause_bunker() {
+            Ok(()) => DbusResponse::success(id, "Bunker paused"),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Resume a paused bunker
+    async fn resume_bunker(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        if let Err(e) = self.check_ready().await {
+            return DbusResponse::error(id, e);
+        }
+
+        let state = self.app_state.read().await;
+        match state.resume_bunker() {
+            Ok(()) => DbusResponse::success(id, "Bunker resumed"),
+            Err(e) => DbusResponse::error(id, e),
+        }
+    }
+
+    /// Get the NIP-42 authentication status of each bunker relay
+    async fn get_bunker_relay_auth(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let state = self.app_state.read().await;
+        DbusResponse::success(id, state.get_bunker_relay_auth())
+    }
+
+    /// Each bunker relay and whether it is connected, as `[url, connected]`
+    /// pairs (empty while the bunker is stopped)
+    async fn get_bunker_relay_status(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
+        let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
+
+        let state = self.app_state.read().await;
+        DbusResponse::success(id, state.get_bunker_relay_status())
+    }
+
     /// Get bunker state
-    async fn get_bunker_state(&self) -> String {
+    async fn get_bunker_state(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> String {
         let id = Self::generate_request_id();
+        if let Err(e) = self.gate(connection, &header).await {
+            return DbusResponse::error(id, e);
+        }
         
         let state = self.app_state.read().await;
         let bunker_state = state.get_bunker_state().await;
@@ -275,24 +1623,325 @@ impl SignerInterface {
 pub struct SignerService;
 
 impl SignerService {
-    pub async fn run(app_state: Arc<RwLock<AppState>>, key_manager: Arc<Mutex<KeyManager>>) -> Result<()> {
-        let interface = SignerInterface::new(app_state, key_manager);
-
-        let _connection = ConnectionBuilder::session()
-            .map_err(|e| SignerError::DbusError(e.to_string()))?
+    /// Register the service on the session bus
+    ///
+    /// The service stays up as long as the returned connection is kept alive.
+    /// Fails with `AlreadyRunning` if another instance owns the bus name.
+    pub async fn start(app_state: Arc<RwLock<AppState>>, key_manager: Arc<Mutex<KeyManager>>) -> Result<Connection> {
+        let builder = ConnectionBuilder::session()
+            .map_err(connection_error)?
             .name(DBUS_NAME)
-            .map_err(|e| SignerError::DbusError(e.to_string()))?
+            .map_err(connection_error)?;
+        let connection = Self::serve(builder, app_state, key_manager).await?;
+        info!("D-Bus service started at {} on {}", DBUS_PATH, DBUS_NAME);
+        Ok(connection)
+    }
+
+    /// Serve the interface on the connection `builder` sets up (the session
+    /// bus, or a private connection in tests)
+    pub async fn serve(
+        builder: ConnectionBuilder<'_>,
+        app_state: Arc<RwLock<AppState>>,
+        key_manager: Arc<Mutex<KeyManager>>,
+    ) -> Result<Connection> {
+        let (engine_config, activity, blocked) = {
+            let state = app_state.read().await;
+            (state.config.engines.clone(), Arc::clone(&state.inactivity), state.blocked_kinds.clone())
+        };
+        let mut key_events = key_manager.lock().await.subscribe_key_events();
+        let engines = EngineRouter::from_config(&engine_config, key_manager, activity, blocked).await;
+        let mut queued = app_state.read().await.approval_queue.subscribe();
+        let interface = SignerInterface::new(app_state, engines);
+
+        let connection = builder
             .serve_at(DBUS_PATH, interface)
-            .map_err(|e| SignerError::DbusError(e.to_string()))?
+            .map_err(connection_error)?
             .build()
             .await
-            .map_err(|e| SignerError::DbusError(e.to_string()))?;
+            .map_err(connection_error)?;
 
-        info!("D-Bus service started at {} on {}", DBUS_PATH, DBUS_NAME);
+        // Announce each request that starts waiting for approval
+        let signals = connection.clone();
+        tokio::spawn(async move {
+            while let Some(request) = queued.recv().await {
+                if let Err(e) = announce_pending(&signals, &request).await {
+                    warn!("Failed to signal pending request {}: {}", request.id, e);
+                }
+            }
+        });
+
+        // Announce keys added, removed and activated through our key manager
+        let signals = connection.clone();
+        tokio::spawn(async move {
+            loop {
+                match key_events.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = announce_key_event(&signals, &event).await {
+                            warn!("Failed to signal {:?}: {}", event, e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Missed {} key change signals", missed);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
 
-        // Keep the connection alive
-        loop {
-            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        Ok(connection)
+    }
+}
+
+/// Emit `PendingRequest` for a newly queued request
+async fn announce_pending(connection: &Connection, request: &SigningRequest) -> zbus::Result<()> {
+    let interface = connection.object_server().interface::<_, SignerInterface>(DBUS_PATH).await?;
+    SignerInterface::pending_request(interface.signal_context(), &request.id, &request.app_id, &request.summary()).await
+}
+
+/// Emit the signal for a key change
+async fn announce_key_event(connection: &Connection, event: &KeyEvent) -> zbus::Result<()> {
+    let interface = connection.object_server().interface::<_, SignerInterface>(DBUS_PATH).await?;
+    let ctxt = interface.signal_context();
+    match event {
+        KeyEvent::Added(npub) => SignerInterface::key_added(ctxt, npub).await,
+        KeyEvent::Removed(npub) => SignerInterface::key_removed(ctxt, npub).await,
+        KeyEvent::ActiveChanged(npub) => SignerInterface::active_key_changed(ctxt, npub).await,
+    }
+}
+
+/// Map a connection setup error, singling out another instance owning our name
+fn connection_error(e: zbus::Error) -> SignerError {
+    match e {
+        zbus::Error::NameTaken => SignerError::AlreadyRunning,
+        other => SignerError::DbusError(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppPermissions;
+
+    fn authorized(app_id: &str, name: &str) -> AuthorizedApp {
+        AuthorizedApp {
+            app_id: app_id.to_string(),
+            name: name.to_string(),
+            authorized_at: chrono::Utc::now(),
+            permissions: AppPermissions::default(),
+            auto_approve: false,
+            auto_approve_decrypt: false,
+            expiration: None,
+        }
+    }
+
+    #[test]
+    fn test_permissions_key_off_verified_sender() {
+        let mut config = Config::default_config();
+        config.authorized_apps.push(authorized("trusted-client", "Trusted"));
+        config.authorized_apps.push(authorized("/usr/bin/gossip", "Gossip"));
+
+        // A different binary claiming a trusted id gets nothing
+        let spoofer = CallerIdentity::new(":1.7", Some(4242), Some("/tmp/evil".into()), "trusted-client");
+        assert_eq!(spoofer.id, "/tmp/evil");
+        assert!(authorized_app_for(&config, &spoofer).is_none());
+        assert_eq!(spoofer.display_name(None), "trusted-client");
+
+        // The real binary is matched whatever it claims to be
+        let gossip = CallerIdentity::new(":1.8", Some(4343), Some("/usr/bin/gossip".into()), "something-else");
+        let app = authorized_app_for(&config, &gossip);
+        assert_eq!(app.map(|a| a.name.as_str()), Some("Gossip"));
+        assert_eq!(gossip.display_name(app), "Gossip");
+
+        // Without a resolvable process, the unique bus name is the identity
+        let unknown = CallerIdentity::new(":1.9", None, None, "trusted-client");
+        assert_eq!(unknown.id, ":1.9");
+        assert!(authorized_app_for(&config, &unknown).is_none());
+    }
+
+    #[test]
+    fn test_executable_allow_list() {
+        let allowed = vec!["/usr/bin/gossip".to_string()];
+        let exe_of = |pid: u32| match pid {
+            100 => Some("/usr/bin/gossip".to_string()),
+            200 => Some("/tmp/evil".to_string()),
+            _ => None,
+        };
+
+        let gossip = CallerIdentity::from_pid(":1.1", Some(100), "", exe_of);
+        assert_eq!(gossip.executable(), Some("/usr/bin/gossip"));
+        assert!(check_executable_allowed(&allowed, &gossip).is_ok());
+
+        // Claiming an allowed app id doesn't help a different binary
+        let evil = CallerIdentity::from_pid(":1.2", Some(200), "/usr/bin/gossip", exe_of);
+        assert!(matches!(check_executable_allowed(&allowed, &evil), Err(SignerError::PermissionDenied(_))));
+
+        // Exited process or no PID from the bus: can't verify, so rejected
+        let vanished = CallerIdentity::from_pid(":1.3", Some(300), "", exe_of);
+        assert_eq!(vanished.executable(), None);
+        assert!(check_executable_allowed(&allowed, &vanished).is_err());
+        let no_pid = CallerIdentity::from_pid(":1.4", None, "", exe_of);
+        assert!(check_executable_allowed(&allowed, &no_pid).is_err());
+
+        // No list: everyone may call
+        assert!(check_executable_allowed(&[], &evil).is_ok());
+        assert!(check_executable_allowed(&[], &no_pid).is_ok());
+    }
+
+    #[test]
+    fn test_own_ui_is_internal() {
+        let exe = std::env::current_exe().unwrap().to_string_lossy().into_owned();
+
+        let ui = CallerIdentity::new(":1.10", Some(5000), Some(exe.clone()), "");
+        assert_eq!(ui.origin(true), RequestOrigin::Internal);
+        assert_eq!(ui.origin(false), RequestOrigin::External);
+
+        // Claiming to be the signer, or to run its binary, isn't enough
+        let claimed = CallerIdentity::new(":1.11", Some(5001), Some("/tmp/evil".into()), &exe);
+        assert_eq!(claimed.origin(true), RequestOrigin::External);
+        let unresolved = CallerIdentity::new(&exe, None, None, &exe);
+        assert_eq!(unresolved.origin(true), RequestOrigin::External);
+    }
+
+    #[test]
+    fn test_my_permissions_match_grant() {
+        let mut config = Config::default_config();
+        let mut app = authorized("/usr/bin/gossip", "Gossip");
+        app.permissions = AppPermissions {
+            get_public_key: true,
+            sign_event: Some(vec![1, 7]),
+            nip44_encrypt: true,
+            nip44_decrypt: true,
+            ..AppPermissions::default()
+        };
+        config.authorized_apps.push(app);
+
+        let caller = CallerIdentity::new(":1.5", Some(100), Some("/usr/bin/gossip".into()), "gossip");
+        let json = DbusResponse::success("req".into(), MyPermissions::resolve(&config, &caller));
+
+        // What a client sees after parsing the response
+        let response: DbusResponse = serde_json::from_str(&json).unwrap();
+        let mine: crate::client::MyPermissions = serde_json::from_str(&response.result.unwrap()).unwrap();
+        assert!(mine.authorized);
+        assert_eq!(mine.permissions.sign_event, Some(vec![1, 7]));
+        assert!(mine.permissions.get_public_key);
+        assert!(mine.permissions.nip44_encrypt && mine.permissions.nip44_decrypt);
+        assert!(!mine.permissions.nip04_decrypt);
+
+        // Unauthorized callers get the default-deny shape
+        let stranger = CallerIdentity::new(":1.6", Some(101), Some("/usr/bin/other".into()), "gossip");
+        let denied = MyPermissions::resolve(&config, &stranger);
+        assert!(!denied.authorized);
+        assert_eq!(denied.permissions.sign_event, Some(vec![]));
+        assert!(!denied.permissions.get_public_key);
+        assert!(!denied.permissions.nip44_decrypt);
+    }
+
+    #[test]
+    fn test_name_taken_means_already_running() {
+        assert!(matches!(connection_error(zbus::Error::NameTaken), SignerError::AlreadyRunning));
+        assert!(matches!(
+            connection_error(zbus::Error::Failure("no session bus".into())),
+            SignerError::DbusError(_)
+        ));
+    }
+
+    #[test]
+    fn test_heartbeat_counter_increments() {
+        let heartbeat = Heartbeat::default();
+        let first = heartbeat.beat();
+        let second = heartbeat.beat();
+        let third = heartbeat.beat();
+
+        assert_eq!(first.counter, 1);
+        assert!(second.counter > first.counter);
+        assert!(third.counter > second.counter);
+        assert!(third.timestamp_ms >= first.timestamp_ms);
+    }
+
+    #[test]
+    fn test_capabilities_parse_on_client() {
+        let body = DbusResponse::success("req-1".into(), capabilities());
+        let response: crate::client::SignerResponse = serde_json::from_str(&body).unwrap();
+        assert!(response.success);
+        let caps: crate::client::Capabilities = serde_json::from_str(&response.result.unwrap()).unwrap();
+        assert_eq!(caps.interface_version, INTERFACE_VERSION);
+        assert_eq!(caps.capabilities.len(), CAPABILITIES.len());
+        for cap in ["nip04", "nip44", "nip46", "sign_event", "get_public_key", "decrypt_zap_event"] {
+            assert!(caps.supports(cap), "{}", cap);
+        }
+        assert!(!caps.supports("nip07"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_event_object_parses_once() {
+        let keys = nostr::Keys::generate();
+        let engine = crate::signing::SigningEngine::new(Arc::new(Mutex::new(KeyManager::with_test_key("main", keys.clone()))));
+        let event_data = UnsignedEventData {
+            kind: 1,
+            content: "no double encoding".into(),
+            tags: vec![vec!["t".into(), "nostr".into()]],
+            created_at: Some(1_700_000_000),
+            relay_hints: Vec::new(),
+        };
+        let result = engine.sign_event(&event_data, None).await.unwrap();
+
+        let body = DbusObjectResponse::success("req-1".into(), signed_event_object(&result).unwrap());
+        #[derive(Deserialize)]
+        struct Response {
+            success: bool,
+            result: nostr::Event,
+        }
+        let response: Response = serde_json::from_str(&body).unwrap();
+        assert!(response.success);
+        assert!(response.result.verify().is_ok());
+        assert_eq!(response.result.pubkey, keys.public_key());
+        assert_eq!(response.result.content, "no double encoding");
+
+        // SignEvent keeps returning the result as a string
+        let legacy: DbusResponse = serde_json::from_str(&DbusResponse::success("req-2".into(), &result)).unwrap();
+        assert!(legacy.result.unwrap().contains("\\\"content\\\""));
+    }
+
+    #[tokio::test]
+    async fn test_rejection_reason_reaches_client() {
+        use crate::approval::{ApprovalQueue, REJECTION_REASONS};
+
+        let queue = Arc::new(ApprovalQueue::new());
+        let request = SigningRequest {
+            id: "req-1".into(),
+            request_type: RequestType::SignEvent,
+            app_id: "/usr/bin/gossip".into(),
+            app_name: Some("Gossip".into()),
+            key_id: None,
+            payload: SigningPayload::Event(UnsignedEventData {
+                kind: 1,
+                content: "hello".into(),
+                tags: Vec::new(),
+                created_at: None,
+                relay_hints: Vec::new(),
+            }),
+            timestamp: chrono::Utc::now(),
+            warnings: Vec::new(),
+            origin: RequestOrigin::External,
+        };
+        let waiter = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { queue.request_approval(request, Duration::from_secs(5)).await })
+        };
+        while queue.pending().is_empty() {
+            tokio::task::yield_now().await;
         }
+
+        let reason = REJECTION_REASONS[0];
+        assert!(queue.reject_with_reason("req-1", reason));
+        let error = waiter.await.unwrap().unwrap_err();
+        assert!(matches!(error, SignerError::UserRejectedWithReason(_)));
+
+        let response: DbusResponse = serde_json::from_str(&DbusResponse::error("req-1".into(), &error)).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("User rejected the request: Wrong account"));
+
+        let entry = AuditEntry::refused("/usr/bin/gossip", RequestType::SignEvent, Some(1), &error.to_string());
+        assert!(entry.reason.unwrap().ends_with(reason));
     }
 }
@@ -0,0 +1,227 @@
+//! Environment report for bug reports (`--diagnostics`)
+//!
+//! Only non-secret information is included: no keys (npub or nsec), no app
+//! identifiers, executable paths or banner text. Paths under the home
+//! directory are shortened to `~`.
+
+use crate::config::Config;
+use crate::dbus::DBUS_NAME;
+use crate::keys::KeysMetadata;
+use std::fmt::Write;
+use std::path::Path;
+use zbus::names::BusName;
+use zbus::Connection;
+
+/// Secret Service name, used by the OS keyring
+const SECRET_SERVICE_NAME: &str = "org.freedesktop.secrets";
+
+/// Environment information collected for a bug report
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub version: String,
+    pub config_path: String,
+    pub data_dir: String,
+    /// XDG_CURRENT_DESKTOP
+    pub desktop: String,
+    /// XDG_SESSION_TYPE (x11, wayland, tty)
+    pub session_type: String,
+    /// Session bus reachability
+    pub dbus_session: String,
+    /// Whether a Secret Service (OS keyring backend) is on the bus
+    pub keyring_backend: String,
+    /// Whether another Pleb Signer owns the D-Bus name
+    pub signer_running: String,
+    pub key_count: String,
+    /// Non-secret configuration values
+    pub settings: Vec<(&'static str, String)>,
+}
+
+impl Diagnostics {
+    /// Gather the report for the current environment
+    pub async fn collect(config: &Config) -> Self {
+        let home = std::env::var("HOME").ok();
+        let shorten = |path: &Path| shorten_home(path, home.as_deref());
+        let env = |name: &str| std::env::var(name).unwrap_or_else(|_| "unset".into());
+
+        let (dbus_session, keyring_backend, signer_running) = match Connection::session().await {
+            Ok(connection) => (
+                "connected".to_string(),
+                name_status(&connection, SECRET_SERVICE_NAME).await,
+                name_status(&connection, DBUS_NAME).await,
+            ),
+            Err(e) => (format!("unavailable ({})", e), "unknown".into(), "unknown".into()),
+        };
+
+        let key_count = match KeysMetadata::load(None).await {
+            Ok(metadata) => metadata.keys.len().to_string(),
+            Err(e) => format!("unknown ({})", e),
+        };
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config_path: shorten(config.config_path()),
+            data_dir: Config::data_dir()
+                .map(|dir| shorten(&dir))
+                .unwrap_or_else(|e| format!("unknown ({})", e)),
+            desktop: env("XDG_CURRENT_DESKTOP"),
+            session_type: env("XDG_SESSION_TYPE"),
+            dbus_session,
+            keyring_backend,
+            signer_running,
+            key_count,
+            settings: settings(config),
+        }
+    }
+
+    /// Plain-text report for pasting into an issue
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Pleb Signer diagnostics");
+        let _ = writeln!(out, "version: {}", self.version);
+        let _ = writeln!(out, "config_path: {}", self.config_path);
+        let _ = writeln!(out, "data_dir: {}", self.data_dir);
+        let _ = writeln!(out, "desktop: {}", self.desktop);
+        let _ = writeln!(out, "session_type: {}", self.session_type);
+        let _ = writeln!(out, "dbus_session: {}", self.dbus_session);
+        let _ = writeln!(out, "keyring_backend: {}", self.keyring_backend);
+        let _ = writeln!(out, "signer_running: {}", self.signer_running);
+        let _ = writeln!(out, "keys: {}", self.key_count);
+        let _ = writeln!(out, "[config]");
+        for (name, value) in &self.settings {
+            let _ = writeln!(out, "{}: {}", name, value);
+        }
+        out
+    }
+}
+
+/// Whether a bus name currently has an owner
+async fn name_status(connection: &Connection, name: &str) -> String {
+    let Ok(bus_name) = BusName::try_from(name) else {
+        return "unknown".into();
+    };
+    match zbus::fdo::DBusProxy::new(connection).await {
+        Ok(proxy) => match proxy.name_has_owner(bus_name).await {
+            Ok(true) => "available".into(),
+            Ok(false) => "not found".into(),
+            Err(e) => format!("unknown ({})", e),
+        },
+        Err(e) => format!("unknown ({})", e),
+    }
+}
+
+/// Replace the home directory prefix with `~`
+fn shorten_home(path: &Path, home: Option<&str>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+/// Config values safe to share; lists that could identify apps or people
+/// are reported as counts only
+fn settings(config: &Config) -> Vec<(&'static str, String)> {
+    let general = &config.general;
+    let security = &config.security;
+    let ui = &config.ui;
+    let bunker = &config.bunker;
+    vec![
+        ("general.start_minimized", general.start_minimized.to_string()),
+        ("general.auto_start", general.auto_start.to_string()),
+        ("general.show_notifications", general.show_notifications.to_string()),
+        ("general.request_timeout_secs", general.request_timeout_secs.to_string()),
+        ("general.missing_active_key", format!("{:?}", general.missing_active_key)),
+        ("security.require_password_on_start", security.require_password_on_start.to_string()),
+        ("security.lock_timeout_mins", security.lock_timeout_mins.to_string()),
+        ("security.lock_warning_secs", security.lock_warning_secs.to_string()),
+        ("security.always_confirm", security.always_confirm.to_string()),
+        ("security.allow_auto_approve", security.allow_auto_approve.to_string()),
+        ("security.auto_approve_read_only", security.auto_approve_read_only.to_string()),
+        ("security.max_auto_approvals_per_min", security.max_auto_approvals_per_min.to_string()),
+        ("security.unknown_kind_policy", format!("{:?}", security.unknown_kind_policy)),
+        ("security.confirm_decrypt", security.confirm_decrypt.to_string()),
+        ("security.session_grant_mins", security.session_grant_mins.to_string()),
+        ("security.max_future_created_at_secs", security.max_future_created_at_secs.to_string()),
+        ("security.future_created_at_policy", format!("{:?}", security.future_created_at_policy)),
+        ("security.trust_own_ui", security.trust_own_ui.to_string()),
+        ("security.allowed_executables", format!("{} entries", security.allowed_executables.len())),
+        ("security.presence_check", format!("{:?}", security.presence_check)),
+        ("security.authorize_unknown_apps", security.authorize_unknown_apps.to_string()),
+        ("security.denial_alert_threshold", security.denial_alert_threshold.to_string()),
+        ("security.denial_alert_window_mins", security.denial_alert_window_mins.to_string()),
+        ("security.max_pending_requests", security.max_pending_requests.to_string()),
+        ("security.scan_content_for_secrets", security.scan_content_for_secrets.to_string()),
+        ("security.sensitive_keywords", format!("{} entries", security.sensitive_keywords.len())),
+        ("security.ncryptsec_log_n", security.ncryptsec_log_n.to_string()),
+        ("security.blocked_event_kinds", format!("{:?}", security.blocked_event_kinds)),
+        ("security.clipboard_clear_secs", security.clipboard_clear_secs.to_string()),
+        ("security.nip04_warning_suppressed", format!("{} entries", security.nip04_warning_suppressed.len())),
+        ("ui.theme", ui.theme.clone()),
+        ("ui.compact_mode", ui.compact_mode.to_string()),
+        ("ui.approval_countdowns", format!("{} entries", ui.approval_countdowns.len())),
+        ("ui.confirmation_template", if ui.confirmation_template.is_empty() { "unset" } else { "set" }.into()),
+        ("ui.tray_left_click", format!("{:?}", ui.tray_left_click)),
+        ("bunker.queue_while_locked", bunker.queue_while_locked.to_string()),
+        ("bunker.auto_start", bunker.auto_start.to_string()),
+        ("bunker.auto_start_delay_secs", bunker.auto_start_delay_secs.to_string()),
+        ("bunker.auto_start_confirm", bunker.auto_start_confirm.to_string()),
+        ("bunker.max_responses_per_sec", bunker.max_responses_per_sec.to_string()),
+        ("bunker.response_backlog", bunker.response_backlog.to_string()),
+        ("bunker.secret", if bunker.secret.is_some() { "configured" } else { "generated" }.to_string()),
+        ("bunker.share_on_lan", bunker.share_on_lan.to_string()),
+        ("authorized_apps", format!("{} entries", config.authorized_apps.len())),
+        ("engines", format!("{} entries", config.engines.len())),
+        ("safe_mode", config.safe_mode.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppPermissions, AuthorizedApp};
+    use nostr::{Keys, ToBech32};
+
+    #[test]
+    fn test_dump_has_fields_and_no_secrets() {
+        let keys = Keys::generate();
+        let npub = keys.public_key().to_bech32().unwrap();
+        let nsec = keys.secret_key().to_bech32().unwrap();
+
+        let mut config = Config::default_config();
+        config.ui.confirmation_template = format!("Signing as {} ({})", npub, nsec);
+        config.security.allowed_executables = vec!["/home/alice/bin/secret-client".into()];
+        config.authorized_apps.push(AuthorizedApp {
+            app_id: "/usr/bin/private-messenger".into(),
+            name: "Private Messenger".into(),
+            authorized_at: chrono::Utc::now(),
+            permissions: AppPermissions::default(),
+            auto_approve: false,
+            auto_approve_decrypt: false,
+            expiration: None,
+        });
+
+        let diagnostics = Diagnostics {
+            version: env!("CARGO_PKG_VERSION").into(),
+            config_path: shorten_home(Path::new("/home/alice/.config/plebsigner/config.toml"), Some("/home/alice")),
+            data_dir: "~/.local/share/plebsigner".into(),
+            desktop: "KDE".into(),
+            session_type: "wayland".into(),
+            dbus_session: "connected".into(),
+            keyring_backend: "available".into(),
+            signer_running: "not found".into(),
+            key_count: "2".into(),
+            settings: settings(&config),
+        };
+        let dump = diagnostics.render();
+
+        for field in [
+            "version:", "config_path: ~/.config/plebsigner/config.toml", "data_dir:", "desktop: KDE",
+            "session_type: wayland", "dbus_session:", "keyring_backend:", "keys: 2",
+            "security.always_confirm:", "authorized_apps: 1 entries",
+        ] {
+            assert!(dump.contains(field), "missing {:?} in:\n{}", field, dump);
+        }
+        for secret in ["npub1", "nsec1", "ncryptsec1", "alice", "private-messenger", "Private Messenger"] {
+            assert!(!dump.contains(secret), "{:?} leaked in:\n{}", secret, dump);
+        }
+    }
+}
@@ -28,6 +28,12 @@ pub enum SignerError {
     #[error("Key already exists: {0}")]
     KeyAlreadyExists(String),
 
+    #[error("Key '{0}' is locked with its own password")]
+    KeyPasswordRequired(String),
+
+    #[error("Key mismatch: {0}")]
+    KeyMismatch(String),
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
@@ -40,15 +46,30 @@ pub enum SignerError {
     #[error("Nostr error: {0}")]
     NostrError(String),
 
+    #[error("NIP-05 verification failed: {0}")]
+    Nip05Error(String),
+
     #[error("D-Bus error: {0}")]
     DbusError(String),
 
+    #[error("Pleb Signer is already running")]
+    AlreadyRunning,
+
     #[error("User rejected the request")]
     UserRejected,
 
+    #[error("User rejected the request: {0}")]
+    UserRejectedWithReason(String),
+
+    #[error("Signer busy: too many requests waiting for approval")]
+    SignerBusy,
+
     #[error("Request timeout")]
     Timeout,
 
+    #[error("Presence check failed: {0}")]
+    PresenceCheckFailed(String),
+
     #[error("Application not authorized: {0}")]
     NotAuthorized(String),
 
@@ -2,19 +2,84 @@
 //!
 //! Uses the OS keyring (Secret Service on Linux) for secure key storage.
 
+use crate::backup::StateBackup;
+use crate::config::{Config, MissingActiveKeyPolicy};
 use crate::error::{Result, SignerError};
+use crate::nip05::Nip05Identifier;
+use crate::relays::normalize_relay_url;
 use nostr::prelude::*;
 use nostr_keyring::NostrKeyring;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
 const KEYRING_SERVICE: &str = "pleb-signer";
-const METADATA_FILE: &str = "keys_metadata.json";
+pub(crate) const METADATA_FILE: &str = "keys_metadata.json";
+/// Appended to a metadata file's name for its lock file
+pub(crate) const LOCK_SUFFIX: &str = ".lock";
+
+/// scrypt cost (NIP-49 log_n) of per-key password verifiers
+const VERIFIER_LOG_N: u8 = if cfg!(test) { 4 } else { 16 };
+
+/// Accepted scrypt costs for NIP-49 exports; below is too weak, above takes
+/// minutes and gigabytes of memory to decrypt
+pub const NCRYPTSEC_LOG_N: std::ops::RangeInclusive<u8> = 14..=22;
+
+/// Word counts of a valid BIP-39 recovery phrase
+pub const MNEMONIC_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Capacity of the key change channel; slower subscribers miss events
+const KEY_EVENT_CAPACITY: usize = 16;
+
+/// A change to the key set, announced to subscribers (e.g. the D-Bus
+/// `KeyAdded`, `KeyRemoved` and `ActiveKeyChanged` signals)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// npub of a key that was added
+    Added(String),
+    /// npub of a key that was deleted
+    Removed(String),
+    /// npub of the new active key, empty when no key is active
+    ActiveChanged(String),
+}
+
+/// The key set as last announced, to tell what a save changed
+#[derive(Debug, Clone, Default)]
+struct KeySnapshot {
+    npubs: HashSet<String>,
+    active: Option<String>,
+}
+
+impl KeySnapshot {
+    fn of(metadata: &KeysMetadata) -> Self {
+        Self {
+            npubs: metadata.keys.values().map(|m| m.npub.clone()).collect(),
+            active: metadata.active_key.as_ref()
+                .and_then(|name| metadata.keys.get(name))
+                .map(|m| m.npub.clone()),
+        }
+    }
+
+    /// Events that take this snapshot to `newer`: additions, then
+    /// removals, then the active key
+    fn changes_to(&self, newer: &KeySnapshot) -> Vec<KeyEvent> {
+        let mut added: Vec<&String> = newer.npubs.difference(&self.npubs).collect();
+        let mut removed: Vec<&String> = self.npubs.difference(&newer.npubs).collect();
+        added.sort();
+        removed.sort();
+        let mut events: Vec<KeyEvent> = added.into_iter().map(|npub| KeyEvent::Added(npub.clone())).collect();
+        events.extend(removed.into_iter().map(|npub| KeyEvent::Removed(npub.clone())));
+        if self.active != newer.active {
+            events.push(KeyEvent::ActiveChanged(newer.active.clone().unwrap_or_default()));
+        }
+        events
+    }
+}
 
 /// Metadata about a stored key (public info only)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyMetadata {
     /// Unique name/label for this key
     pub name: String,
@@ -26,6 +91,122 @@ pub struct KeyMetadata {
     pub created_at: chrono::DateTime<chrono::Utc>,
     /// Whether this is the active/default key
     pub is_active: bool,
+    /// Free-form labels for grouping keys (work, personal, bots...)
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// NIP-49 encryption of a throwaway key under this key's own password;
+    /// when set, the key can't sign or be exported until that password is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_verifier: Option<String>,
+    /// The user's relays for this key
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relays: Vec<String>,
+    /// NIP-05 identifier (name@domain)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nip05: Option<String>,
+    /// Lightning address for zaps (name@domain)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lud16: Option<String>,
+    /// Public key only: there is no keyring entry, so it can't sign,
+    /// encrypt or decrypt, or become the active key
+    #[serde(default)]
+    pub watch_only: bool,
+}
+
+impl KeyMetadata {
+    /// Whether the key needs its own password on top of the app unlock
+    pub fn is_password_protected(&self) -> bool {
+        self.password_verifier.is_some()
+    }
+
+    /// Check `password` against the key's verifier (true if it has none)
+    fn password_matches(&self, password: &str) -> bool {
+        let Some(ref verifier) = self.password_verifier else {
+            return true;
+        };
+        EncryptedSecretKey::from_bech32(verifier)
+            .is_ok_and(|encrypted| encrypted.decrypt(password).is_ok())
+    }
+
+    /// Whether this key is shown under a label filter (None = show all)
+    pub fn matches_label(&self, filter: Option<&str>) -> bool {
+        match filter {
+            Some(label) => self.labels.iter().any(|l| l == label),
+            None => true,
+        }
+    }
+}
+
+/// A key exported together with the user's relays and profile, e.g.
+/// `{"nsec": "nsec1...", "relays": ["wss://..."], "profile": {"nip05": "...", "lud16": "..."}}`
+///
+/// `relays` may also be a NIP-65 style object keyed by URL, and `profile`
+/// may be called `metadata`. Each part is checked on its own, so an error
+/// names the part that is wrong.
+pub struct CombinedImport {
+    pub keys: Keys,
+    pub relays: Vec<String>,
+    pub nip05: Option<String>,
+    pub lud16: Option<String>,
+}
+
+impl CombinedImport {
+    pub fn parse(json: &str) -> Result<Self> {
+        let blob: serde_json::Value = serde_json::from_str(json.trim())
+            .map_err(|e| SignerError::InvalidRequest(format!("Invalid combined key JSON: {}", e)))?;
+
+        let secret = blob.get("nsec").or_else(|| blob.get("secret_key"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| SignerError::InvalidKeyFormat("Missing nsec".into()))?;
+        let keys = Keys::parse(secret)
+            .map_err(|e| SignerError::InvalidKeyFormat(format!("nsec: {}", e)))?;
+
+        let urls: Vec<&str> = match blob.get("relays") {
+            None | Some(serde_json::Value::Null) => Vec::new(),
+            Some(serde_json::Value::Array(list)) => list.iter()
+                .map(|v| v.as_str().ok_or_else(|| SignerError::InvalidRequest(format!("Invalid relay: {}", v))))
+                .collect::<Result<_>>()?,
+            Some(serde_json::Value::Object(map)) => map.keys().map(String::as_str).collect(),
+            Some(other) => return Err(SignerError::InvalidRequest(format!("Invalid relays: {}", other))),
+        };
+        let mut relays = Vec::with_capacity(urls.len());
+        for url in urls {
+            let relay = normalize_relay_url(url)
+                .ok_or_else(|| SignerError::InvalidRequest(format!("Invalid relay: {}", url)))?;
+            if !relays.contains(&relay) {
+                relays.push(relay);
+            }
+        }
+
+        let profile = blob.get("profile").or_else(|| blob.get("metadata"));
+        let field = |name: &str| {
+            profile.and_then(|p| p.get(name)).or_else(|| blob.get(name))
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let nip05 = field("nip05")
+            .map(|id| Nip05Identifier::parse(id).map(|id| format!("{}@{}", id.name, id.domain)))
+            .transpose()?;
+        let lud16 = field("lud16").map(parse_lightning_address).transpose()?;
+
+        Ok(Self { keys, relays, nip05, lud16 })
+    }
+}
+
+/// Validate a lightning address (`name@domain`)
+fn parse_lightning_address(address: &str) -> Result<String> {
+    let invalid = || SignerError::InvalidRequest(format!("Invalid lud16 address: {}", address));
+    let address = address.to_lowercase();
+    let (name, domain) = address.split_once('@').ok_or_else(invalid)?;
+    let valid_name = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'));
+    let valid_domain = domain.contains('.')
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'));
+    if !valid_name || !valid_domain {
+        return Err(invalid());
+    }
+    Ok(address)
 }
 
 /// Stored key metadata (persisted to disk)
@@ -35,19 +216,31 @@ pub struct KeysMetadata {
     pub keys: HashMap<String, KeyMetadata>,
     /// Currently active key name
     pub active_key: Option<String>,
+    /// Bumped on every save, so cached keys from an older version are dropped
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl KeysMetadata {
-    fn path() -> Result<PathBuf> {
+    /// Metadata file for a key namespace (None = the default key set)
+    pub fn path_for(namespace: Option<&str>) -> Result<PathBuf> {
         let proj_dirs = directories::ProjectDirs::from("com", "plebsigner", "PlebSigner")
             .ok_or_else(|| SignerError::ConfigError("Could not determine data directory".into()))?;
-        Ok(proj_dirs.data_dir().join(METADATA_FILE))
+        let file = match namespace {
+            Some(ns) => format!("keys_metadata.{}.json", ns),
+            None => METADATA_FILE.to_string(),
+        };
+        Ok(proj_dirs.data_dir().join(file))
+    }
+
+    pub async fn load(namespace: Option<&str>) -> Result<Self> {
+        Self::load_from(&Self::path_for(namespace)?).await
     }
 
-    pub async fn load() -> Result<Self> {
-        let path = Self::path()?;
+    /// Load from `path` (empty if there is no file yet)
+    pub async fn load_from(path: &Path) -> Result<Self> {
         if path.exists() {
-            let content = fs::read_to_string(&path).await?;
+            let content = fs::read_to_string(path).await?;
             let metadata: KeysMetadata = serde_json::from_str(&content)?;
             Ok(metadata)
         } else {
@@ -55,44 +248,352 @@ impl KeysMetadata {
         }
     }
 
-    pub async fn save(&self) -> Result<()> {
-        let path = Self::path()?;
+    /// Make sure an active key is set whenever keys exist
+    ///
+    /// A dangling `active_key` (e.g. left behind by a failed delete) counts as
+    /// unset. With `SelectNewest` the most recently created key is activated,
+    /// ties broken by name so the choice is deterministic; with `Prompt` the
+    /// key is left unset for the user to choose. Returns true if anything
+    /// changed.
+    pub fn reconcile_active_key(&mut self, policy: MissingActiveKeyPolicy) -> bool {
+        if let Some(ref name) = self.active_key {
+            if self.keys.contains_key(name) {
+                return false;
+            }
+        }
+
+        let selected = match policy {
+            MissingActiveKeyPolicy::SelectNewest => self.keys.values()
+                .filter(|m| !m.watch_only)
+                .max_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| b.name.cmp(&a.name)))
+                .map(|m| m.name.clone()),
+            MissingActiveKeyPolicy::Prompt => None,
+        };
+        let changed = self.active_key.is_some()
+            || selected.is_some()
+            || self.keys.values().any(|m| m.is_active);
+
+        for (key_name, meta) in &mut self.keys {
+            meta.is_active = selected.as_ref() == Some(key_name);
+        }
+        self.active_key = selected;
+        changed
+    }
+
+    /// Make `is_active` true for exactly the key named by `active_key`
+    ///
+    /// `active_key` is the source of truth; flags left over from manual
+    /// edits or interrupted writes are corrected. Returns true if any flag
+    /// changed.
+    pub fn normalize_active_flags(&mut self) -> bool {
+        let mut changed = false;
+        for (key_name, meta) in &mut self.keys {
+            let is_active = self.active_key.as_ref() == Some(key_name);
+            if meta.is_active != is_active {
+                meta.is_active = is_active;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Save to `path`, replacing the file atomically
+    pub async fn save_to(&mut self, path: &Path) -> Result<()> {
+        self.normalize_active_flags();
+        self.version += 1;
+        let content = serde_json::to_string_pretty(self)?;
+        crate::config::write_atomic(path, &content).await
+    }
+
+    /// Our changes since `base` applied on top of `theirs`, what another
+    /// process saved in the meantime
+    ///
+    /// Keys we added, deleted or edited and the active key, if we switched
+    /// it, are ours; everything else is theirs. `is_active` flags follow
+    /// the active key and don't count as edits.
+    fn merge(base: &KeysMetadata, ours: &KeysMetadata, mut theirs: KeysMetadata) -> KeysMetadata {
+        let unflagged = |meta: Option<&KeyMetadata>| meta.map(|m| KeyMetadata { is_active: false, ..m.clone() });
+        let names: HashSet<&String> = base.keys.keys().chain(ours.keys.keys()).collect();
+        for name in names {
+            if unflagged(base.keys.get(name)) == unflagged(ours.keys.get(name)) {
+                continue;
+            }
+            match ours.keys.get(name) {
+                Some(meta) => theirs.keys.insert(name.clone(), meta.clone()),
+                None => theirs.keys.remove(name),
+            };
+        }
+        if base.active_key != ours.active_key {
+            theirs.active_key = ours.active_key.clone();
+        }
+        theirs
+    }
+}
+
+/// Kind of access to a metadata file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockMode {
+    Read,
+    Write,
+}
+
+/// Advisory lock on a metadata file, held while reading or replacing it
+///
+/// The signer process and the UI subprocess each have their own
+/// `KeyManager`; the lock makes a save's read-merge-write one step, so
+/// neither saves over what the other just wrote. Released on drop.
+struct MetadataLock {
+    _file: std::fs::File,
+}
+
+impl MetadataLock {
+    async fn acquire(metadata: &Path, mode: LockMode) -> Result<Self> {
+        let mut name = metadata.file_name().unwrap_or_default().to_os_string();
+        name.push(LOCK_SUFFIX);
+        let path = metadata.with_file_name(name);
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content).await?;
-        Ok(())
+        tokio::task::spawn_blocking(move || -> Result<Self> {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            match mode {
+                LockMode::Read => fs2::FileExt::lock_shared(&file)?,
+                LockMode::Write => fs2::FileExt::lock_exclusive(&file)?,
+            }
+            Ok(Self { _file: file })
+        })
+        .await
+        .map_err(std::io::Error::other)?
+    }
+}
+
+/// Where secret keys are stored
+enum KeyStore {
+    /// The OS keyring
+    Os(NostrKeyring),
+    /// In-memory store, so tests can change entries behind the manager's back
+    #[cfg(test)]
+    Memory(std::sync::Arc<std::sync::Mutex<HashMap<String, Keys>>>),
+}
+
+impl KeyStore {
+    async fn get_async(&self, name: &str) -> std::result::Result<Keys, String> {
+        match self {
+            KeyStore::Os(keyring) => keyring.get_async(name).await.map_err(|e| e.to_string()),
+            #[cfg(test)]
+            KeyStore::Memory(keys) => keys.lock().unwrap().get(name).cloned()
+                .ok_or_else(|| format!("no keyring entry for '{}'", name)),
+        }
+    }
+
+    async fn set_async(&self, name: &str, keys: &Keys) -> std::result::Result<(), String> {
+        match self {
+            KeyStore::Os(keyring) => keyring.set_async(name, keys).await.map_err(|e| e.to_string()),
+            #[cfg(test)]
+            KeyStore::Memory(store) => {
+                store.lock().unwrap().insert(name.to_string(), keys.clone());
+                Ok(())
+            }
+        }
+    }
+
+    async fn delete_async(&self, name: &str) -> std::result::Result<(), String> {
+        match self {
+            KeyStore::Os(keyring) => keyring.delete_async(name).await.map_err(|e| e.to_string()),
+            #[cfg(test)]
+            KeyStore::Memory(store) => {
+                store.lock().unwrap().remove(name);
+                Ok(())
+            }
+        }
     }
 }
 
+/// Derive the keys of NIP-06 account `account` from a BIP-39 mnemonic
+fn keys_from_mnemonic(mnemonic: &str, passphrase: Option<&str>, account: Option<u32>) -> Result<Keys> {
+    Keys::from_mnemonic_with_account(mnemonic, passphrase, account)
+        .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()))
+}
+
 /// Key manager using nostr-keyring for secure storage
 pub struct KeyManager {
-    keyring: NostrKeyring,
+    /// Separate key set (own keyring service and metadata file), None = default
+    namespace: Option<String>,
+    keyring: KeyStore,
     metadata: KeysMetadata,
+    /// Startup handling of keys without an active one
+    missing_active_key: MissingActiveKeyPolicy,
     /// Cached active keys (loaded from keyring when unlocked)
     cached_keys: Option<Keys>,
+    /// Metadata version the cached keys were loaded under
+    cached_version: u64,
+    /// Password-protected keys unlocked with their own password
+    unlocked_keys: HashSet<String>,
+    /// Announces key changes made through this manager
+    key_events: tokio::sync::broadcast::Sender<KeyEvent>,
+    /// Key set as of the last load or announced save
+    announced: KeySnapshot,
+    /// Metadata file in place of the namespace's one in the data dir
+    metadata_file: Option<PathBuf>,
+    /// Metadata as last read from or written to disk, to tell our changes
+    /// from another process's
+    base: KeysMetadata,
 }
 
 impl KeyManager {
     /// Create a new key manager
     pub fn new() -> Self {
         Self {
-            keyring: NostrKeyring::new(KEYRING_SERVICE),
+            namespace: None,
+            keyring: KeyStore::Os(NostrKeyring::new(KEYRING_SERVICE)),
+            metadata: KeysMetadata::default(),
+            missing_active_key: MissingActiveKeyPolicy::default(),
+            cached_keys: None,
+            cached_version: 0,
+            unlocked_keys: HashSet::new(),
+            key_events: tokio::sync::broadcast::channel(KEY_EVENT_CAPACITY).0,
+            announced: KeySnapshot::default(),
+            metadata_file: None,
+            base: KeysMetadata::default(),
+        }
+    }
+
+    /// Create a key manager for a separate, named key set
+    pub fn with_namespace(namespace: &str) -> Self {
+        Self {
+            namespace: Some(namespace.to_string()),
+            keyring: KeyStore::Os(NostrKeyring::new(format!("{}.{}", KEYRING_SERVICE, namespace))),
             metadata: KeysMetadata::default(),
+            missing_active_key: MissingActiveKeyPolicy::default(),
             cached_keys: None,
+            cached_version: 0,
+            unlocked_keys: HashSet::new(),
+            key_events: tokio::sync::broadcast::channel(KEY_EVENT_CAPACITY).0,
+            announced: KeySnapshot::default(),
+            metadata_file: None,
+            base: KeysMetadata::default(),
+        }
+    }
+
+    /// Keep metadata in `path` instead of the data dir
+    pub fn with_metadata_file(mut self, path: PathBuf) -> Self {
+        self.metadata_file = Some(path);
+        self
+    }
+
+    /// The metadata file this manager loads and saves
+    fn metadata_path(&self) -> Result<PathBuf> {
+        match self.metadata_file {
+            Some(ref path) => Ok(path.clone()),
+            None => KeysMetadata::path_for(self.namespace.as_deref()),
         }
     }
 
+    /// Set how `load` handles keys without an active one
+    pub fn with_missing_active_key(mut self, policy: MissingActiveKeyPolicy) -> Self {
+        self.missing_active_key = policy;
+        self
+    }
+
     /// Load metadata from disk
     pub async fn load(&mut self) -> Result<()> {
-        self.metadata = KeysMetadata::load().await?;
+        self.metadata = self.read_metadata().await?;
+        // What was on disk is the starting point, not a change
+        self.announced = KeySnapshot::of(&self.metadata);
+        let reconciled = self.metadata.reconcile_active_key(self.missing_active_key);
+        if reconciled {
+            match self.metadata.active_key {
+                Some(ref name) => tracing::warn!("No active key was set; activated '{}'", name),
+                None => tracing::warn!("No active key is set; waiting for the user to choose one"),
+            }
+        }
+        let normalized = self.metadata.normalize_active_flags();
+        if normalized {
+            tracing::warn!("Key metadata had stale is_active flags; corrected to match the active key");
+        }
+        if reconciled || normalized {
+            self.cached_keys = None;
+            self.save_metadata().await?;
+        }
         Ok(())
     }
 
-    /// Check if any keys exist
-    pub fn has_keys(&self) -> bool {
-        !self.metadata.keys.is_empty()
+    /// Read the metadata file under a shared lock, as the new base
+    async fn read_metadata(&mut self) -> Result<KeysMetadata> {
+        let path = self.metadata_path()?;
+        let _lock = MetadataLock::acquire(&path, LockMode::Read).await?;
+        let metadata = KeysMetadata::load_from(&path).await?;
+        self.base = metadata.clone();
+        Ok(metadata)
+    }
+
+    /// Save metadata; cached keys survive our own saves
+    ///
+    /// Callers that change keys clear the cache themselves. If another
+    /// process saved since we last read the file, our changes are merged
+    /// into its version rather than written over it. Added and removed
+    /// keys and a new active key are announced once saved.
+    pub(crate) async fn save_metadata(&mut self) -> Result<()> {
+        let path = self.metadata_path()?;
+        let _lock = MetadataLock::acquire(&path, LockMode::Write).await?;
+        let on_disk = KeysMetadata::load_from(&path).await?;
+        if on_disk.version != self.base.version {
+            tracing::info!("Key metadata was saved by another process; merging our changes");
+            let active = self.metadata.active_key.clone();
+            let version = on_disk.version;
+            self.metadata = KeysMetadata::merge(&self.base, &self.metadata, on_disk);
+            self.metadata.version = version;
+            self.metadata.reconcile_active_key(self.missing_active_key);
+            if self.metadata.active_key != active {
+                self.cached_keys = None;
+            }
+            let keys = &self.metadata.keys;
+            self.unlocked_keys.retain(|name| keys.contains_key(name));
+        }
+        self.metadata.save_to(&path).await?;
+        self.base = self.metadata.clone();
+        if self.cached_keys.is_some() {
+            self.cached_version = self.metadata.version;
+        }
+        self.announce_changes();
+        Ok(())
+    }
+
+    /// Pick up metadata saved by another process (e.g. the UI subprocess)
+    ///
+    /// Unlike `load`, nothing is written back. Cached keys are dropped if
+    /// the active key changed, per-key unlocks of deleted keys are
+    /// forgotten, and the changes are announced as if made here.
+    pub async fn reload(&mut self) -> Result<()> {
+        let active = self.metadata.active_key.clone();
+        self.metadata = self.read_metadata().await?;
+        self.metadata.normalize_active_flags();
+        if self.metadata.active_key != active {
+            self.cached_keys = None;
+        }
+        let keys = &self.metadata.keys;
+        self.unlocked_keys.retain(|name| keys.contains_key(name));
+        self.announce_changes();
+        Ok(())
+    }
+
+    /// Send an event for each change since the last announcement
+    pub(crate) fn announce_changes(&mut self) {
+        let current = KeySnapshot::of(&self.metadata);
+        for event in self.announced.changes_to(&current) {
+            // No subscribers is fine
+            let _ = self.key_events.send(event);
+        }
+        self.announced = current;
+    }
+
+    /// Receive the key changes saved from now on
+    pub fn subscribe_key_events(&self) -> tokio::sync::broadcast::Receiver<KeyEvent> {
+        self.key_events.subscribe()
     }
 
     /// Get list of all keys (metadata only)
@@ -100,6 +601,11 @@ impl KeyManager {
         self.metadata.keys.values().collect()
     }
 
+    /// Metadata of the key named `name`
+    pub fn key_metadata(&self, name: &str) -> Option<&KeyMetadata> {
+        self.metadata.keys.get(name)
+    }
+
     /// Get the active key's public key
     pub fn get_active_pubkey(&self) -> Option<&str> {
         self.metadata.active_key.as_ref()
@@ -107,6 +613,35 @@ impl KeyManager {
             .map(|m| m.npub.as_str())
     }
 
+    /// The active key's relays (empty if it has none)
+    pub fn active_key_relays(&self) -> Vec<String> {
+        self.metadata.active_key.as_ref()
+            .and_then(|name| self.metadata.keys.get(name))
+            .map(|m| m.relays.clone())
+            .unwrap_or_default()
+    }
+
+    /// Set the relays used with a key, e.g. by bunker mode (empty clears them)
+    pub async fn set_key_relays(&mut self, name: &str, relays: &[String]) -> Result<()> {
+        self.apply_key_relays(name, relays)?;
+        self.save_metadata().await
+    }
+
+    pub(crate) fn apply_key_relays(&mut self, name: &str, relays: &[String]) -> Result<()> {
+        let mut normalized: Vec<String> = Vec::with_capacity(relays.len());
+        for url in relays {
+            let relay = normalize_relay_url(url)
+                .ok_or_else(|| SignerError::InvalidRequest(format!("Invalid relay: {}", url)))?;
+            if !normalized.contains(&relay) {
+                normalized.push(relay);
+            }
+        }
+        let meta = self.metadata.keys.get_mut(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+        meta.relays = normalized;
+        Ok(())
+    }
+
     /// Get the active key name
     pub fn get_active_key_name(&self) -> Option<&str> {
         self.metadata.active_key.as_deref()
@@ -117,6 +652,7 @@ impl KeyManager {
         if !self.metadata.keys.contains_key(name) {
             return Err(SignerError::KeyNotFound(name.to_string()));
         }
+        self.check_can_sign(name)?;
 
         // Update is_active flags
         for (key_name, meta) in &mut self.metadata.keys {
@@ -127,7 +663,7 @@ impl KeyManager {
         // Clear cached keys to force reload
         self.cached_keys = None;
         
-        self.metadata.save().await?;
+        self.save_metadata().await?;
         Ok(())
     }
 
@@ -153,95 +689,370 @@ impl KeyManager {
         self.store_key(name, &keys).await
     }
 
+    /// Add a watch-only key: just the public key (npub or hex), without a
+    /// secret in the keyring. It answers `get_public_key` but can't sign.
+    pub async fn add_watch_key(&mut self, name: &str, npub: &str) -> Result<KeyMetadata> {
+        let metadata = self.add_watch_metadata(name, npub)?;
+        self.save_metadata().await?;
+        Ok(metadata)
+    }
+
+    /// Add a watch-only key's metadata, without saving
+    fn add_watch_metadata(&mut self, name: &str, npub: &str) -> Result<KeyMetadata> {
+        if self.metadata.keys.contains_key(name) {
+            return Err(SignerError::KeyAlreadyExists(name.to_string()));
+        }
+
+        let public_key = PublicKey::parse(npub.trim())
+            .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()))?;
+        let metadata = KeyMetadata {
+            name: name.to_string(),
+            npub: public_key.to_bech32().unwrap_or_default(),
+            pubkey_hex: public_key.to_hex(),
+            created_at: chrono::Utc::now(),
+            is_active: false,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: true,
+        };
+        self.metadata.keys.insert(name.to_string(), metadata.clone());
+        Ok(metadata)
+    }
+
     /// Import a key from mnemonic (NIP-06)
+    ///
+    /// `account` is the NIP-06 account index (`m/44'/1237'/<account>'/0/0`);
+    /// `None` is account 0, as most clients use.
     pub async fn import_from_mnemonic(
         &mut self,
         name: &str,
         mnemonic: &str,
         passphrase: Option<&str>,
+        account: Option<u32>,
     ) -> Result<KeyMetadata> {
         if self.metadata.keys.contains_key(name) {
             return Err(SignerError::KeyAlreadyExists(name.to_string()));
         }
 
-        let keys = Keys::from_mnemonic(mnemonic, passphrase)
-            .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()))?;
+        let keys = keys_from_mnemonic(mnemonic, passphrase, account)?;
         
         self.store_key(name, &keys).await
     }
 
+    /// Import a key bundled with its relays and profile (see [`CombinedImport`])
+    pub async fn import_combined(&mut self, name: &str, json: &str) -> Result<KeyMetadata> {
+        let import = CombinedImport::parse(json)?;
+        let metadata = self.add_combined(name, import).await?;
+        self.save_metadata().await?;
+        Ok(metadata)
+    }
+
+    /// Add a parsed combined import without saving metadata
+    async fn add_combined(&mut self, name: &str, import: CombinedImport) -> Result<KeyMetadata> {
+        if self.metadata.keys.contains_key(name) {
+            return Err(SignerError::KeyAlreadyExists(name.to_string()));
+        }
+        self.add_key(name, &import.keys).await?;
+
+        let meta = self.metadata.keys.get_mut(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+        meta.relays = import.relays;
+        meta.nip05 = import.nip05;
+        meta.lud16 = import.lud16;
+        Ok(meta.clone())
+    }
+
     /// Store a key in the keyring
     async fn store_key(&mut self, name: &str, keys: &Keys) -> Result<KeyMetadata> {
+        let metadata = self.add_key(name, keys).await?;
+        self.save_metadata().await?;
+        Ok(metadata)
+    }
+
+    /// Store a key in the keyring and add its metadata, without saving
+    async fn add_key(&mut self, name: &str, keys: &Keys) -> Result<KeyMetadata> {
         // Store in OS keyring
         self.keyring.set_async(name, keys).await
             .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
 
+        // Watch-only keys don't count: they can't be active
+        let first = self.metadata.keys.values().all(|m| m.watch_only);
         let public_key = keys.public_key();
         let metadata = KeyMetadata {
             name: name.to_string(),
             npub: public_key.to_bech32().unwrap_or_default(),
             pubkey_hex: public_key.to_hex(),
             created_at: chrono::Utc::now(),
-            is_active: self.metadata.keys.is_empty(),
+            is_active: first,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: false,
         };
 
         // Set as active if first key
-        if self.metadata.keys.is_empty() {
+        if first {
             self.metadata.active_key = Some(name.to_string());
         }
 
         self.metadata.keys.insert(name.to_string(), metadata.clone());
-        self.metadata.save().await?;
-
         Ok(metadata)
     }
 
+    /// Add a label to a key (no-op if already present)
+    pub async fn add_label(&mut self, name: &str, label: &str) -> Result<()> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(SignerError::InvalidRequest("Label cannot be empty".into()));
+        }
+
+        let meta = self.metadata.keys.get_mut(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+        if !meta.labels.iter().any(|l| l == label) {
+            meta.labels.push(label.to_string());
+            self.save_metadata().await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a label from a key
+    pub async fn remove_label(&mut self, name: &str, label: &str) -> Result<()> {
+        let meta = self.metadata.keys.get_mut(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+        meta.labels.retain(|l| l != label);
+        self.save_metadata().await?;
+        Ok(())
+    }
+
     /// Delete a key
     pub async fn delete_key(&mut self, name: &str) -> Result<()> {
         if !self.metadata.keys.contains_key(name) {
             return Err(SignerError::KeyNotFound(name.to_string()));
         }
 
-        // Remove from keyring
-        self.keyring.delete_async(name).await
-            .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+        // Remove from keyring (watch-only keys have no entry)
+        if !self.metadata.keys[name].watch_only {
+            self.keyring.delete_async(name).await
+                .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+        }
 
         self.metadata.keys.remove(name);
         
         // Update active key if needed
         if self.metadata.active_key.as_deref() == Some(name) {
-            self.metadata.active_key = self.metadata.keys.keys().next().cloned();
+            self.metadata.active_key = self.metadata.keys.values()
+                .find(|m| !m.watch_only)
+                .map(|m| m.name.clone());
             self.cached_keys = None;
         }
 
-        self.metadata.save().await?;
+        self.save_metadata().await?;
         Ok(())
     }
 
     /// Get the active signing keys
+    ///
+    /// Cached keys are dropped when the metadata has moved on to a newer
+    /// version, or when they no longer match the stored public key (e.g. the
+    /// keyring entry was replaced externally); the key is then reloaded from
+    /// the keyring.
     pub async fn get_signing_keys(&mut self) -> Result<&Keys> {
-        if self.cached_keys.is_some() {
-            return Ok(self.cached_keys.as_ref().unwrap());
-        }
-
-        let name = self.metadata.active_key.as_ref()
+        let name = self.metadata.active_key.clone()
             .ok_or(SignerError::NoKeysConfigured)?;
+        self.check_can_sign(&name)?;
+        self.check_key_unlocked(&name)?;
 
-        let keys = self.keyring.get_async(name).await
-            .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+        let mut mismatch = None;
+        if let Some(cached) = self.cached_keys.take() {
+            if self.cached_version != self.metadata.version {
+                tracing::info!("Key metadata changed; reloading '{}' from the keyring", name);
+            } else if let Err(e) = self.verify_pubkey(&name, &cached) {
+                tracing::warn!("Cached key for '{}' is stale; reloading from the keyring", name);
+                mismatch = Some(e);
+            } else {
+                self.cached_keys = Some(cached);
+                return Ok(self.cached_keys.as_ref().unwrap());
+            }
+        }
+
+        let keys = match self.keyring.get_async(&name).await {
+            Ok(keys) => keys,
+            // Report the mismatch rather than the failed reload
+            Err(e) => return Err(mismatch.unwrap_or(SignerError::DecryptionError(e))),
+        };
+        self.verify_pubkey(&name, &keys)?;
         
         self.cached_keys = Some(keys);
+        self.cached_version = self.metadata.version;
         Ok(self.cached_keys.as_ref().unwrap())
     }
 
+    /// Resolve a key name, npub or hex public key to the key's name
+    pub fn resolve_key_name(&self, key_id: &str) -> Result<String> {
+        if self.metadata.keys.contains_key(key_id) {
+            return Ok(key_id.to_string());
+        }
+        self.metadata.keys.values()
+            .find(|m| m.npub == key_id || m.pubkey_hex.eq_ignore_ascii_case(key_id))
+            .map(|m| m.name.clone())
+            .ok_or_else(|| SignerError::KeyNotFound(key_id.to_string()))
+    }
+
+    /// Public key selected by name, npub or hex public key (None = the
+    /// active key). Watch-only keys answer from metadata; others are
+    /// loaded, so a missing or locked secret is reported as before.
+    pub async fn get_public_key_for(&mut self, key_id: Option<&str>) -> Result<PublicKey> {
+        if let Some(id) = key_id {
+            let meta = &self.metadata.keys[&self.resolve_key_name(id)?];
+            if meta.watch_only {
+                return PublicKey::from_hex(&meta.pubkey_hex)
+                    .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()));
+            }
+        }
+        Ok(self.get_keys_for(key_id).await?.public_key())
+    }
+
+    /// Keys selected by name, npub or hex public key (None = the active key)
+    pub async fn get_keys_for(&mut self, key_id: Option<&str>) -> Result<Keys> {
+        let name = match key_id {
+            Some(id) => self.resolve_key_name(id)?,
+            None => return self.get_signing_keys().await.cloned(),
+        };
+        if self.metadata.active_key.as_deref() == Some(name.as_str()) {
+            // Reuse the cached active key
+            return self.get_signing_keys().await.cloned();
+        }
+        self.get_keys_by_name(&name).await
+    }
+
     /// Get keys by name
     pub async fn get_keys_by_name(&self, name: &str) -> Result<Keys> {
         if !self.metadata.keys.contains_key(name) {
             return Err(SignerError::KeyNotFound(name.to_string()));
         }
+        self.check_can_sign(name)?;
+        self.check_key_unlocked(name)?;
 
-        self.keyring.get_async(name).await
-            .map_err(|e| SignerError::DecryptionError(e.to_string()))
+        let keys = self.keyring.get_async(name).await
+            .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
+        self.verify_pubkey(name, &keys)?;
+        Ok(keys)
+    }
+
+    /// Every key that can sign right now, by name. Keys still locked with
+    /// their own password or missing from the keyring are skipped.
+    pub async fn get_all_signing_keys(&mut self) -> Result<Vec<(String, Keys)>> {
+        if self.metadata.keys.is_empty() {
+            return Err(SignerError::NoKeysConfigured);
+        }
+        let mut names: Vec<String> = self.metadata.keys.values()
+            .filter(|m| !m.watch_only)
+            .map(|m| m.name.clone())
+            .collect();
+        names.sort();
+
+        let mut all = Vec::with_capacity(names.len());
+        for name in names {
+            match self.get_keys_for(Some(&name)).await {
+                Ok(keys) => all.push((name, keys)),
+                Err(e) => tracing::warn!("Skipping key '{}': {}", name, e),
+            }
+        }
+        Ok(all)
+    }
+
+    /// Get keys by npub
+    pub async fn get_keys_by_npub(&self, npub: &str) -> Result<Keys> {
+        let name = self.metadata.keys.values()
+            .find(|m| m.npub == npub)
+            .map(|m| m.name.clone())
+            .ok_or_else(|| SignerError::KeyNotFound(npub.to_string()))?;
+        self.get_keys_by_name(&name).await
+    }
+
+    /// Protect a key with its own password (None removes the protection).
+    /// A key that already has a password must be unlocked first; setting a
+    /// password leaves the key locked until it is entered.
+    pub async fn set_key_password(&mut self, name: &str, password: Option<&str>) -> Result<()> {
+        self.apply_key_password(name, password)?;
+        self.save_metadata().await
+    }
+
+    fn apply_key_password(&mut self, name: &str, password: Option<&str>) -> Result<()> {
+        self.check_key_unlocked(name)?;
+        let verifier = match password {
+            Some("") => {
+                return Err(SignerError::InvalidRequest("Key password can't be empty".into()));
+            }
+            Some(password) => {
+                let encrypted = EncryptedSecretKey::new(&SecretKey::generate(), password, VERIFIER_LOG_N, KeySecurity::Unknown)
+                    .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+                Some(encrypted.to_bech32().map_err(|e| SignerError::NostrError(e.to_string()))?)
+            }
+            None => None,
+        };
+        let meta = self.metadata.keys.get_mut(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+        meta.password_verifier = verifier;
+        self.unlocked_keys.remove(name);
+        Ok(())
+    }
+
+    /// Unlock a password-protected key (name, npub or hex) until the signer
+    /// is locked. Keys without their own password need nothing.
+    pub fn unlock_key(&mut self, key_id: &str, password: &str) -> Result<()> {
+        let name = self.resolve_key_name(key_id)?;
+        let meta = &self.metadata.keys[&name];
+        if !meta.is_password_protected() {
+            return Ok(());
+        }
+        if !meta.password_matches(password) {
+            tracing::warn!("Wrong password for key '{}'", name);
+            return Err(SignerError::InvalidPassword);
+        }
+        self.unlocked_keys.insert(name);
+        Ok(())
+    }
+
+    /// Refuse a watch-only key for anything that needs the secret
+    fn check_can_sign(&self, name: &str) -> Result<()> {
+        match self.metadata.keys.get(name) {
+            Some(meta) if meta.watch_only => Err(SignerError::PermissionDenied(format!(
+                "'{}' is a watch-only key and can't sign, encrypt or decrypt",
+                name
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse a password-protected key that hasn't been unlocked with its password
+    fn check_key_unlocked(&self, name: &str) -> Result<()> {
+        match self.metadata.keys.get(name) {
+            Some(meta) if meta.is_password_protected() && !self.unlocked_keys.contains(name) => {
+                Err(SignerError::KeyPasswordRequired(name.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Ensure keys loaded from the keyring match the public key in metadata
+    fn verify_pubkey(&self, name: &str, keys: &Keys) -> Result<()> {
+        let meta = self.metadata.keys.get(name)
+            .ok_or_else(|| SignerError::KeyNotFound(name.to_string()))?;
+
+        if keys.public_key().to_hex() != meta.pubkey_hex {
+            tracing::error!("Keyring entry for '{}' does not match its stored public key", name);
+            return Err(SignerError::KeyMismatch(format!(
+                "keyring entry for '{}' does not match stored public key {}",
+                name, meta.npub
+            )));
+        }
+        Ok(())
     }
 
     /// Export key as nsec (bech32)
@@ -252,19 +1063,41 @@ impl KeyManager {
     }
 
     /// Export key as NIP-49 encrypted format (ncryptsec)
-    pub async fn export_encrypted(&self, name: &str, password: &str) -> Result<String> {
+    ///
+    /// `log_n` is the scrypt cost and must be within `NCRYPTSEC_LOG_N`.
+    pub async fn export_encrypted(&self, name: &str, password: &str, log_n: u8, security: KeySecurity) -> Result<String> {
+        if !NCRYPTSEC_LOG_N.contains(&log_n) {
+            return Err(SignerError::ConfigError(format!(
+                "ncryptsec_log_n must be between {} and {}, got {}",
+                NCRYPTSEC_LOG_N.start(), NCRYPTSEC_LOG_N.end(), log_n
+            )));
+        }
         let keys = self.get_keys_by_name(name).await?;
-        let encrypted = EncryptedSecretKey::new(
-            keys.secret_key(),
-            password,
-            16, // log_n for scrypt
-            KeySecurity::Medium,
-        ).map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+        let encrypted = EncryptedSecretKey::new(keys.secret_key(), password, log_n, security)
+            .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
         
         encrypted.to_bech32()
             .map_err(|e| SignerError::NostrError(e.to_string()))
     }
 
+    /// Write a key's ncryptsec (NIP-49) to `path`, readable only by the user
+    pub async fn export_encrypted_to_file(&self, name: &str, password: &str, log_n: u8, path: &Path) -> Result<()> {
+        if password.is_empty() {
+            return Err(SignerError::InvalidRequest("A password is required to export a key".into()));
+        }
+        let ncryptsec = self.export_encrypted(name, password, log_n, KeySecurity::Medium).await?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path).await?;
+        file.write_all(format!("{}\n", ncryptsec).as_bytes()).await?;
+        file.flush().await?;
+        // An existing file keeps its mode when overwritten
+        crate::backup::restrict_permissions(path)
+    }
+
     /// Import from NIP-49 encrypted format
     pub async fn import_encrypted(&mut self, name: &str, ncryptsec: &str, password: &str) -> Result<KeyMetadata> {
         if self.metadata.keys.contains_key(name) {
@@ -281,9 +1114,75 @@ impl KeyManager {
         self.store_key(name, &keys).await
     }
 
-    /// Clear cached keys (for locking)
+    /// Back up config, key metadata and, if a password is given, all secret
+    /// keys (NIP-49 encrypted) into a single archive
+    pub async fn export_state(&self, config: &Config, path: &Path, keys_password: Option<&str>) -> Result<()> {
+        let mut backup = StateBackup::collect(config.config_path(), &self.metadata_path()?).await?;
+
+        if let Some(password) = keys_password {
+            let mut encrypted = HashMap::new();
+            for name in self.metadata.keys.values().filter(|m| !m.watch_only).map(|m| &m.name) {
+                encrypted.insert(name.clone(), self.export_encrypted(name, password, config.security.ncryptsec_log_n, KeySecurity::Medium).await?);
+            }
+            backup.encrypted_keys = Some(encrypted);
+        }
+
+        backup.write_archive(path)
+    }
+
+    /// Restore an archive created by `export_state`
+    ///
+    /// Secret keys in the archive are only imported into the keyring when the
+    /// password is given.
+    pub async fn import_state(&mut self, config_path: &Path, path: &Path, keys_password: Option<&str>) -> Result<()> {
+        let backup = StateBackup::read_archive(path)?;
+
+        // Decrypt everything up front so a wrong password changes nothing
+        let mut restored_keys = Vec::new();
+        if let (Some(keys), Some(password)) = (&backup.encrypted_keys, keys_password) {
+            for (name, ncryptsec) in keys {
+                let encrypted = EncryptedSecretKey::from_bech32(ncryptsec)
+                    .map_err(|e| SignerError::InvalidKeyFormat(e.to_string()))?;
+                let secret_key = encrypted.decrypt(password)
+                    .map_err(|_| SignerError::InvalidPassword)?;
+                restored_keys.push((name.clone(), Keys::new(secret_key)));
+            }
+        }
+
+        backup.restore(config_path, &self.metadata_path()?).await?;
+
+        for (name, keys) in &restored_keys {
+            self.keyring.set_async(name, keys).await
+                .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
+        }
+
+        self.cached_keys = None;
+        self.load().await
+    }
+
+    /// Delete the secret of every key in this set from the keyring (for
+    /// `--wipe`), returning each key's name and outcome. Metadata on disk is
+    /// left to the caller.
+    pub async fn wipe_keyring(&mut self) -> Vec<(String, std::result::Result<(), String>)> {
+        self.lock();
+        let mut names: Vec<String> = self.metadata.keys.values()
+            .filter(|m| !m.watch_only)
+            .map(|m| m.name.clone())
+            .collect();
+        names.sort();
+
+        let mut outcomes = Vec::with_capacity(names.len());
+        for name in names {
+            let outcome = self.keyring.delete_async(&name).await;
+            outcomes.push((name, outcome));
+        }
+        outcomes
+    }
+
+    /// Clear cached keys and per-key unlocks (for locking)
     pub fn lock(&mut self) {
         self.cached_keys = None;
+        self.unlocked_keys.clear();
     }
 
     /// Check if keys are cached (unlocked)
@@ -297,3 +1196,602 @@ impl Default for KeyManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+impl KeyManager {
+    /// Key manager holding a single unlocked key, without touching the keyring
+    pub(crate) fn with_test_key(name: &str, keys: Keys) -> Self {
+        let public_key = keys.public_key();
+        let mut km = Self::with_namespace(name);
+        km.metadata.keys.insert(name.to_string(), KeyMetadata {
+            name: name.to_string(),
+            npub: public_key.to_bech32().unwrap_or_default(),
+            pubkey_hex: public_key.to_hex(),
+            created_at: chrono::Utc::now(),
+            is_active: true,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: false,
+        });
+        km.metadata.active_key = Some(name.to_string());
+        km.cached_keys = Some(keys);
+        km.announced = KeySnapshot::of(&km.metadata);
+        km
+    }
+
+    /// Use the same in-memory keyring as `other`, the way the UI and the
+    /// signer process share the OS keyring
+    pub(crate) fn share_test_keyring(&mut self, other: &mut KeyManager) {
+        if matches!(other.keyring, KeyStore::Os(_)) {
+            other.keyring = KeyStore::Memory(Default::default());
+        }
+        if let KeyStore::Memory(ref store) = other.keyring {
+            self.keyring = KeyStore::Memory(std::sync::Arc::clone(store));
+        }
+    }
+
+    /// Add an inactive key, held in an in-memory keyring
+    pub(crate) fn add_test_key(&mut self, name: &str, keys: Keys) {
+        if matches!(self.keyring, KeyStore::Os(_)) {
+            self.keyring = KeyStore::Memory(Default::default());
+        }
+        if let KeyStore::Memory(ref store) = self.keyring {
+            store.lock().unwrap().insert(name.to_string(), keys.clone());
+        }
+        let public_key = keys.public_key();
+        self.metadata.keys.insert(name.to_string(), KeyMetadata {
+            name: name.to_string(),
+            npub: public_key.to_bech32().unwrap_or_default(),
+            pubkey_hex: public_key.to_hex(),
+            created_at: chrono::Utc::now(),
+            is_active: false,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_for(name: &str, keys: &Keys, is_active: bool) -> KeyMetadata {
+        KeyMetadata {
+            name: name.to_string(),
+            npub: keys.public_key().to_bech32().unwrap(),
+            pubkey_hex: keys.public_key().to_hex(),
+            created_at: chrono::Utc::now(),
+            is_active,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_both_sides_changes() {
+        let main = Keys::generate();
+        let base = KeysMetadata {
+            keys: HashMap::from([("main".to_string(), metadata_for("main", &main, true))]),
+            active_key: Some("main".into()),
+            version: 3,
+        };
+
+        // We labelled main and switched to a new key...
+        let ours_key = Keys::generate();
+        let mut ours = base.clone();
+        ours.keys.get_mut("main").unwrap().labels.push("work".into());
+        ours.keys.insert("ours".into(), metadata_for("ours", &ours_key, false));
+        ours.active_key = Some("ours".into());
+        ours.normalize_active_flags();
+
+        // ...while the UI added another key
+        let theirs_key = Keys::generate();
+        let mut theirs = base.clone();
+        theirs.keys.insert("theirs".into(), metadata_for("theirs", &theirs_key, false));
+        theirs.version = 4;
+
+        let merged = KeysMetadata::merge(&base, &ours, theirs.clone());
+        assert_eq!(merged.keys.len(), 3);
+        assert_eq!(merged.keys["main"].labels, ["work"]);
+        assert_eq!(merged.active_key.as_deref(), Some("ours"));
+
+        // A key deleted elsewhere stays deleted unless we edited it
+        let mut theirs = base.clone();
+        theirs.keys.clear();
+        theirs.active_key = None;
+        let merged = KeysMetadata::merge(&base, &base, theirs);
+        assert!(merged.keys.is_empty());
+        assert!(merged.active_key.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_managers_keep_each_others_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(METADATA_FILE);
+        let npub = || Keys::generate().public_key().to_bech32().unwrap();
+
+        // The signer process and the UI subprocess, both loaded before either saves
+        let mut service = KeyManager::new().with_metadata_file(path.clone());
+        let mut ui = KeyManager::new().with_metadata_file(path.clone());
+        service.load().await.unwrap();
+        ui.load().await.unwrap();
+
+        ui.add_watch_key("phone", &npub()).await.unwrap();
+        service.add_watch_key("laptop", &npub()).await.unwrap();
+        ui.add_label("phone", "mobile").await.unwrap();
+
+        let on_disk = KeysMetadata::load_from(&path).await.unwrap();
+        let mut names: Vec<_> = on_disk.keys.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, ["laptop", "phone"]);
+        assert_eq!(on_disk.keys["phone"].labels, ["mobile"]);
+        // Each manager also sees the other's key now
+        assert!(ui.key_metadata("laptop").is_some());
+        assert!(service.key_metadata("phone").is_some());
+        assert!(dir.path().join(format!("{}{}", METADATA_FILE, LOCK_SUFFIX)).exists());
+    }
+
+    #[test]
+    fn test_key_changes_announced() {
+        let main = Keys::generate();
+        let alt = Keys::generate();
+        let npub = |keys: &Keys| keys.public_key().to_bech32().unwrap();
+        let mut km = KeyManager::with_test_key("main", main.clone());
+        let mut events = km.subscribe_key_events();
+
+        // Nothing changed, nothing to announce
+        km.announce_changes();
+        assert!(events.try_recv().is_err());
+
+        km.metadata.keys.insert("alt".into(), metadata_for("alt", &alt, false));
+        km.metadata.active_key = Some("alt".into());
+        km.announce_changes();
+        assert_eq!(events.try_recv().unwrap(), KeyEvent::Added(npub(&alt)));
+        assert_eq!(events.try_recv().unwrap(), KeyEvent::ActiveChanged(npub(&alt)));
+        assert!(events.try_recv().is_err());
+
+        km.metadata.keys.remove("alt");
+        km.metadata.active_key = None;
+        km.announce_changes();
+        assert_eq!(events.try_recv().unwrap(), KeyEvent::Removed(npub(&alt)));
+        assert_eq!(events.try_recv().unwrap(), KeyEvent::ActiveChanged(String::new()));
+
+        // Switching back and forth is announced each time
+        km.metadata.active_key = Some("main".into());
+        km.announce_changes();
+        assert_eq!(events.try_recv().unwrap(), KeyEvent::ActiveChanged(npub(&main)));
+    }
+
+    #[tokio::test]
+    async fn test_select_key_by_npub_or_name() {
+        let keys = Keys::generate();
+        let npub = keys.public_key().to_bech32().unwrap();
+        let mut km = KeyManager::with_test_key("main", keys.clone());
+
+        assert_eq!(km.resolve_key_name("main").unwrap(), "main");
+        assert_eq!(km.resolve_key_name(&npub).unwrap(), "main");
+        assert_eq!(km.resolve_key_name(&keys.public_key().to_hex()).unwrap(), "main");
+
+        let by_name = km.get_keys_for(Some("main")).await.unwrap();
+        let by_npub = km.get_keys_for(Some(&npub)).await.unwrap();
+        let active = km.get_keys_for(None).await.unwrap();
+        assert_eq!(by_name.public_key(), keys.public_key());
+        assert_eq!(by_npub.public_key(), by_name.public_key());
+        assert_eq!(active.public_key(), by_name.public_key());
+
+        // Unknown keys never fall back to the active key
+        let stranger = Keys::generate().public_key().to_bech32().unwrap();
+        assert!(matches!(km.get_keys_for(Some(&stranger)).await, Err(SignerError::KeyNotFound(_))));
+
+        // Direct npub lookup of an inactive key
+        let alt = Keys::generate();
+        km.add_test_key("alt", alt.clone());
+        let alt_npub = alt.public_key().to_bech32().unwrap();
+        assert_eq!(km.get_keys_by_npub(&alt_npub).await.unwrap().public_key(), alt.public_key());
+        assert!(matches!(km.get_keys_by_npub(&stranger).await, Err(SignerError::KeyNotFound(_))));
+    }
+
+    #[test]
+    fn test_missing_active_key_selects_newest() {
+        let now = chrono::Utc::now();
+        let created = |name: &str, age_mins: i64| KeyMetadata {
+            created_at: now - chrono::Duration::minutes(age_mins),
+            ..metadata_for(name, &Keys::generate(), false)
+        };
+        let mut metadata = KeysMetadata::default();
+        for key in [created("old", 60), created("newest-b", 1), created("newest-a", 1)] {
+            metadata.keys.insert(key.name.clone(), key);
+        }
+        // Left behind by a failed delete
+        metadata.active_key = Some("deleted".into());
+
+        // Round-trip through JSON as `load` would see it
+        let json = serde_json::to_string(&metadata).unwrap();
+        let mut loaded: KeysMetadata = serde_json::from_str(&json).unwrap();
+        assert!(loaded.reconcile_active_key(MissingActiveKeyPolicy::SelectNewest));
+        assert_eq!(loaded.active_key.as_deref(), Some("newest-a"));
+        let active: Vec<_> = loaded.keys.values().filter(|k| k.is_active).map(|k| k.name.as_str()).collect();
+        assert_eq!(active, ["newest-a"]);
+
+        // Already consistent: nothing to do
+        assert!(!loaded.reconcile_active_key(MissingActiveKeyPolicy::SelectNewest));
+
+        // Prompt leaves the choice to the user
+        let mut prompted: KeysMetadata = serde_json::from_str(&json).unwrap();
+        assert!(prompted.reconcile_active_key(MissingActiveKeyPolicy::Prompt));
+        assert!(prompted.active_key.is_none());
+        assert!(prompted.keys.values().all(|k| !k.is_active));
+
+        // No keys, nothing to select
+        let mut empty = KeysMetadata::default();
+        assert!(!empty.reconcile_active_key(MissingActiveKeyPolicy::SelectNewest));
+    }
+
+    #[test]
+    fn test_active_flags_follow_active_key() {
+        let mut metadata = KeysMetadata::default();
+        for name in ["alice", "bob", "carol"] {
+            let key = metadata_for(name, &Keys::generate(), true);
+            metadata.keys.insert(key.name.clone(), key);
+        }
+        metadata.active_key = Some("bob".into());
+
+        // Round-trip through JSON as `load` would see it
+        let json = serde_json::to_string(&metadata).unwrap();
+        let mut loaded: KeysMetadata = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.reconcile_active_key(MissingActiveKeyPolicy::SelectNewest));
+        assert!(loaded.normalize_active_flags());
+        let active: Vec<_> = loaded.keys.values().filter(|k| k.is_active).map(|k| k.name.as_str()).collect();
+        assert_eq!(active, ["bob"]);
+        assert_eq!(loaded.active_key.as_deref(), Some("bob"));
+
+        // Already consistent: nothing to do
+        assert!(!loaded.normalize_active_flags());
+
+        // No active key: no key is flagged
+        loaded.active_key = None;
+        assert!(loaded.normalize_active_flags());
+        assert!(loaded.keys.values().all(|k| !k.is_active));
+    }
+
+    #[tokio::test]
+    async fn test_import_combined_blob() {
+        let keys = Keys::generate();
+        let blob = serde_json::json!({
+            "nsec": keys.secret_key().to_bech32().unwrap(),
+            "relays": {"wss://relay.example.com/": {"read": true, "write": true}, "wss://nos.lol": {"read": true}},
+            "profile": {"name": "alice", "nip05": "Alice@Example.com", "lud16": "alice@getalby.com"},
+        })
+        .to_string();
+
+        let mut km = KeyManager::new();
+        km.keyring = KeyStore::Memory(Default::default());
+        let meta = km.add_combined("alice", CombinedImport::parse(&blob).unwrap()).await.unwrap();
+
+        assert_eq!(meta.pubkey_hex, keys.public_key().to_hex());
+        let mut relays = meta.relays.clone();
+        relays.sort();
+        assert_eq!(relays, ["wss://nos.lol", "wss://relay.example.com"]);
+        assert_eq!(meta.nip05.as_deref(), Some("alice@example.com"));
+        assert_eq!(meta.lud16.as_deref(), Some("alice@getalby.com"));
+        assert_eq!(km.metadata.active_key.as_deref(), Some("alice"));
+        assert_eq!(km.get_keys_for(Some("alice")).await.unwrap().public_key(), keys.public_key());
+    }
+
+    #[tokio::test]
+    async fn test_watch_key_cannot_sign() {
+        let watched = Keys::generate();
+        let npub = watched.public_key().to_bech32().unwrap();
+        let mut km = KeyManager::new();
+        km.keyring = KeyStore::Memory(Default::default());
+        let meta = km.add_watch_metadata("watched", &npub).unwrap();
+        assert!(meta.watch_only);
+        assert!(!meta.is_active);
+        assert!(km.add_watch_metadata("bad", "npub1nope").is_err());
+
+        // The public key is there, the secret isn't
+        assert_eq!(km.get_public_key_for(Some(&npub)).await.unwrap(), watched.public_key());
+        assert!(matches!(km.get_keys_for(Some("watched")).await, Err(SignerError::PermissionDenied(_))));
+        assert!(matches!(km.set_active_key("watched").await, Err(SignerError::PermissionDenied(_))));
+        assert!(km.metadata.active_key.is_none());
+
+        // A real key added later still becomes the active one
+        let keys = Keys::generate();
+        km.add_key("main", &keys).await.unwrap();
+        assert_eq!(km.metadata.active_key.as_deref(), Some("main"));
+        let signing: Vec<String> = km.get_all_signing_keys().await.unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(signing, ["main"]);
+
+        // Never picked as the active key on load either
+        km.metadata.active_key = None;
+        km.metadata.keys.remove("main");
+        assert!(!km.metadata.reconcile_active_key(MissingActiveKeyPolicy::SelectNewest));
+        assert!(km.metadata.active_key.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wipe_keyring_deletes_every_secret() {
+        let mut km = KeyManager::new();
+        km.add_test_key("work", Keys::generate());
+        km.add_test_key("personal", Keys::generate());
+        km.add_watch_metadata("watched", &Keys::generate().public_key().to_hex()).unwrap();
+
+        let outcomes = km.wipe_keyring().await;
+        let names: Vec<&str> = outcomes.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["personal", "work"]);
+        assert!(outcomes.iter().all(|(_, outcome)| outcome.is_ok()));
+        let KeyStore::Memory(ref store) = km.keyring else { unreachable!() };
+        assert!(store.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_combined_import_names_bad_part() {
+        let nsec = Keys::generate().secret_key().to_bech32().unwrap();
+        let parse_err = |blob: serde_json::Value| CombinedImport::parse(&blob.to_string()).err().unwrap().to_string();
+
+        assert!(parse_err(serde_json::json!({"nsec": "nsec1nope"})).contains("nsec"));
+        assert!(parse_err(serde_json::json!({"nsec": nsec, "relays": ["https://relay.example.com"]})).contains("Invalid relay"));
+        assert!(parse_err(serde_json::json!({"nsec": nsec, "metadata": {"nip05": "not an id"}})).contains("NIP-05"));
+        assert!(parse_err(serde_json::json!({"nsec": nsec, "lud16": "alice"})).contains("lud16"));
+
+        // Only the key is required
+        let bare = CombinedImport::parse(&serde_json::json!({"nsec": nsec}).to_string()).unwrap();
+        assert!(bare.relays.is_empty() && bare.nip05.is_none() && bare.lud16.is_none());
+    }
+
+    #[test]
+    fn test_key_relays() {
+        let mut km = KeyManager::with_test_key("main", Keys::generate());
+        assert!(km.active_key_relays().is_empty());
+
+        let relays = ["wss://relay.example.com/".to_string(), "wss://relay.example.com".to_string(), "wss://nos.lol".to_string()];
+        km.apply_key_relays("main", &relays).unwrap();
+        assert_eq!(km.active_key_relays(), ["wss://relay.example.com", "wss://nos.lol"]);
+
+        // Nothing changes if any relay is invalid
+        assert!(km.apply_key_relays("main", &["https://relay.example.com".to_string()]).is_err());
+        assert_eq!(km.active_key_relays().len(), 2);
+        assert!(matches!(km.apply_key_relays("other", &[]), Err(SignerError::KeyNotFound(_))));
+
+        km.apply_key_relays("main", &[]).unwrap();
+        assert!(km.active_key_relays().is_empty());
+
+        // Metadata written before relays existed still loads
+        let old = r#"{"name":"main","npub":"npub1x","pubkey_hex":"00","created_at":"2024-01-01T00:00:00Z","is_active":true}"#;
+        let meta: KeyMetadata = serde_json::from_str(old).unwrap();
+        assert!(meta.relays.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_all_signing_keys_skips_locked() {
+        let main = Keys::generate();
+        let alt = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", main.clone());
+        km.add_test_key("alt", alt.clone());
+        km.add_test_key("vault", Keys::generate());
+        km.apply_key_password("vault", Some("vault password")).unwrap();
+
+        let all = km.get_all_signing_keys().await.unwrap();
+        let names: Vec<&str> = all.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["alt", "main"]);
+        assert_eq!(all[0].1.public_key(), alt.public_key());
+        assert_eq!(all[1].1.public_key(), main.public_key());
+
+        km.unlock_key("vault", "vault password").unwrap();
+        assert_eq!(km.get_all_signing_keys().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_password_protected_key_needs_its_password() {
+        let main = Keys::generate();
+        let vault = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", main.clone());
+        km.add_test_key("vault", vault.clone());
+        km.apply_key_password("vault", Some("vault password")).unwrap();
+        assert!(km.metadata.keys["vault"].is_password_protected());
+
+        // The app is unlocked, but the vault key still needs its own password
+        assert!(km.get_keys_for(Some("main")).await.is_ok());
+        assert!(matches!(km.get_keys_for(Some("vault")).await, Err(SignerError::KeyPasswordRequired(_))));
+        assert!(matches!(km.export_nsec("vault").await, Err(SignerError::KeyPasswordRequired(_))));
+
+        assert!(matches!(km.unlock_key("vault", "guess"), Err(SignerError::InvalidPassword)));
+        assert!(km.get_keys_for(Some("vault")).await.is_err());
+
+        // Selecting it by npub works too
+        let npub = vault.public_key().to_bech32().unwrap();
+        km.unlock_key(&npub, "vault password").unwrap();
+        assert_eq!(km.get_keys_for(Some("vault")).await.unwrap().public_key(), vault.public_key());
+
+        // Locking the signer locks the key again
+        km.lock();
+        assert!(matches!(km.get_keys_for(Some("vault")).await, Err(SignerError::KeyPasswordRequired(_))));
+
+        // Removing the password needs it unlocked first
+        assert!(km.apply_key_password("vault", None).is_err());
+        km.unlock_key("vault", "vault password").unwrap();
+        km.apply_key_password("vault", None).unwrap();
+        km.lock();
+        assert!(km.get_keys_for(Some("vault")).await.is_ok());
+    }
+
+    #[test]
+    fn test_label_filter() {
+        let labelled = |name: &str, labels: &[&str]| KeyMetadata {
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            ..metadata_for(name, &Keys::generate(), false)
+        };
+        let keys = [
+            labelled("alice", &["personal"]),
+            labelled("work", &["work", "bots"]),
+            labelled("relaybot", &["bots"]),
+            labelled("spare", &[]),
+        ];
+
+        let names = |filter: Option<&str>| -> Vec<&str> {
+            keys.iter().filter(|k| k.matches_label(filter)).map(|k| k.name.as_str()).collect()
+        };
+        assert_eq!(names(Some("bots")), ["work", "relaybot"]);
+        assert_eq!(names(Some("personal")), ["alice"]);
+        assert!(names(Some("Bots")).is_empty());
+        assert_eq!(names(None).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_cached_key_rejected() {
+        let stored = Keys::generate();
+        let tampered = Keys::generate();
+
+        let mut km = KeyManager::new();
+        km.metadata.keys.insert("main".into(), metadata_for("main", &stored, true));
+        km.metadata.active_key = Some("main".into());
+        km.cached_keys = Some(tampered);
+
+        assert!(matches!(km.get_signing_keys().await, Err(SignerError::KeyMismatch(_))));
+        assert!(!km.is_unlocked());
+    }
+
+    #[tokio::test]
+    async fn test_external_key_change_reloads() {
+        let old = Keys::generate();
+        let new = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", old.clone());
+        km.keyring = KeyStore::Memory(Default::default());
+        km.keyring.set_async("main", &old).await.unwrap();
+        assert_eq!(km.get_signing_keys().await.unwrap().public_key(), old.public_key());
+
+        // Another instance replaces the key and saves newer metadata, which
+        // `load` picks up
+        km.keyring.set_async("main", &new).await.unwrap();
+        km.metadata.keys.insert("main".into(), metadata_for("main", &new, true));
+        km.metadata.version += 1;
+
+        let keys = km.get_signing_keys().await.unwrap();
+        let event = EventBuilder::text_note("after rotation").sign_with_keys(keys).unwrap();
+        assert_eq!(event.pubkey, new.public_key());
+
+        // Same metadata version but a stale cache: the mismatch forces a reload
+        let newer = Keys::generate();
+        km.keyring.set_async("main", &newer).await.unwrap();
+        km.metadata.keys.insert("main".into(), metadata_for("main", &newer, true));
+        assert_eq!(km.get_signing_keys().await.unwrap().public_key(), newer.public_key());
+        assert!(km.is_unlocked());
+    }
+
+    #[tokio::test]
+    async fn test_matching_cached_key_accepted() {
+        let stored = Keys::generate();
+
+        let mut km = KeyManager::new();
+        km.metadata.keys.insert("main".into(), metadata_for("main", &stored, true));
+        km.metadata.active_key = Some("main".into());
+        km.cached_keys = Some(stored.clone());
+
+        let keys = km.get_signing_keys().await.unwrap();
+        assert_eq!(keys.public_key(), stored.public_key());
+    }
+
+    #[test]
+    fn test_mnemonic_account_index() {
+        // NIP-06 test vector (account 0)
+        let mnemonic = "leader monkey parrot ring guide accident before fence cannon height naive bean";
+        let default = keys_from_mnemonic(mnemonic, None, None).unwrap();
+        assert_eq!(
+            default.secret_key().to_secret_hex(),
+            "7f7ff03d123792d6ac594bfa67bf6d0c0ab55b6b1fdb6249303fe861f1ccba9a"
+        );
+        assert_eq!(keys_from_mnemonic(mnemonic, None, Some(0)).unwrap().public_key(), default.public_key());
+
+        let second = keys_from_mnemonic(mnemonic, None, Some(2)).unwrap();
+        assert_ne!(second.public_key(), default.public_key());
+        assert_eq!(keys_from_mnemonic(mnemonic, None, Some(2)).unwrap().public_key(), second.public_key());
+
+        assert!(matches!(
+            keys_from_mnemonic("leader monkey parrot", None, None),
+            Err(SignerError::InvalidKeyFormat(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_encrypted_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alt.ncryptsec");
+        let alt = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", Keys::generate());
+        km.add_test_key("alt", alt.clone());
+
+        assert!(matches!(
+            km.export_encrypted_to_file("alt", "", 16, &path).await,
+            Err(SignerError::InvalidRequest(_))
+        ));
+        assert!(!path.exists());
+
+        // Overwriting a world-readable file still leaves it private
+        std::fs::write(&path, "old").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        km.export_encrypted_to_file("alt", "export password", 16, &path).await.unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let ncryptsec = std::fs::read_to_string(&path).unwrap();
+        let encrypted = EncryptedSecretKey::from_bech32(ncryptsec.trim()).unwrap();
+        assert_eq!(encrypted.decrypt("export password").unwrap(), *alt.secret_key());
+    }
+
+    #[tokio::test]
+    async fn test_import_encrypted_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let alt = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", Keys::generate());
+        km.add_test_key("alt", alt.clone());
+        let ncryptsec = km.export_encrypted("alt", "pw", 14, KeySecurity::Medium).await.unwrap();
+
+        let mut other = KeyManager::new().with_metadata_file(dir.path().join("keys.json"));
+        other.keyring = KeyStore::Memory(Default::default());
+        assert!(matches!(other.import_encrypted("alt", &ncryptsec, "wrong").await, Err(SignerError::InvalidPassword)));
+        let meta = other.import_encrypted("alt", &ncryptsec, "pw").await.unwrap();
+        assert_eq!(meta.pubkey_hex, alt.public_key().to_hex());
+        assert_eq!(other.get_keys_for(Some("alt")).await.unwrap().public_key(), alt.public_key());
+    }
+
+    #[tokio::test]
+    async fn test_export_encrypted_cost() {
+        let alt = Keys::generate();
+        let mut km = KeyManager::with_test_key("main", Keys::generate());
+        km.add_test_key("alt", alt.clone());
+
+        for log_n in [0, 13, 23, 255] {
+            assert!(matches!(
+                km.export_encrypted("alt", "pw", log_n, KeySecurity::Medium).await,
+                Err(SignerError::ConfigError(_))
+            ));
+        }
+
+        let ncryptsec = km.export_encrypted("alt", "pw", 14, KeySecurity::Weak).await.unwrap();
+        let encrypted = EncryptedSecretKey::from_bech32(&ncryptsec).unwrap();
+        assert_eq!(encrypted.log_n(), 14);
+        assert_eq!(encrypted.key_security(), KeySecurity::Weak);
+        assert_eq!(encrypted.decrypt("pw").unwrap(), *alt.secret_key());
+
+        // The default cost is unchanged, so older exports read the same way
+        assert_eq!(crate::config::SecurityConfig::default().ncryptsec_log_n, 16);
+    }
+}
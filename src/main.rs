@@ -3,17 +3,36 @@
 //! This is the main entry point for the Pleb Signer application.
 //! It provides secure key management and event signing for Nostr clients.
 
+mod airgap;
 mod app;
+mod approval;
+mod audit;
+mod autolock;
+mod backup;
+mod batch;
 mod bunker;
+mod clipboard;
 pub mod client;
 mod config;
 mod dbus;
+mod diagnostics;
 mod error;
 mod keys;
+mod nip05;
+mod nip26;
+mod notify;
 mod permissions;
+mod presence;
+mod relays;
+#[cfg(feature = "share-server")]
+mod share;
 mod signing;
+mod stats;
+#[cfg(test)]
+mod test_bus;
 mod tray;
 mod ui;
+mod wipe;
 
 use anyhow::Result;
 use std::sync::Arc;
@@ -22,17 +41,51 @@ use tokio::sync::{Mutex, RwLock};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
-use crate::app::AppState;
-use crate::config::Config;
+use crate::app::{AppMessage, AppState};
+use crate::audit::AuditEntry;
+use crate::autolock::IdleAction;
+use crate::bunker::BunkerState;
+use crate::client::PlebSignerClient;
+use crate::config::{Config, CONFIG_ENV};
 use crate::dbus::SignerService;
+use crate::error::SignerError;
 use crate::keys::KeyManager;
+use crate::notify::{confirm_bunker_start, show_denial_alert, RequestNotice, RequestNotifier, MIN_NOTIFY_INTERVAL};
 
 fn main() -> Result<()> {
     // Check if we're being run in UI-only mode (spawned by tray)
     let args: Vec<String> = std::env::args().collect();
+    // Passed on to the UI process through the environment
+    if let Some(path) = arg_value(&args, "--config") {
+        std::env::set_var(CONFIG_ENV, path);
+    }
+    let safe_mode = args.iter().any(|a| a == "--safe-mode");
     if args.len() > 1 && args[1] == "--ui-only" {
-        return run_ui_only();
+        return run_ui_only(safe_mode);
+    }
+    if let Some(path) = arg_value(&args, "--export-state") {
+        return run_export_state(path, args.iter().any(|a| a == "--with-keys"));
+    }
+    if let Some(path) = arg_value(&args, "--import-state") {
+        return run_import_state(path);
+    }
+    if args.iter().any(|a| a == "--diagnostics") {
+        return run_diagnostics();
+    }
+    if args.iter().any(|a| a == "--wipe") {
+        return run_wipe();
     }
+    if args.iter().any(|a| a == "--unlock") {
+        return run_unlock();
+    }
+    if let Some(name) = arg_value(&args, "--generate-key") {
+        return run_generate_key(name);
+    }
+    if let Some(path) = arg_value(&args, "--sign-batch") {
+        return run_sign_batch(path, arg_value(&args, "--key"), arg_value(&args, "--output"), safe_mode);
+    }
+    // D-Bus service only, e.g. as a systemd user service: no tray, no windows
+    let headless = args.iter().any(|a| a == "--headless");
 
     // Initialize logging
     FmtSubscriber::builder()
@@ -50,12 +103,18 @@ fn main() -> Result<()> {
     let runtime = tokio::runtime::Runtime::new()?;
     
     // Load configuration and initialize state in the runtime
-    let (_config, key_manager, app_state) = runtime.block_on(async {
-        let config = Config::load().await?;
+    let (config, key_manager, app_state) = runtime.block_on(async {
+        let mut config = Config::load().await?;
         info!("Configuration loaded");
+        if safe_mode {
+            tracing::warn!("Starting in SAFE MODE: bunker and auto-approval are disabled");
+            config.apply_safe_mode();
+        }
 
         // Create shared key manager
-        let key_manager = Arc::new(Mutex::new(KeyManager::new()));
+        let key_manager = Arc::new(Mutex::new(
+            KeyManager::new().with_missing_active_key(config.general.missing_active_key)
+        ));
         
         // Load key metadata
         {
@@ -66,7 +125,11 @@ fn main() -> Result<()> {
         }
 
         // Initialize application state
-        let app_state = Arc::new(RwLock::new(AppState::new(config.clone()).await?));
+        let mut state = AppState::new(config.clone()).await?;
+        if state.lock_for_launch(&*key_manager.lock().await) {
+            info!("Starting locked: the bunker auto-starts once Unlock is given its key's password");
+        }
+        let app_state = Arc::new(RwLock::new(state));
         
         // Initialize bunker with key manager
         {
@@ -77,23 +140,117 @@ fn main() -> Result<()> {
         Ok::<_, anyhow::Error>((config, key_manager, app_state))
     })?;
 
+    // Messages raised by background components (e.g. bunker unlock prompts)
+    let app_messages = runtime.block_on(async {
+        app_state.read().await.message_receiver.clone()
+    });
+
     // Clone for D-Bus service - IMPORTANT: load keys for D-Bus too
     let dbus_state = Arc::clone(&app_state);
     let dbus_km = Arc::clone(&key_manager);
 
-    // Start D-Bus service in background on the runtime
-    runtime.spawn(async move {
-        if let Err(e) = SignerService::run(dbus_state, dbus_km).await {
-            tracing::error!("D-Bus service error: {}", e);
+    // Register the D-Bus service before anything else is shown; the
+    // connection keeps serving on the runtime for as long as it's held
+    let _dbus_connection = match runtime.block_on(SignerService::start(dbus_state, dbus_km)) {
+        Ok(connection) => connection,
+        Err(SignerError::AlreadyRunning) if headless => {
+            anyhow::bail!("Pleb Signer is already running");
         }
-    });
+        Err(SignerError::AlreadyRunning) => {
+            eprintln!("Pleb Signer is already running; bringing its window to the front.");
+            runtime.block_on(async {
+                match PlebSignerClient::new("pleb-signer").await {
+                    Ok(client) => {
+                        if let Err(e) = client.show_window().await {
+                            tracing::warn!("Could not reach the running instance: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not reach the running instance: {}", e),
+                }
+            });
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Bring the bunker up on its own once the signer is unlocked
+    runtime.spawn(app::auto_start_bunker(Arc::clone(&app_state)));
+
+    if headless {
+        info!("Running headless: D-Bus service only, no tray or windows");
+        return runtime.block_on(run_headless(app_state, key_manager, app_messages));
+    }
+
+    // Announce requests that wait for approval, for desktop notifications
+    {
+        let app_state = Arc::clone(&app_state);
+        runtime.spawn(async move {
+            let (mut queued, sender) = {
+                let state = app_state.read().await;
+                (state.approval_queue.subscribe(), state.get_message_sender())
+            };
+            while let Some(request) = queued.recv().await {
+                let notice = RequestNotice {
+                    app: request.app_name.clone().unwrap_or(request.app_id),
+                    request_type: request.request_type,
+                };
+                let _ = sender.send(AppMessage::NewRequest(notice)).await;
+            }
+        });
+    }
+    let mut request_notifier = RequestNotifier::new(MIN_NOTIFY_INTERVAL);
 
     // Start system tray (runs in its own thread)
-    let tray_state = tray::start_tray();
+    let tray_state = tray::start_tray(config.ui.tray_left_click);
     info!("System tray initialized");
 
+    // Keep the tray's bunker, lock and queue status in sync with the signer
+    // (D-Bus clients can lock and unlock it too)
+    {
+        let app_state = Arc::clone(&app_state);
+        let tray_state = Arc::clone(&tray_state);
+        runtime.spawn(async move {
+            loop {
+                let locked = app_state.read().await.is_locked;
+                tray_state.is_locked.store(locked, Ordering::Relaxed);
+                let bunker_state = app_state.read().await.get_bunker_state().await;
+                let enabled = !matches!(bunker_state, BunkerState::Disconnected | BunkerState::Error(_));
+                tray_state.bunker_enabled.store(enabled, Ordering::Relaxed);
+                tray_state.bunker_paused.store(matches!(bunker_state, BunkerState::Paused), Ordering::Relaxed);
+                let pending = app_state.read().await.approval_queue.pending_count();
+                tray_state.pending_requests.store(pending, Ordering::Relaxed);
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+        });
+    }
+
+    // Inactivity lock: warn first, then lock
+    {
+        let app_state = Arc::clone(&app_state);
+        let key_manager = Arc::clone(&key_manager);
+        let tray_state = Arc::clone(&tray_state);
+        runtime.spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                let action = app_state.read().await.inactivity.lock().unwrap().poll(std::time::Instant::now());
+                match action {
+                    IdleAction::Warn { secs_left } => {
+                        let sender = app_state.read().await.get_message_sender();
+                        let _ = sender.try_send(AppMessage::LockWarning(secs_left));
+                    }
+                    IdleAction::Lock => {
+                        info!("Locking after inactivity");
+                        app::lock_signer(&app_state, &key_manager).await;
+                        tray_state.is_locked.store(true, Ordering::Relaxed);
+                    }
+                    IdleAction::None => {}
+                }
+            }
+        });
+    }
+
     // Show the UI window initially (spawn as subprocess)
-    spawn_ui_window();
+    spawn_ui_window(safe_mode);
 
     // Main loop - tray controls the lifecycle
     loop {
@@ -106,7 +263,100 @@ fn main() -> Result<()> {
         // Check if window should be shown
         if tray_state.show_requested.swap(false, Ordering::Relaxed) {
             info!("Spawning UI window...");
-            spawn_ui_window();
+            spawn_ui_window(safe_mode);
+        }
+
+        if tray_state.unlock_requested.swap(false, Ordering::Relaxed) {
+            info!("Unlock requested from tray");
+            let unlocked = runtime.block_on(async { app_state.write().await.unlock_without_password() });
+            if !unlocked {
+                tracing::warn!("The signer launched locked; run pleb-signer --unlock and enter the bunker key's password");
+            }
+            tray_state.is_locked.store(!unlocked, Ordering::Relaxed);
+        }
+
+        if tray_state.toggle_lock_requested.swap(false, Ordering::Relaxed) {
+            let lock = !tray_state.is_locked.load(Ordering::Relaxed);
+            info!("{} from tray", if lock { "Lock requested" } else { "Unlock requested" });
+            let locked = runtime.block_on(async {
+                if lock {
+                    app::lock_signer(&app_state, &key_manager).await;
+                    true
+                } else {
+                    !app_state.write().await.unlock_without_password()
+                }
+            });
+            if locked && !lock {
+                tracing::warn!("The signer launched locked; run pleb-signer --unlock and enter the bunker key's password");
+            }
+            tray_state.is_locked.store(locked, Ordering::Relaxed);
+        }
+
+        if tray_state.approve_top_requested.swap(false, Ordering::Relaxed) {
+            let (queue, ui_config) = runtime.block_on(async {
+                let state = app_state.read().await;
+                (Arc::clone(&state.approval_queue), state.config.ui.clone())
+            });
+            // A kind with an approval countdown, or a request carrying a warning,
+            // must be approved in the window where the user can read it
+            let countdown = |request: &signing::SigningRequest| match request.payload {
+                signing::SigningPayload::Event(ref event) => ui_config.countdown_for(event.kind),
+                _ => None,
+            };
+            match queue.pending().first() {
+                Some(request) if countdown(request).is_some() || !request.warnings.is_empty() => {
+                    info!("Tray click: {} from {} needs the approval view", request.request_type.as_str(), request.app_id);
+                    spawn_ui_window(safe_mode);
+                }
+                Some(request) => {
+                    info!("Approving {} from {} via tray click", request.request_type.as_str(), request.app_id);
+                    queue.resolve(&request.id, true);
+                }
+                None => info!("Tray click: no pending requests to approve"),
+            }
+        }
+
+        // Handle requests from background components
+        while let Ok(message) = app_messages.try_recv() {
+            match message {
+                AppMessage::RequestUnlock => {
+                    info!("Unlock requested, spawning UI window...");
+                    spawn_ui_window(safe_mode);
+                }
+                AppMessage::ShowWindow => {
+                    info!("Window requested over D-Bus, spawning UI window...");
+                    spawn_ui_window(safe_mode);
+                }
+                AppMessage::LockWarning(secs_left) => {
+                    let sender = runtime.block_on(async { app_state.read().await.get_message_sender() });
+                    show_lock_warning(secs_left, sender);
+                }
+                AppMessage::StayUnlocked => {
+                    info!("Staying unlocked");
+                    runtime.block_on(async { app_state.read().await.record_activity() });
+                }
+                AppMessage::DenialAlert(alert) if show_notifications(&runtime, &app_state) => {
+                    show_denial_alert(&alert);
+                }
+                AppMessage::NewRequest(notice) if show_notifications(&runtime, &app_state) => {
+                    let now = std::time::Instant::now();
+                    let focused = runtime.block_on(async { app_state.read().await.window_focused(now) });
+                    request_notifier.notify(&notice, focused, now);
+                }
+                AppMessage::DenialAlert(_) | AppMessage::NewRequest(_) => {}
+                AppMessage::BunkerActivity(entry) => {
+                    runtime.block_on(record_bunker_activity(&app_state, entry));
+                }
+                AppMessage::ConfirmBunkerStart => {
+                    let sender = runtime.block_on(async { app_state.read().await.get_message_sender() });
+                    confirm_bunker_start(sender);
+                }
+                AppMessage::StartBunker => {
+                    info!("Starting the bunker as confirmed");
+                    runtime.block_on(app::start_bunker_unless_locked(&app_state));
+                }
+                _ => {}
+            }
         }
 
         // Sleep a bit before checking again
@@ -117,13 +367,70 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Serve D-Bus until Ctrl-C or SIGTERM (`--headless`)
+///
+/// Only the inactivity lock runs alongside the service; there's no window
+/// to prompt in, so clients unlock the signer with the `Unlock` method.
+async fn run_headless(
+    app_state: Arc<RwLock<AppState>>,
+    key_manager: Arc<Mutex<KeyManager>>,
+    app_messages: async_channel::Receiver<AppMessage>,
+) -> Result<()> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    let mut tick = tokio::time::interval(std::time::Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = terminate.recv() => break,
+            _ = tick.tick() => {
+                let action = app_state.read().await.inactivity.lock().unwrap().poll(std::time::Instant::now());
+                if action == IdleAction::Lock {
+                    info!("Locking after inactivity");
+                    app::lock_signer(&app_state, &key_manager).await;
+                }
+            }
+            message = app_messages.recv() => match message {
+                Ok(AppMessage::RequestUnlock) => {
+                    tracing::warn!("Unlock needed, but there is no window in headless mode; run pleb-signer --unlock");
+                }
+                Ok(AppMessage::ConfirmBunkerStart) => {
+                    tracing::warn!("bunker.auto_start_confirm can't ask in headless mode; start the bunker over D-Bus");
+                }
+                Ok(AppMessage::BunkerActivity(entry)) => record_bunker_activity(&app_state, entry).await,
+                Ok(_) => {}
+                Err(_) => break,
+            },
+        }
+    }
+
+    info!("Pleb Signer shutting down");
+    Ok(())
+}
+
+/// Add a request the bunker handled to the audit log, next to D-Bus requests
+async fn record_bunker_activity(app_state: &RwLock<AppState>, entry: AuditEntry) {
+    let mut state = app_state.write().await;
+    if let Err(e) = state.audit_log.record(entry).await {
+        tracing::warn!("Failed to write audit entry: {}", e);
+    }
+}
+
+/// Whether desktop notifications are on (read each time, as the config can
+/// be reloaded)
+fn show_notifications(runtime: &tokio::runtime::Runtime, app_state: &RwLock<AppState>) -> bool {
+    runtime.block_on(async { app_state.read().await.config.general.show_notifications })
+}
+
 /// Spawn the UI window as a separate process
-fn spawn_ui_window() {
+fn spawn_ui_window(safe_mode: bool) {
     let exe = std::env::current_exe().unwrap_or_else(|_| "pleb-signer".into());
     
-    match std::process::Command::new(&exe)
-        .arg("--ui-only")
-        .spawn()
+    let mut command = std::process::Command::new(&exe);
+    command.arg("--ui-only");
+    if safe_mode {
+        command.arg("--safe-mode");
+    }
+    match command.spawn()
     {
         Ok(_child) => {
             info!("UI window process spawned");
@@ -134,8 +441,29 @@ fn spawn_ui_window() {
     }
 }
 
+/// Desktop notification before the inactivity lock; clicking it keeps the
+/// signer unlocked
+fn show_lock_warning(secs_left: u64, sender: async_channel::Sender<AppMessage>) {
+    std::thread::spawn(move || {
+        let output = std::process::Command::new("notify-send")
+            .arg("--app-name=Pleb Signer")
+            .arg("--wait")
+            .arg("--action=stay=Stay unlocked")
+            .arg("Pleb Signer")
+            .arg(format!("Locking in {}s — click to stay unlocked", secs_left))
+            .output();
+        match output {
+            Ok(output) if String::from_utf8_lossy(&output.stdout).trim() == "stay" => {
+                let _ = sender.send_blocking(AppMessage::StayUnlocked);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Could not show lock warning: {}", e),
+        }
+    });
+}
+
 /// Run only the UI (called when spawned with --ui-only)
-fn run_ui_only() -> Result<()> {
+fn run_ui_only(safe_mode: bool) -> Result<()> {
     // Minimal logging for UI subprocess
     FmtSubscriber::builder()
         .with_max_level(Level::WARN)
@@ -147,8 +475,13 @@ fn run_ui_only() -> Result<()> {
     let runtime = tokio::runtime::Runtime::new()?;
     
     let (config, key_manager) = runtime.block_on(async {
-        let config = Config::load().await?;
-        let key_manager = Arc::new(tokio::sync::Mutex::new(KeyManager::new()));
+        let mut config = Config::load().await?;
+        if safe_mode {
+            config.apply_safe_mode();
+        }
+        let key_manager = Arc::new(tokio::sync::Mutex::new(
+            KeyManager::new().with_missing_active_key(config.general.missing_active_key)
+        ));
         
         {
             let mut km = key_manager.lock().await;
@@ -168,3 +501,207 @@ fn run_ui_only() -> Result<()> {
     
     Ok(())
 }
+
+/// Get the value following a flag, e.g. `--export-state <path>`
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Read a line (e.g. a password) from stdin
+fn read_line(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Export config, key metadata and optionally encrypted keys to an archive
+fn run_export_state(path: &str, with_keys: bool) -> Result<()> {
+    let password = if with_keys {
+        let password = read_line("Password to encrypt keys with: ")?;
+        if password.is_empty() {
+            anyhow::bail!("A password is required to include keys");
+        }
+        Some(password)
+    } else {
+        None
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let config = Config::load().await?;
+        let mut km = KeyManager::new();
+        km.load().await?;
+        km.export_state(&config, std::path::Path::new(path), password.as_deref()).await?;
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    println!("State exported to {}{}", path, if with_keys { " (including encrypted keys)" } else { "" });
+    Ok(())
+}
+
+/// Restore an archive created with `--export-state`
+fn run_import_state(path: &str) -> Result<()> {
+    let archive = std::path::Path::new(path);
+    let includes_keys = backup::StateBackup::read_archive(archive)?.encrypted_keys.is_some();
+    let password = if includes_keys {
+        Some(read_line("Password the keys were encrypted with: ")?)
+    } else {
+        None
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let config = Config::load().await?;
+        let mut km = KeyManager::new();
+        km.import_state(config.config_path(), archive, password.as_deref()).await?;
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    println!("State imported from {}", path);
+    Ok(())
+}
+
+/// Unlock the running signer, e.g. one started with `--headless` (`--unlock`)
+///
+/// A signer that launched locked for the bunker needs the bunker key's own
+/// password; otherwise the password is left empty.
+fn run_unlock() -> Result<()> {
+    let password = read_line("Password (empty if the signer didn't ask for one at launch): ")?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let client = PlebSignerClient::new("pleb-signer").await?;
+        client.unlock(&password).await?;
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    println!("Unlocked");
+    Ok(())
+}
+
+/// Generate and store a new key, then print its npub (`--generate-key`)
+fn run_generate_key(name: &str) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let metadata = runtime.block_on(async {
+        let mut km = KeyManager::new();
+        km.load().await?;
+        Ok::<_, anyhow::Error>(km.generate_key(name).await?)
+    })?;
+
+    println!("{}", metadata.npub);
+    Ok(())
+}
+
+/// Sign a file of unsigned events and write the results (`--sign-batch`)
+///
+/// There is nobody to approve the events, so this is refused in safe mode.
+/// Exits with an error status if any event failed, after writing the rest.
+fn run_sign_batch(path: &str, key: Option<&str>, output: Option<&str>, safe_mode: bool) -> Result<()> {
+    if safe_mode {
+        anyhow::bail!("Batch signing skips approval prompts and is disabled in safe mode");
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let items = runtime.block_on(async {
+        let config = Config::load().await?;
+        let mut km = KeyManager::new().with_missing_active_key(config.general.missing_active_key);
+        km.load().await?;
+        let engine = signing::SigningEngine::new(Arc::new(Mutex::new(km)))
+            .with_blocked_kinds(signing::BlockedKinds::new(config.security.blocked_event_kinds.clone()));
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let items = batch::sign_batch_file(&engine, std::path::Path::new(path), key, &config.security, now).await?;
+        Ok::<_, anyhow::Error>(items)
+    })?;
+
+    let json = serde_json::to_string_pretty(&items)?;
+    match output {
+        Some(output) => std::fs::write(output, json + "\n")?,
+        None => println!("{}", json),
+    }
+
+    let failed = items.iter().filter(|item| !item.is_signed()).count();
+    eprintln!("Signed {} of {} events", items.len() - failed, items.len());
+    if failed > 0 {
+        anyhow::bail!("{} events could not be signed", failed);
+    }
+    Ok(())
+}
+
+/// Phrase the user must type to confirm `--wipe`
+const WIPE_CONFIRMATION: &str = "wipe all keys";
+
+/// Remove every key, setting and log from this machine (`--wipe`)
+///
+/// Secret keys are deleted from the keyring before their metadata, so a
+/// failure never leaves secrets that nothing points to.
+fn run_wipe() -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let config = runtime.block_on(Config::load())?;
+    let targets = wipe::WipeTargets::for_this_user(&config)?;
+    runtime.block_on(wipe::check_not_running())?;
+
+    eprintln!("This permanently deletes all Pleb Signer keys (from the keyring), key metadata,");
+    eprintln!("config, audit logs, bunker sessions and the autostart entry on this machine:");
+    eprintln!("  {}", targets.config_file.display());
+    eprintln!("  {}", targets.data_dir.display());
+    if let Some(ref entry) = targets.autostart_entry {
+        eprintln!("  {}", entry.display());
+    }
+    eprintln!("Keys without a backup can't be recovered.");
+    let answer = read_line(&format!("Type '{}' to continue: ", WIPE_CONFIRMATION))?;
+    if answer.trim() != WIPE_CONFIRMATION {
+        anyhow::bail!("Wipe cancelled; nothing was removed");
+    }
+
+    let mut report = wipe::WipeReport::default();
+    let mut namespaces = targets.key_namespaces();
+    for engine in &config.engines {
+        if !namespaces.contains(&Some(engine.name.clone())) {
+            namespaces.push(Some(engine.name.clone()));
+        }
+    }
+    runtime.block_on(async {
+        for namespace in &namespaces {
+            let mut km = match namespace {
+                Some(namespace) => KeyManager::with_namespace(namespace),
+                None => KeyManager::new(),
+            };
+            if let Err(e) = km.load().await {
+                report.failed.push(format!("key metadata ({}): {}", namespace.as_deref().unwrap_or("default"), e));
+                continue;
+            }
+            for (name, outcome) in km.wipe_keyring().await {
+                match outcome {
+                    Ok(()) => report.removed.push(format!("keyring entry '{}'", name)),
+                    Err(e) => report.failed.push(format!("keyring entry '{}': {}", name, e)),
+                }
+            }
+        }
+    });
+    if report.failed.is_empty() {
+        wipe::wipe_files(&targets, &mut report);
+    } else {
+        eprintln!("Some keyring entries couldn't be removed; key metadata and config were kept");
+    }
+
+    print!("{}", report.render());
+    if !report.failed.is_empty() {
+        anyhow::bail!("Wipe incomplete: {} item(s) could not be removed", report.failed.len());
+    }
+    Ok(())
+}
+
+/// Print environment info for bug reports (no keys or app identifiers)
+fn run_diagnostics() -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let report = runtime.block_on(async {
+        let config = Config::load().await?;
+        Ok::<_, anyhow::Error>(diagnostics::Diagnostics::collect(&config).await)
+    })?;
+
+    print!("{}", report.render());
+    Ok(())
+}
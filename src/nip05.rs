@@ -0,0 +1,219 @@
+//! NIP-05 identifier verification
+//!
+//! Looks up `name@domain` in the domain's `/.well-known/nostr.json` and checks
+//! that it maps to the expected public key. This works for any pubkey, not
+//! just stored keys, and needs no unlocked keys.
+
+use crate::error::{Result, SignerError};
+use nostr::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long to wait for the domain to answer
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of a NIP-05 lookup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Nip05Verification {
+    /// The identifier maps to the given pubkey
+    pub valid: bool,
+    /// Relays the domain lists for the pubkey (empty if not valid)
+    pub relays: Vec<String>,
+}
+
+/// Contents of `/.well-known/nostr.json`
+#[derive(Debug, Deserialize)]
+struct WellKnown {
+    #[serde(default)]
+    names: HashMap<String, String>,
+    #[serde(default)]
+    relays: HashMap<String, Vec<String>>,
+}
+
+/// A parsed `name@domain` identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nip05Identifier {
+    pub name: String,
+    pub domain: String,
+}
+
+impl Nip05Identifier {
+    /// Parse `name@domain`; a bare domain means the root name `_`
+    pub fn parse(identifier: &str) -> Result<Self> {
+        let identifier = identifier.trim().to_lowercase();
+        let (name, domain) = identifier.split_once('@').unwrap_or(("_", identifier.as_str()));
+
+        let valid_name = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        let valid_domain = domain.contains('.')
+            && domain.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':'));
+        if !valid_name || !valid_domain {
+            return Err(SignerError::InvalidRequest(format!("Invalid NIP-05 identifier: {}", identifier)));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            domain: domain.to_string(),
+        })
+    }
+
+    /// Well-known URL relative to a base such as `https://example.com`
+    fn url(&self, base: &str) -> String {
+        format!("{}/.well-known/nostr.json?name={}", base, self.name)
+    }
+}
+
+/// Verify that `identifier` resolves to `pubkey` (hex or npub)
+pub async fn verify(identifier: &str, pubkey: &str) -> Result<Nip05Verification> {
+    let id = Nip05Identifier::parse(identifier)?;
+    let base = format!("https://{}", id.domain);
+    verify_at(&id, pubkey, &base).await
+}
+
+async fn verify_at(id: &Nip05Identifier, pubkey: &str, base: &str) -> Result<Nip05Verification> {
+    let pubkey = PublicKey::parse(pubkey)
+        .map_err(|e| SignerError::InvalidRequest(format!("Invalid public key: {}", e)))?;
+    let document = fetch(&id.url(base)).await?;
+    check_document(&id.name, &pubkey, &document)
+}
+
+/// Fetch the well-known document; NIP-05 forbids following redirects
+async fn fetch(url: &str) -> Result<String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| SignerError::Nip05Error(e.to_string()))?;
+
+    let response = client.get(url).send().await.map_err(|e| {
+        if e.is_timeout() {
+            SignerError::Timeout
+        } else {
+            SignerError::Nip05Error(format!("Could not reach {}: {}", url, e))
+        }
+    })?;
+    if !response.status().is_success() {
+        return Err(SignerError::Nip05Error(format!("{} returned {}", url, response.status())));
+    }
+
+    response.text().await.map_err(|e| SignerError::Nip05Error(e.to_string()))
+}
+
+/// Check a `nostr.json` document for `name` -> `pubkey`
+fn check_document(name: &str, pubkey: &PublicKey, document: &str) -> Result<Nip05Verification> {
+    let well_known: WellKnown = serde_json::from_str(document)
+        .map_err(|e| SignerError::Nip05Error(format!("Malformed nostr.json: {}", e)))?;
+
+    let valid = well_known.names
+        .get(name)
+        .and_then(|hex| PublicKey::from_hex(hex).ok())
+        .is_some_and(|listed| listed == *pubkey);
+    let relays = if valid {
+        well_known.relays.get(&pubkey.to_hex()).cloned().unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Nip05Verification { valid, relays })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a single canned HTTP response and return the base URL
+    async fn mock_well_known(status: &str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let status = status.to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        base
+    }
+
+    fn document(name: &str, keys: &Keys) -> String {
+        let hex = keys.public_key().to_hex();
+        serde_json::json!({
+            "names": { name: hex },
+            "relays": { hex: ["wss://relay.example.com"] },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_identifier() {
+        let id = Nip05Identifier::parse("Bob@Example.com").unwrap();
+        assert_eq!(id, Nip05Identifier { name: "bob".into(), domain: "example.com".into() });
+        assert_eq!(Nip05Identifier::parse("example.com").unwrap().name, "_");
+        assert!(Nip05Identifier::parse("bob@").is_err());
+        assert!(Nip05Identifier::parse("bob@example.com/evil").is_err());
+        assert!(Nip05Identifier::parse("b b@example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_match() {
+        let keys = Keys::generate();
+        let id = Nip05Identifier::parse("bob@example.com").unwrap();
+        let base = mock_well_known("200 OK", document("bob", &keys)).await;
+
+        let result = verify_at(&id, &keys.public_key().to_hex(), &base).await.unwrap();
+        assert!(result.valid);
+        assert_eq!(result.relays, ["wss://relay.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_mismatch() {
+        let listed = Keys::generate();
+        let other = Keys::generate();
+        let id = Nip05Identifier::parse("bob@example.com").unwrap();
+        let base = mock_well_known("200 OK", document("bob", &listed)).await;
+
+        let result = verify_at(&id, &other.public_key().to_hex(), &base).await.unwrap();
+        assert_eq!(result, Nip05Verification { valid: false, relays: Vec::new() });
+    }
+
+    #[tokio::test]
+    async fn test_verify_missing_name() {
+        let keys = Keys::generate();
+        let id = Nip05Identifier::parse("alice@example.com").unwrap();
+        let base = mock_well_known("200 OK", document("bob", &keys)).await;
+
+        let result = verify_at(&id, &keys.public_key().to_hex(), &base).await.unwrap();
+        assert!(!result.valid);
+    }
+
+    #[tokio::test]
+    async fn test_verify_bad_responses() {
+        let keys = Keys::generate();
+        let pubkey = keys.public_key().to_hex();
+        let id = Nip05Identifier::parse("bob@example.com").unwrap();
+
+        let base = mock_well_known("200 OK", "<html>not json</html>".into()).await;
+        assert!(matches!(verify_at(&id, &pubkey, &base).await, Err(SignerError::Nip05Error(_))));
+
+        let base = mock_well_known("404 Not Found", String::new()).await;
+        assert!(matches!(verify_at(&id, &pubkey, &base).await, Err(SignerError::Nip05Error(_))));
+
+        // Nothing listening
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        assert!(matches!(verify_at(&id, &pubkey, &base).await, Err(SignerError::Nip05Error(_))));
+
+        assert!(matches!(verify_at(&id, "not-a-key", &base).await, Err(SignerError::InvalidRequest(_))));
+    }
+}
@@ -0,0 +1,200 @@
+//! NIP-26 delegated event signing
+//!
+//! A delegation lets another key (the delegatee) publish events that count
+//! as ours, within conditions on the kind and created_at. We sign
+//! `nostr:delegation:<delegatee hex>:<conditions>` and the delegatee adds
+//! `["delegation", <our hex>, <conditions>, <signature>]` to its events.
+//! rust-nostr no longer ships NIP-26, so the token is built here.
+
+use crate::error::{Result, SignerError};
+use nostr::hashes::{sha256, Hash};
+use nostr::prelude::*;
+use nostr::secp256k1::schnorr::Signature;
+use nostr::secp256k1::{Message, Secp256k1, XOnlyPublicKey};
+use std::fmt;
+use std::str::FromStr;
+
+/// What a delegatee may publish
+///
+/// NIP-26 conditions all have to hold, so there is at most one kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DelegationConditions {
+    /// The only event kind allowed (None = any kind)
+    pub kind: Option<u16>,
+    /// Events must be created after this time (unix seconds)
+    pub created_after: Option<u64>,
+    /// Events must be created before this time (unix seconds)
+    pub created_before: Option<u64>,
+}
+
+impl DelegationConditions {
+    /// Whether an event of `kind` created at `created_at` is covered
+    pub fn allows(&self, kind: u16, created_at: u64) -> bool {
+        self.kind.is_none_or(|k| k == kind)
+            && self.created_after.is_none_or(|after| created_at > after)
+            && self.created_before.is_none_or(|before| created_at < before)
+    }
+
+    /// For the approval dialog, e.g. "kind 1 events created until
+    /// 2026-11-15 12:00 UTC"
+    pub fn describe(&self) -> String {
+        let at = |ts: u64| {
+            chrono::DateTime::from_timestamp(ts as i64, 0)
+                .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_else(|| ts.to_string())
+        };
+        let events = match self.kind {
+            Some(kind) => format!("kind {} events", kind),
+            None => "events of any kind".to_string(),
+        };
+        match (self.created_after, self.created_before) {
+            (Some(after), Some(before)) => format!("{} created from {} until {}", events, at(after), at(before)),
+            (Some(after), None) => format!("{} created from {}, with no end", events, at(after)),
+            (None, Some(before)) => format!("{} created until {}", events, at(before)),
+            (None, None) => format!("{} at any time", events),
+        }
+    }
+}
+
+impl fmt::Display for DelegationConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let conditions: Vec<String> = [
+            self.kind.map(|kind| format!("kind={}", kind)),
+            self.created_after.map(|after| format!("created_at>{}", after)),
+            self.created_before.map(|before| format!("created_at<{}", before)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}", conditions.join("&"))
+    }
+}
+
+impl FromStr for DelegationConditions {
+    type Err = SignerError;
+
+    /// Parse a NIP-26 query string, e.g. `kind=1&created_at<1700000000`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |msg: String| SignerError::InvalidRequest(format!("Invalid delegation conditions: {}", msg));
+        let mut conditions = Self::default();
+        for condition in s.trim().split('&').filter(|c| !c.is_empty()) {
+            let (slot, value) = if let Some(kind) = condition.strip_prefix("kind=") {
+                let kind = kind.parse().map_err(|_| invalid(format!("bad kind '{}'", kind)))?;
+                if conditions.kind.replace(kind).is_some() {
+                    return Err(invalid("more than one kind".into()));
+                }
+                continue;
+            } else if let Some(after) = condition.strip_prefix("created_at>") {
+                (&mut conditions.created_after, after)
+            } else if let Some(before) = condition.strip_prefix("created_at<") {
+                (&mut conditions.created_before, before)
+            } else {
+                return Err(invalid(format!("unknown condition '{}'", condition)));
+            };
+            let ts = value.parse().map_err(|_| invalid(format!("bad timestamp '{}'", value)))?;
+            if slot.replace(ts).is_some() {
+                return Err(invalid(format!("'{}' given twice", condition)));
+            }
+        }
+        if let (Some(after), Some(before)) = (conditions.created_after, conditions.created_before) {
+            if after >= before {
+                return Err(invalid("the time range is empty".into()));
+            }
+        }
+        Ok(conditions)
+    }
+}
+
+/// A delegation signed by the delegator
+#[derive(Debug, Clone)]
+pub struct Delegation {
+    pub delegator: PublicKey,
+    pub conditions: DelegationConditions,
+    pub signature: Signature,
+}
+
+impl Delegation {
+    /// Sign a delegation to `delegatee` with `keys`
+    pub fn create(keys: &Keys, delegatee: &PublicKey, conditions: DelegationConditions) -> Self {
+        let signature = keys.sign_schnorr(&token(delegatee, &conditions));
+        Self {
+            delegator: keys.public_key(),
+            conditions,
+            signature,
+        }
+    }
+
+    /// Check the signature covers `delegatee` and these conditions
+    pub fn verify(&self, delegatee: &PublicKey) -> Result<()> {
+        let delegator = XOnlyPublicKey::from_str(&self.delegator.to_hex())
+            .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(&self.signature, &token(delegatee, &self.conditions), &delegator)
+            .map_err(|_| SignerError::InvalidRequest("Invalid delegation signature".into()))
+    }
+
+    /// The `delegation` tag the delegatee adds to its events
+    pub fn tag(&self) -> Vec<String> {
+        vec![
+            "delegation".to_string(),
+            self.delegator.to_hex(),
+            self.conditions.to_string(),
+            self.signature.to_string(),
+        ]
+    }
+}
+
+/// Hash of the delegation token, which is what gets signed
+fn token(delegatee: &PublicKey, conditions: &DelegationConditions) -> Message {
+    let token = format!("nostr:delegation:{}:{}", delegatee.to_hex(), conditions);
+    Message::from_digest(sha256::Hash::hash(token.as_bytes()).to_byte_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions_round_trip() {
+        let conditions: DelegationConditions = "kind=1&created_at>1700000000&created_at<1702592000".parse().unwrap();
+        assert_eq!(conditions.kind, Some(1));
+        assert_eq!(conditions.created_after, Some(1_700_000_000));
+        assert_eq!(conditions.created_before, Some(1_702_592_000));
+        assert_eq!(conditions.to_string(), "kind=1&created_at>1700000000&created_at<1702592000");
+
+        assert!(conditions.allows(1, 1_701_000_000));
+        assert!(!conditions.allows(7, 1_701_000_000));
+        assert!(!conditions.allows(1, 1_702_592_000));
+        assert!(!conditions.allows(1, 1_600_000_000));
+
+        for bad in ["kind=1&kind=7", "kind=note", "created_at<soon", "created_at>2&created_at<1", "pubkey=abc"] {
+            assert!(bad.parse::<DelegationConditions>().is_err(), "{}", bad);
+        }
+        assert_eq!("".parse::<DelegationConditions>().unwrap(), DelegationConditions::default());
+    }
+
+    #[test]
+    fn test_delegation_tag_verifies() {
+        let delegator = Keys::generate();
+        let bot = Keys::generate().public_key();
+        let conditions = DelegationConditions {
+            kind: Some(1),
+            created_before: Some(1_702_592_000),
+            ..Default::default()
+        };
+        let delegation = Delegation::create(&delegator, &bot, conditions);
+        delegation.verify(&bot).unwrap();
+
+        let tag = delegation.tag();
+        assert_eq!(tag[0], "delegation");
+        assert_eq!(tag[1], delegator.public_key().to_hex());
+        assert_eq!(tag[2], "kind=1&created_at<1702592000");
+        assert_eq!(tag[3].len(), 128);
+
+        // Neither another delegatee nor widened conditions are covered
+        assert!(delegation.verify(&Keys::generate().public_key()).is_err());
+        let mut widened = delegation.clone();
+        widened.conditions.kind = None;
+        assert!(widened.verify(&bot).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Desktop notifications for incoming signing requests, refused apps and
+//! starting the bunker
+//!
+//! Request notices arrive from the approval queue (D-Bus callers) and the bunker
+//! (NIP-46 clients). At most one notification is shown per interval; requests
+//! held back in between are counted in the next one. Nothing is shown while
+//! the signer's own window has focus, since the request is already on screen.
+
+use crate::app::AppMessage;
+use crate::audit::DenialAlert;
+use crate::permissions::RequestType;
+use notify_rust::{Notification, Urgency};
+use std::time::{Duration, Instant};
+
+/// Shortest time between two request notifications
+pub const MIN_NOTIFY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A new request from an app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestNotice {
+    /// App name, or its ID if it has none
+    pub app: String,
+    pub request_type: RequestType,
+}
+
+/// Rate-limited request notifications
+pub struct RequestNotifier {
+    min_interval: Duration,
+    last_shown: Option<Instant>,
+    /// Requests not shown since the last notification
+    held_back: u32,
+}
+
+impl RequestNotifier {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_shown: None,
+            held_back: 0,
+        }
+    }
+
+    /// Notify about `notice` unless the window has focus or one was shown
+    /// too recently
+    pub fn notify(&mut self, notice: &RequestNotice, window_focused: bool, now: Instant) {
+        let Some(held_back) = self.admit(window_focused, now) else {
+            return;
+        };
+        let mut body = format!("{} wants to {}", notice.app, notice.request_type.display_name());
+        if held_back > 0 {
+            body.push_str(&format!(" (and {} more)", held_back));
+        }
+        let result = Notification::new()
+            .appname("Pleb Signer")
+            .summary("Pleb Signer: new request")
+            .body(&body)
+            .icon("security-high")
+            .urgency(Urgency::Normal)
+            .show();
+        if let Err(e) = result {
+            tracing::warn!("Could not show request notification: {}", e);
+        }
+    }
+
+    /// Whether to show a notification now, and how many requests were
+    /// held back before it
+    fn admit(&mut self, window_focused: bool, now: Instant) -> Option<u32> {
+        if window_focused {
+            return None;
+        }
+        let too_soon = self.last_shown.is_some_and(|last| now.duration_since(last) < self.min_interval);
+        if too_soon {
+            self.held_back += 1;
+            return None;
+        }
+        self.last_shown = Some(now);
+        Some(std::mem::take(&mut self.held_back))
+    }
+}
+
+/// Suggest the user review an app that keeps getting refused
+pub fn show_denial_alert(alert: &DenialAlert) {
+    let body = format!(
+        "{} was refused {} times in the last {} minutes. Review or revoke it under Apps.",
+        alert.app_id, alert.denials, alert.window_mins
+    );
+    let result = Notification::new()
+        .appname("Pleb Signer")
+        .summary("Pleb Signer: app refused repeatedly")
+        .body(&body)
+        .icon("security-high")
+        .urgency(Urgency::Critical)
+        .show();
+    if let Err(e) = result {
+        tracing::warn!("Could not show denial alert: {}", e);
+    }
+}
+
+/// Ask before the bunker auto-starts; clicking "Start bunker" starts it,
+/// dismissing the notification leaves the bunker off
+pub fn confirm_bunker_start(sender: async_channel::Sender<AppMessage>) {
+    std::thread::spawn(move || {
+        let shown = Notification::new()
+            .appname("Pleb Signer")
+            .summary("Pleb Signer")
+            .body("Start the bunker and accept remote signing requests?")
+            .icon("security-high")
+            .action("start", "Start bunker")
+            .show();
+        match shown {
+            Ok(handle) => handle.wait_for_action(|action| {
+                if action == "start" {
+                    let _ = sender.send_blocking(AppMessage::StartBunker);
+                } else {
+                    tracing::info!("Bunker auto-start declined");
+                }
+            }),
+            Err(e) => tracing::warn!("Could not ask to start the bunker: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_is_rate_limited() {
+        let mut notifier = RequestNotifier::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert_eq!(notifier.admit(false, start), Some(0));
+        for i in 1..=10 {
+            assert_eq!(notifier.admit(false, start + Duration::from_millis(i * 100)), None);
+        }
+        // The next one mentions the requests held back
+        assert_eq!(notifier.admit(false, start + Duration::from_secs(5)), Some(10));
+        assert_eq!(notifier.admit(false, start + Duration::from_secs(6)), None);
+    }
+
+    #[test]
+    fn test_focused_window_suppresses() {
+        let mut notifier = RequestNotifier::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert_eq!(notifier.admit(true, start), None);
+        // Suppressed requests don't use up the interval or count as held back
+        assert_eq!(notifier.admit(false, start + Duration::from_secs(1)), Some(0));
+    }
+}
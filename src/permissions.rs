@@ -1,9 +1,39 @@
 //! Permission management for Pleb Signer
 
-use crate::config::AppPermissions;
+use crate::config::{AppPermissions, AuthorizedApp, FutureTimestampPolicy, SecurityConfig, UnknownKindPolicy};
+use crate::error::{Result as SignerResult, SignerError};
+use crate::keys::MNEMONIC_WORD_COUNTS;
 use serde::{Deserialize, Serialize};
+use nostr::nips::nip06::FromMnemonic;
+use nostr::Keys;
 use std::collections::HashMap;
 
+/// Shown when an app asks for NIP-04 encryption or decryption
+pub const NIP04_WARNING: &str = "This app uses NIP-04, which is deprecated: it leaks who talks to whom and when, and its encryption is weak. Apps should move to NIP-44.";
+
+/// Event kinds defined by NIPs that the signer recognizes
+const KNOWN_KINDS: &[u16] = &[
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 20, 21, 22,
+    40, 41, 42, 43, 44, 62, 64, 818, 1018, 1021, 1022, 1040, 1059, 1063, 1068,
+    1111, 1222, 1244, 1311, 1617, 1621, 1622, 1630, 1631, 1632, 1633, 1971,
+    1984, 1985, 2003, 2004, 2022, 4550, 7000, 7374, 7375, 7376, 9041, 9321,
+    9467, 9734, 9735, 9802, 10000, 10001, 10002, 10003, 10004, 10005, 10006,
+    10007, 10009, 10015, 10019, 10020, 10030, 10050, 10063, 10096, 13194,
+    17375, 21000, 22242, 23194, 23195, 24133, 24242, 27235, 30000, 30001,
+    30002, 30003, 30004, 30005, 30007, 30008, 30009, 30015, 30017, 30018,
+    30019, 30020, 30023, 30024, 30030, 30063, 30078, 30311, 30315, 30388,
+    30402, 30403, 30617, 30618, 30818, 30819, 31234, 31388, 31890, 31922,
+    31923, 31924, 31925, 31989, 31990, 34235, 34236, 34550, 38383,
+];
+
+/// Returns true if the kind is defined by a NIP
+pub fn is_known_kind(kind: u16) -> bool {
+    KNOWN_KINDS.contains(&kind)
+        || (5000..=6999).contains(&kind) // Data vending machine jobs
+        || (9000..=9030).contains(&kind) // Group control events
+        || (39000..=39009).contains(&kind) // Group metadata
+}
+
 /// Types of requests that can be made to the signer
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -15,6 +45,7 @@ pub enum RequestType {
     Nip44Encrypt,
     Nip44Decrypt,
     DecryptZapEvent,
+    CreateDelegation,
 }
 
 impl RequestType {
@@ -27,6 +58,7 @@ impl RequestType {
             RequestType::Nip44Encrypt => "nip44_encrypt",
             RequestType::Nip44Decrypt => "nip44_decrypt",
             RequestType::DecryptZapEvent => "decrypt_zap_event",
+            RequestType::CreateDelegation => "create_delegation",
         }
     }
 
@@ -39,6 +71,7 @@ impl RequestType {
             RequestType::Nip44Encrypt => "NIP-44 Encrypt",
             RequestType::Nip44Decrypt => "NIP-44 Decrypt",
             RequestType::DecryptZapEvent => "Decrypt Zap Event",
+            RequestType::CreateDelegation => "Create Delegation",
         }
     }
 
@@ -51,6 +84,7 @@ impl RequestType {
             RequestType::Nip44Encrypt => "Encrypt a message using NIP-44",
             RequestType::Nip44Decrypt => "Decrypt a message using NIP-44",
             RequestType::DecryptZapEvent => "Decrypt a zap event",
+            RequestType::CreateDelegation => "Let another key publish events as you (NIP-26)",
         }
     }
 
@@ -60,6 +94,28 @@ impl RequestType {
             _ => true,
         }
     }
+
+    /// Reads only reveal data to the caller; writes produce something signed
+    /// or encrypted with the user's key that can be published
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            RequestType::GetPublicKey
+            | RequestType::Nip04Decrypt
+            | RequestType::Nip44Decrypt
+            | RequestType::DecryptZapEvent => true,
+            RequestType::SignEvent
+            | RequestType::Nip04Encrypt
+            | RequestType::Nip44Encrypt
+            | RequestType::CreateDelegation => false,
+        }
+    }
+
+    pub fn is_decrypt(&self) -> bool {
+        matches!(
+            self,
+            RequestType::Nip04Decrypt | RequestType::Nip44Decrypt | RequestType::DecryptZapEvent
+        )
+    }
 }
 
 impl std::str::FromStr for RequestType {
@@ -74,11 +130,23 @@ impl std::str::FromStr for RequestType {
             "nip44_encrypt" => Ok(RequestType::Nip44Encrypt),
             "nip44_decrypt" => Ok(RequestType::Nip44Decrypt),
             "decrypt_zap_event" => Ok(RequestType::DecryptZapEvent),
+            "create_delegation" => Ok(RequestType::CreateDelegation),
             _ => Err(format!("Unknown request type: {}", s)),
         }
     }
 }
 
+/// How a request should be handled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    /// Carry out the request immediately
+    Allow,
+    /// Ask the user first
+    Prompt,
+    /// Refuse, with the reason
+    Deny(String),
+}
+
 /// Permission checker for applications
 pub struct PermissionChecker;
 
@@ -109,10 +177,176 @@ impl PermissionChecker {
             RequestType::Nip44Encrypt => permissions.nip44_encrypt,
             RequestType::Nip44Decrypt => permissions.nip44_decrypt,
             RequestType::DecryptZapEvent => permissions.decrypt_zap_event,
+            RequestType::CreateDelegation => permissions.create_delegation,
+        }
+    }
+}
+
+impl PermissionChecker {
+    /// Decide whether a request may proceed, needs the user, or is refused
+    pub fn approval_for(
+        security: &SecurityConfig,
+        app: Option<&AuthorizedApp>,
+        request_type: RequestType,
+        event_kind: Option<u16>,
+    ) -> Approval {
+        // Apps may only do what they were authorized for
+        match app {
+            Some(app) if !Self::check_permission(&app.permissions, request_type, event_kind) => {
+                return Approval::Deny(format!("{} not permitted for {}", request_type.display_name(), app.name));
+            }
+            None if !security.authorize_unknown_apps => {
+                return Approval::Deny("Application is not authorized".into());
+            }
+            _ => {}
+        }
+
+        // A delegation hands out signing rights, so the user always decides
+        if request_type == RequestType::CreateDelegation {
+            return Approval::Prompt;
+        }
+
+        // Coarse mode: permitted reads go through, writes always need the user
+        if security.auto_approve_read_only {
+            if !request_type.is_read_only() {
+                return Approval::Prompt;
+            }
+            if app.is_some() {
+                return Approval::Allow;
+            }
+        }
+
+        // Signing asks the user unless auto-approval is on, both globally and
+        // for this app
+        if request_type == RequestType::SignEvent && security.always_confirm {
+            let auto_approved = security.allow_auto_approve && app.is_some_and(|app| app.auto_approve);
+            if !auto_approved {
+                return Approval::Prompt;
+            }
+        }
+
+        // Decrypting reveals message content, so it can be gated like signing
+        if request_type.is_decrypt() && security.always_confirm && security.confirm_decrypt {
+            let Some(app) = app else {
+                return Approval::Deny("Application is not authorized to decrypt".into());
+            };
+            return if app.auto_approve_decrypt {
+                Approval::Allow
+            } else {
+                Approval::Prompt
+            };
+        }
+
+        Approval::Allow
+    }
+
+    /// Apply the unknown-kind policy to an event kind
+    ///
+    /// Returns a caution to show in the approval dialog for `Warn`, and
+    /// `PermissionDenied` for `Block`.
+    pub fn check_event_kind(policy: UnknownKindPolicy, kind: u16) -> SignerResult<Option<String>> {
+        if is_known_kind(kind) {
+            return Ok(None);
+        }
+
+        match policy {
+            UnknownKindPolicy::Allow => Ok(None),
+            UnknownKindPolicy::Warn => Ok(Some(format!(
+                "Event kind {} is not a known Nostr kind", kind
+            ))),
+            UnknownKindPolicy::Block => Err(SignerError::PermissionDenied(format!(
+                "Signing unknown event kind {} is blocked", kind
+            ))),
+        }
+    }
+
+    /// Cautions for event content that may leak a secret: anything that looks
+    /// like an nsec or a recovery phrase (unless `scan_content_for_secrets`
+    /// is off) and the configured `sensitive_keywords`. Any caution forces a
+    /// confirmation prompt.
+    pub fn content_warnings(security: &SecurityConfig, content: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if security.scan_content_for_secrets {
+            if contains_nsec(content) {
+                warnings.push("The content contains what looks like a private key (nsec). Publishing it gives away the key.".into());
+            }
+            if contains_mnemonic(content) {
+                warnings.push("The content contains what looks like a recovery phrase. Publishing it gives away the key.".into());
+            }
+        }
+        let lowercase = content.to_lowercase();
+        for keyword in &security.sensitive_keywords {
+            if !keyword.trim().is_empty() && lowercase.contains(&keyword.trim().to_lowercase()) {
+                warnings.push(format!("The content contains the sensitive keyword \"{}\"", keyword.trim()));
+            }
+        }
+        warnings
+    }
+
+    /// Advisory for NIP-04 requests, unless the user suppressed it for the app
+    pub fn nip04_warning(security: &SecurityConfig, request_type: RequestType, app_id: &str) -> Option<&'static str> {
+        let nip04 = matches!(request_type, RequestType::Nip04Encrypt | RequestType::Nip04Decrypt);
+        (nip04 && !security.nip04_warning_suppressed.iter().any(|id| id == app_id)).then_some(NIP04_WARNING)
+    }
+
+    /// Check a requested created_at against the future-dating cap
+    ///
+    /// Returns the timestamp to sign with: unchanged when within the cap,
+    /// moved back to `now + max_future_secs` under `Clamp`, and
+    /// `InvalidRequest` under `Reject`.
+    pub fn check_created_at(
+        created_at: Option<u64>,
+        max_future_secs: u64,
+        policy: FutureTimestampPolicy,
+        now: u64,
+    ) -> SignerResult<Option<u64>> {
+        let limit = now.saturating_add(max_future_secs);
+        match created_at {
+            Some(ts) if max_future_secs > 0 && ts > limit => match policy {
+                FutureTimestampPolicy::Reject => Err(SignerError::InvalidRequest(format!(
+                    "created_at is {}s in the future (limit {}s); relays would reject this event",
+                    ts - now, max_future_secs
+                ))),
+                FutureTimestampPolicy::Clamp => Ok(Some(limit)),
+            },
+            other => Ok(other),
         }
     }
 }
 
+/// Whether `content` has a token shaped like a bech32 nsec
+fn contains_nsec(content: &str) -> bool {
+    const BECH32_CHARS: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    content
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(str::to_ascii_lowercase)
+        .any(|token| {
+            token.len() >= 50
+                && token.starts_with("nsec1")
+                && token[5..].chars().all(|c| BECH32_CHARS.contains(c))
+        })
+}
+
+/// Whether `content` has 12 to 24 consecutive words forming a valid BIP-39
+/// phrase (numbering and punctuation between the words are ignored)
+fn contains_mnemonic(content: &str) -> bool {
+    let words: Vec<String> = content
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    // BIP-39 English words are 3 to 8 letters long
+    let candidate = |word: &String| (3..=8).contains(&word.len());
+    (0..words.len()).any(|start| {
+        MNEMONIC_WORD_COUNTS.iter().any(|&len| {
+            words
+                .get(start..start + len)
+                .filter(|window| window.iter().all(candidate))
+                .is_some_and(|window| Keys::from_mnemonic(window.join(" "), None).is_ok())
+        })
+    })
+}
+
 /// Rate limiter for auto-approved requests
 pub struct RateLimiter {
     /// Map of app_id to (request_type -> timestamps of recent requests)
@@ -129,6 +363,11 @@ impl RateLimiter {
         }
     }
 
+    /// Change the limit; requests already recorded still count
+    pub fn set_max_per_minute(&mut self, max_per_minute: u32) {
+        self.max_per_minute = max_per_minute;
+    }
+
     /// Check if a request is allowed and record it
     pub fn check_and_record(&mut self, app_id: &str, request_type: RequestType) -> bool {
         let now = std::time::Instant::now();
@@ -170,6 +409,7 @@ impl RateLimiter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nostr::ToBech32;
 
     #[test]
     fn test_permission_check() {
@@ -202,6 +442,218 @@ mod tests {
         ));
     }
 
+    fn authorized_app(permissions: AppPermissions, auto_approve_decrypt: bool) -> AuthorizedApp {
+        AuthorizedApp {
+            app_id: "messenger".into(),
+            name: "Messenger".into(),
+            authorized_at: chrono::Utc::now(),
+            permissions,
+            auto_approve: false,
+            auto_approve_decrypt,
+            expiration: None,
+        }
+    }
+
+    #[test]
+    fn test_decrypt_confirmation() {
+        let mut security = SecurityConfig {
+            confirm_decrypt: true,
+            ..Default::default()
+        };
+
+        // Unauthorized apps are denied
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, None, RequestType::Nip44Decrypt, None),
+            Approval::Deny(_)
+        ));
+
+        // Authorized apps without the permission are denied
+        let no_decrypt = authorized_app(AppPermissions::default(), false);
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, Some(&no_decrypt), RequestType::Nip44Decrypt, None),
+            Approval::Deny(_)
+        ));
+
+        // Permitted apps are prompted unless trusted for decrypts
+        let permissions = AppPermissions {
+            nip44_decrypt: true,
+            ..Default::default()
+        };
+        let messenger = authorized_app(permissions.clone(), false);
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&messenger), RequestType::Nip44Decrypt, None),
+            Approval::Prompt
+        );
+        let trusted = authorized_app(permissions, true);
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&trusted), RequestType::Nip44Decrypt, None),
+            Approval::Allow
+        );
+
+        // Without confirm_decrypt the request goes straight through
+        security.confirm_decrypt = false;
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&messenger), RequestType::Nip44Decrypt, None),
+            Approval::Allow
+        );
+    }
+
+    #[test]
+    fn test_app_permissions_enforced() {
+        let mut security = SecurityConfig {
+            always_confirm: false,
+            ..Default::default()
+        };
+        let app = authorized_app(
+            AppPermissions {
+                sign_event: Some(vec![1]),
+                nip44_encrypt: true,
+                ..Default::default()
+            },
+            false,
+        );
+
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Allow
+        );
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip44Encrypt, None),
+            Approval::Allow
+        );
+        // Kinds and operations the app wasn't granted are denied
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(4)),
+            Approval::Deny(_)
+        ));
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip04Encrypt, None),
+            Approval::Deny(_)
+        ));
+
+        // Unknown apps are denied unless explicitly allowed
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, None, RequestType::SignEvent, Some(1)),
+            Approval::Deny(_)
+        ));
+        security.authorize_unknown_apps = true;
+        assert_eq!(
+            PermissionChecker::approval_for(&security, None, RequestType::SignEvent, Some(1)),
+            Approval::Allow
+        );
+    }
+
+    #[test]
+    fn test_always_confirm_prompts_for_signing() {
+        let mut security = SecurityConfig::default();
+        let mut app = authorized_app(
+            AppPermissions {
+                sign_event: Some(vec![1]),
+                nip44_encrypt: true,
+                ..Default::default()
+            },
+            false,
+        );
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Prompt
+        );
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip44Encrypt, None),
+            Approval::Allow
+        );
+
+        // Auto-approval needs both the global switch and the app's flag
+        app.auto_approve = true;
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Prompt
+        );
+        security.allow_auto_approve = true;
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Allow
+        );
+        app.auto_approve = false;
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Prompt
+        );
+    }
+
+    #[test]
+    fn test_delegation_always_asks() {
+        let security = SecurityConfig {
+            auto_approve_read_only: true,
+            ..Default::default()
+        };
+        let bot_manager = authorized_app(
+            AppPermissions {
+                create_delegation: true,
+                ..Default::default()
+            },
+            true,
+        );
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&bot_manager), RequestType::CreateDelegation, None),
+            Approval::Prompt
+        );
+        // Not granted by default
+        let app = authorized_app(AppPermissions::default(), true);
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::CreateDelegation, None),
+            Approval::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_auto_approve_read_only() {
+        let security = SecurityConfig {
+            auto_approve_read_only: true,
+            confirm_decrypt: true,
+            ..Default::default()
+        };
+        let permissions = AppPermissions {
+            nip44_decrypt: true,
+            nip44_encrypt: true,
+            ..Default::default()
+        };
+        let app = authorized_app(permissions, false);
+
+        // Decrypt goes through even though confirm_decrypt would prompt
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip44Decrypt, None),
+            Approval::Allow
+        );
+        // Writes always prompt
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::SignEvent, Some(1)),
+            Approval::Prompt
+        );
+        assert_eq!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip44Encrypt, None),
+            Approval::Prompt
+        );
+        // Reads the app isn't permitted fall back to the usual checks
+        assert!(matches!(
+            PermissionChecker::approval_for(&security, Some(&app), RequestType::Nip04Decrypt, None),
+            Approval::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_unknown_kind_policy() {
+        let made_up_kind = 54321;
+
+        assert_eq!(PermissionChecker::check_event_kind(UnknownKindPolicy::Allow, made_up_kind).unwrap(), None);
+        assert!(PermissionChecker::check_event_kind(UnknownKindPolicy::Warn, made_up_kind).unwrap().is_some());
+        assert!(PermissionChecker::check_event_kind(UnknownKindPolicy::Block, made_up_kind).is_err());
+
+        // Known kinds pass under every policy
+        assert_eq!(PermissionChecker::check_event_kind(UnknownKindPolicy::Block, 1).unwrap(), None);
+        assert_eq!(PermissionChecker::check_event_kind(UnknownKindPolicy::Warn, 5100).unwrap(), None);
+    }
+
     #[test]
     fn test_rate_limiter() {
         let mut limiter = RateLimiter::new(3);
@@ -214,4 +666,68 @@ mod tests {
         // Different app should work
         assert!(limiter.check_and_record("app2", RequestType::SignEvent));
     }
+
+    #[test]
+    fn test_future_created_at_cap() {
+        let now = 1_700_000_000;
+        let cap = 900;
+        let reject = FutureTimestampPolicy::Reject;
+        let clamp = FutureTimestampPolicy::Clamp;
+
+        // At the boundary, in the past, or unset: unchanged
+        assert_eq!(PermissionChecker::check_created_at(Some(now + cap), cap, reject, now).unwrap(), Some(now + cap));
+        assert_eq!(PermissionChecker::check_created_at(Some(now - 60), cap, reject, now).unwrap(), Some(now - 60));
+        assert_eq!(PermissionChecker::check_created_at(None, cap, reject, now).unwrap(), None);
+
+        // Beyond the boundary
+        assert!(matches!(
+            PermissionChecker::check_created_at(Some(now + cap + 1), cap, reject, now),
+            Err(SignerError::InvalidRequest(_))
+        ));
+        assert_eq!(PermissionChecker::check_created_at(Some(now + 86_400), cap, clamp, now).unwrap(), Some(now + cap));
+
+        // A zero cap disables the check
+        assert_eq!(PermissionChecker::check_created_at(Some(now + 86_400), 0, reject, now).unwrap(), Some(now + 86_400));
+    }
+
+    #[test]
+    fn test_nip04_warning_per_app() {
+        let mut security = SecurityConfig::default();
+        assert_eq!(PermissionChecker::nip04_warning(&security, RequestType::Nip04Decrypt, "gossip"), Some(NIP04_WARNING));
+        assert_eq!(PermissionChecker::nip04_warning(&security, RequestType::Nip44Decrypt, "gossip"), None);
+
+        security.nip04_warning_suppressed.push("gossip".into());
+        assert_eq!(PermissionChecker::nip04_warning(&security, RequestType::Nip04Encrypt, "gossip"), None);
+        assert!(PermissionChecker::nip04_warning(&security, RequestType::Nip04Encrypt, "amethyst").is_some());
+    }
+
+    #[test]
+    fn test_content_warnings_flag_secrets() {
+        let security = SecurityConfig::default();
+        let nsec = Keys::generate().secret_key().to_bech32().unwrap();
+        let warnings = PermissionChecker::content_warnings(&security, &format!("my new key: {}!", nsec));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("nsec"));
+        assert!(!PermissionChecker::content_warnings(&security, &format!("check out {}", nsec.replacen("nsec", "npub", 1))).iter().any(|w| w.contains("nsec")));
+
+        // A numbered recovery phrase (BIP-39 test vector)
+        let phrase = "1. abandon 2. abandon 3. abandon 4. abandon 5. abandon 6. abandon \
+                      7. abandon 8. abandon 9. abandon 10. abandon 11. abandon 12. about";
+        let warnings = PermissionChecker::content_warnings(&security, &format!("backup: {}", phrase));
+        assert!(warnings.iter().any(|w| w.contains("recovery phrase")), "{:?}", warnings);
+
+        // Ordinary notes pass
+        let note = "gm nostr, the weather is lovely today and the coffee is even better than yesterday";
+        assert!(PermissionChecker::content_warnings(&security, note).is_empty());
+
+        // Configured keywords, case-insensitive; the scan can be turned off
+        let security = SecurityConfig {
+            scan_content_for_secrets: false,
+            sensitive_keywords: vec!["Project Falcon".into(), "  ".into()],
+            ..Default::default()
+        };
+        assert!(PermissionChecker::content_warnings(&security, &nsec).is_empty());
+        let warnings = PermissionChecker::content_warnings(&security, "launching project falcon tomorrow");
+        assert_eq!(warnings, vec!["The content contains the sensitive keyword \"Project Falcon\"".to_string()]);
+    }
 }
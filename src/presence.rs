@@ -0,0 +1,39 @@
+//! Presence checks on approval
+//!
+//! After the user approves a request, a presence verifier can demand proof
+//! that a person is at the machine (a fingerprint, a security key touch)
+//! before the request goes through. This is off by default.
+
+use crate::config::PresenceCheck;
+use crate::error::{Result, SignerError};
+use crate::signing::SigningRequest;
+use std::process::Command;
+use std::sync::Arc;
+
+/// Confirms the user is physically present
+pub trait PresenceVerifier: Send + Sync {
+    /// Prompt for presence for `request`; Ok(false) means the check failed
+    /// or was cancelled
+    fn verify(&self, request: &SigningRequest) -> Result<bool>;
+}
+
+/// Fingerprint prompt through fprintd
+pub struct FingerprintVerifier;
+
+impl PresenceVerifier for FingerprintVerifier {
+    fn verify(&self, request: &SigningRequest) -> Result<bool> {
+        tracing::info!("Asking for a fingerprint to approve {}", request.request_type.as_str());
+        let status = Command::new("fprintd-verify")
+            .status()
+            .map_err(|e| SignerError::PresenceCheckFailed(format!("could not run fprintd-verify: {}", e)))?;
+        Ok(status.success())
+    }
+}
+
+/// Verifier for the configured check (None = no check)
+pub fn verifier_for(check: PresenceCheck) -> Option<Arc<dyn PresenceVerifier>> {
+    match check {
+        PresenceCheck::Off => None,
+        PresenceCheck::Fingerprint => Some(Arc::new(FingerprintVerifier)),
+    }
+}
@@ -0,0 +1,222 @@
+//! NIP-65 relay list lookup
+//!
+//! A key's relay list is fetched from several relays at once. Each relay is
+//! reported separately, so a caller can see which ones answered and why the
+//! others failed instead of getting a best-effort merge.
+
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long to wait for each relay
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Trim a relay URL and drop its trailing slash; None unless it is ws:// or wss://
+pub fn normalize_relay_url(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let host = url.strip_prefix("wss://").or_else(|| url.strip_prefix("ws://"))?;
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return None;
+    }
+    RelayUrl::parse(url).ok()?;
+    Some(url.to_string())
+}
+
+/// Outcome of querying one source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SourceOutcome<T> {
+    Ok { value: T },
+    Failed { reason: String },
+}
+
+/// One source (e.g. a relay) and what it returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceResult<T> {
+    pub source: String,
+    #[serde(flatten)]
+    pub outcome: SourceOutcome<T>,
+}
+
+/// Per-source results of an aggregated lookup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceReport<T> {
+    pub sources: Vec<SourceResult<T>>,
+}
+
+impl<T> Default for SourceReport<T> {
+    fn default() -> Self {
+        Self { sources: Vec::new() }
+    }
+}
+
+impl<T> SourceReport<T> {
+    /// Record the result of one source
+    pub fn record<E: ToString>(&mut self, source: impl Into<String>, result: std::result::Result<T, E>) {
+        let outcome = match result {
+            Ok(value) => SourceOutcome::Ok { value },
+            Err(e) => SourceOutcome::Failed { reason: e.to_string() },
+        };
+        self.sources.push(SourceResult { source: source.into(), outcome });
+    }
+
+    /// Values from the sources that answered
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.sources.iter().filter_map(|s| match s.outcome {
+            SourceOutcome::Ok { ref value } => Some((s.source.as_str(), value)),
+            SourceOutcome::Failed { .. } => None,
+        })
+    }
+
+    /// Sources that failed, with the reason
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.sources.iter().filter_map(|s| match s.outcome {
+            SourceOutcome::Failed { ref reason } => Some((s.source.as_str(), reason.as_str())),
+            SourceOutcome::Ok { .. } => None,
+        })
+    }
+
+    /// Some sources answered and some didn't
+    pub fn is_partial(&self) -> bool {
+        self.succeeded().next().is_some() && self.failed().next().is_some()
+    }
+}
+
+/// A relay from a NIP-65 list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayListEntry {
+    pub url: String,
+    pub read: bool,
+    pub write: bool,
+}
+
+/// A NIP-65 relay list event as seen by one relay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayList {
+    pub created_at: u64,
+    pub relays: Vec<RelayListEntry>,
+}
+
+impl RelayList {
+    fn from_event(event: &Event) -> Self {
+        let relays = nip65::extract_relay_list(event)
+            .map(|(url, metadata)| RelayListEntry {
+                url: url.to_string(),
+                read: !matches!(metadata, Some(RelayMetadata::Write)),
+                write: !matches!(metadata, Some(RelayMetadata::Read)),
+            })
+            .collect();
+        Self {
+            created_at: event.created_at.as_secs(),
+            relays,
+        }
+    }
+}
+
+/// Relay lookup result: the newest list found, and what each relay said
+/// (`None` = the relay answered but has no list for the key)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelaysResponse {
+    pub relays: Vec<RelayListEntry>,
+    #[serde(flatten)]
+    pub report: SourceReport<Option<RelayList>>,
+}
+
+impl From<SourceReport<Option<RelayList>>> for RelaysResponse {
+    fn from(report: SourceReport<Option<RelayList>>) -> Self {
+        let relays = report
+            .succeeded()
+            .filter_map(|(_, list)| list.as_ref())
+            .max_by_key(|list| list.created_at)
+            .map(|list| list.relays.clone())
+            .unwrap_or_default();
+        Self { relays, report }
+    }
+}
+
+/// Fetch `pubkey`'s NIP-65 relay list from each of `relays`
+pub async fn fetch_relay_list(pubkey: PublicKey, relays: &[String]) -> RelaysResponse {
+    let client = Client::default();
+    let filter = Filter::new().author(pubkey).kind(Kind::RelayList).limit(1);
+    let mut report = SourceReport::default();
+
+    for relay in relays {
+        let result = async {
+            client.add_relay(relay).await?;
+            client.try_connect_relay(relay, FETCH_TIMEOUT).await?;
+            let events = client.fetch_events_from([relay], filter.clone(), FETCH_TIMEOUT).await?;
+            Ok::<_, nostr_sdk::client::Error>(
+                events.into_iter().max_by_key(|e| e.created_at).map(|e| RelayList::from_event(&e)),
+            )
+        }
+        .await;
+        report.record(relay.as_str(), result);
+    }
+
+    client.disconnect().await;
+    report.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(created_at: u64, url: &str) -> Option<RelayList> {
+        Some(RelayList {
+            created_at,
+            relays: vec![RelayListEntry { url: url.into(), read: true, write: true }],
+        })
+    }
+
+    #[test]
+    fn test_mixed_success_and_failure() {
+        let mut report: SourceReport<Option<RelayList>> = SourceReport::default();
+        report.record("wss://old.example.com", Ok::<_, String>(list(100, "wss://a.example.com")));
+        report.record("wss://down.example.com", Err("connection refused"));
+        report.record("wss://new.example.com", Ok::<_, String>(list(200, "wss://b.example.com")));
+        report.record("wss://empty.example.com", Ok::<_, String>(None));
+
+        assert!(report.is_partial());
+        assert_eq!(report.succeeded().count(), 3);
+        assert_eq!(report.failed().collect::<Vec<_>>(), [("wss://down.example.com", "connection refused")]);
+
+        // The newest list wins, and every source is still reported
+        let response = RelaysResponse::from(report);
+        assert_eq!(response.relays[0].url, "wss://b.example.com");
+        assert_eq!(response.report.sources.len(), 4);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["sources"][1]["status"], "failed");
+        assert_eq!(json["sources"][1]["reason"], "connection refused");
+        assert_eq!(json["sources"][3]["status"], "ok");
+        assert!(json["sources"][3]["value"].is_null());
+    }
+
+    #[test]
+    fn test_all_sources_failed() {
+        let mut report: SourceReport<Option<RelayList>> = SourceReport::default();
+        report.record("wss://a.example.com", Err::<Option<RelayList>, _>("timeout"));
+        report.record("wss://b.example.com", Err::<Option<RelayList>, _>("auth-required"));
+
+        assert!(!report.is_partial());
+        let response = RelaysResponse::from(report);
+        assert!(response.relays.is_empty());
+        assert_eq!(response.report.failed().count(), 2);
+    }
+
+    #[test]
+    fn test_relay_list_markers() {
+        let keys = Keys::generate();
+        let event = EventBuilder::relay_list([
+            (RelayUrl::parse("wss://both.example.com").unwrap(), None),
+            (RelayUrl::parse("wss://read.example.com").unwrap(), Some(RelayMetadata::Read)),
+            (RelayUrl::parse("wss://write.example.com").unwrap(), Some(RelayMetadata::Write)),
+        ])
+        .sign_with_keys(&keys)
+        .unwrap();
+
+        let list = RelayList::from_event(&event);
+        let flags: Vec<_> = list.relays.iter().map(|r| (r.read, r.write)).collect();
+        assert_eq!(flags, [(true, true), (true, false), (false, true)]);
+    }
+}
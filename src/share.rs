@@ -0,0 +1,155 @@
+//! Short-lived web page showing the bunker URI as a QR code
+//!
+//! Built with the `share-server` feature. The page is served on loopback
+//! (or, if the user opts in, on every interface so a phone on the same
+//! network can open it) under a random path, and the server shuts down by
+//! itself once the page expires.
+
+use crate::error::{Result, SignerError};
+use nostr::prelude::SecretKey;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// A running share server
+pub struct SharedPage {
+    /// Address of the page, including its random path
+    pub url: String,
+    /// Finishes when the page has expired and the server has stopped
+    pub handle: JoinHandle<()>,
+}
+
+/// Serve `uri` as a QR page for `lifetime`
+///
+/// With `on_lan`, the page is reachable by anyone on the local network who
+/// learns its address until it expires.
+pub async fn serve_bunker_uri(uri: &str, on_lan: bool, lifetime: Duration) -> Result<SharedPage> {
+    let page = render_page(uri)?;
+    let bind = if on_lan { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+    let listener = TcpListener::bind(SocketAddr::from((bind, 0))).await?;
+    let port = listener.local_addr()?.port();
+
+    let host = if on_lan {
+        warn!("Sharing the bunker URI on the local network for {}s", lifetime.as_secs());
+        lan_address().unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    };
+    let path = format!("/{}", hex::encode(&SecretKey::generate().to_secret_bytes()[..16]));
+    let url = format!("http://{}:{}{}", host, port, path);
+
+    let handle = tokio::spawn(async move {
+        let expired = tokio::time::sleep(lifetime);
+        tokio::pin!(expired);
+        loop {
+            tokio::select! {
+                _ = &mut expired => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        if let Err(e) = respond(stream, &path, &page).await {
+                            warn!("Share page request from {} failed: {}", peer, e);
+                        }
+                    }
+                    Err(e) => warn!("Share server accept failed: {}", e),
+                },
+            }
+        }
+        info!("Bunker URI share page expired");
+    });
+
+    Ok(SharedPage { url, handle })
+}
+
+/// Answer one HTTP request: the page on its path, 404 anywhere else
+async fn respond(mut stream: TcpStream, path: &str, page: &str) -> std::io::Result<()> {
+    let mut request = [0u8; 1024];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut request))
+        .await
+        .unwrap_or(Ok(0))?;
+    let request_line = String::from_utf8_lossy(&request[..read]);
+    let requested = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = if requested == path {
+        ("200 OK", page)
+    } else {
+        ("404 Not Found", "Not found")
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn render_page(uri: &str) -> Result<String> {
+    let qr = QrCode::new(uri.as_bytes())
+        .map_err(|e| SignerError::InvalidRequest(format!("Can't encode bunker URI as QR: {}", e)))?
+        .render::<svg::Color>()
+        .min_dimensions(280, 280)
+        .build();
+    Ok(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width\">\
+         <title>Pleb Signer bunker</title></head>\
+         <body style=\"font-family:sans-serif;text-align:center\">\
+         <h2>Scan with your remote client</h2>{}<p style=\"word-break:break-all\"><code>{}</code></p></body></html>",
+        qr,
+        escape_html(uri)
+    ))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// This machine's address on the local network (no packets are sent)
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(url: &str) -> std::io::Result<String> {
+        let rest = url.strip_prefix("http://").unwrap();
+        let (addr, path) = rest.split_at(rest.find('/').unwrap());
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.write_all(format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, addr).as_bytes()).await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_page_serves_uri_until_expiry() {
+        let uri = "bunker://abcdef?relay=wss://relay.example.com&secret=s3cret";
+        let shared = serve_bunker_uri(uri, false, Duration::from_millis(500)).await.unwrap();
+        assert!(shared.url.starts_with("http://127.0.0.1:"));
+
+        let page = get(&shared.url).await.unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("bunker://abcdef?relay=wss://relay.example.com&amp;secret=s3cret"));
+        assert!(page.contains("<svg"));
+
+        // Only the random path serves the page
+        let root = shared.url.rsplit_once('/').unwrap().0.to_string() + "/";
+        assert!(get(&root).await.unwrap().starts_with("HTTP/1.1 404"));
+
+        // After expiry the server stops and the port is closed
+        tokio::time::timeout(Duration::from_secs(5), shared.handle).await.unwrap().unwrap();
+        assert!(get(&shared.url).await.is_err());
+    }
+}
@@ -2,14 +2,89 @@
 //!
 //! Uses the NostrSigner trait from the nostr crate.
 
+use crate::airgap::AirgapChallenge;
+use crate::autolock::InactivityTimer;
+use crate::config::{EngineConfig, SecurityConfig};
 use crate::error::{Result, SignerError};
 use crate::keys::KeyManager;
-use crate::permissions::RequestType;
+use crate::nip26::{Delegation, DelegationConditions};
+use crate::permissions::{PermissionChecker, RequestType};
 use nostr::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 
+/// Event kinds no engine signs (`security.blocked_event_kinds`), shared so a
+/// config reload reaches running engines
+#[derive(Debug, Clone, Default)]
+pub struct BlockedKinds(Arc<std::sync::RwLock<Vec<u16>>>);
+
+impl BlockedKinds {
+    pub fn new(kinds: Vec<u16>) -> Self {
+        Self(Arc::new(std::sync::RwLock::new(kinds)))
+    }
+
+    /// Replace the list, e.g. after a config reload
+    pub fn set(&self, kinds: Vec<u16>) {
+        *self.0.write().unwrap() = kinds;
+    }
+
+    /// Refuse a blocked kind
+    pub fn check(&self, kind: u16) -> Result<()> {
+        if self.0.read().unwrap().contains(&kind) {
+            return Err(SignerError::PermissionDenied(format!(
+                "Signing kind {} events is blocked (security.blocked_event_kinds)",
+                kind
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Signing policy for events no one is asked about (the bunker has no
+/// approval prompt), shared so a config reload reaches it
+#[derive(Debug, Clone, Default)]
+pub struct SignPolicy(Arc<std::sync::RwLock<SecurityConfig>>);
+
+impl SignPolicy {
+    pub fn new(security: SecurityConfig) -> Self {
+        Self(Arc::new(std::sync::RwLock::new(security)))
+    }
+
+    /// Replace the settings, e.g. after a config reload
+    pub fn set(&self, security: SecurityConfig) {
+        *self.0.write().unwrap() = security;
+    }
+
+    /// Apply the unknown-kind policy, the created_at cap and the content
+    /// scan, returning the timestamp to sign with
+    ///
+    /// An unknown-kind caution has no dialog to go to, so it is only logged;
+    /// content that may leak a secret needs a confirmation no one can give,
+    /// so it is refused.
+    pub fn check_unattended(&self, kind: u16, content: &str, created_at: Option<u64>) -> Result<Option<u64>> {
+        let security = self.0.read().unwrap();
+        if let Some(caution) = PermissionChecker::check_event_kind(security.unknown_kind_policy, kind)? {
+            tracing::warn!("{} (remote request)", caution);
+        }
+        let cautions = PermissionChecker::content_warnings(&security, content);
+        if !cautions.is_empty() {
+            return Err(SignerError::PermissionDenied(format!(
+                "Remote request needs confirmation: {}", cautions.join("; ")
+            )));
+        }
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        PermissionChecker::check_created_at(
+            created_at,
+            security.max_future_created_at_secs,
+            security.future_created_at_policy,
+            now,
+        )
+    }
+}
+
 /// Data for an unsigned event (simplified for serialization)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnsignedEventData {
@@ -19,6 +94,55 @@ pub struct UnsignedEventData {
     pub tags: Vec<Vec<String>>,
     #[serde(default)]
     pub created_at: Option<u64>,
+    /// Relays to include as hints in the returned `nevent`
+    #[serde(default)]
+    pub relay_hints: Vec<String>,
+}
+
+impl UnsignedEventData {
+    /// NIP-40 expiration timestamp, if the event has one
+    pub fn expiration(&self) -> Option<u64> {
+        self.tags.iter()
+            .find(|tag| tag.first().map(String::as_str) == Some("expiration"))
+            .and_then(|tag| tag.get(1)?.parse().ok())
+    }
+
+    /// Append a NIP-40 expiration tag `after_secs` from `now`, unless the
+    /// event already has one. Returns true if the tag was added.
+    pub fn add_expiration(&mut self, after_secs: u64, now: u64) -> bool {
+        if self.tags.iter().any(|tag| tag.first().map(String::as_str) == Some("expiration")) {
+            return false;
+        }
+        self.tags.push(vec!["expiration".to_string(), now.saturating_add(after_secs).to_string()]);
+        true
+    }
+
+    /// Builder for this event: kind, content, parsed tags and created_at
+    /// (now if unset)
+    pub fn to_builder(&self) -> Result<EventBuilder> {
+        let created_at = self.created_at
+            .map(Timestamp::from)
+            .unwrap_or_else(Timestamp::now);
+        let mut builder = EventBuilder::new(Kind::from(self.kind), &self.content);
+        for tag_data in &self.tags {
+            if !tag_data.is_empty() {
+                let tag = Tag::parse(tag_data)
+                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
+                builder = builder.tag(tag);
+            }
+        }
+        Ok(builder.custom_created_at(created_at))
+    }
+}
+
+/// Give a batch `base + index` timestamps so relays order it as sent
+///
+/// The base is the first event's created_at, or `now` if it has none.
+pub fn assign_sequential_timestamps(events: &mut [UnsignedEventData], now: u64) {
+    let base = events.first().and_then(|e| e.created_at).unwrap_or(now);
+    for (index, event) in events.iter_mut().enumerate() {
+        event.created_at = Some(base + index as u64);
+    }
 }
 
 /// Payload for signing requests
@@ -41,6 +165,11 @@ pub enum SigningPayload {
     },
     /// Zap event to decrypt
     ZapEvent(String),
+    /// NIP-26 delegation to grant (conditions as the NIP-26 query string)
+    Delegation {
+        delegatee: String,
+        conditions: String,
+    },
 }
 
 /// A signing request
@@ -62,6 +191,35 @@ pub struct SigningRequest {
     pub payload: SigningPayload,
     /// When the request was made
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Cautions to show the user when approving
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Where the request came from; never deserialized, so only the signer
+    /// process itself can mark a request as internal
+    #[serde(skip)]
+    pub origin: RequestOrigin,
+}
+
+impl SigningRequest {
+    /// One line for prompts, e.g. "Gossip: Sign Event (kind 1)"
+    pub fn summary(&self) -> String {
+        let app = self.app_name.as_deref().unwrap_or(&self.app_id);
+        match self.payload {
+            SigningPayload::Event(ref event) => format!("{}: {} (kind {})", app, self.request_type.display_name(), event.kind),
+            SigningPayload::Delegation { ref conditions, .. } => format!("{}: {} ({})", app, self.request_type.display_name(), conditions),
+            _ => format!("{}: {}", app, self.request_type.display_name()),
+        }
+    }
+}
+
+/// Source of a signing request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequestOrigin {
+    /// Another application (D-Bus caller or NIP-46 client)
+    #[default]
+    External,
+    /// Pleb Signer's own UI; skips the external-app approval flow
+    Internal,
 }
 
 /// Result data from signing operations
@@ -70,12 +228,14 @@ pub struct SigningRequest {
 pub enum SigningResultData {
     /// Public key result
     PublicKey { npub: String, hex: String },
-    /// Signed event
-    Event { event_json: String, signature: String },
+    /// Signed event (id in hex), with its NIP-19 `nevent` and `note` encodings
+    Event { event_json: String, signature: String, event_id: String, nevent: String, note: String },
     /// Encrypted data
     Encrypted { ciphertext: String },
     /// Decrypted data
     Decrypted { plaintext: String },
+    /// NIP-26 `delegation` tag for the delegatee's events
+    Delegation { tag: Vec<String> },
 }
 
 /// Result of a signing operation
@@ -89,66 +249,257 @@ pub struct SigningResult {
     pub error: Option<String>,
 }
 
+/// Routes callers to signing engines, each with its own key set
+///
+/// Senders without a routing rule use the default engine, so a signer with
+/// no extra engines behaves exactly like a single-engine one.
+pub struct EngineRouter {
+    default: Arc<SigningEngine>,
+    engines: HashMap<String, Arc<SigningEngine>>,
+    /// Sender -> engine name
+    routes: HashMap<String, String>,
+}
+
+impl EngineRouter {
+    /// Router with only the default engine
+    pub fn new(default: Arc<SigningEngine>) -> Self {
+        Self {
+            default,
+            engines: HashMap::new(),
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Build the default engine plus one per configured engine, each with its own key set
+    ///
+    /// Every engine restarts the shared inactivity lock `activity` when used
+    /// and refuses the kinds in `blocked`.
+    pub async fn from_config(
+        engines: &[EngineConfig],
+        default_keys: Arc<Mutex<KeyManager>>,
+        activity: Arc<std::sync::Mutex<InactivityTimer>>,
+        blocked: BlockedKinds,
+    ) -> Self {
+        let default = SigningEngine::new(default_keys)
+            .with_activity(Arc::clone(&activity))
+            .with_blocked_kinds(blocked.clone());
+        let mut router = Self::new(Arc::new(default));
+        for engine in engines {
+            let mut km = KeyManager::with_namespace(&engine.name);
+            if let Err(e) = km.load().await {
+                tracing::warn!("Failed to load keys for engine {}: {}", engine.name, e);
+            }
+            let signing = SigningEngine::new(Arc::new(Mutex::new(km)))
+                .with_activity(Arc::clone(&activity))
+                .with_blocked_kinds(blocked.clone());
+            router.add_engine(&engine.name, Arc::new(signing), &engine.senders);
+            tracing::info!("Signing engine '{}' serves {} sender(s)", engine.name, engine.senders.len());
+        }
+        router
+    }
+
+    /// Add a named engine serving the given senders
+    pub fn add_engine(&mut self, name: &str, engine: Arc<SigningEngine>, senders: &[String]) {
+        for sender in senders {
+            self.routes.insert(sender.clone(), name.to_string());
+        }
+        self.engines.insert(name.to_string(), engine);
+    }
+
+    /// Engine for senders without a routing rule
+    pub fn default_engine(&self) -> &Arc<SigningEngine> {
+        &self.default
+    }
+
+    /// Drop the cached keys of every engine
+    pub async fn lock_all(&self) {
+        self.default.lock().await;
+        for engine in self.engines.values() {
+            engine.lock().await;
+        }
+    }
+
+    /// Load every engine's active key; only the default engine's failure is fatal
+    pub async fn unlock_all(&self) -> Result<()> {
+        self.default.unlock().await?;
+        for (name, engine) in &self.engines {
+            if let Err(e) = engine.unlock().await {
+                tracing::warn!("Failed to unlock engine {}: {}", name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-read every engine's key metadata from disk
+    pub async fn reload_all(&self) -> Result<()> {
+        self.default.reload_keys().await?;
+        for (name, engine) in &self.engines {
+            if let Err(e) = engine.reload_keys().await {
+                tracing::warn!("Failed to reload keys for engine {}: {}", name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Engine that handles requests from `sender`
+    pub fn route(&self, sender: &str) -> &Arc<SigningEngine> {
+        self.routes
+            .get(sender)
+            .and_then(|name| self.engines.get(name))
+            .unwrap_or(&self.default)
+    }
+}
+
 /// Signing engine that wraps key management with signing operations
 pub struct SigningEngine {
+    /// Never held while waiting on the app state (see the lock order in app.rs)
     key_manager: Arc<Mutex<KeyManager>>,
+    /// Inactivity lock timer to restart on use
+    activity: Option<Arc<std::sync::Mutex<InactivityTimer>>>,
+    /// Kinds refused before anything is signed
+    blocked_kinds: BlockedKinds,
 }
 
 impl SigningEngine {
     /// Create a new signing engine
     pub fn new(key_manager: Arc<Mutex<KeyManager>>) -> Self {
-        Self { key_manager }
+        Self { key_manager, activity: None, blocked_kinds: BlockedKinds::default() }
+    }
+
+    /// Restart `timer` (the inactivity lock) after every successful operation
+    pub fn with_activity(mut self, timer: Arc<std::sync::Mutex<InactivityTimer>>) -> Self {
+        self.activity = Some(timer);
+        self
+    }
+
+    /// Refuse to sign the event kinds in `blocked`
+    pub fn with_blocked_kinds(mut self, blocked: BlockedKinds) -> Self {
+        self.blocked_kinds = blocked;
+        self
+    }
+
+    fn record_activity(&self) {
+        if let Some(ref timer) = self.activity {
+            timer.lock().unwrap().touch(Instant::now());
+        }
     }
 
     /// Get the public key
-    pub async fn get_public_key(&self) -> Result<SigningResultData> {
+    pub async fn get_public_key(&self, key_id: Option<&str>) -> Result<SigningResultData> {
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
-        let pubkey = keys.public_key();
+        let pubkey = km.get_public_key_for(key_id).await?;
         
+        self.record_activity();
         Ok(SigningResultData::PublicKey {
             npub: pubkey.to_bech32().unwrap_or_default(),
             hex: pubkey.to_hex(),
         })
     }
 
+    /// Load the active key, checking that the keyring is reachable
+    ///
+    /// Succeeds without reloading when the key is already unlocked. An
+    /// active key with its own password stays locked until `unlock_key`.
+    pub async fn unlock(&self) -> Result<()> {
+        let mut km = self.key_manager.lock().await;
+        match km.get_signing_keys().await {
+            Ok(_) | Err(SignerError::KeyPasswordRequired(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Unlock a key protected with its own password
+    pub async fn unlock_key(&self, key_id: &str, password: &str) -> Result<()> {
+        self.key_manager.lock().await.unlock_key(key_id, password)
+    }
+
+    /// Names of the keys that can sign right now
+    pub async fn signing_key_names(&self) -> Result<Vec<String>> {
+        let mut km = self.key_manager.lock().await;
+        let all = km.get_all_signing_keys().await?;
+        Ok(all.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Pick up key changes saved by another process
+    pub async fn reload_keys(&self) -> Result<()> {
+        self.key_manager.lock().await.reload().await
+    }
+
+    /// Drop the cached key
+    pub async fn lock(&self) {
+        self.key_manager.lock().await.lock();
+    }
+
     /// Sign an unsigned event from data
-    pub async fn sign_event(&self, event_data: &UnsignedEventData) -> Result<SigningResultData> {
+    pub async fn sign_event(&self, event_data: &UnsignedEventData, key_id: Option<&str>) -> Result<SigningResultData> {
+        self.blocked_kinds.check(event_data.kind)?;
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
-        
-        // Build the event
-        let kind = Kind::from(event_data.kind);
-        let created_at = event_data.created_at
-            .map(Timestamp::from)
-            .unwrap_or_else(Timestamp::now);
+        let keys = km.get_keys_for(key_id).await?;
         
-        let mut builder = EventBuilder::new(kind, &event_data.content);
+        let relay_hints = event_data.relay_hints
+            .iter()
+            .map(|url| RelayUrl::parse(url)
+                .map_err(|e| SignerError::InvalidRequest(format!("Invalid relay hint {}: {}", url, e))))
+            .collect::<Result<Vec<_>>>()?;
         
-        // Add tags
-        for tag_data in &event_data.tags {
-            if !tag_data.is_empty() {
-                let tag = Tag::parse(tag_data)
-                    .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
-                builder = builder.tag(tag);
-            }
-        }
+        let event = event_data.to_builder()?
+            .sign_with_keys(&keys)
+            .map_err(|e| SignerError::NostrError(e.to_string()))?;
         
-        let event = builder
-            .custom_created_at(created_at)
-            .sign_with_keys(keys)
+        let nevent = Nip19Event::new(event.id)
+            .author(event.pubkey)
+            .kind(event.kind)
+            .relays(relay_hints)
+            .to_bech32()
+            .map_err(|e| SignerError::NostrError(e.to_string()))?;
+        let note = event.id.to_bech32()
             .map_err(|e| SignerError::NostrError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Event {
             event_json: event.as_json(),
             signature: event.sig.to_string(),
+            event_id: event.id.to_hex(),
+            nevent,
+            note,
         })
     }
 
+    /// Challenge for signing an event on an air-gapped device
+    ///
+    /// Only the public key is needed, so this works for watch-only keys
+    /// whose secret lives on the offline machine. The result is shown as a
+    /// QR code; the signature scanned back completes the event (see
+    /// [`AirgapChallenge::assemble`]).
+    pub async fn airgap_challenge(&self, event_data: &UnsignedEventData, key_id: Option<&str>) -> Result<AirgapChallenge> {
+        self.blocked_kinds.check(event_data.kind)?;
+        let pubkey = self.key_manager.lock().await.get_public_key_for(key_id).await?;
+        Ok(AirgapChallenge::new(event_data.to_builder()?.build(pubkey)))
+    }
+
+    /// NIP-26 delegation letting `delegatee_pubkey` (npub or hex) publish
+    /// events as this key within `conditions`
+    ///
+    /// A delegation limited to a blocked kind is refused; one without a
+    /// kind condition isn't, as we never sign the delegatee's events.
+    pub async fn create_delegation(&self, delegatee_pubkey: &str, conditions: &DelegationConditions, key_id: Option<&str>) -> Result<SigningResultData> {
+        if let Some(kind) = conditions.kind {
+            self.blocked_kinds.check(kind)?;
+        }
+        let delegatee = PublicKey::parse(delegatee_pubkey)
+            .map_err(|e| SignerError::InvalidRequest(format!("Invalid delegatee public key: {}", e)))?;
+        let mut km = self.key_manager.lock().await;
+        let keys = km.get_keys_for(key_id).await?;
+
+        let delegation = Delegation::create(&keys, &delegatee, conditions.clone());
+        self.record_activity();
+        Ok(SigningResultData::Delegation { tag: delegation.tag() })
+    }
+
     /// NIP-04 encrypt
-    pub async fn nip04_encrypt(&self, recipient_pubkey: &str, plaintext: &str) -> Result<SigningResultData> {
+    pub async fn nip04_encrypt(&self, recipient_pubkey: &str, plaintext: &str, key_id: Option<&str>) -> Result<SigningResultData> {
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
+        let keys = km.get_keys_for(key_id).await?;
         
         let pubkey = PublicKey::parse(recipient_pubkey)
             .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
@@ -156,13 +507,14 @@ impl SigningEngine {
         let ciphertext = nip04::encrypt(keys.secret_key(), &pubkey, plaintext)
             .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Encrypted { ciphertext })
     }
 
     /// NIP-04 decrypt
-    pub async fn nip04_decrypt(&self, sender_pubkey: &str, ciphertext: &str) -> Result<SigningResultData> {
+    pub async fn nip04_decrypt(&self, sender_pubkey: &str, ciphertext: &str, key_id: Option<&str>) -> Result<SigningResultData> {
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
+        let keys = km.get_keys_for(key_id).await?;
         
         let pubkey = PublicKey::parse(sender_pubkey)
             .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
@@ -170,13 +522,14 @@ impl SigningEngine {
         let plaintext = nip04::decrypt(keys.secret_key(), &pubkey, ciphertext)
             .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Decrypted { plaintext })
     }
 
     /// NIP-44 encrypt
-    pub async fn nip44_encrypt(&self, recipient_pubkey: &str, plaintext: &str) -> Result<SigningResultData> {
+    pub async fn nip44_encrypt(&self, recipient_pubkey: &str, plaintext: &str, key_id: Option<&str>) -> Result<SigningResultData> {
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
+        let keys = km.get_keys_for(key_id).await?;
         
         let pubkey = PublicKey::parse(recipient_pubkey)
             .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
@@ -184,13 +537,14 @@ impl SigningEngine {
         let ciphertext = nip44::encrypt(keys.secret_key(), &pubkey, plaintext, nip44::Version::default())
             .map_err(|e| SignerError::EncryptionError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Encrypted { ciphertext })
     }
 
     /// NIP-44 decrypt
-    pub async fn nip44_decrypt(&self, sender_pubkey: &str, ciphertext: &str) -> Result<SigningResultData> {
+    pub async fn nip44_decrypt(&self, sender_pubkey: &str, ciphertext: &str, key_id: Option<&str>) -> Result<SigningResultData> {
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
+        let keys = km.get_keys_for(key_id).await?;
         
         let pubkey = PublicKey::parse(sender_pubkey)
             .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
@@ -198,11 +552,12 @@ impl SigningEngine {
         let plaintext = nip44::decrypt(keys.secret_key(), &pubkey, ciphertext)
             .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Decrypted { plaintext })
     }
 
     /// Decrypt a zap event (NIP-57)
-    pub async fn decrypt_zap_event(&self, event_json: &str) -> Result<SigningResultData> {
+    pub async fn decrypt_zap_event(&self, event_json: &str, key_id: Option<&str>) -> Result<SigningResultData> {
         let event: Event = Event::from_json(event_json)
             .map_err(|e| SignerError::InvalidRequest(e.to_string()))?;
         
@@ -212,12 +567,271 @@ impl SigningEngine {
             .ok_or_else(|| SignerError::InvalidRequest("No sender pubkey in zap event".into()))?;
         
         let mut km = self.key_manager.lock().await;
-        let keys = km.get_signing_keys().await?;
+        let keys = km.get_keys_for(key_id).await?;
         
         // Decrypt the content
         let plaintext = nip04::decrypt(keys.secret_key(), sender_pubkey, &event.content)
             .map_err(|e| SignerError::DecryptionError(e.to_string()))?;
         
+        self.record_activity();
         Ok(SigningResultData::Decrypted { plaintext })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_with(name: &str, keys: &Keys) -> Arc<SigningEngine> {
+        let km = KeyManager::with_test_key(name, keys.clone());
+        Arc::new(SigningEngine::new(Arc::new(Mutex::new(km))))
+    }
+
+    async fn pubkey_hex(engine: &SigningEngine) -> String {
+        match engine.get_public_key(None).await.unwrap() {
+            SigningResultData::PublicKey { hex, .. } => hex,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expiration_tag_added_once() {
+        let keys = Keys::generate();
+        let engine = engine_with("main", &keys);
+        let now = 1_700_000_000;
+        let mut event_data = UnsignedEventData {
+            kind: 1,
+            content: "gone tomorrow".into(),
+            tags: vec![vec!["t".into(), "nostr".into()]],
+            created_at: Some(now),
+            relay_hints: Vec::new(),
+        };
+
+        assert!(event_data.add_expiration(86_400, now));
+        assert_eq!(event_data.expiration(), Some(now + 86_400));
+
+        // An existing expiration (ours or the app's) is left alone
+        assert!(!event_data.add_expiration(60, now + 10));
+        assert_eq!(event_data.tags.len(), 2);
+
+        let SigningResultData::Event { event_json, .. } = engine.sign_event(&event_data, None).await.unwrap() else {
+            panic!("expected a signed event");
+        };
+        let event = Event::from_json(&event_json).unwrap();
+        let expirations: Vec<_> = event.tags.iter()
+            .filter(|tag| tag.kind() == TagKind::Expiration)
+            .collect();
+        assert_eq!(expirations.len(), 1);
+        assert_eq!(expirations[0].content(), Some((now + 86_400).to_string().as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_batch_timestamps() {
+        let keys = Keys::generate();
+        let engine = engine_with("main", &keys);
+        let now = 1_700_000_000;
+        let note = |content: &str| UnsignedEventData {
+            kind: 1,
+            content: content.into(),
+            tags: Vec::new(),
+            created_at: None,
+            relay_hints: Vec::new(),
+        };
+        let mut batch = vec![note("1/3"), note("2/3"), note("3/3")];
+        assign_sequential_timestamps(&mut batch, now);
+
+        let mut signed = Vec::new();
+        for event_data in &batch {
+            let SigningResultData::Event { event_json, .. } = engine.sign_event(event_data, None).await.unwrap() else {
+                panic!("expected a signed event");
+            };
+            signed.push(Event::from_json(&event_json).unwrap());
+        }
+
+        let contents: Vec<_> = signed.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(contents, ["1/3", "2/3", "3/3"]);
+        let timestamps: Vec<_> = signed.iter().map(|e| e.created_at.as_secs()).collect();
+        assert_eq!(timestamps, [now, now + 1, now + 2]);
+        assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_successful_operations_restart_inactivity_lock() {
+        use crate::autolock::IdleAction;
+        use std::time::Duration;
+
+        let keys = Keys::generate();
+        let start = Instant::now();
+        let timer = Arc::new(std::sync::Mutex::new(InactivityTimer::new(1, 0, start)));
+        let km = KeyManager::with_test_key("main", keys.clone());
+        let engine = SigningEngine::new(Arc::new(Mutex::new(km))).with_activity(Arc::clone(&timer));
+        // Exactly the timeout after `start`: locks unless activity came later
+        let timeout_at = start + Duration::from_secs(60);
+
+        // A failed request doesn't count as activity
+        assert!(engine.get_public_key(Some("unknown")).await.is_err());
+        assert_eq!(timer.lock().unwrap().poll(timeout_at), IdleAction::Lock);
+
+        timer.lock().unwrap().touch(start);
+        let event_data = UnsignedEventData {
+            kind: 1,
+            content: "still here".into(),
+            tags: Vec::new(),
+            created_at: None,
+            relay_hints: Vec::new(),
+        };
+        engine.sign_event(&event_data, None).await.unwrap();
+        assert_eq!(timer.lock().unwrap().poll(timeout_at), IdleAction::None);
+    }
+
+    #[tokio::test]
+    async fn test_airgap_challenge_for_key() {
+        let keys = Keys::generate();
+        let km = KeyManager::with_test_key("main", keys.clone());
+        let engine = SigningEngine::new(Arc::new(Mutex::new(km))).with_blocked_kinds(BlockedKinds::new(vec![4]));
+        let mut event_data = UnsignedEventData {
+            kind: 1,
+            content: "from the cold wallet".into(),
+            tags: vec![vec!["t".into(), "airgap".into()]],
+            created_at: Some(1_700_000_000),
+            relay_hints: Vec::new(),
+        };
+
+        let challenge = engine.airgap_challenge(&event_data, None).await.unwrap();
+        assert_eq!(challenge.event().pubkey, keys.public_key());
+        assert_eq!(challenge.event().created_at.as_secs(), 1_700_000_000);
+        let signed = challenge.event().clone().sign_with_keys(&keys).unwrap();
+        assert_eq!(challenge.assemble(&signed.sig.to_string()).unwrap().id, challenge.id());
+
+        event_data.kind = 4;
+        assert!(matches!(engine.airgap_challenge(&event_data, None).await, Err(SignerError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_delegation_for_bot() {
+        let keys = Keys::generate();
+        let km = KeyManager::with_test_key("main", keys.clone());
+        let engine = SigningEngine::new(Arc::new(Mutex::new(km))).with_blocked_kinds(BlockedKinds::new(vec![4]));
+        let bot = Keys::generate().public_key();
+        // Kind-1 notes for 30 days
+        let now = 1_700_000_000;
+        let conditions = DelegationConditions {
+            kind: Some(1),
+            created_after: Some(now),
+            created_before: Some(now + 30 * 86_400),
+        };
+
+        let SigningResultData::Delegation { tag } =
+            engine.create_delegation(&bot.to_bech32().unwrap(), &conditions, None).await.unwrap()
+        else {
+            panic!("expected a delegation");
+        };
+        assert_eq!(tag[1], keys.public_key().to_hex());
+        let delegation = Delegation {
+            delegator: keys.public_key(),
+            conditions: tag[2].parse().unwrap(),
+            signature: tag[3].parse().unwrap(),
+        };
+        delegation.verify(&bot).unwrap();
+
+        let blocked = DelegationConditions { kind: Some(4), ..conditions.clone() };
+        assert!(matches!(engine.create_delegation(&bot.to_hex(), &blocked, None).await, Err(SignerError::PermissionDenied(_))));
+        assert!(engine.create_delegation("not a key", &conditions, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unlock_and_lock() {
+        let keys = Keys::generate();
+        let engine = engine_with("main", &keys);
+
+        // Already unlocked: unlocking again is a no-op that succeeds
+        engine.unlock().await.unwrap();
+        engine.unlock().await.unwrap();
+        assert!(engine.key_manager.lock().await.is_unlocked());
+
+        engine.lock().await;
+        assert!(!engine.key_manager.lock().await.is_unlocked());
+
+        // Nothing to unlock without keys
+        let empty = SigningEngine::new(Arc::new(Mutex::new(KeyManager::with_namespace("no-keys"))));
+        assert!(matches!(empty.unlock().await, Err(SignerError::NoKeysConfigured)));
+    }
+
+    #[tokio::test]
+    async fn test_senders_route_to_distinct_engines() {
+        let default_keys = Keys::generate();
+        let alice_keys = Keys::generate();
+        let bob_keys = Keys::generate();
+
+        let mut router = EngineRouter::new(engine_with("default", &default_keys));
+        router.add_engine("alice", engine_with("alice", &alice_keys), &["/usr/bin/alice-client".to_string()]);
+        router.add_engine("bob", engine_with("bob", &bob_keys), &["/usr/bin/bob-client".to_string()]);
+
+        let alice = router.route("/usr/bin/alice-client");
+        let bob = router.route("/usr/bin/bob-client");
+        assert!(!Arc::ptr_eq(alice, bob));
+        assert_eq!(pubkey_hex(alice).await, alice_keys.public_key().to_hex());
+        assert_eq!(pubkey_hex(bob).await, bob_keys.public_key().to_hex());
+
+        // Unrouted senders fall back to the default engine
+        assert_eq!(pubkey_hex(router.route(":1.99")).await, default_keys.public_key().to_hex());
+    }
+
+    #[tokio::test]
+    async fn test_signed_event_includes_nevent() {
+        let keys = Keys::generate();
+        let engine = engine_with("main", &keys);
+        let event_data = UnsignedEventData {
+            kind: 1,
+            content: "hello".into(),
+            tags: Vec::new(),
+            created_at: None,
+            relay_hints: vec!["wss://relay.example.com".into()],
+        };
+
+        let SigningResultData::Event { event_json, event_id, nevent, note, .. } = engine.sign_event(&event_data, None).await.unwrap() else {
+            panic!("expected a signed event");
+        };
+        let event = Event::from_json(&event_json).unwrap();
+        assert_eq!(event_id, event.id.to_hex());
+
+        let decoded = Nip19Event::from_bech32(&nevent).unwrap();
+        assert_eq!(decoded.event_id, event.id);
+        assert_eq!(decoded.author, Some(keys.public_key()));
+        assert_eq!(decoded.relays, [RelayUrl::parse("wss://relay.example.com").unwrap()]);
+        assert_eq!(EventId::from_bech32(&note).unwrap(), event.id);
+
+        let bad_hint = UnsignedEventData { relay_hints: vec!["not a url".into()], ..event_data };
+        assert!(matches!(engine.sign_event(&bad_hint, None).await, Err(SignerError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_blocked_kinds_refused_before_signing() {
+        let blocked = BlockedKinds::new(vec![4, 5]);
+        let engine = SigningEngine::new(Arc::new(Mutex::new(KeyManager::with_test_key("main", Keys::generate()))))
+            .with_blocked_kinds(blocked.clone());
+        let event = |kind: u16| UnsignedEventData {
+            kind,
+            content: String::new(),
+            tags: Vec::new(),
+            created_at: None,
+            relay_hints: Vec::new(),
+        };
+
+        let Err(SignerError::PermissionDenied(reason)) = engine.sign_event(&event(5), None).await else {
+            panic!("kind 5 should be blocked");
+        };
+        assert!(reason.contains("kind 5"));
+        assert!(engine.sign_event(&event(1), None).await.is_ok());
+
+        // Refused before any key is touched
+        let keyless = SigningEngine::new(Arc::new(Mutex::new(KeyManager::with_namespace("no-keys"))))
+            .with_blocked_kinds(blocked.clone());
+        assert!(matches!(keyless.sign_event(&event(4), None).await, Err(SignerError::PermissionDenied(_))));
+        assert!(matches!(keyless.sign_event(&event(1), None).await, Err(SignerError::NoKeysConfigured)));
+
+        // A reload reaches engines that are already running
+        blocked.set(Vec::new());
+        assert!(engine.sign_event(&event(5), None).await.is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+//! Statistics over the audit log
+//!
+//! Entries are folded into hourly buckets as they are recorded, so a summary
+//! only walks the buckets in the selected range instead of the whole log.
+//! Ranges are counted back from the start of the current hour.
+
+use crate::audit::AuditEntry;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+const SECS_PER_HOUR: i64 = 3600;
+
+/// Time range a summary covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsRange {
+    Day,
+    #[default]
+    Week,
+    Month,
+    All,
+}
+
+impl StatsRange {
+    pub const ALL: [StatsRange; 4] = [
+        StatsRange::Day,
+        StatsRange::Week,
+        StatsRange::Month,
+        StatsRange::All,
+    ];
+
+    /// Number of hourly buckets covered (None = everything)
+    fn hours(&self) -> Option<i64> {
+        match self {
+            StatsRange::Day => Some(24),
+            StatsRange::Week => Some(24 * 7),
+            StatsRange::Month => Some(24 * 30),
+            StatsRange::All => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StatsRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StatsRange::Day => "Last 24 hours",
+            StatsRange::Week => "Last 7 days",
+            StatsRange::Month => "Last 30 days",
+            StatsRange::All => "All time",
+        })
+    }
+}
+
+/// Counts for one hour of the log
+#[derive(Debug, Clone, Default)]
+struct Bucket {
+    requests: u32,
+    denied: u32,
+    /// Signed event kinds (approved sign requests only)
+    kinds: HashMap<u16, u32>,
+    /// Requests per app, approved or not
+    apps: HashMap<String, u32>,
+}
+
+/// Running per-kind and per-app counts, updated one entry at a time
+#[derive(Debug, Clone, Default)]
+pub struct AuditStats {
+    buckets: BTreeMap<i64, Bucket>,
+}
+
+impl AuditStats {
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a AuditEntry>) -> Self {
+        let mut stats = Self::default();
+        for entry in entries {
+            stats.add(entry);
+        }
+        stats
+    }
+
+    /// Count one more entry
+    pub fn add(&mut self, entry: &AuditEntry) {
+        let bucket = self.buckets.entry(hour_of(entry.timestamp)).or_default();
+        bucket.requests += 1;
+        if entry.approved {
+            if let Some(kind) = entry.event_kind {
+                *bucket.kinds.entry(kind).or_insert(0) += 1;
+            }
+        } else {
+            bucket.denied += 1;
+        }
+        *bucket.apps.entry(entry.app_id.clone()).or_insert(0) += 1;
+    }
+
+    /// Summarize the range ending at `now`, keeping the `top` largest kinds and apps
+    pub fn summary(&self, range: StatsRange, now: DateTime<Utc>, top: usize) -> StatsSummary {
+        let current = hour_of(now);
+        let start = range.hours().map_or(i64::MIN, |hours| current - hours + 1);
+
+        let mut requests = 0;
+        let mut denied = 0;
+        let mut kinds: HashMap<u16, u32> = HashMap::new();
+        let mut apps: HashMap<&str, u32> = HashMap::new();
+        for bucket in self.buckets.range(start..=current).map(|(_, b)| b) {
+            requests += bucket.requests;
+            denied += bucket.denied;
+            for (kind, count) in &bucket.kinds {
+                *kinds.entry(*kind).or_insert(0) += count;
+            }
+            for (app, count) in &bucket.apps {
+                *apps.entry(app.as_str()).or_insert(0) += count;
+            }
+        }
+
+        StatsSummary {
+            range,
+            requests,
+            denied,
+            top_kinds: top_n(kinds, top),
+            top_apps: top_n(apps, top).into_iter().map(|(app, n)| (app.to_string(), n)).collect(),
+        }
+    }
+}
+
+/// Aggregated counts for one time range
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsSummary {
+    pub range: StatsRange,
+    pub requests: u32,
+    pub denied: u32,
+    /// Most signed kinds, largest first
+    pub top_kinds: Vec<(u16, u32)>,
+    /// Apps with the most requests, largest first
+    pub top_apps: Vec<(String, u32)>,
+}
+
+impl StatsSummary {
+    /// Share of requests that were refused (0.0 with no requests)
+    pub fn denial_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            f64::from(self.denied) / f64::from(self.requests)
+        }
+    }
+}
+
+fn hour_of(timestamp: DateTime<Utc>) -> i64 {
+    timestamp.timestamp().div_euclid(SECS_PER_HOUR)
+}
+
+/// Largest counts first, ties broken by key so the order is stable
+fn top_n<K: Ord>(counts: HashMap<K, u32>, n: usize) -> Vec<(K, u32)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(n);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::RequestType;
+    use chrono::Duration;
+
+    fn entry(app_id: &str, kind: Option<u16>, approved: bool, age: Duration, now: DateTime<Utc>) -> AuditEntry {
+        let request_type = if kind.is_some() { RequestType::SignEvent } else { RequestType::Nip44Decrypt };
+        AuditEntry {
+            timestamp: now - age,
+            ..AuditEntry::new(app_id, request_type, kind, approved)
+        }
+    }
+
+    /// Two apps over the last 40 days
+    fn synthetic_log(now: DateTime<Utc>) -> Vec<AuditEntry> {
+        let mut log = Vec::new();
+        // Today: client signs notes and reactions, one refused
+        for _ in 0..5 {
+            log.push(entry("client", Some(1), true, Duration::hours(1), now));
+        }
+        for _ in 0..3 {
+            log.push(entry("client", Some(7), true, Duration::hours(2), now));
+        }
+        log.push(entry("client", Some(1), false, Duration::hours(3), now));
+        // Three days ago: dm app decrypts and signs DMs
+        for _ in 0..4 {
+            log.push(entry("dm", None, true, Duration::days(3), now));
+        }
+        log.push(entry("dm", Some(14), true, Duration::days(3), now));
+        // Ten days ago: a burst of refused deletions
+        for _ in 0..6 {
+            log.push(entry("dm", Some(5), false, Duration::days(10), now));
+        }
+        // Forty days ago: old profile updates
+        for _ in 0..2 {
+            log.push(entry("client", Some(0), true, Duration::days(40), now));
+        }
+        log
+    }
+
+    #[test]
+    fn test_summary_per_range() {
+        let now = Utc::now();
+        let stats = AuditStats::from_entries(&synthetic_log(now));
+
+        let day = stats.summary(StatsRange::Day, now, 5);
+        assert_eq!((day.requests, day.denied), (9, 1));
+        assert_eq!(day.top_kinds, [(1, 5), (7, 3)]);
+        assert_eq!(day.top_apps, [("client".to_string(), 9)]);
+
+        let week = stats.summary(StatsRange::Week, now, 5);
+        assert_eq!((week.requests, week.denied), (14, 1));
+        assert_eq!(week.top_kinds, [(1, 5), (7, 3), (14, 1)]);
+        assert_eq!(week.top_apps, [("client".to_string(), 9), ("dm".to_string(), 5)]);
+
+        // Refused requests count toward apps and the denial rate, not kinds
+        let month = stats.summary(StatsRange::Month, now, 5);
+        assert_eq!((month.requests, month.denied), (20, 7));
+        assert_eq!(month.top_apps, [("dm".to_string(), 11), ("client".to_string(), 9)]);
+        assert!(!month.top_kinds.iter().any(|(kind, _)| *kind == 5));
+        assert!((month.denial_rate() - 0.35).abs() < 1e-9);
+
+        let all = stats.summary(StatsRange::All, now, 2);
+        assert_eq!(all.requests, 22);
+        assert_eq!(all.top_kinds, [(1, 5), (7, 3)]);
+    }
+
+    #[test]
+    fn test_incremental_matches_rebuild() {
+        let now = Utc::now();
+        let log = synthetic_log(now);
+        let (older, newer) = log.split_at(10);
+
+        let mut stats = AuditStats::from_entries(older);
+        for entry in newer {
+            stats.add(entry);
+        }
+        let rebuilt = AuditStats::from_entries(&log);
+        for range in StatsRange::ALL {
+            assert_eq!(stats.summary(range, now, 10), rebuilt.summary(range, now, 10));
+        }
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        let summary = AuditStats::default().summary(StatsRange::All, Utc::now(), 5);
+        assert_eq!(summary.requests, 0);
+        assert_eq!(summary.denial_rate(), 0.0);
+        assert!(summary.top_kinds.is_empty() && summary.top_apps.is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+//! In-process D-Bus harness for end-to-end tests of the signer interface
+//!
+//! `SignerInterface` is served on one end of a Unix socket pair as a
+//! peer-to-peer connection, so no session bus is needed, and called from
+//! the other end. Requests go through the same path as on the session bus:
+//! argument decoding, permission checks, the approval queue, signing and
+//! the audit log. Approval prompts are answered by a mock backend instead
+//! of the UI.
+//!
+//! Peer-to-peer calls carry no sender, so the caller is known by the
+//! `app_id` it passes (as an unresolvable bus client would be).
+
+use crate::app::AppState;
+use crate::audit::AuditLog;
+use crate::client::Capabilities;
+use crate::config::{AppPermissions, AuthorizedApp, Config};
+use crate::dbus::{DbusResponse, SignerService, DBUS_PATH};
+use crate::keys::{KeyManager, METADATA_FILE};
+use nostr::Keys;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use zbus::zvariant::DynamicType;
+use zbus::Connection;
+
+const INTERFACE: &str = "com.plebsigner.Signer1";
+
+/// App ID authorized by `TestBus::config`
+pub const TEST_APP: &str = "test-app";
+
+/// How the mock prompt backend answers requests waiting for approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    Approve,
+    Reject,
+    /// Leave requests pending until they time out
+    Ignore,
+}
+
+/// A signer served over a private connection, with a client end to call it
+pub struct TestBus {
+    pub app_state: Arc<RwLock<AppState>>,
+    pub key_manager: Arc<Mutex<KeyManager>>,
+    /// The signer's active key
+    pub keys: Keys,
+    client: Connection,
+    _server: Connection,
+    dir: tempfile::TempDir,
+}
+
+impl TestBus {
+    /// Config that authorizes `TEST_APP` for everything and always prompts
+    pub fn config() -> Config {
+        let mut config = Config::default_config();
+        config.general.request_timeout_secs = 2;
+        config.authorized_apps.push(AuthorizedApp {
+            app_id: TEST_APP.into(),
+            name: "Test App".into(),
+            authorized_at: chrono::Utc::now(),
+            permissions: AppPermissions {
+                get_public_key: true,
+                sign_event: None,
+                nip04_encrypt: true,
+                nip04_decrypt: true,
+                nip44_encrypt: true,
+                nip44_decrypt: true,
+                decrypt_zap_event: true,
+                create_delegation: true,
+                daily_sign_quota: None,
+            },
+            auto_approve: false,
+            auto_approve_decrypt: false,
+            expiration: None,
+        });
+        config
+    }
+
+    /// Serve a signer with a fresh key, answering prompts with `prompt`
+    pub async fn start(config: Config, prompt: Prompt) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let keys = Keys::generate();
+        let key_manager = KeyManager::with_test_key("main", keys.clone())
+            .with_metadata_file(dir.path().join(METADATA_FILE));
+        let key_manager = Arc::new(Mutex::new(key_manager));
+        let audit_log = AuditLog::load_from(dir.path().join("audit.jsonl")).await.unwrap();
+        let app_state = Arc::new(RwLock::new(AppState::with_audit_log(config, audit_log)));
+
+        let queue = Arc::clone(&app_state.read().await.approval_queue);
+        let mut queued = queue.subscribe();
+        tokio::spawn(async move {
+            while let Some(request) = queued.recv().await {
+                match prompt {
+                    Prompt::Approve => queue.resolve(&request.id, true),
+                    Prompt::Reject => queue.resolve(&request.id, false),
+                    Prompt::Ignore => continue,
+                };
+            }
+        });
+
+        // Both ends authenticate with each other, so build them together
+        let (server_end, client_end) = tokio::net::UnixStream::pair().unwrap();
+        let server = zbus::connection::Builder::unix_stream(server_end)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p();
+        let client = zbus::connection::Builder::unix_stream(client_end).p2p();
+        let (server, client) = tokio::join!(
+            SignerService::serve(server, Arc::clone(&app_state), Arc::clone(&key_manager)),
+            client.build(),
+        );
+
+        Self {
+            app_state,
+            key_manager,
+            keys,
+            client: client.unwrap(),
+            _server: server.unwrap(),
+            dir,
+        }
+    }
+
+    /// Call `method` and return its raw reply
+    pub async fn call_raw<B, R>(&self, method: &str, body: &B) -> R
+    where
+        B: Serialize + DynamicType,
+        R: for<'d> zbus::zvariant::DynamicDeserialize<'d>,
+    {
+        let reply = self.client
+            .call_method(None::<&str>, DBUS_PATH, Some(INTERFACE), method, body)
+            .await
+            .unwrap_or_else(|e| panic!("{} failed: {}", method, e));
+        reply.body().deserialize().unwrap()
+    }
+
+    /// Call a method that answers with a JSON response
+    pub async fn call<B: Serialize + DynamicType>(&self, method: &str, body: &B) -> DbusResponse {
+        let reply: String = self.call_raw(method, body).await;
+        serde_json::from_str(&reply).unwrap_or_else(|e| panic!("{} returned {}: {}", method, reply, e))
+    }
+
+    pub async fn version(&self) -> String {
+        self.call_raw("Version", &()).await
+    }
+
+    pub async fn is_ready(&self) -> bool {
+        self.call_raw("IsReady", &()).await
+    }
+
+    pub async fn capabilities(&self) -> Capabilities {
+        let response = self.call("GetCapabilities", &()).await;
+        serde_json::from_str(&response.result.unwrap()).unwrap()
+    }
+
+    pub async fn lock(&self) -> DbusResponse {
+        self.call("Lock", &()).await
+    }
+
+    pub async fn get_public_key(&self, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("GetPublicKey", &(key_id, app_id)).await
+    }
+
+    pub async fn get_my_permissions(&self, app_id: &str) -> DbusResponse {
+        self.call("GetMyPermissions", &(app_id,)).await
+    }
+
+    pub async fn sign_event(&self, event_json: &str, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("SignEvent", &(event_json, key_id, app_id)).await
+    }
+
+    pub async fn nip44_encrypt(&self, plaintext: &str, recipient: &str, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("Nip44Encrypt", &(plaintext, recipient, key_id, app_id)).await
+    }
+
+    pub async fn nip44_decrypt(&self, ciphertext: &str, sender: &str, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("Nip44Decrypt", &(ciphertext, sender, key_id, app_id)).await
+    }
+
+    pub async fn nip04_encrypt(&self, plaintext: &str, recipient: &str, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("Nip04Encrypt", &(plaintext, recipient, key_id, app_id)).await
+    }
+
+    pub async fn nip04_decrypt(&self, ciphertext: &str, sender: &str, key_id: &str, app_id: &str) -> DbusResponse {
+        self.call("Nip04Decrypt", &(ciphertext, sender, key_id, app_id)).await
+    }
+
+    /// Metadata file of the signer's keys (in a temp dir)
+    pub fn metadata_file(&self) -> std::path::PathBuf {
+        self.dir.path().join(METADATA_FILE)
+    }
+
+    /// Every message arriving from the signer, signals included
+    pub fn messages(&self) -> zbus::MessageStream {
+        zbus::MessageStream::from(&self.client)
+    }
+
+    /// Audit entries recorded for `app_id`, as (approved, reason)
+    pub async fn audited(&self, app_id: &str) -> Vec<(bool, Option<String>)> {
+        let state = self.app_state.read().await;
+        state.audit_log.entries_for(app_id).map(|e| (e.approved, e.reason.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::SignedEventResult;
+
+    /// Signed event carried in a successful `SignEvent` response
+    fn signed_event(response: &DbusResponse) -> nostr::Event {
+        use nostr::JsonUtil;
+        let result: SignedEventResult = serde_json::from_str(response.result.as_deref().unwrap()).unwrap();
+        let event = nostr::Event::from_json(&result.event_json).unwrap();
+        // The loose fields must describe the same event
+        assert_eq!(result.event_id, event.id.to_hex());
+        assert_eq!(result.signature, event.sig.to_string());
+        event
+    }
+
+    /// Next request queued for approval, failing instead of hanging if none comes
+    async fn next_prompt(queued: &mut tokio::sync::mpsc::UnboundedReceiver<crate::signing::SigningRequest>) -> crate::signing::SigningRequest {
+        let prompt = tokio::time::timeout(std::time::Duration::from_secs(5), queued.recv()).await;
+        prompt.expect("no request was queued for approval").unwrap()
+    }
+
+    fn note(content: &str) -> String {
+        serde_json::json!({"kind": 1, "content": content, "tags": [], "created_at": chrono::Utc::now().timestamp()}).to_string()
+    }
+
+    /// Field of the JSON object in a successful response
+    fn field(response: DbusResponse, name: &str) -> String {
+        assert!(response.success, "{:?}", response.error);
+        let result: serde_json::Value = serde_json::from_str(&response.result.unwrap()).unwrap();
+        result[name].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_plain_methods_over_the_bus() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        assert_eq!(bus.version().await, env!("CARGO_PKG_VERSION"));
+        assert!(bus.is_ready().await);
+        assert!(bus.capabilities().await.supports("nip44"));
+
+        let response = bus.get_my_permissions(TEST_APP).await;
+        assert!(response.success);
+        let permissions: crate::client::MyPermissions = serde_json::from_str(&response.result.unwrap()).unwrap();
+        assert!(permissions.authorized);
+        assert_eq!(permissions.app_id, TEST_APP);
+    }
+
+    #[tokio::test]
+    async fn test_key_changes_signalled() {
+        use crate::client::KeyChange;
+        use futures_util::StreamExt;
+        use nostr::ToBech32;
+
+        let bus = TestBus::start(TestBus::config(), Prompt::Ignore).await;
+        let mut messages = bus.messages();
+        let alt = Keys::generate();
+        {
+            let mut km = bus.key_manager.lock().await;
+            km.add_test_key("alt", alt.clone());
+            km.announce_changes();
+        }
+
+        let change = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let message = messages.next().await.unwrap().unwrap();
+                if let Some(change) = KeyChange::from_message(&message) {
+                    break change;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(change, KeyChange::Added(alt.public_key().to_bech32().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_approved_sign_event_end_to_end() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+
+        let response = bus.sign_event(&note("over the bus"), "", TEST_APP).await;
+        assert!(response.success, "{:?}", response.error);
+        let event = signed_event(&response);
+        assert!(event.verify().is_ok());
+        assert_eq!(event.pubkey, bus.keys.public_key());
+        assert_eq!(event.content, "over the bus");
+
+        assert_eq!(bus.audited(TEST_APP).await, [(true, None)]);
+    }
+
+    #[tokio::test]
+    async fn test_default_config_queues_sign_event() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let mut queued = bus.app_state.read().await.approval_queue.subscribe();
+
+        assert!(bus.sign_event(&note("asked first"), "", TEST_APP).await.success);
+        let request = next_prompt(&mut queued).await;
+        assert_eq!(request.request_type, crate::permissions::RequestType::SignEvent);
+        assert_eq!(request.app_id, TEST_APP);
+    }
+
+    #[tokio::test]
+    async fn test_auto_approvals_rate_limited() {
+        let mut config = TestBus::config();
+        config.security.allow_auto_approve = true;
+        config.security.max_auto_approvals_per_min = 1;
+        config.authorized_apps[0].auto_approve = true;
+        let bus = TestBus::start(config, Prompt::Reject).await;
+
+        assert!(bus.sign_event(&note("first"), "", TEST_APP).await.success);
+        let response = bus.sign_event(&note("second"), "", TEST_APP).await;
+        assert_eq!(response.error.as_deref(), Some("User rejected the request"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_and_unanswered_prompts() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Reject).await;
+        let response = bus.sign_event(&note("no"), "", TEST_APP).await;
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("User rejected the request"));
+        assert!(!bus.audited(TEST_APP).await[0].0);
+
+        let bus = TestBus::start(TestBus::config(), Prompt::Ignore).await;
+        let response = bus.sign_event(&note("anyone?"), "", TEST_APP).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("timeout"));
+        assert_eq!(bus.app_state.read().await.approval_queue.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_app_refused_without_prompt() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+
+        let response = bus.sign_event(&note("hi"), "", "stranger").await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().starts_with("Permission denied"));
+        let response = bus.get_public_key("", "stranger").await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().starts_with("Permission denied"));
+        assert!(bus.get_public_key("", TEST_APP).await.success);
+        let response = bus.call("GetRelays", &("", "stranger")).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().starts_with("Permission denied"));
+
+        assert_eq!(bus.audited("stranger").await.len(), 3);
+        assert!(bus.audited(TEST_APP).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lock_refuses_requests() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        assert!(bus.lock().await.success);
+        assert!(!bus.is_ready().await);
+
+        let response = bus.sign_event(&note("locked"), "", TEST_APP).await;
+        assert_eq!(response.error.as_deref(), Some("Signer is locked"));
+        let response = bus.nip44_encrypt("hi", &Keys::generate().public_key().to_hex(), "", TEST_APP).await;
+        assert_eq!(response.error.as_deref(), Some("Signer is locked"));
+
+        // Apps can't undo the lock
+        let response = bus.call("Unlock", &("",)).await;
+        assert!(response.error.unwrap().starts_with("Permission denied"));
+        assert!(!bus.is_ready().await);
+    }
+
+    #[tokio::test]
+    async fn test_encryption_round_trips_with_a_peer() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let peer = Keys::generate();
+        let peer_hex = peer.public_key().to_hex();
+        let ours = bus.keys.public_key();
+
+        // What the signer encrypts, the peer can read, and back
+        let ciphertext = field(bus.nip44_encrypt("to the peer", &peer_hex, "", TEST_APP).await, "ciphertext");
+        assert_eq!(nostr::nips::nip44::decrypt(peer.secret_key(), &ours, &ciphertext).unwrap(), "to the peer");
+        let from_peer = nostr::nips::nip44::encrypt(peer.secret_key(), &ours, "nip44 reply", Default::default()).unwrap();
+        assert_eq!(field(bus.nip44_decrypt(&from_peer, &peer_hex, "", TEST_APP).await, "plaintext"), "nip44 reply");
+
+        let ciphertext = field(bus.nip04_encrypt("legacy", &peer_hex, "", TEST_APP).await, "ciphertext");
+        assert_eq!(nostr::nips::nip04::decrypt(peer.secret_key(), &ours, &ciphertext).unwrap(), "legacy");
+        let from_peer = nostr::nips::nip04::encrypt(peer.secret_key(), &ours, "nip04 reply").unwrap();
+        assert_eq!(field(bus.nip04_decrypt(&from_peer, &peer_hex, "", TEST_APP).await, "plaintext"), "nip04 reply");
+    }
+
+    #[tokio::test]
+    async fn test_active_key_set_in_ui_reaches_service() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let alt = Keys::generate();
+        {
+            let mut km = bus.key_manager.lock().await;
+            km.add_test_key("alt", alt.clone());
+            km.save_metadata().await.unwrap();
+        }
+
+        // The UI subprocess has its own key manager over the same file
+        let mut ui = KeyManager::new().with_metadata_file(bus.metadata_file());
+        ui.load().await.unwrap();
+        ui.set_active_key("alt").await.unwrap();
+        assert_eq!(field(bus.get_public_key("", TEST_APP).await, "hex"), bus.keys.public_key().to_hex());
+
+        assert!(bus.call("ReloadKeys", &()).await.success);
+        assert_eq!(field(bus.get_public_key("", TEST_APP).await, "hex"), alt.public_key().to_hex());
+        let response = bus.sign_event(&note("from alt"), "", TEST_APP).await;
+        assert_eq!(signed_event(&response).pubkey, alt.public_key());
+    }
+
+    #[tokio::test]
+    async fn test_key_imported_in_ui_signs_over_dbus() {
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let imported = Keys::generate();
+
+        let mut ui = KeyManager::new().with_metadata_file(bus.metadata_file());
+        ui.share_test_keyring(&mut *bus.key_manager.lock().await);
+        ui.load().await.unwrap();
+        ui.import_key("imported", &imported.secret_key().to_secret_hex()).await.unwrap();
+        assert!(!bus.sign_event(&note("too early"), "imported", TEST_APP).await.success);
+
+        // What the UI does after an import
+        assert!(bus.call("ReloadKeys", &()).await.success);
+        let response = bus.sign_event(&note("from the imported key"), "imported", TEST_APP).await;
+        assert_eq!(signed_event(&response).pubkey, imported.public_key());
+
+        ui.delete_key("imported").await.unwrap();
+        assert!(bus.call("ReloadKeys", &()).await.success);
+        assert!(!bus.sign_event(&note("deleted"), "imported", TEST_APP).await.success);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kind_caution_shown_when_approving() {
+        let mut config = TestBus::config();
+        config.security.unknown_kind_policy = crate::config::UnknownKindPolicy::Warn;
+        let bus = TestBus::start(config, Prompt::Approve).await;
+        let mut queued = bus.app_state.read().await.approval_queue.subscribe();
+
+        let event = serde_json::json!({"kind": 12345, "content": "odd", "tags": [], "created_at": 1_700_000_000}).to_string();
+        assert!(bus.sign_event(&event, "", TEST_APP).await.success);
+        let request = next_prompt(&mut queued).await;
+        assert!(request.warnings.iter().any(|w| w.contains("12345")), "{:?}", request.warnings);
+    }
+
+    #[tokio::test]
+    async fn test_delegation_over_dbus() {
+        use crate::client::DelegationResult;
+        use crate::nip26::Delegation;
+
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let mut queued = bus.app_state.read().await.approval_queue.subscribe();
+        let bot = Keys::generate().public_key();
+        let conditions = "kind=1&created_at>1700000000&created_at<1702592000";
+
+        let response = bus.call("CreateDelegation", &(bot.to_hex(), conditions, "", TEST_APP)).await;
+        assert!(response.success, "{:?}", response.error);
+        let request = next_prompt(&mut queued).await;
+        assert!(request.warnings.iter().any(|w| w.contains("kind 1 events")));
+
+        let DelegationResult { tag } = serde_json::from_str(response.result.as_deref().unwrap()).unwrap();
+        assert_eq!(tag[..3], ["delegation".to_string(), bus.keys.public_key().to_hex(), conditions.to_string()]);
+        let delegation = Delegation {
+            delegator: bus.keys.public_key(),
+            conditions: conditions.parse().unwrap(),
+            signature: tag[3].parse().unwrap(),
+        };
+        delegation.verify(&bot).unwrap();
+
+        // Bad conditions are refused before anyone is asked
+        let response = bus.call("CreateDelegation", &(bot.to_hex(), "kind=1&kind=7", "", TEST_APP)).await;
+        assert!(!response.success);
+    }
+
+    #[tokio::test]
+    async fn test_nip04_warning_until_suppressed() {
+        use crate::permissions::NIP04_WARNING;
+
+        // Under the default config, encryption by an authorized app isn't asked
+        let bus = TestBus::start(TestBus::config(), Prompt::Approve).await;
+        let mut queued = bus.app_state.read().await.approval_queue.subscribe();
+        let peer_hex = Keys::generate().public_key().to_hex();
+
+        // The first NIP-04 request is asked once, with the warning
+        assert!(bus.nip04_encrypt("legacy", &peer_hex, "", TEST_APP).await.success);
+        assert!(next_prompt(&mut queued).await.warnings.iter().any(|w| w == NIP04_WARNING));
+        assert!(bus.nip04_encrypt("legacy again", &peer_hex, "", TEST_APP).await.success);
+        // NIP-44 is what we nudge towards
+        assert!(bus.nip44_encrypt("modern", &peer_hex, "", TEST_APP).await.success);
+        assert!(queued.try_recv().is_err());
+
+        // Suppressed for an app: not asked
+        let mut config = TestBus::config();
+        config.security.nip04_warning_suppressed.push(TEST_APP.into());
+        let bus = TestBus::start(config, Prompt::Approve).await;
+        let mut queued = bus.app_state.read().await.approval_queue.subscribe();
+        assert!(bus.nip04_encrypt("legacy", &peer_hex, "", TEST_APP).await.success);
+        assert!(queued.try_recv().is_err());
+    }
+}
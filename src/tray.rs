@@ -3,9 +3,10 @@
 //! Uses ksni which implements the StatusNotifierItem D-Bus protocol
 //! (org.kde.StatusNotifierItem) supported by Cosmic, KDE, GNOME, etc.
 
+use crate::config::TrayClickAction;
 use ksni::{Icon, Tray, TrayService};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::info;
 
 /// Shared state between tray and main app
@@ -13,24 +14,57 @@ pub struct TrayState {
     pub is_locked: AtomicBool,
     pub quit_requested: AtomicBool,
     pub show_requested: AtomicBool,
+    pub unlock_requested: AtomicBool,
+    pub toggle_lock_requested: AtomicBool,
+    pub approve_top_requested: AtomicBool,
     pub bunker_enabled: AtomicBool,
+    pub bunker_paused: AtomicBool,
+    /// Requests waiting for approval
+    pub pending_requests: AtomicUsize,
+    /// What a left click does
+    pub left_click: Mutex<TrayClickAction>,
 }
 
 impl TrayState {
-    pub fn new() -> Self {
+    pub fn new(left_click: TrayClickAction) -> Self {
         Self {
             is_locked: AtomicBool::new(false),
             quit_requested: AtomicBool::new(false),
             show_requested: AtomicBool::new(false),
+            unlock_requested: AtomicBool::new(false),
+            toggle_lock_requested: AtomicBool::new(false),
+            approve_top_requested: AtomicBool::new(false),
             bunker_enabled: AtomicBool::new(false),
+            bunker_paused: AtomicBool::new(false),
+            pending_requests: AtomicUsize::new(0),
+            left_click: Mutex::new(left_click),
         }
     }
+
+    /// Request flag raised by a click with `action`
+    fn click_flag(&self, action: TrayClickAction) -> &AtomicBool {
+        match action {
+            TrayClickAction::ShowWindow => &self.show_requested,
+            TrayClickAction::ToggleLock => &self.toggle_lock_requested,
+            TrayClickAction::ApproveTopRequest => &self.approve_top_requested,
+        }
+    }
+
+    /// Handle a left click on the tray icon
+    pub fn left_clicked(&self) {
+        let action = *self.left_click.lock().unwrap();
+        self.click_flag(action).store(true, Ordering::Relaxed);
+        info!("Tray icon clicked: {}", action);
+    }
 }
 
+/// Width and height of the key icon in pixels
+pub const ICON_SIZE: usize = 22;
+
 /// Generate a simple key icon as ARGB pixel data
 /// This creates a 22x22 golden key icon
 fn generate_key_icon() -> Vec<u8> {
-    let size = 22;
+    let size = ICON_SIZE;
     let mut pixels = vec![0u8; size * size * 4]; // ARGB format
     
     // Colors (ARGB format: Alpha, Red, Green, Blue)
@@ -94,6 +128,25 @@ fn generate_key_icon() -> Vec<u8> {
     pixels
 }
 
+/// Encode the key icon as a PNG (for clients displaying the signer's branding)
+pub fn key_icon_png() -> Result<Vec<u8>, png::EncodingError> {
+    // Convert ARGB to RGBA
+    let rgba: Vec<u8> = generate_key_icon()
+        .chunks_exact(4)
+        .flat_map(|p| [p[1], p[2], p[3], p[0]])
+        .collect();
+
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, ICON_SIZE as u32, ICON_SIZE as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&rgba)?;
+    }
+    Ok(png_data)
+}
+
 /// System tray icon implementation
 pub struct PlebSignerTray {
     state: Arc<TrayState>,
@@ -116,8 +169,8 @@ impl Tray for PlebSignerTray {
 
     fn icon_pixmap(&self) -> Vec<Icon> {
         vec![Icon {
-            width: 22,
-            height: 22,
+            width: ICON_SIZE as i32,
+            height: ICON_SIZE as i32,
             data: self.icon_pixels.clone(),
         }]
     }
@@ -139,6 +192,13 @@ impl Tray for PlebSignerTray {
 
         let is_locked = self.state.is_locked.load(Ordering::Relaxed);
         let bunker_enabled = self.state.bunker_enabled.load(Ordering::Relaxed);
+        let bunker_paused = self.state.bunker_paused.load(Ordering::Relaxed);
+        let pending = self.state.pending_requests.load(Ordering::Relaxed);
+        let bunker_label = match (bunker_enabled, bunker_paused) {
+            (true, true) => "⏸ Paused",
+            (true, false) => "🌐 Active",
+            (false, _) => "⭘ Off",
+        };
         
         vec![
             StandardItem {
@@ -147,11 +207,24 @@ impl Tray for PlebSignerTray {
                 ..Default::default()
             }.into(),
             StandardItem {
-                label: format!("Bunker: {}", if bunker_enabled { "🌐 Active" } else { "⭘ Off" }),
+                label: format!("Bunker: {}", bunker_label),
+                enabled: false,
+                ..Default::default()
+            }.into(),
+            StandardItem {
+                label: format!("Waiting for approval: {}", pending),
                 enabled: false,
                 ..Default::default()
             }.into(),
             MenuItem::Separator,
+            StandardItem {
+                label: "Unlock".into(),
+                visible: is_locked,
+                activate: Box::new(|this: &mut Self| {
+                    this.state.unlock_requested.store(true, Ordering::Relaxed);
+                }),
+                ..Default::default()
+            }.into(),
             StandardItem {
                 label: "Show Window".into(),
                 activate: Box::new(|this: &mut Self| {
@@ -175,16 +248,15 @@ impl Tray for PlebSignerTray {
     }
 
     fn activate(&mut self, _x: i32, _y: i32) {
-        // Called when the tray icon is clicked
-        self.state.show_requested.store(true, Ordering::Relaxed);
-        info!("Tray icon clicked - show window requested");
+        // Called on left click; right click opens the menu
+        self.state.left_clicked();
     }
 }
 
 /// Start the system tray in a background thread
 /// Returns the shared state that can be used to communicate with the tray
-pub fn start_tray() -> Arc<TrayState> {
-    let state = Arc::new(TrayState::new());
+pub fn start_tray(left_click: TrayClickAction) -> Arc<TrayState> {
+    let state = Arc::new(TrayState::new(left_click));
     let tray_state = Arc::clone(&state);
 
     std::thread::spawn(move || {
@@ -204,3 +276,44 @@ pub fn start_tray() -> Arc<TrayState> {
     info!("System tray started");
     state
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_png_decodes() {
+        let png_data = key_icon_png().unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(png_data));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+
+        assert_eq!(info.width, ICON_SIZE as u32);
+        assert_eq!(info.height, ICON_SIZE as u32);
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+    }
+
+    #[test]
+    fn test_left_click_dispatch() {
+        let state = TrayState::new(TrayClickAction::default());
+        let raised = |state: &TrayState| {
+            [
+                state.show_requested.swap(false, Ordering::Relaxed),
+                state.toggle_lock_requested.swap(false, Ordering::Relaxed),
+                state.approve_top_requested.swap(false, Ordering::Relaxed),
+            ]
+        };
+
+        state.left_clicked();
+        assert_eq!(raised(&state), [true, false, false]);
+
+        *state.left_click.lock().unwrap() = TrayClickAction::ToggleLock;
+        state.left_clicked();
+        assert_eq!(raised(&state), [false, true, false]);
+
+        *state.left_click.lock().unwrap() = TrayClickAction::ApproveTopRequest;
+        state.left_clicked();
+        assert_eq!(raised(&state), [false, false, true]);
+        assert!(!state.unlock_requested.load(Ordering::Relaxed));
+    }
+}
@@ -1,17 +1,29 @@
 //! Simple UI for Pleb Signer using iced
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use iced::{
-    Element, Length, Task, Theme,
-    widget::{button, column, container, row, text, scrollable, horizontal_space, text_input, checkbox},
+    Element, Length, Subscription, Task, Theme, window,
+    widget::{button, column, container, row, text, scrollable, horizontal_space, text_input, checkbox, text_editor, qr_code, pick_list},
 };
 
-use crate::keys::{KeyManager, KeyMetadata};
-use crate::config::Config;
+use crate::approval::REJECTION_REASONS;
+use crate::audit::{AuditEntry, AuditLog};
+use crate::keys::{KeyManager, KeyMetadata, MNEMONIC_WORD_COUNTS};
+use crate::config::{Config, TrayClickAction};
+use crate::permissions::NIP04_WARNING;
 use crate::client::PlebSignerClient;
 use crate::error::SignerError;
+use crate::signing::{SigningPayload, SigningRequest};
+use crate::stats::{AuditStats, StatsRange};
+
+/// Kinds and apps listed in the activity summary
+const TOP_STATS: usize = 5;
+
+/// Bunker requests listed in the remote activity feed
+const REMOTE_FEED_LEN: usize = 10;
 
 /// Main view states
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -22,6 +34,11 @@ pub enum ViewState {
     Settings,
     AddKey,
     Bunker,
+    Apps,
+    /// Requests waiting for the user's decision
+    Approval,
+    /// What has been signed, by kind and app
+    Activity,
 }
 
 /// UI Messages
@@ -34,30 +51,113 @@ pub enum Message {
     GenerateKey,
     KeyNameInput(String),
     ImportKeyInput(String),
+    ImportPasswordInput(String),
     ImportKey,
+    MnemonicAction(text_editor::Action),
+    MnemonicPassphraseInput(String),
+    MnemonicAccountInput(String),
+    ImportMnemonic,
     DeleteKey(String),
     SelectKey(String),
     KeyOperationComplete(Result<String, String>),
     RefreshKeys,
     KeysRefreshed(Vec<KeyMetadata>),
+    KeyLabelInput(String),
+    AddKeyLabel(String),
+    RemoveKeyLabel(String, String),
+    KeyRelaysInput(String),
+    SetKeyRelays(String),
+    ExportKey(String),
+    ExportPasswordInput(String),
+    ConfirmExport,
+    CopyExport,
+    CancelExport,
+    KeyExported(Result<Option<String>, String>),
+    EditKeyPassword(String),
+    KeyPasswordInput(String),
+    SetKeyPassword,
+    ClearKeyPassword,
+    CancelKeyPassword,
+    FilterByLabel(Option<String>),
+    ToggleNpubQr,
+    QuotasRefreshed(Vec<AppQuota>),
+    
+    // Authorized apps
+    ExportSessionLog(String),
+    SessionLogExported(Result<String, String>),
+    
+    // Activity
+    StatsLoaded(Result<(AuditStats, Vec<AuditEntry>), String>),
+    SetStatsRange(StatsRange),
     
     // Settings
     ToggleAutoStart(bool),
     ToggleNotifications(bool),
+    SetTrayLeftClick(TrayClickAction),
     SaveSettings,
     SettingsSaved(Result<(), String>),
+    ReloadConfig,
+    ConfigReloaded(Result<Vec<String>, String>),
     
     // Bunker
     ToggleBunker(bool),
     GenerateBunkerUri,
     BunkerUriGenerated(Result<String, String>),
+    BunkerUriAction(text_editor::Action),
     CopyBunkerUri,
+    ShareBunkerUri,
+    BunkerUriShared(Result<String, String>),
+    ToggleBunkerPause,
+    BunkerPauseChanged(Result<bool, String>),
+    BunkerRelaysRefreshed(Result<Vec<(String, bool)>, String>),
+    
+    // Approval
+    CountdownTick,
+    PollPending,
+    PendingRefreshed(Result<Vec<SigningRequest>, String>),
+    ApproveRequest(String),
+    ApproveForSession(String),
+    RejectRequest(String),
+    /// Request ID and the key to sign it with instead
+    SigningKeyPicked(String, String),
+    /// Request ID and the reason to give the app if it's rejected
+    RejectionReasonChanged(String, String),
+    RequestAnswered(Result<(), String>),
+    SuppressNip04Warning(String),
+    Nip04WarningSuppressed(Result<(), String>),
+    WindowFocused(bool),
     
     // General
     Lock,
     Noop,
 }
 
+/// What the user picked in the approval view before answering a request
+#[derive(Debug, Clone, Default)]
+struct RequestChoice {
+    /// Key to sign with instead of the requested one
+    signing_key: Option<String>,
+    /// Told to the app on rejection, if not empty
+    rejection_reason: String,
+}
+
+/// The user's answer to a pending request
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Answer {
+    Approve,
+    ApproveForSession,
+    ApproveWithKey(String),
+    Reject(String),
+}
+
+/// Remaining daily signing quota for an authorized app
+#[derive(Debug, Clone)]
+pub struct AppQuota {
+    pub app_name: String,
+    pub remaining: u32,
+    pub quota: u32,
+}
+
 /// Main UI state
 pub struct PlebSignerUi {
     view: ViewState,
@@ -67,15 +167,65 @@ pub struct PlebSignerUi {
     // Key management
     key_name_input: String,
     import_key_input: String,
+    /// Password of an imported ncryptsec
+    import_password: String,
+    /// BIP-39 recovery phrase to import
+    mnemonic_content: text_editor::Content,
+    /// Optional BIP-39 passphrase ("25th word")
+    mnemonic_passphrase: String,
+    /// NIP-06 account index to derive (empty = 0)
+    mnemonic_account: String,
     keys_list: Vec<KeyMetadata>,
+    /// Label typed for adding to a key
+    label_input: String,
+    /// Relay URLs typed for a key, comma or space separated
+    relays_input: String,
+    /// Key waiting for a password to be exported (NIP-49)
+    export_key: Option<String>,
+    export_password: String,
+    /// Key whose own password is being set or removed
+    password_key: Option<String>,
+    key_password: String,
+    /// Only show keys with this label
+    label_filter: Option<String>,
+    /// Active key's npub and its QR code
+    npub_qr: Option<(String, qr_code::Data)>,
+    show_npub_qr: bool,
+    app_quotas: Vec<AppQuota>,
     
     // Settings
     auto_start: bool,
     notifications_enabled: bool,
+    tray_left_click: TrayClickAction,
     
     // Bunker
     bunker_enabled: bool,
     bunker_uri: Option<String>,
+    /// Read-only, selectable view of the full bunker URI
+    bunker_uri_content: text_editor::Content,
+    /// QR code of the bunker URI, for scanning from a phone
+    bunker_qr: Option<qr_code::Data>,
+    /// Bunker is connected but refusing sign/decrypt requests
+    bunker_paused: bool,
+    /// Each bunker relay and whether it is connected
+    bunker_relays: Vec<(String, bool)>,
+    
+    // Approval
+    /// Seconds left before the Approve button is enabled
+    approval_countdown: u64,
+    /// Requests waiting for approval, oldest first
+    pending_requests: Vec<SigningRequest>,
+    /// Key override and rejection reason picked for each pending request
+    request_choices: HashMap<String, RequestChoice>,
+    /// Whether this window has focus (reported to the signer so it skips
+    /// request notifications)
+    window_focused: bool,
+    
+    // Activity
+    audit_stats: Option<AuditStats>,
+    stats_range: StatsRange,
+    /// Latest requests handled by the bunker, newest first
+    remote_activity: Vec<AuditEntry>,
     
     // Shared state
     key_manager: Arc<Mutex<KeyManager>>,
@@ -90,11 +240,37 @@ impl Default for PlebSignerUi {
             success_message: None,
             key_name_input: String::new(),
             import_key_input: String::new(),
+            import_password: String::new(),
+            mnemonic_content: text_editor::Content::new(),
+            mnemonic_passphrase: String::new(),
+            mnemonic_account: String::new(),
             keys_list: Vec::new(),
+            label_input: String::new(),
+            relays_input: String::new(),
+            export_key: None,
+            export_password: String::new(),
+            password_key: None,
+            key_password: String::new(),
+            label_filter: None,
+            npub_qr: None,
+            show_npub_qr: false,
+            app_quotas: Vec::new(),
             auto_start: false,
             notifications_enabled: true,
+            tray_left_click: TrayClickAction::default(),
             bunker_enabled: false,
             bunker_uri: None,
+            bunker_uri_content: text_editor::Content::new(),
+            bunker_qr: None,
+            bunker_paused: false,
+            bunker_relays: Vec::new(),
+            approval_countdown: 0,
+            pending_requests: Vec::new(),
+            request_choices: HashMap::new(),
+            window_focused: false,
+            audit_stats: None,
+            stats_range: StatsRange::default(),
+            remote_activity: Vec::new(),
             key_manager: Arc::new(Mutex::new(KeyManager::new())),
             config: Config::default_config(),
         }
@@ -109,18 +285,44 @@ impl PlebSignerUi {
             success_message: None,
             key_name_input: String::new(),
             import_key_input: String::new(),
+            import_password: String::new(),
+            mnemonic_content: text_editor::Content::new(),
+            mnemonic_passphrase: String::new(),
+            mnemonic_account: String::new(),
             keys_list: Vec::new(),
+            label_input: String::new(),
+            relays_input: String::new(),
+            export_key: None,
+            export_password: String::new(),
+            password_key: None,
+            key_password: String::new(),
+            label_filter: None,
+            npub_qr: None,
+            show_npub_qr: false,
+            app_quotas: Vec::new(),
             auto_start: config.general.auto_start,
             notifications_enabled: config.general.show_notifications,
+            tray_left_click: config.ui.tray_left_click,
             bunker_enabled: false,
             bunker_uri: None,
+            bunker_uri_content: text_editor::Content::new(),
+            bunker_qr: None,
+            bunker_paused: false,
+            bunker_relays: Vec::new(),
+            approval_countdown: 0,
+            pending_requests: Vec::new(),
+            request_choices: HashMap::new(),
+            window_focused: false,
+            audit_stats: None,
+            stats_range: StatsRange::default(),
+            remote_activity: Vec::new(),
             key_manager,
             config,
         };
         
         // Load keys on startup
         let km = ui.key_manager.clone();
-        let task = Task::perform(
+        let keys_task = Task::perform(
             async move {
                 let mut manager = km.lock().await;
                 let _ = manager.load().await;
@@ -129,7 +331,29 @@ impl PlebSignerUi {
             Message::KeysRefreshed,
         );
         
-        (ui, task)
+        // Load remaining daily quotas for apps that have one
+        let apps = ui.config.authorized_apps.clone();
+        let quota_task = Task::perform(
+            async move {
+                let Ok(log) = AuditLog::load().await else {
+                    return Vec::new();
+                };
+                let now = chrono::Utc::now();
+                apps.iter()
+                    .filter_map(|app| {
+                        let quota = app.permissions.daily_sign_quota?;
+                        Some(AppQuota {
+                            app_name: app.name.clone(),
+                            remaining: log.remaining_quota(&app.app_id, Some(quota), now)?,
+                            quota,
+                        })
+                    })
+                    .collect()
+            },
+            Message::QuotasRefreshed,
+        );
+        
+        (ui, Task::batch([keys_task, quota_task]))
     }
 
     pub fn title(&self) -> String {
@@ -142,6 +366,16 @@ impl PlebSignerUi {
                 self.view = view;
                 self.error_message = None;
                 self.success_message = None;
+                if self.view == ViewState::Activity {
+                    return Task::perform(
+                        async {
+                            AuditLog::load().await
+                                .map(|log| (log.stats().clone(), remote_feed(&log)))
+                                .map_err(|e| e.to_string())
+                        },
+                        Message::StatsLoaded,
+                    );
+                }
                 Task::none()
             }
             
@@ -150,6 +384,11 @@ impl PlebSignerUi {
                 Task::none()
             }
             
+            Message::ImportPasswordInput(password) => {
+                self.import_password = password;
+                Task::none()
+            }
+            
             Message::ImportKeyInput(key) => {
                 self.import_key_input = key;
                 Task::none()
@@ -165,9 +404,9 @@ impl PlebSignerUi {
                 let km = self.key_manager.clone();
                 Task::perform(
                     async move {
-                        let mut manager = km.lock().await;
-                        match manager.generate_key(&name).await {
-                            Ok(meta) => Ok(format!("Generated key: {}", meta.npub)),
+                        let generated = km.lock().await.generate_key(&name).await;
+                        match generated {
+                            Ok(meta) => Ok(signer_synced(format!("Generated key: {}", meta.npub)).await),
                             Err(e) => Err(e.to_string()),
                         }
                     },
@@ -178,6 +417,7 @@ impl PlebSignerUi {
             Message::ImportKey => {
                 let name = self.key_name_input.clone();
                 let secret = self.import_key_input.clone();
+                let password = self.import_password.clone();
                 
                 if name.is_empty() {
                     self.error_message = Some("Please enter a key name".into());
@@ -187,13 +427,32 @@ impl PlebSignerUi {
                     self.error_message = Some("Please enter the private key".into());
                     return Task::none();
                 }
+                let encrypted = secret.trim_start().starts_with("ncryptsec1");
+                if encrypted && password.is_empty() {
+                    self.error_message = Some("Please enter the password the key was encrypted with".into());
+                    return Task::none();
+                }
                 
                 let km = self.key_manager.clone();
                 Task::perform(
                     async move {
-                        let mut manager = km.lock().await;
-                        match manager.import_key(&name, &secret).await {
-                            Ok(meta) => Ok(format!("Imported key: {}", meta.npub)),
+                        let imported = {
+                            let mut manager = km.lock().await;
+                            // Exports that bundle relays and profile are JSON;
+                            // an npub is kept as a watch-only key
+                            if secret.trim_start().starts_with('{') {
+                                manager.import_combined(&name, &secret).await
+                            } else if encrypted {
+                                manager.import_encrypted(&name, secret.trim(), &password).await
+                            } else if secret.trim_start().starts_with("npub1") {
+                                manager.add_watch_key(&name, &secret).await
+                            } else {
+                                manager.import_key(&name, &secret).await
+                            }
+                        };
+                        match imported {
+                            Ok(meta) if meta.watch_only => Ok(signer_synced(format!("Added watch-only key: {}", meta.npub)).await),
+                            Ok(meta) => Ok(signer_synced(format!("Imported key: {}", meta.npub)).await),
                             Err(e) => Err(e.to_string()),
                         }
                     },
@@ -201,13 +460,57 @@ impl PlebSignerUi {
                 )
             }
             
-            Message::DeleteKey(name) => {
+            Message::MnemonicAction(action) => {
+                self.mnemonic_content.perform(action);
+                Task::none()
+            }
+            
+            Message::MnemonicPassphraseInput(passphrase) => {
+                self.mnemonic_passphrase = passphrase;
+                Task::none()
+            }
+            
+            Message::MnemonicAccountInput(account) => {
+                self.mnemonic_account = account;
+                Task::none()
+            }
+            
+            Message::ImportMnemonic => {
+                let name = self.key_name_input.clone();
+                if name.is_empty() {
+                    self.error_message = Some("Please enter a key name".into());
+                    return Task::none();
+                }
+                let mnemonic = match normalize_mnemonic(&self.mnemonic_content.text()) {
+                    Ok(mnemonic) => mnemonic,
+                    Err(e) => {
+                        self.error_message = Some(e);
+                        return Task::none();
+                    }
+                };
+                let passphrase = Some(self.mnemonic_passphrase.clone()).filter(|p| !p.is_empty());
+                let account = match self.mnemonic_account.trim() {
+                    "" => None,
+                    account => match account.parse::<u32>() {
+                        Ok(account) => Some(account),
+                        Err(_) => {
+                            self.error_message = Some("The account number must be a whole number like 0, 1 or 2".into());
+                            return Task::none();
+                        }
+                    },
+                };
+                
                 let km = self.key_manager.clone();
                 Task::perform(
                     async move {
-                        let mut manager = km.lock().await;
-                        match manager.delete_key(&name).await {
-                            Ok(_) => Ok("Key deleted".to_string()),
+                        let imported = km.lock().await
+                            .import_from_mnemonic(&name, &mnemonic, passphrase.as_deref(), account)
+                            .await;
+                        match imported {
+                            Ok(meta) => Ok(signer_synced(format!("Imported key: {}", meta.npub)).await),
+                            Err(SignerError::InvalidKeyFormat(_)) => Err(
+                                "That recovery phrase isn't valid. Check for misspelled words and that they're in the right order.".into()
+                            ),
                             Err(e) => Err(e.to_string()),
                         }
                     },
@@ -215,13 +518,162 @@ impl PlebSignerUi {
                 )
             }
             
-            Message::SelectKey(name) => {
+            Message::ExportKey(name) => {
+                self.export_key = Some(name);
+                self.export_password.clear();
+                Task::none()
+            }
+            
+            Message::ExportPasswordInput(password) => {
+                self.export_password = password;
+                Task::none()
+            }
+            
+            Message::CancelExport => {
+                self.export_key = None;
+                self.export_password.clear();
+                Task::none()
+            }
+            
+            Message::EditKeyPassword(name) => {
+                self.password_key = Some(name);
+                self.key_password.clear();
+                Task::none()
+            }
+            
+            Message::KeyPasswordInput(password) => {
+                self.key_password = password;
+                Task::none()
+            }
+            
+            Message::CancelKeyPassword => {
+                self.password_key = None;
+                self.key_password.clear();
+                Task::none()
+            }
+            
+            Message::SetKeyPassword | Message::ClearKeyPassword if self.key_password.is_empty() => {
+                self.error_message = Some("Please enter the key's password".into());
+                Task::none()
+            }
+            
+            Message::SetKeyPassword => {
+                let Some(name) = self.password_key.clone() else {
+                    return Task::none();
+                };
+                let password = self.key_password.clone();
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        km.lock().await.set_key_password(&name, Some(&password)).await.map_err(|e| e.to_string())?;
+                        Ok(signer_synced(format!("{} now needs its own password", name)).await)
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
+            Message::ClearKeyPassword => {
+                let Some(name) = self.password_key.clone() else {
+                    return Task::none();
+                };
+                let password = self.key_password.clone();
                 let km = self.key_manager.clone();
                 Task::perform(
                     async move {
+                        // Removing the password takes the current one
                         let mut manager = km.lock().await;
-                        match manager.set_active_key(&name).await {
-                            Ok(_) => Ok(format!("Active key: {}", name)),
+                        manager.unlock_key(&name, &password).map_err(|e| e.to_string())?;
+                        manager.set_key_password(&name, None).await.map_err(|e| e.to_string())?;
+                        drop(manager);
+                        Ok(signer_synced(format!("Removed the password from {}", name)).await)
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
+            Message::ConfirmExport => {
+                let Some(name) = self.export_key.clone() else {
+                    return Task::none();
+                };
+                if self.export_password.is_empty() {
+                    self.error_message = Some("Please enter a password to encrypt the key with".into());
+                    return Task::none();
+                }
+                let password = self.export_password.clone();
+                let log_n = self.config.security.ncryptsec_log_n;
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let file_name = format!("{}.ncryptsec", name);
+                        let path = tokio::task::spawn_blocking(move || {
+                            rfd::FileDialog::new()
+                                .set_title("Export encrypted key")
+                                .set_file_name(file_name)
+                                .save_file()
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?;
+                        let Some(path) = path else {
+                            return Ok(None);
+                        };
+                        let manager = km.lock().await;
+                        manager.export_encrypted_to_file(&name, &password, log_n, &path).await
+                            .map_err(|e| e.to_string())?;
+                        Ok(Some(format!("Exported {} to {}", name, path.display())))
+                    },
+                    Message::KeyExported,
+                )
+            }
+            
+            Message::CopyExport => {
+                let Some(name) = self.export_key.clone() else {
+                    return Task::none();
+                };
+                if self.export_password.is_empty() {
+                    self.error_message = Some("Please enter a password to encrypt the key with".into());
+                    return Task::none();
+                }
+                let password = self.export_password.clone();
+                let log_n = self.config.security.ncryptsec_log_n;
+                let clear_secs = self.config.security.clipboard_clear_secs;
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let ncryptsec = km.lock().await
+                            .export_encrypted(&name, &password, log_n, nostr::nips::nip49::KeySecurity::Medium).await
+                            .map_err(|e| e.to_string())?;
+                        crate::clipboard::copy_sensitive(&ncryptsec, clear_secs)?;
+                        Ok(Some(format!("Encrypted {} copied to clipboard{}", name, clear_note(clear_secs))))
+                    },
+                    Message::KeyExported,
+                )
+            }
+            
+            Message::KeyExported(result) => {
+                match result {
+                    Ok(Some(msg)) => {
+                        self.success_message = Some(msg);
+                        self.error_message = None;
+                        self.export_key = None;
+                        self.export_password.clear();
+                    }
+                    // The file dialog was cancelled; keep the prompt open
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.error_message = Some(format!("Export failed: {}", e));
+                        self.success_message = None;
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::DeleteKey(name) => {
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let deleted = km.lock().await.delete_key(&name).await;
+                        match deleted {
+                            Ok(_) => Ok(signer_synced("Key deleted".to_string()).await),
                             Err(e) => Err(e.to_string()),
                         }
                     },
@@ -229,6 +681,17 @@ impl PlebSignerUi {
                 )
             }
             
+            Message::SelectKey(name) => {
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        km.lock().await.set_active_key(&name).await.map_err(|e| e.to_string())?;
+                        Ok(signer_synced(format!("Active key: {}", name)).await)
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
             Message::KeyOperationComplete(result) => {
                 match result {
                     Ok(msg) => {
@@ -236,6 +699,12 @@ impl PlebSignerUi {
                         self.error_message = None;
                         self.key_name_input.clear();
                         self.import_key_input.clear();
+                        self.import_password.clear();
+                        self.password_key = None;
+                        self.key_password.clear();
+                        self.mnemonic_content = text_editor::Content::new();
+                        self.mnemonic_passphrase.clear();
+                        self.mnemonic_account.clear();
                         self.view = ViewState::KeyManagement;
                     }
                     Err(e) => {
@@ -268,6 +737,149 @@ impl PlebSignerUi {
             
             Message::KeysRefreshed(keys) => {
                 self.keys_list = keys;
+                self.npub_qr = self.keys_list.iter()
+                    .find(|k| k.is_active)
+                    .and_then(|k| Some((k.npub.clone(), qr_code::Data::new(&k.npub).ok()?)));
+                // Keys but no active one: ask the user to choose
+                if self.view == ViewState::Main
+                    && !self.keys_list.is_empty()
+                    && !self.keys_list.iter().any(|k| k.is_active)
+                {
+                    self.view = ViewState::KeyManagement;
+                    self.error_message = Some("No active key is set. Choose which key to sign with.".into());
+                }
+                // Drop a filter whose label no longer exists
+                if let Some(ref label) = self.label_filter {
+                    if !self.keys_list.iter().any(|k| k.labels.contains(label)) {
+                        self.label_filter = None;
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::KeyLabelInput(label) => {
+                self.label_input = label;
+                Task::none()
+            }
+            
+            Message::AddKeyLabel(name) => {
+                let label = self.label_input.trim().to_string();
+                if label.is_empty() {
+                    self.error_message = Some("Please enter a label".into());
+                    return Task::none();
+                }
+                self.label_input.clear();
+                
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let mut manager = km.lock().await;
+                        match manager.add_label(&name, &label).await {
+                            Ok(_) => Ok(format!("Labelled {} as '{}'", name, label)),
+                            Err(e) => Err(e.to_string()),
+                        }
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
+            Message::KeyRelaysInput(relays) => {
+                self.relays_input = relays;
+                Task::none()
+            }
+            
+            Message::SetKeyRelays(name) => {
+                let relays: Vec<String> = self.relays_input
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|r| !r.is_empty())
+                    .map(String::from)
+                    .collect();
+                self.relays_input.clear();
+                
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let mut manager = km.lock().await;
+                        match manager.set_key_relays(&name, &relays).await {
+                            Ok(_) if relays.is_empty() => Ok(format!("Cleared relays of {}", name)),
+                            Ok(_) => Ok(format!("Set {} relay(s) for {}; bunker mode uses them after a restart", relays.len(), name)),
+                            Err(e) => Err(e.to_string()),
+                        }
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
+            Message::RemoveKeyLabel(name, label) => {
+                let km = self.key_manager.clone();
+                Task::perform(
+                    async move {
+                        let mut manager = km.lock().await;
+                        match manager.remove_label(&name, &label).await {
+                            Ok(_) => Ok(format!("Removed label '{}' from {}", label, name)),
+                            Err(e) => Err(e.to_string()),
+                        }
+                    },
+                    Message::KeyOperationComplete,
+                )
+            }
+            
+            Message::ToggleNpubQr => {
+                self.show_npub_qr = !self.show_npub_qr;
+                Task::none()
+            }
+            
+            Message::FilterByLabel(label) => {
+                self.label_filter = label;
+                Task::none()
+            }
+            
+            Message::QuotasRefreshed(quotas) => {
+                self.app_quotas = quotas;
+                Task::none()
+            }
+            
+            Message::ExportSessionLog(app_id) => {
+                let app_name = self.config.get_authorized_app(&app_id).map(|a| a.name.clone());
+                Task::perform(
+                    async move {
+                        let log = AuditLog::load().await.map_err(|e| e.to_string())?;
+                        log.export_session_log(&app_id, app_name.as_deref())
+                            .await
+                            .map(|path| format!("Session log saved to {}", path.display()))
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::SessionLogExported,
+                )
+            }
+            
+            Message::StatsLoaded(result) => {
+                match result {
+                    Ok((stats, remote)) => {
+                        self.audit_stats = Some(stats);
+                        self.remote_activity = remote;
+                    }
+                    Err(e) => self.error_message = Some(format!("Failed to load activity: {}", e)),
+                }
+                Task::none()
+            }
+            
+            Message::SetStatsRange(range) => {
+                self.stats_range = range;
+                Task::none()
+            }
+            
+            Message::SessionLogExported(result) => {
+                match result {
+                    Ok(msg) => {
+                        self.success_message = Some(msg);
+                        self.error_message = None;
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("Failed to export session log: {}", e));
+                        self.success_message = None;
+                    }
+                }
                 Task::none()
             }
             
@@ -281,10 +893,16 @@ impl PlebSignerUi {
                 Task::none()
             }
             
+            Message::SetTrayLeftClick(action) => {
+                self.tray_left_click = action;
+                Task::none()
+            }
+            
             Message::SaveSettings => {
                 let mut config = self.config.clone();
                 config.general.auto_start = self.auto_start;
                 config.general.show_notifications = self.notifications_enabled;
+                config.ui.tray_left_click = self.tray_left_click;
                 
                 Task::perform(
                     async move {
@@ -308,6 +926,42 @@ impl PlebSignerUi {
                 Task::none()
             }
             
+            Message::ReloadConfig => {
+                Task::perform(
+                    async move {
+                        let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+                        client.reload_config().await.map_err(|e| e.to_string())
+                    },
+                    Message::ConfigReloaded,
+                )
+            }
+            
+            Message::ConfigReloaded(result) => {
+                match result {
+                    Ok(restart) if restart.is_empty() => {
+                        self.success_message = Some("Config reloaded".into());
+                        self.error_message = None;
+                    }
+                    Ok(restart) => {
+                        self.success_message = Some(format!(
+                            "Config reloaded. Restart Pleb Signer to apply: {}",
+                            restart.join(", ")
+                        ));
+                        self.error_message = None;
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("Could not reload config: {}", e));
+                        self.success_message = None;
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::CountdownTick => {
+                self.approval_countdown = self.approval_countdown.saturating_sub(1);
+                Task::none()
+            }
+            
             Message::Lock => {
                 // Lock the key manager
                 let km = self.key_manager.clone();
@@ -340,6 +994,8 @@ impl PlebSignerUi {
                 } else {
                     // Call D-Bus to stop the bunker
                     self.bunker_uri = None;
+                    self.bunker_paused = false;
+                    self.bunker_relays.clear();
                     Task::perform(
                         async move {
                             if let Ok(client) = PlebSignerClient::new("pleb-signer-ui").await {
@@ -360,7 +1016,7 @@ impl PlebSignerUi {
                             Ok(client) => {
                                 // First try to get existing URI, if not start bunker
                                 match client.get_bunker_state().await {
-                                    Ok(state) if state.contains("WaitingForConnection") || state.contains("Connected") => {
+                                    Ok(state) if state.contains("WaitingForConnection") || state.contains("Connected") || state.contains("Paused") => {
                                         client.get_bunker_uri().await.map_err(|e| e.to_string())
                                     }
                                     _ => {
@@ -378,6 +1034,8 @@ impl PlebSignerUi {
             Message::BunkerUriGenerated(result) => {
                 match result {
                     Ok(uri) => {
+                        self.bunker_uri_content = text_editor::Content::with_text(&uri);
+                        self.bunker_qr = qr_code::Data::new(&uri).ok();
                         self.bunker_uri = Some(uri);
                         self.error_message = None;
                     }
@@ -389,44 +1047,338 @@ impl PlebSignerUi {
                 Task::none()
             }
             
-            Message::CopyBunkerUri => {
-                if let Some(ref uri) = self.bunker_uri {
-                    // Use wl-copy for Wayland (arboard doesn't work on Wayland)
-                    // Fall back to arboard for X11
-                    let copied = if std::env::var("WAYLAND_DISPLAY").is_ok() {
-                        // Wayland - use wl-copy
-                        std::process::Command::new("wl-copy")
-                            .arg(uri)
-                            .spawn()
-                            .map(|mut child| child.wait().is_ok())
-                            .unwrap_or(false)
-                    } else {
-                        // X11 - use arboard
-                        arboard::Clipboard::new()
-                            .and_then(|mut clip| clip.set_text(uri.clone()))
-                            .is_ok()
-                    };
-                    
-                    if copied {
-                        self.success_message = Some("Bunker URI copied to clipboard!".into());
-                    } else {
-                        self.error_message = Some("Failed to copy to clipboard".into());
-                    }
+            Message::BunkerUriAction(action) => {
+                // Allow selection and scrolling, but keep the URI read-only
+                if !action.is_edit() {
+                    self.bunker_uri_content.perform(action);
                 }
                 Task::none()
             }
             
-            Message::Noop => Task::none(),
-        }
-    }
-
-    pub fn view(&self) -> Element<Message> {
-        let content: Element<Message> = match self.view {
+            Message::ToggleBunkerPause => {
+                let pause = !self.bunker_paused;
+                Task::perform(
+                    async move {
+                        let client = PlebSignerClient::new("pleb-signer-ui").await
+                            .map_err(|e| e.to_string())?;
+                        let result = if pause {
+                            client.pause_bunker().await
+                        } else {
+                            client.resume_bunker().await
+                        };
+                        result.map(|_| pause).map_err(|e| e.to_string())
+                    },
+                    Message::BunkerPauseChanged,
+                )
+            }
+            
+            Message::BunkerPauseChanged(result) => {
+                match result {
+                    Ok(paused) => {
+                        self.bunker_paused = paused;
+                        self.error_message = None;
+                    }
+                    Err(e) => self.error_message = Some(e),
+                }
+                Task::none()
+            }
+            
+            Message::ShareBunkerUri => {
+                let Some(uri) = self.bunker_uri.clone() else {
+                    return Task::none();
+                };
+                let lifetime = std::time::Duration::from_secs(self.config.bunker.share_page_secs);
+                Task::perform(share_bunker_uri(uri, self.config.bunker.share_on_lan, lifetime), Message::BunkerUriShared)
+            }
+            
+            Message::BunkerUriShared(Ok(url)) => {
+                let mut msg = format!("Open {} to scan the URI (expires in {}s)", url, self.config.bunker.share_page_secs);
+                if self.config.bunker.share_on_lan {
+                    msg.push_str(". Anyone on your network with this address can read the URI until then.");
+                }
+                self.success_message = Some(msg);
+                Task::none()
+            }
+            
+            Message::BunkerUriShared(Err(e)) => {
+                self.error_message = Some(format!("Could not share the URI: {}", e));
+                Task::none()
+            }
+            
+            Message::CopyBunkerUri => {
+                if let Some(ref uri) = self.bunker_uri {
+                    let clear_secs = self.config.security.clipboard_clear_secs;
+                    match crate::clipboard::copy_sensitive(uri, clear_secs) {
+                        Ok(()) => {
+                            self.success_message = Some(format!("Bunker URI copied to clipboard{}", clear_note(clear_secs)));
+                        }
+                        Err(e) => {
+                            self.error_message = Some(format!("Failed to copy to clipboard: {}", e));
+                        }
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::PollPending => {
+                let focused = self.window_focused;
+                let pending = Task::perform(
+                    async move {
+                        let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+                        // Renew the focus report, which lapses on its own
+                        if focused {
+                            let _ = client.set_window_focused(true).await;
+                        }
+                        client.list_pending_requests().await.map_err(|e| e.to_string())
+                    },
+                    Message::PendingRefreshed,
+                );
+                if self.view != ViewState::Bunker || self.bunker_uri.is_none() {
+                    return pending;
+                }
+                let relays = Task::perform(
+                    async move {
+                        let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+                        client.get_bunker_relay_status().await.map_err(|e| e.to_string())
+                    },
+                    Message::BunkerRelaysRefreshed,
+                );
+                Task::batch([pending, relays])
+            }
+            
+            Message::BunkerRelaysRefreshed(Ok(relays)) => {
+                self.bunker_relays = relays;
+                Task::none()
+            }
+            
+            Message::BunkerRelaysRefreshed(Err(e)) => {
+                tracing::debug!("Could not fetch bunker relay status: {}", e);
+                Task::none()
+            }
+            
+            Message::WindowFocused(focused) => {
+                self.window_focused = focused;
+                Task::perform(
+                    async move {
+                        if let Ok(client) = PlebSignerClient::new("pleb-signer-ui").await {
+                            let _ = client.set_window_focused(focused).await;
+                        }
+                    },
+                    |_| Message::Noop,
+                )
+            }
+            
+            Message::PendingRefreshed(Ok(requests)) => {
+                if self.set_pending_requests(requests) {
+                    // Bring the window forward for the new request
+                    self.view = ViewState::Approval;
+                    window::get_latest().and_then(window::gain_focus)
+                } else {
+                    Task::none()
+                }
+            }
+            
+            Message::PendingRefreshed(Err(e)) => {
+                tracing::debug!("Could not fetch pending requests: {}", e);
+                Task::none()
+            }
+            
+            Message::ApproveRequest(id) | Message::ApproveForSession(id) | Message::RejectRequest(id)
+                if !self.pending_requests.iter().any(|r| r.id == id) =>
+            {
+                Task::none()
+            }
+            
+            Message::ApproveRequest(id) | Message::ApproveForSession(id) if !self.can_approve(&id) => Task::none(),
+
+            Message::ApproveRequest(id) => {
+                let answer = self.chosen_answer(&id, true);
+                self.answer_request(id, answer)
+            }
+            
+            Message::ApproveForSession(id) => self.answer_request(id, Answer::ApproveForSession),
+            
+            Message::RejectRequest(id) => {
+                let answer = self.chosen_answer(&id, false);
+                self.answer_request(id, answer)
+            }
+            
+            Message::SigningKeyPicked(id, key) => {
+                self.request_choices.entry(id).or_default().signing_key = Some(key);
+                Task::none()
+            }
+            
+            Message::RejectionReasonChanged(id, reason) => {
+                self.request_choices.entry(id).or_default().rejection_reason = reason;
+                Task::none()
+            }
+            
+            Message::RequestAnswered(result) => {
+                if let Err(e) = result {
+                    self.error_message = Some(e);
+                }
+                Task::done(Message::PollPending)
+            }
+            
+            Message::SuppressNip04Warning(app_id) => {
+                for request in self.pending_requests.iter_mut().filter(|r| r.app_id == app_id) {
+                    request.warnings.retain(|w| w != NIP04_WARNING);
+                }
+                if !self.config.security.nip04_warning_suppressed.contains(&app_id) {
+                    self.config.security.nip04_warning_suppressed.push(app_id.clone());
+                }
+                let path = self.config.config_path().clone();
+                Task::perform(
+                    async move {
+                        // Start from the file, so changes the signer made since we started are kept
+                        let mut config = Config::load_from(path).await.map_err(|e| e.to_string())?;
+                        if !config.security.nip04_warning_suppressed.contains(&app_id) {
+                            config.security.nip04_warning_suppressed.push(app_id);
+                            config.save().await.map_err(|e| e.to_string())?;
+                        }
+                        let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+                        client.reload_config().await.map_err(|e| e.to_string())?;
+                        Ok(())
+                    },
+                    Message::Nip04WarningSuppressed,
+                )
+            }
+            
+            Message::Nip04WarningSuppressed(result) => {
+                if let Err(e) = result {
+                    self.error_message = Some(format!("Could not save the NIP-04 warning setting: {}", e));
+                }
+                Task::none()
+            }
+            
+            Message::Noop => Task::none(),
+        }
+    }
+
+    /// Replace the pending requests; true if any of them are new
+    fn set_pending_requests(&mut self, requests: Vec<SigningRequest>) -> bool {
+        let arrived = requests.iter().any(|r| !self.pending_requests.iter().any(|p| p.id == r.id));
+        let top = |requests: &[SigningRequest]| requests.first().map(|r| r.id.clone());
+        if top(&requests) != top(&self.pending_requests) {
+            self.start_approval_countdown(requests.first().and_then(event_kind));
+        }
+        self.pending_requests = requests;
+        let pending = &self.pending_requests;
+        self.request_choices.retain(|id, _| pending.iter().any(|r| r.id == *id));
+        if self.pending_requests.is_empty() && self.view == ViewState::Approval {
+            self.view = ViewState::Main;
+        }
+        arrived
+    }
+
+    /// Approval or rejection of a request, with the key or reason picked for it
+    fn chosen_answer(&self, id: &str, approve: bool) -> Answer {
+        let choice = self.request_choices.get(id).cloned().unwrap_or_default();
+        match (approve, choice.signing_key) {
+            (true, Some(key)) => Answer::ApproveWithKey(key),
+            (true, None) => Answer::Approve,
+            (false, _) => Answer::Reject(choice.rejection_reason),
+        }
+    }
+
+    /// Send the user's decision on a request to the signer
+    fn answer_request(&mut self, id: String, answer: Answer) -> Task<Message> {
+        let remaining = self.pending_requests.iter().filter(|r| r.id != id).cloned().collect();
+        self.set_pending_requests(remaining);
+        Task::perform(
+            async move {
+                let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+                let result = match answer {
+                    Answer::Approve => client.answer_request(&id, true, "").await,
+                    Answer::ApproveForSession => client.approve_for_session(&id).await,
+                    Answer::ApproveWithKey(key) => client.approve_with_key(&id, &key).await,
+                    Answer::Reject(reason) => client.answer_request(&id, false, &reason).await,
+                };
+                result.map_err(|e| e.to_string())
+            },
+            Message::RequestAnswered,
+        )
+    }
+
+    /// Event content to preview in the approval view, if the settings allow it
+    fn content_preview<'a>(&self, request: &'a SigningRequest) -> Option<&'a str> {
+        if !self.config.ui.show_event_content || self.config.ui.compact_mode {
+            return None;
+        }
+        match request.payload {
+            SigningPayload::Event(ref event) => Some(event.content.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Start the approval countdown for a request (kind None for non-sign requests)
+    fn start_approval_countdown(&mut self, kind: Option<u16>) {
+        self.approval_countdown = kind.and_then(|k| self.config.ui.countdown_for(k)).unwrap_or(0);
+    }
+    
+    /// Whether the Approve button can be pressed yet
+    fn approve_enabled(&self) -> bool {
+        self.approval_countdown == 0
+    }
+
+    /// Whether request `id` can be approved now: the countdown runs for the
+    /// front request, so a later one with a countdown waits its turn
+    fn can_approve(&self, id: &str) -> bool {
+        match self.pending_requests.iter().position(|r| r.id == id) {
+            Some(0) => self.approve_enabled(),
+            Some(index) => event_kind(&self.pending_requests[index])
+                .and_then(|kind| self.config.ui.countdown_for(kind))
+                .is_none(),
+            None => false,
+        }
+    }
+    
+    pub fn subscription(&self) -> Subscription<Message> {
+        let poll = iced::time::every(std::time::Duration::from_secs(2)).map(|_| Message::PollPending);
+        let focus = iced::event::listen_with(|event, _status, _window| match event {
+            iced::Event::Window(window::Event::Focused) => Some(Message::WindowFocused(true)),
+            iced::Event::Window(window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+            _ => None,
+        });
+        if self.approval_countdown > 0 {
+            Subscription::batch([
+                poll,
+                focus,
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::CountdownTick),
+            ])
+        } else {
+            Subscription::batch([poll, focus])
+        }
+    }
+    
+    pub fn view(&self) -> Element<Message> {
+        let content: Element<Message> = match self.view {
             ViewState::Main => self.view_main(),
             ViewState::KeyManagement => self.view_keys(),
             ViewState::Settings => self.view_settings(),
             ViewState::AddKey => self.view_add_key(),
             ViewState::Bunker => self.view_bunker(),
+            ViewState::Apps => self.view_apps(),
+            ViewState::Approval => self.view_approval(),
+            ViewState::Activity => self.view_activity(),
+        };
+        
+        let content: Element<Message> = if self.config.safe_mode {
+            column![
+                container(
+                    text("SAFE MODE - bunker and auto-approval are disabled; security settings are not saved")
+                        .size(13)
+                        .color(iced::Color::WHITE)
+                )
+                .padding(8)
+                .width(Length::Fill)
+                .style(|_theme: &Theme| container::background(iced::Color::from_rgb(0.8, 0.3, 0.1))),
+                content,
+            ]
+            .spacing(10)
+            .into()
+        } else {
+            content
         };
         
         container(content)
@@ -441,6 +1393,8 @@ impl PlebSignerUi {
             text("⚡ Pleb Signer").size(28),
             horizontal_space(),
             button(text("Keys")).on_press(Message::NavigateTo(ViewState::KeyManagement)),
+            button(text("Apps")).on_press(Message::NavigateTo(ViewState::Apps)),
+            button(text("Activity")).on_press(Message::NavigateTo(ViewState::Activity)),
             button(text("Settings")).on_press(Message::NavigateTo(ViewState::Settings)),
         ]
         .spacing(10)
@@ -454,13 +1408,34 @@ impl PlebSignerUi {
             "No active key selected".to_string()
         };
         
-        let status = column![
+        let mut status = column![
             text("Status: Ready").size(16),
             text(active_key_text).size(14),
             text(format!("Keys: {}", self.keys_list.len())).size(14),
         ]
         .spacing(8);
         
+        if let Some((ref npub, ref qr)) = self.npub_qr {
+            let toggle_label = if self.show_npub_qr { "Hide QR" } else { "Show npub QR" };
+            status = status.push(button(text(toggle_label).size(12)).on_press(Message::ToggleNpubQr));
+            if self.show_npub_qr {
+                status = status.push(qr_panel(qr, "Scan to add this key in a client", npub));
+            }
+        }
+        
+        if !self.pending_requests.is_empty() {
+            status = status.push(
+                button(text(format!("{} request(s) waiting for approval", self.pending_requests.len())).size(14))
+                    .on_press(Message::NavigateTo(ViewState::Approval))
+            );
+        }
+        
+        for quota in &self.app_quotas {
+            status = status.push(
+                text(format!("{}: {} of {} signatures left today", quota.app_name, quota.remaining, quota.quota)).size(12)
+            );
+        }
+        
         let mut content = column![header, status].spacing(30).padding(10);
         
         if let Some(ref msg) = self.success_message {
@@ -506,22 +1481,40 @@ impl PlebSignerUi {
         } else {
             let keys: Vec<Element<Message>> = self.keys_list
                 .iter()
+                .filter(|key| key.matches_label(self.label_filter.as_deref()))
                 .map(|key| {
                     let active_indicator = if key.is_active { "● " } else { "○ " };
+                    let watch_marker = if key.watch_only { "  👁 Watch-only" } else { "" };
                     let name = key.name.clone();
                     let name_for_select = key.name.clone();
                     let name_for_delete = key.name.clone();
                     
+                    let labels = key.labels.iter().fold(row![].spacing(4), |labels, label| {
+                        labels.push(
+                            button(text(format!("{} ×", label)).size(11))
+                                .padding([2, 6])
+                                .style(button::secondary)
+                                .on_press(Message::RemoveKeyLabel(key.name.clone(), label.clone())),
+                        )
+                    });
+                    
                     container(
                         row![
                             column![
-                                text(format!("{}{}", active_indicator, name)).size(16),
+                                text(format!("{}{}{}", active_indicator, name, watch_marker)).size(16),
                                 text(format!("{}...", &key.npub[..30.min(key.npub.len())])).size(12),
+                                text(if key.relays.is_empty() { "Default relays".to_string() } else { key.relays.join(", ") }).size(11),
+                                labels,
                             ]
                             .spacing(4),
                             horizontal_space(),
+                            button(text("+ Label")).on_press(Message::AddKeyLabel(key.name.clone())),
+                            button(text("Set Relays")).on_press(Message::SetKeyRelays(key.name.clone())),
+                            // Watch-only keys have no secret to export or sign with
+                            button(text("Export")).on_press_maybe((!key.watch_only).then(|| Message::ExportKey(key.name.clone()))),
+                            button(text("Password")).on_press_maybe((!key.watch_only).then(|| Message::EditKeyPassword(key.name.clone()))),
                             if !key.is_active {
-                                button(text("Set Active")).on_press(Message::SelectKey(name_for_select))
+                                button(text("Set Active")).on_press_maybe((!key.watch_only).then_some(Message::SelectKey(name_for_select)))
                             } else {
                                 button(text("✓ Active")).style(button::success)
                             },
@@ -540,7 +1533,75 @@ impl PlebSignerUi {
             scrollable(column(keys).spacing(10)).height(Length::Fill).into()
         };
         
-        let mut content = column![header, keys_list].spacing(20);
+        // Label filter and input for new labels
+        let mut all_labels: Vec<&String> = self.keys_list.iter().flat_map(|k| &k.labels).collect();
+        all_labels.sort();
+        all_labels.dedup();
+        let filter_button = |label: &str, filter: Option<String>| {
+            let style = if self.label_filter == filter { button::primary } else { button::secondary };
+            button(text(label.to_string()).size(12)).style(style).on_press(Message::FilterByLabel(filter))
+        };
+        let filters = all_labels.iter().fold(
+            row![text("Filter:").size(12), filter_button("All", None)]
+                .spacing(6)
+                .align_y(iced::Alignment::Center),
+            |filters, label| filters.push(filter_button(label, Some(label.to_string()))),
+        );
+        let label_row = row![
+            filters,
+            horizontal_space(),
+            text_input("New label", &self.label_input)
+                .on_input(Message::KeyLabelInput)
+                .width(Length::Fixed(160.0)),
+            text_input("wss://relay1, wss://relay2", &self.relays_input)
+                .on_input(Message::KeyRelaysInput)
+                .width(Length::Fixed(220.0)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+        
+        let mut content = column![header, label_row].spacing(20);
+        if let Some(ref name) = self.export_key {
+            content = content.push(
+                row![
+                    text(format!("Encrypt '{}' with password (NIP-49)", name)).size(14),
+                    text_input("Password", &self.export_password)
+                        .on_input(Message::ExportPasswordInput)
+                        .on_submit(Message::ConfirmExport)
+                        .secure(true)
+                        .width(Length::Fixed(200.0)),
+                    button(text("Save As…")).on_press(Message::ConfirmExport),
+                    button(text("Copy")).on_press(Message::CopyExport),
+                    button(text("Cancel")).style(button::secondary).on_press(Message::CancelExport),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+        if let Some(ref name) = self.password_key {
+            let protected = self.keys_list.iter().any(|k| k.name == *name && k.is_password_protected());
+            let (prompt, action) = if protected {
+                (format!("Current password of '{}'", name), button(text("Remove Password")).on_press(Message::ClearKeyPassword))
+            } else {
+                (format!("Require a password to use '{}'", name), button(text("Set Password")).on_press(Message::SetKeyPassword))
+            };
+            let submit = if protected { Message::ClearKeyPassword } else { Message::SetKeyPassword };
+            content = content.push(
+                row![
+                    text(prompt).size(14),
+                    text_input("Password", &self.key_password)
+                        .on_input(Message::KeyPasswordInput)
+                        .on_submit(submit)
+                        .secure(true)
+                        .width(Length::Fixed(200.0)),
+                    action,
+                    button(text("Cancel")).style(button::secondary).on_press(Message::CancelKeyPassword),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+        content = content.push(keys_list);
         
         if let Some(ref msg) = self.success_message {
             content = content.push(
@@ -584,22 +1645,57 @@ impl PlebSignerUi {
         
         let import_section = column![
             text("Or Import Existing Key").size(16),
-            text_input("nsec1... or hex private key", &self.import_key_input)
+            text_input("nsec1..., ncryptsec1..., hex private key, exported JSON with relays and profile, or npub1... (watch-only)", &self.import_key_input)
                 .on_input(Message::ImportKeyInput)
                 .padding(10)
                 .width(Length::Fixed(350.0))
                 .secure(true),
+            text_input("Password (ncryptsec1... only)", &self.import_password)
+                .on_input(Message::ImportPasswordInput)
+                .padding(10)
+                .width(Length::Fixed(350.0))
+                .secure(true),
             button(text("Import Key"))
                 .on_press(Message::ImportKey)
                 .padding([10, 20]),
         ]
         .spacing(10);
         
+        let mnemonic_section = column![
+            text("Or Restore From a Recovery Phrase").size(16),
+            text_editor(&self.mnemonic_content)
+                .placeholder("12 to 24 words, separated by spaces or new lines")
+                .on_action(Message::MnemonicAction)
+                .height(Length::Fixed(90.0))
+                .padding(10)
+                .width(350.0),
+            text_input("Passphrase (optional)", &self.mnemonic_passphrase)
+                .on_input(Message::MnemonicPassphraseInput)
+                .padding(10)
+                .width(Length::Fixed(350.0))
+                .secure(true),
+            row![
+                text("Account").size(14),
+                text_input("0", &self.mnemonic_account)
+                    .on_input(Message::MnemonicAccountInput)
+                    .padding(10)
+                    .width(Length::Fixed(80.0)),
+                text("Use the same number as your other clients").size(12),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            button(text("Import Recovery Phrase"))
+                .on_press(Message::ImportMnemonic)
+                .padding([10, 20]),
+        ]
+        .spacing(10);
+        
         let mut content = column![
             header,
             name_input,
             generate_section,
             import_section,
+            mnemonic_section,
         ]
         .spacing(25);
         
@@ -626,15 +1722,32 @@ impl PlebSignerUi {
         let notifications_checkbox = checkbox("Show notifications", self.notifications_enabled)
             .on_toggle(Message::ToggleNotifications);
         
+        let tray_click = column![
+            row![
+                text("Tray icon left click"),
+                pick_list(TrayClickAction::ALL, Some(self.tray_left_click), Message::SetTrayLeftClick),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text("Right click always opens the menu. Applies after restarting Pleb Signer.").size(12),
+        ]
+        .spacing(5);
+        
         let save_btn = button(text("Save Settings"))
             .on_press(Message::SaveSettings)
             .padding([10, 20]);
         
+        let reload_btn = button(text("Reload Config"))
+            .on_press(Message::ReloadConfig)
+            .padding([10, 20]);
+        
         let mut content = column![
             header,
             auto_start_checkbox,
             notifications_checkbox,
-            save_btn,
+            tray_click,
+            row![save_btn, reload_btn].spacing(10),
+            text("Reload applies edits to the config file without restarting.").size(12),
         ]
         .spacing(20);
         
@@ -653,6 +1766,140 @@ impl PlebSignerUi {
         content.into()
     }
     
+    fn view_apps(&self) -> Element<Message> {
+        let header = row![
+            button(text("← Back")).on_press(Message::NavigateTo(ViewState::Main)),
+            text("Authorized Apps").size(24),
+        ]
+        .spacing(20)
+        .align_y(iced::Alignment::Center);
+        
+        let mut apps_list = column![].spacing(10);
+        
+        if self.config.authorized_apps.is_empty() {
+            apps_list = apps_list.push(text("No apps have been authorized yet.").size(14));
+        }
+        
+        for app in &self.config.authorized_apps {
+            let mut info = column![
+                text(&app.name).size(16),
+                text(&app.app_id).size(11),
+            ]
+            .spacing(4);
+            
+            if let Some(quota) = self.app_quotas.iter().find(|q| q.app_name == app.name) {
+                info = info.push(
+                    text(format!("{} of {} signatures left today", quota.remaining, quota.quota)).size(12)
+                );
+            }
+            
+            let app_row = row![
+                info,
+                horizontal_space(),
+                button(text("Export Log").size(12))
+                    .on_press(Message::ExportSessionLog(app.app_id.clone())),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center);
+            
+            apps_list = apps_list.push(container(app_row).padding(10));
+        }
+        
+        let mut content = column![
+            header,
+            text("Exported session logs hide secrets and shorten public keys, so they can be shared in bug reports.").size(12),
+            scrollable(apps_list).height(Length::Fill),
+        ]
+        .spacing(20);
+        
+        if let Some(ref msg) = self.success_message {
+            content = content.push(
+                text(msg).size(14).color(iced::Color::from_rgb(0.2, 0.8, 0.2))
+            );
+        }
+        
+        if let Some(ref err) = self.error_message {
+            content = content.push(
+                text(err).size(14).color(iced::Color::from_rgb(0.9, 0.2, 0.2))
+            );
+        }
+        
+        content.into()
+    }
+    
+    fn view_activity(&self) -> Element<Message> {
+        let header = row![
+            button(text("← Back")).on_press(Message::NavigateTo(ViewState::Main)),
+            text("Activity").size(24),
+            horizontal_space(),
+            pick_list(StatsRange::ALL, Some(self.stats_range), Message::SetStatsRange),
+        ]
+        .spacing(20)
+        .align_y(iced::Alignment::Center);
+        
+        let mut content = column![header].spacing(20);
+        
+        if let Some(ref stats) = self.audit_stats {
+            let summary = stats.summary(self.stats_range, chrono::Utc::now(), TOP_STATS);
+            content = content.push(
+                text(format!(
+                    "{} requests, {} denied ({:.0}%)",
+                    summary.requests,
+                    summary.denied,
+                    summary.denial_rate() * 100.0,
+                ))
+                .size(16),
+            );
+            
+            let mut kinds = column![text("Most signed kinds").size(16)].spacing(6);
+            if summary.top_kinds.is_empty() {
+                kinds = kinds.push(text("Nothing signed in this range.").size(12));
+            }
+            for (kind, count) in &summary.top_kinds {
+                kinds = kinds.push(text(format!("Kind {}: {}", kind, count)).size(14));
+            }
+            
+            let mut apps = column![text("Most active apps").size(16)].spacing(6);
+            for (app_id, count) in &summary.top_apps {
+                let name = self.config.get_authorized_app(app_id).map_or(app_id.as_str(), |a| a.name.as_str());
+                apps = apps.push(text(format!("{}: {}", name, count)).size(14));
+            }
+            
+            content = content.push(row![kinds, apps].spacing(40));
+        }
+        
+        let mut remote = column![text("Remote signing (bunker)").size(16)].spacing(6);
+        if self.remote_activity.is_empty() {
+            remote = remote.push(text("No bunker requests yet.").size(12));
+        }
+        for entry in &self.remote_activity {
+            let client = entry.app_id.strip_prefix(crate::bunker::BUNKER_APP_PREFIX).unwrap_or(&entry.app_id);
+            let kind = entry.event_kind.map(|k| format!(" kind {}", k)).unwrap_or_default();
+            let outcome = match (entry.approved, &entry.reason) {
+                (true, _) => "done".to_string(),
+                (false, Some(reason)) => format!("refused: {}", reason),
+                (false, None) => "refused".to_string(),
+            };
+            remote = remote.push(text(format!(
+                "{} {}{} from {}… ({})",
+                entry.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S"),
+                entry.request_type.as_str(),
+                kind,
+                &client[..client.len().min(16)],
+                outcome,
+            )).size(12));
+        }
+        content = content.push(remote);
+        
+        if let Some(ref err) = self.error_message {
+            content = content.push(
+                text(err).size(14).color(iced::Color::from_rgb(0.9, 0.2, 0.2))
+            );
+        }
+        
+        content.into()
+    }
+    
     fn view_bunker(&self) -> Element<Message> {
         let header = row![
             button(text("← Back")).on_press(Message::NavigateTo(ViewState::Main)),
@@ -674,29 +1921,44 @@ impl PlebSignerUi {
             .on_toggle(Message::ToggleBunker);
         
         let uri_section: Element<Message> = if self.bunker_enabled {
-            if let Some(ref uri) = self.bunker_uri {
-                let display_uri: String = if uri.len() > 60 {
-                    format!("{}...", &uri[..60])
+            if self.bunker_uri.is_some() {
+                let relay_warning = crate::bunker::relay_status_warning(&self.bunker_relays);
+                let (pause_label, status) = if self.bunker_paused {
+                    ("▶ Resume", text("Status: Paused - sign and decrypt requests are refused").size(12).color([0.8, 0.5, 0.0]))
+                } else if let Some(warning) = relay_warning {
+                    ("⏸ Pause", text(format!("Status: {}", warning)).size(12).color([0.9, 0.2, 0.2]))
                 } else {
-                    uri.clone()
+                    ("⏸ Pause", text("Status: Connected to relays, waiting for client").size(12).color([0.0, 0.6, 0.0]))
                 };
-                
-                column![
+                let mut section = column![
                     text("Connection URI:").size(14),
-                    container(
-                        text(display_uri).size(12)
-                    )
-                    .padding(10)
-                    .style(container::bordered_box)
-                    .width(Length::Fill),
+                    text_editor(&self.bunker_uri_content)
+                        .on_action(Message::BunkerUriAction)
+                        .size(12)
+                        .padding(10)
+                        .height(Length::Fixed(90.0)),
                     text("").size(4),
-                    row![
-                        button(text("📋 Copy URI")).on_press(Message::CopyBunkerUri),
-                        button(text("🔄 Refresh")).on_press(Message::GenerateBunkerUri),
-                    ]
-                    .spacing(10),
+                ]
+                .spacing(4);
+                
+                if let Some(ref qr) = self.bunker_qr {
+                    section = section.push(qr_panel(qr, "Scan with your remote client", ""));
+                }
+                
+                let mut actions = row![
+                    button(text("📋 Copy URI")).on_press(Message::CopyBunkerUri),
+                    button(text("🔄 Refresh")).on_press(Message::GenerateBunkerUri),
+                    button(text(pause_label)).on_press(Message::ToggleBunkerPause),
+                ]
+                .spacing(10);
+                if cfg!(feature = "share-server") {
+                    actions = actions.push(button(text("🌐 Share page")).on_press(Message::ShareBunkerUri));
+                }
+                
+                section.push(column![
+                    actions,
                     text("").size(12),
-                    text("Status: Connected to relays, waiting for client").size(12).color([0.0, 0.6, 0.0]),
+                    status,
                     text("").size(8),
                     text("How to use:").size(14),
                     text("1. Copy the URI above").size(12),
@@ -705,7 +1967,7 @@ impl PlebSignerUi {
                     text("3. Paste this URI or scan it as QR code").size(12),
                     text("4. Your signing requests will appear here").size(12),
                 ]
-                .spacing(4)
+                .spacing(4))
                 .into()
             } else {
                 column![
@@ -746,11 +2008,165 @@ impl PlebSignerUi {
         content.into()
     }
     
+    fn view_approval(&self) -> Element<Message> {
+        let header = row![
+            button(text("← Back")).on_press(Message::NavigateTo(ViewState::Main)),
+            text("Approval Requests").size(24),
+        ]
+        .spacing(20)
+        .align_y(iced::Alignment::Center);
+        
+        let mut requests = column![].spacing(15);
+        if self.pending_requests.is_empty() {
+            requests = requests.push(text("No requests waiting").size(14));
+        }
+        for (index, request) in self.pending_requests.iter().enumerate() {
+            let app = match request.app_name {
+                Some(ref name) if *name != request.app_id => format!("{} ({})", name, request.app_id),
+                _ => request.app_id.clone(),
+            };
+            let mut details = column![
+                text(request.request_type.display_name()).size(16),
+                text(format!("From: {}", app)).size(12),
+            ]
+            .spacing(4);
+            if let Some(kind) = event_kind(request) {
+                details = details.push(text(format!("Event kind: {}", kind)).size(12));
+            }
+            let npub = self.keys_list.iter().find(|k| k.is_active).map(|k| k.npub.as_str()).unwrap_or("");
+            if let Some(banner) = self.config.ui.confirmation_banner(&app, event_kind(request), npub) {
+                details = details.push(text(banner).size(12));
+            }
+            if let Some(content) = self.content_preview(request) {
+                details = details.push(text(content).size(12));
+            }
+            for warning in &request.warnings {
+                details = details.push(text(format!("⚠ {}", warning)).size(12).color([0.8, 0.5, 0.0]));
+            }
+            if request.warnings.iter().any(|w| w == NIP04_WARNING) {
+                details = details.push(
+                    button(text("Don't warn again for this app").size(12))
+                        .style(button::text)
+                        .on_press(Message::SuppressNip04Warning(request.app_id.clone())),
+                );
+            }
+            
+            // The countdown applies to the request at the front of the queue
+            let can_approve = self.can_approve(&request.id);
+            let approve_label = match index {
+                0 if !can_approve => format!("Approve ({})", self.approval_countdown),
+                _ if !can_approve => "Approve (after the requests above)".to_string(),
+                _ => "Approve".to_string(),
+            };
+            let choice = self.request_choices.get(&request.id);
+            let signing_keys: Vec<String> = self.keys_list.iter()
+                .filter(|k| !k.watch_only)
+                .map(|k| k.name.clone())
+                .collect();
+            if signing_keys.len() > 1 {
+                let id = request.id.clone();
+                details = details.push(
+                    pick_list(signing_keys, choice.and_then(|c| c.signing_key.clone()), move |key| {
+                        Message::SigningKeyPicked(id.clone(), key)
+                    })
+                    .placeholder("Sign with the requested key")
+                    .text_size(12),
+                );
+            }
+            let session_label = format!("Allow for {} min", self.config.security.session_grant_mins);
+            details = details.push(
+                row![
+                    button(text(approve_label))
+                        .on_press_maybe(can_approve.then(|| Message::ApproveRequest(request.id.clone()))),
+                    button(text(session_label))
+                        .on_press_maybe(can_approve.then(|| Message::ApproveForSession(request.id.clone()))),
+                    button(text("Reject")).on_press(Message::RejectRequest(request.id.clone())),
+                ]
+                .spacing(10),
+            );
+            let reason = choice.map(|c| c.rejection_reason.as_str()).unwrap_or("");
+            let (pick_id, input_id) = (request.id.clone(), request.id.clone());
+            details = details.push(
+                row![
+                    pick_list(REJECTION_REASONS, REJECTION_REASONS.iter().find(|r| **r == reason).copied(), move |r: &str| {
+                        Message::RejectionReasonChanged(pick_id.clone(), r.to_string())
+                    })
+                    .placeholder("Rejection reason")
+                    .text_size(12),
+                    text_input("or your own (optional)", reason)
+                        .on_input(move |r| Message::RejectionReasonChanged(input_id.clone(), r))
+                        .size(12),
+                ]
+                .spacing(10),
+            );
+            requests = requests.push(details);
+        }
+        
+        let mut content = column![header, scrollable(requests)].spacing(20);
+        
+        if let Some(ref err) = self.error_message {
+            content = content.push(
+                text(err).size(14).color(iced::Color::from_rgb(0.9, 0.2, 0.2))
+            );
+        }
+        
+        content.into()
+    }
+    
     pub fn theme(&self) -> Theme {
         Theme::Dark
     }
 }
 
+/// Kind of the event a request wants signed
+fn event_kind(request: &SigningRequest) -> Option<u16> {
+    match request.payload {
+        SigningPayload::Event(ref event) => Some(event.kind),
+        _ => None,
+    }
+}
+
+/// The latest requests handled by the bunker, newest first
+fn remote_feed(log: &AuditLog) -> Vec<AuditEntry> {
+    log.entries()
+        .iter()
+        .rev()
+        .filter(|entry| entry.app_id.starts_with(crate::bunker::BUNKER_APP_PREFIX))
+        .take(REMOTE_FEED_LEN)
+        .cloned()
+        .collect()
+}
+
+/// Serve the bunker URI on a temporary QR page and return its address
+#[cfg(feature = "share-server")]
+async fn share_bunker_uri(uri: String, on_lan: bool, lifetime: std::time::Duration) -> Result<String, String> {
+    crate::share::serve_bunker_uri(&uri, on_lan, lifetime)
+        .await
+        .map(|page| page.url)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "share-server"))]
+async fn share_bunker_uri(_uri: String, _on_lan: bool, _lifetime: std::time::Duration) -> Result<String, String> {
+    Err("Pleb Signer was built without the share-server feature".into())
+}
+
+/// A QR code with a caption above and the encoded text (if any) below
+fn qr_panel<'a>(data: &'a qr_code::Data, label: &'a str, encoded: &'a str) -> Element<'a, Message> {
+    let mut panel = column![
+        text(label).size(12),
+        qr_code(data).cell_size(4),
+    ]
+    .spacing(6)
+    .align_x(iced::Alignment::Center);
+    
+    if !encoded.is_empty() {
+        panel = panel.push(text(encoded).size(11));
+    }
+    
+    panel.into()
+}
+
 /// Run the UI application
 pub fn run_ui(
     key_manager: Arc<Mutex<KeyManager>>,
@@ -758,9 +2174,270 @@ pub fn run_ui(
 ) -> Result<(), SignerError> {
     iced::application("Pleb Signer", PlebSignerUi::update, PlebSignerUi::view)
         .theme(PlebSignerUi::theme)
+        .subscription(PlebSignerUi::subscription)
         .window_size((550.0, 450.0))
         .run_with(move || PlebSignerUi::new(key_manager, config))
         .map_err(|e| SignerError::ConfigError(format!("UI error: {}", e)))?;
     
     Ok(())
-}
\ No newline at end of file
+}
+/// Have the signer process re-read the key metadata we just saved
+async fn reload_signer_keys() -> Result<(), String> {
+    let client = PlebSignerClient::new("pleb-signer-ui").await.map_err(|e| e.to_string())?;
+    client.reload_keys().await.map_err(|e| e.to_string())
+}
+
+/// Pass a key change on to the signer process (it has its own key
+/// manager), noting in the status message if it didn't pick it up
+async fn signer_synced(done: String) -> String {
+    match reload_signer_keys().await {
+        Ok(()) => done,
+        Err(e) => format!("{} (the running signer didn't pick it up: {})", done, e),
+    }
+}
+
+/// How long a copied secret stays on the clipboard, for status messages
+fn clear_note(clear_secs: u64) -> String {
+    if clear_secs == 0 {
+        String::new()
+    } else {
+        format!(" (cleared in {}s)", clear_secs)
+    }
+}
+
+/// Join a recovery phrase's words with single spaces, checking the word count
+fn normalize_mnemonic(input: &str) -> Result<String, String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    if words.is_empty() {
+        return Err("Please enter the recovery phrase".into());
+    }
+    if !MNEMONIC_WORD_COUNTS.contains(&words.len()) {
+        return Err(format!(
+            "A recovery phrase has 12, 15, 18, 21 or 24 words, but this one has {}",
+            words.len()
+        ));
+    }
+    Ok(words.join(" ").to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_bunker_uri_shown_in_full() {
+        let mut ui = PlebSignerUi::default();
+        let uri = format!(
+            "bunker://{}?relay=wss%3A%2F%2Frelay.nsec.app&relay=wss%3A%2F%2Frelay.damus.io&relay=wss%3A%2F%2Fnos.lol&secret=abc123",
+            "a".repeat(64)
+        );
+
+        let _ = ui.update(Message::BunkerUriGenerated(Ok(uri.clone())));
+        assert_eq!(ui.bunker_uri.as_deref(), Some(uri.as_str()));
+        assert_eq!(ui.bunker_uri_content.text().trim_end(), uri);
+
+        // Edits are ignored so the displayed URI can't be altered
+        let _ = ui.update(Message::BunkerUriAction(text_editor::Action::Edit(text_editor::Edit::Insert('x'))));
+        assert_eq!(ui.bunker_uri_content.text().trim_end(), uri);
+    }
+
+    #[test]
+    fn test_mnemonic_word_count_checked_before_import() {
+        let twelve = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(normalize_mnemonic(&format!("  {}\n", twelve.replace(' ', "\n  "))).unwrap(), twelve);
+        assert!(normalize_mnemonic("").is_err());
+        let error = normalize_mnemonic("abandon abandon about").unwrap_err();
+        assert!(error.contains("12, 15, 18, 21 or 24 words") && error.contains("has 3"), "{}", error);
+
+        let mut ui = PlebSignerUi::default();
+        let _ = ui.update(Message::KeyNameInput("restored".into()));
+        ui.mnemonic_content = text_editor::Content::with_text("abandon\nabout");
+        let _ = ui.update(Message::ImportMnemonic);
+        assert!(ui.error_message.unwrap().contains("has 2"));
+    }
+
+    #[test]
+    fn test_key_password_and_ncryptsec_import_need_input() {
+        let mut ui = PlebSignerUi::default();
+        let _ = ui.update(Message::EditKeyPassword("main".into()));
+        let _ = ui.update(Message::SetKeyPassword);
+        assert_eq!(ui.error_message.as_deref(), Some("Please enter the key's password"));
+        let _ = ui.update(Message::CancelKeyPassword);
+        assert!(ui.password_key.is_none());
+
+        let _ = ui.update(Message::KeyNameInput("restored".into()));
+        let _ = ui.update(Message::ImportKeyInput("ncryptsec1qgg9947rlpvqu76pj5ecreduf9jxhselq2nae2kghhvd5g7dgjtcxfqtd67p9m0w57lspw8gsq6yphnm8623nsl8xn9j4jdzz84zm3frztj3z7s35vpzmqf6ksu8r89qk5z2zxfmu5gv8th8wclt0h4p".into()));
+        let _ = ui.update(Message::ImportKey);
+        assert_eq!(ui.error_message.as_deref(), Some("Please enter the password the key was encrypted with"));
+    }
+
+    #[test]
+    fn test_export_needs_a_password() {
+        let mut ui = PlebSignerUi::default();
+        let _ = ui.update(Message::ExportKey("main".into()));
+        let _ = ui.update(Message::ConfirmExport);
+        assert_eq!(ui.error_message.as_deref(), Some("Please enter a password to encrypt the key with"));
+        assert_eq!(ui.export_key.as_deref(), Some("main"));
+        ui.error_message = None;
+        let _ = ui.update(Message::CopyExport);
+        assert_eq!(ui.error_message.as_deref(), Some("Please enter a password to encrypt the key with"));
+
+        let _ = ui.update(Message::CancelExport);
+        assert!(ui.export_key.is_none());
+    }
+
+    fn sign_request(id: &str, content: &str) -> SigningRequest {
+        SigningRequest {
+            id: id.into(),
+            request_type: crate::permissions::RequestType::SignEvent,
+            app_id: "/usr/bin/gossip".into(),
+            app_name: Some("Gossip".into()),
+            key_id: None,
+            payload: SigningPayload::Event(crate::signing::UnsignedEventData {
+                kind: 1,
+                content: content.into(),
+                tags: Vec::new(),
+                created_at: None,
+                relay_hints: Vec::new(),
+            }),
+            timestamp: chrono::Utc::now(),
+            warnings: Vec::new(),
+            origin: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_new_request_opens_approval_view() {
+        let mut ui = PlebSignerUi::default();
+        ui.config.ui.approval_countdowns.push(crate::config::KindCountdown { kind: 1, secs: 2 });
+
+        let _ = ui.update(Message::PendingRefreshed(Ok(vec![sign_request("a", "hello")])));
+        assert_eq!(ui.view, ViewState::Approval);
+        assert!(!ui.approve_enabled());
+        assert_eq!(ui.content_preview(&ui.pending_requests[0]), Some("hello"));
+
+        // Seeing the same queue again doesn't pull the user back
+        ui.view = ViewState::Settings;
+        assert!(!ui.set_pending_requests(vec![sign_request("a", "hello")]));
+        assert_eq!(ui.view, ViewState::Settings);
+
+        // Answering removes the request; an empty queue leaves the view
+        ui.view = ViewState::Approval;
+        let _ = ui.update(Message::RejectRequest("a".into()));
+        assert!(ui.pending_requests.is_empty());
+        assert_eq!(ui.view, ViewState::Main);
+    }
+
+    #[test]
+    fn test_compact_mode_hides_content() {
+        let mut ui = PlebSignerUi::default();
+        let request = sign_request("a", "secret plans");
+        ui.config.ui.compact_mode = true;
+        assert_eq!(ui.content_preview(&request), None);
+
+        ui.config.ui.compact_mode = false;
+        ui.config.ui.show_event_content = false;
+        assert_eq!(ui.content_preview(&request), None);
+    }
+
+    #[test]
+    fn test_countdown_enables_approve_after_delay() {
+        let mut ui = PlebSignerUi::default();
+        ui.config.ui.approval_countdowns.push(crate::config::KindCountdown { kind: 5, secs: 3 });
+
+        ui.start_approval_countdown(Some(5));
+        assert!(!ui.approve_enabled());
+        for _ in 0..2 {
+            let _ = ui.update(Message::CountdownTick);
+            assert!(!ui.approve_enabled());
+        }
+        let _ = ui.update(Message::CountdownTick);
+        assert!(ui.approve_enabled());
+
+        // Unconfigured kinds and non-sign requests can be approved at once
+        ui.start_approval_countdown(Some(1));
+        assert!(ui.approve_enabled());
+        ui.start_approval_countdown(None);
+        assert!(ui.approve_enabled());
+    }
+
+    #[test]
+    fn test_countdown_kind_waits_behind_front_request() {
+        let mut ui = PlebSignerUi::default();
+        ui.config.ui.approval_countdowns.push(crate::config::KindCountdown { kind: 5, secs: 3 });
+        let mut deletion = sign_request("b", "");
+        if let SigningPayload::Event(ref mut event) = deletion.payload {
+            event.kind = 5;
+        }
+        ui.set_pending_requests(vec![sign_request("a", "hello"), deletion, sign_request("c", "hi")]);
+
+        assert!(ui.can_approve("a"));
+        assert!(!ui.can_approve("b"));
+        assert!(ui.can_approve("c"));
+        let _ = ui.update(Message::ApproveRequest("b".into()));
+        assert_eq!(ui.pending_requests.len(), 3);
+
+        // At the front, it gets its own countdown
+        let _ = ui.update(Message::ApproveRequest("a".into()));
+        assert!(!ui.can_approve("b"));
+        for _ in 0..3 {
+            let _ = ui.update(Message::CountdownTick);
+        }
+        assert!(ui.can_approve("b"));
+    }
+
+    #[test]
+    fn test_choices_shape_the_answer() {
+        let mut ui = PlebSignerUi::default();
+        ui.set_pending_requests(vec![sign_request("a", "hello"), sign_request("b", "hi")]);
+        assert_eq!(ui.chosen_answer("a", true), Answer::Approve);
+        assert_eq!(ui.chosen_answer("a", false), Answer::Reject(String::new()));
+
+        let _ = ui.update(Message::SigningKeyPicked("a".into(), "work".into()));
+        let _ = ui.update(Message::RejectionReasonChanged("b".into(), REJECTION_REASONS[0].into()));
+        assert_eq!(ui.chosen_answer("a", true), Answer::ApproveWithKey("work".into()));
+        assert_eq!(ui.chosen_answer("b", false), Answer::Reject("Wrong account".into()));
+        assert_eq!(ui.chosen_answer("b", true), Answer::Approve);
+
+        // Choices are dropped with their request
+        let _ = ui.update(Message::ApproveRequest("a".into()));
+        assert!(!ui.request_choices.contains_key("a"));
+        assert!(ui.request_choices.contains_key("b"));
+    }
+
+    #[test]
+    fn test_npub_qr_encodes_active_npub() {
+        let mut ui = PlebSignerUi::default();
+        // NIP-19 test vector
+        let pubkey = nostr::PublicKey::from_hex("7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e").unwrap();
+        let npub = nostr::ToBech32::to_bech32(&pubkey).unwrap();
+        let metadata = |name: &str, npub: &str, is_active: bool| KeyMetadata {
+            name: name.into(),
+            npub: npub.into(),
+            pubkey_hex: String::new(),
+            created_at: chrono::Utc::now(),
+            is_active,
+            labels: Vec::new(),
+            password_verifier: None,
+            relays: Vec::new(),
+            nip05: None,
+            lud16: None,
+            watch_only: false,
+        };
+
+        let _ = ui.update(Message::KeysRefreshed(vec![
+            metadata("spare", "npub1spare", false),
+            metadata("main", &npub, true),
+        ]));
+        let (encoded, _) = ui.npub_qr.as_ref().expect("QR for the active key");
+        assert_eq!(encoded, &npub);
+        assert_eq!(
+            npub,
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        );
+
+        // No active key, no QR
+        let _ = ui.update(Message::KeysRefreshed(vec![metadata("spare", "npub1spare", false)]));
+        assert!(ui.npub_qr.is_none());
+    }
+}
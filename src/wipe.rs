@@ -0,0 +1,241 @@
+//! Removing everything Pleb Signer stored on this machine (`--wipe`)
+//!
+//! Only files the signer creates itself are deleted, by name: the config
+//! file, key metadata of every key set, the audit log and its exports,
+//! bunker state and the autostart entry. The data and config directories
+//! are removed once empty; anything else found in them is kept and
+//! reported. Secret keys are removed from the keyring with
+//! [`KeyManager::wipe_keyring`](crate::keys::KeyManager::wipe_keyring).
+
+use crate::audit::{AUDIT_FILE, EXPORT_DIR};
+use crate::bunker::{CURSOR_FILE, PAIRING_FILE};
+use crate::config::{Config, KEYS_FILE};
+use crate::error::{Result, SignerError};
+use crate::keys::{LOCK_SUFFIX, METADATA_FILE};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the autostart entry suggested in the README
+const AUTOSTART_FILE: &str = "pleb-signer.desktop";
+
+/// Where the signer keeps its files on this machine
+#[derive(Debug, Clone)]
+pub struct WipeTargets {
+    /// The config file in use (may be outside the config dir)
+    pub config_file: PathBuf,
+    /// The default config directory
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// Login autostart entry (None if the platform has no autostart dir)
+    pub autostart_entry: Option<PathBuf>,
+}
+
+impl WipeTargets {
+    /// The locations used by the current user with `config`
+    pub fn for_this_user(config: &Config) -> Result<Self> {
+        let autostart_entry = directories::BaseDirs::new()
+            .map(|dirs| dirs.config_dir().join("autostart").join(AUTOSTART_FILE));
+        Ok(Self {
+            config_file: config.config_path().clone(),
+            config_dir: Config::config_dir()?,
+            data_dir: Config::data_dir()?,
+            autostart_entry,
+        })
+    }
+
+    /// Key sets with a metadata file in the data dir (None = the default set)
+    pub fn key_namespaces(&self) -> Vec<Option<String>> {
+        let mut namespaces = vec![None];
+        let Ok(entries) = std::fs::read_dir(&self.data_dir) else {
+            return namespaces;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(namespace) = name.strip_prefix("keys_metadata.").and_then(|n| n.strip_suffix(".json")) {
+                namespaces.push(Some(namespace.to_string()));
+            }
+        }
+        namespaces.sort();
+        namespaces
+    }
+
+    /// Files and directories in the data dir that the signer creates
+    fn data_artifacts(&self) -> Vec<PathBuf> {
+        let mut artifacts: Vec<PathBuf> = [AUDIT_FILE, EXPORT_DIR, CURSOR_FILE, PAIRING_FILE, KEYS_FILE]
+            .iter()
+            .map(|name| self.data_dir.join(name))
+            .collect();
+        let metadata_files = self.key_namespaces().into_iter().map(|namespace| match namespace {
+            Some(namespace) => format!("keys_metadata.{}.json", namespace),
+            None => METADATA_FILE.to_string(),
+        });
+        for file in metadata_files {
+            artifacts.push(self.data_dir.join(format!("{}{}", file, LOCK_SUFFIX)));
+            artifacts.push(self.data_dir.join(file));
+        }
+        artifacts
+    }
+}
+
+/// What a wipe did
+#[derive(Debug, Clone, Default)]
+pub struct WipeReport {
+    pub removed: Vec<String>,
+    /// Unknown files left in the signer's directories
+    pub kept: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl WipeReport {
+    /// Plain-text summary for the terminal
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for item in &self.removed {
+            let _ = writeln!(out, "removed: {}", item);
+        }
+        for item in &self.kept {
+            let _ = writeln!(out, "kept (not created by Pleb Signer): {}", item);
+        }
+        for item in &self.failed {
+            let _ = writeln!(out, "FAILED: {}", item);
+        }
+        if self.removed.is_empty() && self.failed.is_empty() {
+            let _ = writeln!(out, "Nothing to remove");
+        }
+        out
+    }
+}
+
+/// Delete the signer's files (everything but keyring entries)
+pub fn wipe_files(targets: &WipeTargets, report: &mut WipeReport) {
+    remove_path(&targets.config_file, report);
+    for artifact in targets.data_artifacts() {
+        remove_path(&artifact, report);
+    }
+    if let Some(ref entry) = targets.autostart_entry {
+        // Only an entry that starts this app; the name alone isn't proof
+        match std::fs::read_to_string(entry) {
+            Ok(content) if content.contains("pleb-signer") => remove_path(entry, report),
+            Ok(_) => report.kept.push(entry.display().to_string()),
+            Err(_) => {}
+        }
+    }
+    for dir in [&targets.data_dir, &targets.config_dir] {
+        remove_dir_if_empty(dir, report);
+    }
+}
+
+/// Remove a file or one of our directories, if it exists
+fn remove_path(path: &Path, report: &mut WipeReport) {
+    let removed = match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(_) => return,
+    };
+    match removed {
+        Ok(()) => report.removed.push(path.display().to_string()),
+        Err(e) => report.failed.push(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Remove a directory with nothing left in it; list what's left otherwise
+fn remove_dir_if_empty(dir: &Path, report: &mut WipeReport) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let leftovers: Vec<String> = entries.flatten().map(|e| e.path().display().to_string()).collect();
+    if !leftovers.is_empty() {
+        report.kept.extend(leftovers);
+        return;
+    }
+    match std::fs::remove_dir(dir) {
+        Ok(()) => report.removed.push(dir.display().to_string()),
+        Err(e) => report.failed.push(format!("{}: {}", dir.display(), e)),
+    }
+}
+
+/// Refuse to wipe while a signer owns the D-Bus name; it would write its
+/// files again
+pub async fn check_not_running() -> Result<()> {
+    let Ok(connection) = zbus::Connection::session().await else {
+        return Ok(());
+    };
+    let proxy = zbus::fdo::DBusProxy::new(&connection).await
+        .map_err(|e| SignerError::DbusError(e.to_string()))?;
+    let name = zbus::names::BusName::try_from(crate::dbus::DBUS_NAME)
+        .map_err(|e| SignerError::DbusError(e.to_string()))?;
+    if proxy.name_has_owner(name).await.unwrap_or(false) {
+        return Err(SignerError::InvalidRequest("Pleb Signer is running; quit it before wiping".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wipe_removes_known_artifacts() {
+        let home = tempfile::tempdir().unwrap();
+        let targets = WipeTargets {
+            config_file: home.path().join("config/plebsigner/config.toml"),
+            config_dir: home.path().join("config/plebsigner"),
+            data_dir: home.path().join("data/plebsigner"),
+            autostart_entry: Some(home.path().join("config/autostart").join(AUTOSTART_FILE)),
+        };
+        std::fs::create_dir_all(&targets.config_dir).unwrap();
+        std::fs::create_dir_all(targets.data_dir.join(EXPORT_DIR)).unwrap();
+        std::fs::create_dir_all(home.path().join("config/autostart")).unwrap();
+        std::fs::write(&targets.config_file, "[general]\n").unwrap();
+        for name in [METADATA_FILE, "keys_metadata.work.json", AUDIT_FILE, CURSOR_FILE, PAIRING_FILE, KEYS_FILE] {
+            std::fs::write(targets.data_dir.join(name), "{}").unwrap();
+        }
+        std::fs::write(targets.data_dir.join(EXPORT_DIR).join("gossip.log"), "log").unwrap();
+        std::fs::write(targets.data_dir.join(format!("{}{}", METADATA_FILE, LOCK_SUFFIX)), "").unwrap();
+        let autostart = targets.autostart_entry.clone().unwrap();
+        std::fs::write(&autostart, "[Desktop Entry]\nExec=/usr/local/bin/pleb-signer --minimized\n").unwrap();
+        // Other apps' autostart entries are left alone
+        let other = home.path().join("config/autostart/other.desktop");
+        std::fs::write(&other, "[Desktop Entry]\nExec=other\n").unwrap();
+
+        assert_eq!(targets.key_namespaces(), [None, Some("work".to_string())]);
+        let mut report = WipeReport::default();
+        wipe_files(&targets, &mut report);
+
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert!(report.kept.is_empty(), "{:?}", report.kept);
+        assert!(!targets.data_dir.exists());
+        assert!(!targets.config_dir.exists());
+        assert!(!autostart.exists());
+        assert!(other.exists());
+        // 6 data files, a lock file, exports, config, autostart and both directories
+        assert_eq!(report.removed.len(), 12);
+
+        // Running again finds nothing
+        let mut again = WipeReport::default();
+        wipe_files(&targets, &mut again);
+        assert!(again.removed.is_empty());
+        assert!(again.render().contains("Nothing to remove"));
+    }
+
+    #[test]
+    fn test_wipe_keeps_unknown_files() {
+        let home = tempfile::tempdir().unwrap();
+        let targets = WipeTargets {
+            config_file: home.path().join("config/config.toml"),
+            config_dir: home.path().join("config"),
+            data_dir: home.path().join("data"),
+            autostart_entry: None,
+        };
+        std::fs::create_dir_all(&targets.data_dir).unwrap();
+        std::fs::write(targets.data_dir.join(AUDIT_FILE), "").unwrap();
+        std::fs::write(targets.data_dir.join("notes.txt"), "mine").unwrap();
+
+        let mut report = WipeReport::default();
+        wipe_files(&targets, &mut report);
+
+        assert!(!targets.data_dir.join(AUDIT_FILE).exists());
+        assert!(targets.data_dir.join("notes.txt").exists());
+        assert_eq!(report.kept, [targets.data_dir.join("notes.txt").display().to_string()]);
+    }
+}